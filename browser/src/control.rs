@@ -0,0 +1,428 @@
+//! Embedded HTTP/WebSocket API to drive a [`ParticipantStore`] from outside the process.
+//!
+//! | Method   | Path                            | Description                                                        |
+//! |----------|---------------------------------|--------------------------------------------------------------------|
+//! | `GET`    | `/participants`                 | All participants with their backend, creation time and state.      |
+//! | `POST`   | `/participants`                 | Spawn a participant. The body holds `Config` fields that override  |
+//! |          |                                 | the simulator config, e.g. `{"backend": "cloudflare"}`.            |
+//! | `DELETE` | `/participants`                 | Close all participants.                                            |
+//! | `GET`    | `/participants/{name}`          | One participant.                                                   |
+//! | `DELETE` | `/participants/{name}`          | Close one participant.                                             |
+//! | `POST`   | `/participants/{name}/messages` | Send a `ParticipantMessage`, e.g. `"ToggleAudio"` or               |
+//! |          |                                 | `{"SetNoiseSuppression": "none"}`.                                 |
+//! | `GET`    | `/participants/events`          | WebSocket streaming `participant` and `removed` updates as JSON.   |
+
+use crate::participant::{
+    shared::messages::ParticipantMessage,
+    Participant,
+    ParticipantState,
+    ParticipantStore,
+};
+use axum::{
+    extract::{
+        ws::{
+            Message,
+            WebSocket,
+            WebSocketUpgrade,
+        },
+        Path,
+        State,
+    },
+    http::StatusCode,
+    response::{
+        IntoResponse,
+        Response,
+    },
+    routing::{
+        get,
+        post,
+    },
+    Json,
+    Router,
+};
+use chrono::{
+    DateTime,
+    Utc,
+};
+use client_simulator_config::{
+    Config,
+    ParticipantBackendKind,
+};
+use eyre::{
+    bail,
+    Context as _,
+    Result,
+};
+use futures::{
+    future::{
+        pending,
+        select_all,
+    },
+    FutureExt as _,
+};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fmt,
+    net::SocketAddr,
+    time::Duration,
+};
+use tokio::{
+    net::TcpListener,
+    task::JoinHandle,
+};
+use tokio_util::sync::CancellationToken;
+
+/// How often event streams look for participants that were added or removed.
+const MEMBERSHIP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A running control server, see the module docs for the API.
+#[derive(Debug)]
+pub struct ControlServer {
+    local_addr: SocketAddr,
+    shutdown: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl ControlServer {
+    /// Binds `addr` and serves the control API for `store`. Participants spawned through the API start from `config`.
+    pub async fn start(addr: SocketAddr, store: ParticipantStore, config: Config) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .wrap_err_with(|| format!("Failed to bind the control server to {addr}"))?;
+        let local_addr = listener.local_addr()?;
+        let shutdown = CancellationToken::new();
+        let app = router(ControlState {
+            store,
+            config,
+            shutdown: shutdown.clone(),
+        });
+
+        let task = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                if let Err(err) = axum::serve(listener, app)
+                    .with_graceful_shutdown(shutdown.cancelled_owned())
+                    .await
+                {
+                    error!("Control server failed: {err}");
+                }
+            }
+        });
+        info!("Control server listening on http://{local_addr}");
+
+        Ok(Self {
+            local_addr,
+            shutdown,
+            task,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stops accepting requests and closes open event streams. Participants are left running.
+    pub async fn shutdown(self) {
+        self.shutdown.cancel();
+        if let Err(err) = self.task.await {
+            error!("Control server task failed: {err}");
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ControlState {
+    store: ParticipantStore,
+    config: Config,
+    shutdown: CancellationToken,
+}
+
+fn router(state: ControlState) -> Router {
+    Router::new()
+        .route(
+            "/participants",
+            get(list_participants).post(spawn_participant).delete(close_all),
+        )
+        .route("/participants/events", get(stream_events))
+        .route("/participants/{name}", get(get_participant).delete(close_participant))
+        .route("/participants/{name}/messages", post(send_message))
+        .with_state(state)
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+struct ParticipantSummary {
+    name: String,
+    backend: ParticipantBackendKind,
+    created: DateTime<Utc>,
+    state: ParticipantState,
+}
+
+impl ParticipantSummary {
+    fn new(participant: &Participant, state: ParticipantState) -> Self {
+        Self {
+            name: participant.name.clone(),
+            backend: participant.backend,
+            created: participant.created,
+            state,
+        }
+    }
+}
+
+impl From<&Participant> for ParticipantSummary {
+    fn from(participant: &Participant) -> Self {
+        Self::new(participant, participant.state.borrow().clone())
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    Participant(ParticipantSummary),
+    Removed { name: String },
+}
+
+#[derive(Debug)]
+struct ControlError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ControlError {
+    fn new(status: StatusCode, message: impl fmt::Display) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+        }
+    }
+
+    fn not_found(name: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, format!("No participant named `{name}`"))
+    }
+}
+
+impl IntoResponse for ControlError {
+    fn into_response(self) -> Response {
+        (self.status, Json(serde_json::json!({ "error": self.message }))).into_response()
+    }
+}
+
+async fn list_participants(State(control): State<ControlState>) -> Json<Vec<ParticipantSummary>> {
+    Json(control.store.values().iter().map(ParticipantSummary::from).collect())
+}
+
+async fn get_participant(
+    State(control): State<ControlState>,
+    Path(name): Path<String>,
+) -> Result<Json<ParticipantSummary>, ControlError> {
+    let participant = control.store.get(&name).ok_or_else(|| ControlError::not_found(&name))?;
+    Ok(Json(ParticipantSummary::from(&participant)))
+}
+
+async fn spawn_participant(
+    State(control): State<ControlState>,
+    Json(overrides): Json<Value>,
+) -> Result<(StatusCode, Json<ParticipantSummary>), ControlError> {
+    let config = config_with_overrides(&control.config, overrides)
+        .map_err(|err| ControlError::new(StatusCode::BAD_REQUEST, format!("{err:#}")))?;
+    let name = control
+        .store
+        .spawn(&config)
+        .map_err(|err| ControlError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{err:#}")))?;
+    let participant = control.store.get(&name).ok_or_else(|| ControlError::not_found(&name))?;
+    Ok((StatusCode::CREATED, Json(ParticipantSummary::from(&participant))))
+}
+
+async fn send_message(
+    State(control): State<ControlState>,
+    Path(name): Path<String>,
+    Json(message): Json<ParticipantMessage>,
+) -> Result<StatusCode, ControlError> {
+    if let ParticipantMessage::Close = message {
+        return close_participant(State(control), Path(name)).await;
+    }
+
+    let participant = control.store.get(&name).ok_or_else(|| ControlError::not_found(&name))?;
+    participant
+        .try_send_message(message)
+        .map_err(|err| ControlError::new(StatusCode::CONFLICT, err))?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn close_participant(
+    State(control): State<ControlState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ControlError> {
+    let participant = control
+        .store
+        .remove(&name)
+        .ok_or_else(|| ControlError::not_found(&name))?;
+    participant.close().await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn close_all(State(control): State<ControlState>) -> StatusCode {
+    control.store.shutdown_all().await;
+    StatusCode::NO_CONTENT
+}
+
+async fn stream_events(ws: WebSocketUpgrade, State(control): State<ControlState>) -> Response {
+    ws.on_upgrade(move |socket| send_events(socket, control))
+}
+
+/// Sends the current state of every participant, then every state change, addition and removal until the client
+/// disconnects or the server shuts down.
+async fn send_events(mut socket: WebSocket, control: ControlState) {
+    let mut participants = HashMap::<String, Participant>::new();
+    let mut sent = HashMap::<String, ParticipantSummary>::new();
+    let mut membership = tokio::time::interval(MEMBERSHIP_POLL_INTERVAL);
+
+    loop {
+        for event in collect_events(&control.store, &mut participants, &mut sent) {
+            let Ok(text) = serde_json::to_string(&event) else {
+                continue;
+            };
+            if socket.send(Message::Text(text.into())).await.is_err() {
+                return;
+            }
+        }
+
+        // Stopped participants drop their state sender, their final state is picked up on the next membership poll.
+        let changed = participants
+            .values_mut()
+            .filter(|participant| participant.state.has_changed().is_ok())
+            .map(|participant| participant.state.changed().boxed())
+            .collect::<Vec<_>>();
+        let changed = async move {
+            if changed.is_empty() {
+                pending::<()>().await;
+            } else {
+                let _ = select_all(changed).await;
+            }
+        };
+
+        tokio::select! {
+            _ = control.shutdown.cancelled() => break,
+            _ = changed => {}
+            _ = membership.tick() => {}
+            message = socket.recv() => match message {
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+fn collect_events(
+    store: &ParticipantStore,
+    participants: &mut HashMap<String, Participant>,
+    sent: &mut HashMap<String, ParticipantSummary>,
+) -> Vec<StreamEvent> {
+    let current = store.values();
+    let mut events = Vec::new();
+
+    participants.retain(|name, _| current.iter().any(|participant| &participant.name == name));
+    sent.retain(|name, _| {
+        let retained = participants.contains_key(name);
+        if !retained {
+            events.push(StreamEvent::Removed { name: name.clone() });
+        }
+        retained
+    });
+
+    for participant in current {
+        let participant = participants.entry(participant.name.clone()).or_insert(participant);
+        let state = participant.state.borrow_and_update().clone();
+        let summary = ParticipantSummary::new(participant, state);
+        if sent.get(&summary.name) != Some(&summary) {
+            sent.insert(summary.name.clone(), summary.clone());
+            events.push(StreamEvent::Participant(summary));
+        }
+    }
+
+    events
+}
+
+/// Applies the fields of `overrides` on top of `base`. Nested tables like `spawn` are merged field by field, `null`
+/// resets an optional field.
+fn config_with_overrides(base: &Config, overrides: Value) -> Result<Config> {
+    if !overrides.is_object() && !overrides.is_null() {
+        bail!("Expected a JSON object with config fields, got {overrides}");
+    }
+
+    let mut value = serde_json::to_value(base).context("Failed to serialize the simulator config")?;
+    merge_json(&mut value, overrides);
+    let mut config: Config = serde_json::from_value(value).context("Invalid participant config")?;
+    config.app_config = base.app_config.clone();
+    Ok(config)
+}
+
+fn merge_json(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge_json(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn overrides_replace_top_level_fields_and_merge_nested_tables() {
+        let mut base = Config {
+            url: Some(url::Url::parse("https://example.com/room").unwrap()),
+            ..Default::default()
+        };
+        base.spawn.interval_ms = 250;
+        base.app_config.data_dir = "/tmp/simulator-data".into();
+
+        let config = config_with_overrides(
+            &base,
+            json!({
+                "backend": "remote-stub",
+                "audio_enabled": true,
+                "url": "https://example.com/other",
+                "spawn": { "max_local": 3 },
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(config.backend, ParticipantBackendKind::RemoteStub);
+        assert!(config.audio_enabled);
+        assert_eq!(config.url.unwrap().as_str(), "https://example.com/other");
+        assert_eq!(config.spawn.interval_ms, 250);
+        assert_eq!(config.spawn.max_local, Some(3));
+        assert_eq!(config.app_config.data_dir, base.app_config.data_dir);
+    }
+
+    #[test]
+    fn null_resets_optional_fields() {
+        let base = Config {
+            video_max_concurrent_tracks: Some(4),
+            ..Default::default()
+        };
+
+        let config = config_with_overrides(&base, json!({ "video_max_concurrent_tracks": null })).unwrap();
+
+        assert_eq!(config.video_max_concurrent_tracks, None);
+    }
+
+    #[test]
+    fn rejects_bodies_that_are_not_config_objects() {
+        let base = Config::default();
+
+        let error = config_with_overrides(&base, json!(["remote-stub"])).unwrap_err();
+        assert!(error.to_string().contains("Expected a JSON object"));
+
+        let error = config_with_overrides(&base, json!({ "backend": "mainframe" })).unwrap_err();
+        assert!(error.to_string().contains("Invalid participant config"));
+    }
+}
@@ -2,6 +2,8 @@
 extern crate tracing;
 
 pub mod auth;
+pub mod control;
+pub mod media;
 pub mod participant;
 
 #[doc(hidden)]
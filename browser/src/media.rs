@@ -0,0 +1,471 @@
+//! Prepares fake media in the background: downloads URLs and converts or generates the files Chrome plays as camera
+//! and microphone, so spawning a participant does not block on it. A preparation nobody waits for anymore is stopped.
+
+use client_simulator_config::media::{
+    partial_path,
+    FakeMedia,
+    FakeMediaConversion,
+    FakeMediaFileOrUrl,
+    FakeMediaFiles,
+    FakeMediaWithDescription,
+};
+use eyre::{
+    bail,
+    eyre,
+    Context as _,
+    Result,
+};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        Mutex,
+        OnceLock,
+    },
+};
+use tokio::{
+    io::AsyncWriteExt as _,
+    sync::watch,
+};
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+#[derive(Debug, Clone)]
+pub enum MediaProgress {
+    Queued,
+    Downloading { received: u64, total: Option<u64> },
+    Converting,
+    Ready(FakeMediaFiles),
+    Failed(String),
+}
+
+impl MediaProgress {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Ready(_) | Self::Failed(_))
+    }
+}
+
+impl fmt::Display for MediaProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Queued => write!(f, "queued"),
+            Self::Downloading {
+                received,
+                total: Some(total),
+            } if *total > 0 => write!(f, "downloading {}%", received * 100 / total),
+            Self::Downloading { received, .. } => write!(f, "downloading {:.1} MB", *received as f64 / 1_000_000.0),
+            Self::Converting => write!(f, "converting"),
+            Self::Ready(files) => match files.error_summary() {
+                Some(_) => write!(f, "partially converted"),
+                None => write!(f, "ready"),
+            },
+            Self::Failed(_) => write!(f, "failed"),
+        }
+    }
+}
+
+/// Fake media preparations by source, shared by every participant of the process so each source is downloaded and
+/// converted once.
+#[derive(Debug, Clone, Default)]
+pub struct FakeMediaPreparations {
+    preparations: Arc<Mutex<HashMap<String, Preparation>>>,
+}
+
+#[derive(Debug)]
+struct Preparation {
+    progress: watch::Receiver<MediaProgress>,
+    cancel: CancellationToken,
+    /// [`FakeMediaPreparations::prepare`] calls waiting for it. The preparation is cancelled when the last one stops
+    /// waiting before it finished.
+    waiters: usize,
+    /// Requested by [`FakeMediaPreparations::prefetch`], which nobody waits for, so it always runs to the end.
+    prefetched: bool,
+}
+
+impl Preparation {
+    fn is_reusable(&self) -> bool {
+        if self.cancel.is_cancelled() {
+            return false;
+        }
+        match &*self.progress.borrow() {
+            MediaProgress::Failed(_) => false,
+            // The files may have been removed from the cache since.
+            MediaProgress::Ready(files) => [&files.audio, &files.video]
+                .into_iter()
+                .flatten()
+                .all(|path| path.exists()),
+            _ => true,
+        }
+    }
+}
+
+/// Stops waiting for a preparation when dropped, e.g. when the participant waiting for it is stopped.
+struct Waiter<'a> {
+    preparations: &'a FakeMediaPreparations,
+    key: String,
+    progress: watch::Receiver<MediaProgress>,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        let mut preparations = self
+            .preparations
+            .preparations
+            .lock()
+            .expect("fake media preparations lock poisoned");
+        let Some(preparation) = preparations.get_mut(&self.key) else {
+            return;
+        };
+        // The preparation may have been replaced by a retry meanwhile.
+        if !preparation.progress.same_channel(&self.progress) {
+            return;
+        }
+        preparation.waiters -= 1;
+        if preparation.waiters == 0 && !preparation.prefetched && !preparation.progress.borrow().is_finished() {
+            preparation.cancel.cancel();
+        }
+    }
+}
+
+impl FakeMediaPreparations {
+    pub fn global() -> &'static Self {
+        static PREPARATIONS: OnceLock<FakeMediaPreparations> = OnceLock::new();
+        PREPARATIONS.get_or_init(Self::default)
+    }
+
+    /// Starts preparing `source` in the background unless it is already prepared or being prepared. Failed
+    /// preparations are retried. Returns `None` for media that needs no preparation.
+    ///
+    /// `username` only matters for the test pattern, which is generated per participant. Once prepared, least
+    /// recently used media is removed from the cache until it fits into `max_cache_size` bytes.
+    ///
+    /// A prefetched preparation runs to the end even if nobody waits for it.
+    pub fn prefetch(
+        &self,
+        source: &FakeMediaWithDescription,
+        username: &str,
+        cache_dir: &Path,
+        max_cache_size: Option<u64>,
+    ) -> Option<watch::Receiver<MediaProgress>> {
+        let (_, progress) = self.start(source, username, cache_dir, max_cache_size, false)?;
+        Some(progress)
+    }
+
+    /// Starts or joins the preparation of `source`, either as one more waiter or as a prefetch. Returns its key and
+    /// progress.
+    fn start(
+        &self,
+        source: &FakeMediaWithDescription,
+        username: &str,
+        cache_dir: &Path,
+        max_cache_size: Option<u64>,
+        waiting: bool,
+    ) -> Option<(String, watch::Receiver<MediaProgress>)> {
+        let key = preparation_key(source, username)?;
+        let mut preparations = self.preparations.lock().expect("fake media preparations lock poisoned");
+        if let Some(preparation) = preparations
+            .get_mut(&key)
+            .filter(|preparation| preparation.is_reusable())
+        {
+            match waiting {
+                true => preparation.waiters += 1,
+                false => preparation.prefetched = true,
+            }
+            return Some((key, preparation.progress.clone()));
+        }
+
+        let (sender, receiver) = watch::channel(MediaProgress::Queued);
+        let cancel = CancellationToken::new();
+        preparations.insert(
+            key.clone(),
+            Preparation {
+                progress: receiver.clone(),
+                cancel: cancel.clone(),
+                waiters: waiting as usize,
+                prefetched: !waiting,
+            },
+        );
+        let media = source.fake_media().clone();
+        let conversion = source.conversion().clone();
+        let username = username.to_string();
+        let cache_dir = cache_dir.to_path_buf();
+        let preparations = self.clone();
+        tokio::spawn(async move {
+            let prepared = prepare(media.clone(), conversion, username, cache_dir.clone(), &sender, &cancel).await;
+            if let Ok(files) = &prepared {
+                // Media prepared for other participants of this process stays, they may still play it.
+                let in_use = preparations.files_in_use();
+                let files = files.clone();
+                let shrunk =
+                    tokio::task::spawn_blocking(move || files.enforce_cache_limit(&cache_dir, max_cache_size, &in_use))
+                        .await;
+                if let Ok(Err(err)) = shrunk {
+                    warn!("Failed to shrink the fake media cache: {err:#}");
+                }
+            }
+            let progress = match prepared {
+                Ok(files) => MediaProgress::Ready(files),
+                Err(_) if cancel.is_cancelled() => {
+                    debug!("Stopped preparing fake media {media}, nobody waits for it anymore");
+                    MediaProgress::Failed(format!("Preparing {media} was stopped"))
+                }
+                Err(err) => {
+                    warn!("Preparing fake media {media} failed: {err:#}");
+                    MediaProgress::Failed(format!("{err:#}"))
+                }
+            };
+            sender.send_replace(progress);
+        });
+        Some((key, receiver))
+    }
+
+    /// Waits until `source` is prepared, starting the preparation if needed. `Ok(None)` for media that needs no
+    /// preparation.
+    ///
+    /// Dropping the returned future stops waiting. Once no one waits anymore, an unfinished download is aborted and
+    /// ffmpeg is killed, unless the source was prefetched.
+    pub async fn prepare(
+        &self,
+        source: &FakeMediaWithDescription,
+        username: &str,
+        cache_dir: &Path,
+        max_cache_size: Option<u64>,
+    ) -> Result<Option<FakeMediaFiles>> {
+        let Some((key, progress)) = self.start(source, username, cache_dir, max_cache_size, true) else {
+            return Ok(None);
+        };
+        let mut waiter = Waiter {
+            preparations: self,
+            key,
+            progress,
+        };
+        let progress = waiter
+            .progress
+            .wait_for(MediaProgress::is_finished)
+            .await
+            .map_err(|_| eyre!("Fake media preparation of {} stopped", source.fake_media()))?
+            .clone();
+        match progress {
+            MediaProgress::Ready(files) => Ok(Some(files)),
+            MediaProgress::Failed(err) => Err(eyre!(err)),
+            _ => unreachable!("waited for a finished preparation"),
+        }
+    }
+
+    /// The files of finished preparations, which participants of this process may be playing.
+    fn files_in_use(&self) -> Vec<PathBuf> {
+        let preparations = self.preparations.lock().expect("fake media preparations lock poisoned");
+        preparations
+            .values()
+            .flat_map(|preparation| match &*preparation.progress.borrow() {
+                MediaProgress::Ready(files) => [files.audio.clone(), files.video.clone()],
+                _ => [None, None],
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// The latest progress of a preparation of `source`, `None` if it was never started.
+    pub fn progress(&self, source: &FakeMediaWithDescription, username: &str) -> Option<MediaProgress> {
+        let key = preparation_key(source, username)?;
+        let preparations = self.preparations.lock().expect("fake media preparations lock poisoned");
+        let progress = preparations.get(&key)?.progress.borrow().clone();
+        Some(progress)
+    }
+}
+
+fn preparation_key(source: &FakeMediaWithDescription, username: &str) -> Option<String> {
+    let media = source.fake_media();
+    match media {
+        FakeMedia::None | FakeMedia::Builtin => None,
+        FakeMedia::TestPattern => Some(format!("{media}:{username}")),
+        FakeMedia::NoiseMix(_) | FakeMedia::FileOrUrl(_) => Some(format!("{media}:{}", source.conversion())),
+    }
+}
+
+async fn prepare(
+    media: FakeMedia,
+    conversion: FakeMediaConversion,
+    username: String,
+    cache_dir: PathBuf,
+    progress: &watch::Sender<MediaProgress>,
+    cancel: &CancellationToken,
+) -> Result<FakeMediaFiles> {
+    // URLs are downloaded here with progress, the conversion below then finds them in the download cache.
+    match &media {
+        FakeMedia::FileOrUrl(source) => download(source, &cache_dir, progress, cancel).await?,
+        FakeMedia::NoiseMix(mix) => {
+            download(&mix.speech, &cache_dir, progress, cancel).await?;
+            download(&mix.noise, &cache_dir, progress, cancel).await?;
+        }
+        FakeMedia::None | FakeMedia::Builtin | FakeMedia::TestPattern => {}
+    }
+    if cancel.is_cancelled() {
+        bail!("Preparing {media} was cancelled");
+    }
+
+    progress.send_replace(MediaProgress::Converting);
+    let cancel = cancel.clone();
+    tokio::task::spawn_blocking(move || {
+        let files = match &media {
+            FakeMedia::FileOrUrl(source) => {
+                FakeMediaFiles::from_file_or_url(source.parse()?, &conversion, &cache_dir, &cancel)?
+            }
+            FakeMedia::NoiseMix(mix) => FakeMediaFiles::noise_mix(mix, &conversion, &cache_dir, &cancel)?,
+            FakeMedia::TestPattern => FakeMediaFiles::test_pattern(&username, &cache_dir)?,
+            FakeMedia::None | FakeMedia::Builtin => bail!("{media} needs no preparation"),
+        };
+        Ok(files)
+    })
+    .await
+    .context("fake media conversion panicked")?
+}
+
+async fn download(
+    source: &str,
+    cache_dir: &Path,
+    progress: &watch::Sender<MediaProgress>,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let FakeMediaFileOrUrl::Url(url) = source.parse()? else {
+        return Ok(());
+    };
+    let path = FakeMediaFiles::download_path(&url, cache_dir)?;
+    if tokio::fs::try_exists(&path).await? {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    // Written next to the final file and renamed once complete, so an aborted download is never converted. Each
+    // download gets its own partial file, the same URL may be downloaded for a file and a noise mix at once.
+    let partial = partial_path(&path);
+    let downloaded = cancel
+        .run_until_cancelled(download_to(&url, &partial, progress))
+        .await
+        .unwrap_or_else(|| Err(eyre!("Downloading {url} was cancelled")));
+    if let Err(err) = downloaded {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(err);
+    }
+    tokio::fs::rename(&partial, &path)
+        .await
+        .wrap_err_with(|| format!("Failed to write {path:?}"))?;
+    debug!(?path, "downloaded");
+    Ok(())
+}
+
+async fn download_to(url: &Url, partial: &Path, progress: &watch::Sender<MediaProgress>) -> Result<()> {
+    let mut response = reqwest::get(url.as_str()).await?;
+    if !response.status().is_success() {
+        bail!("Failed to download file from {url}, status code: {}", response.status());
+    }
+    let total = response.content_length();
+    let mut received = 0;
+    progress.send_replace(MediaProgress::Downloading { received, total });
+
+    let mut file = tokio::fs::File::create(partial).await?;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        received += chunk.len() as u64;
+        progress.send_replace(MediaProgress::Downloading { received, total });
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn builtin_media_needs_no_preparation() {
+        let preparations = FakeMediaPreparations::default();
+        let builtin = FakeMediaWithDescription::new(FakeMedia::Builtin, None);
+
+        let files = preparations
+            .prepare(&builtin, "alice", Path::new("unused"), None)
+            .await
+            .unwrap();
+        assert!(files.is_none());
+        assert!(preparations.progress(&builtin, "alice").is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_preparation_is_reported_and_retried() {
+        let preparations = FakeMediaPreparations::default();
+        let media = FakeMediaWithDescription::new(FakeMedia::from("/does/not/exist.mp4"), None);
+
+        let err = preparations
+            .prepare(&media, "alice", Path::new("unused"), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("/does/not/exist.mp4"), "{err}");
+        assert!(matches!(
+            preparations.progress(&media, "bob"),
+            Some(MediaProgress::Failed(_))
+        ));
+
+        let retry = preparations
+            .prefetch(&media, "alice", Path::new("unused"), None)
+            .unwrap();
+        assert!(!matches!(*retry.borrow(), MediaProgress::Failed(_)));
+
+        let converted = media.clone().with_conversion("360p".parse().unwrap());
+        assert!(preparations.progress(&converted, "alice").is_none());
+    }
+
+    #[tokio::test]
+    async fn preparation_stops_when_nobody_waits_for_it() {
+        // Announces a body it never sends, so the download only ends when it is aborted.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/stalled.mp4", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 1000000\r\n\r\n")
+                .await
+                .unwrap();
+            std::future::pending::<()>().await;
+        });
+        let nonce = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let cache_dir = std::env::temp_dir().join(format!("hyper-browser-simulator-media-{nonce}"));
+        let preparations = FakeMediaPreparations::default();
+        let media = FakeMediaWithDescription::new(FakeMedia::from(url.as_str()), None);
+
+        let (key, mut progress) = preparations.start(&media, "alice", &cache_dir, None, true).unwrap();
+        let waiter = Waiter {
+            preparations: &preparations,
+            key,
+            progress: progress.clone(),
+        };
+        progress
+            .wait_for(|progress| matches!(progress, MediaProgress::Downloading { .. }))
+            .await
+            .unwrap();
+        drop(waiter);
+
+        let stopped = progress.wait_for(MediaProgress::is_finished).await.unwrap().clone();
+        assert!(
+            matches!(&stopped, MediaProgress::Failed(err) if err.contains("stopped")),
+            "{stopped:?}"
+        );
+        let download_dir = FakeMediaFiles::download_path(&Url::parse(&url).unwrap(), &cache_dir)
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        assert_eq!(std::fs::read_dir(&download_dir).unwrap().count(), 0);
+        server.abort();
+        let _ = std::fs::remove_dir_all(cache_dir);
+    }
+}
@@ -18,7 +18,9 @@ use crate::{
         ParticipantDriverSession,
         ParticipantLaunchSpec,
         ParticipantState,
+        PhaseTimings,
         ResolvedFrontendKind,
+        StartPhase,
     },
 };
 use client_simulator_config::{
@@ -56,6 +58,11 @@ use tokio::{
     time::MissedTickBehavior,
 };
 
+#[cfg(not(test))]
+const WORKER_CAPACITY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+#[cfg(test)]
+const WORKER_CAPACITY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 enum CloudflareAuth {
     HyperCore {
         cookie: Option<BorrowedCookie>,
@@ -69,6 +76,7 @@ pub(super) struct CloudflareLaunchOptions {
     headless: bool,
     fake_media: FakeMedia,
     browser_logs: bool,
+    worker_limits: bool,
 }
 
 impl From<&client_simulator_config::Config> for CloudflareLaunchOptions {
@@ -77,6 +85,7 @@ impl From<&client_simulator_config::Config> for CloudflareLaunchOptions {
             headless: config.headless,
             fake_media: config.fake_media(),
             browser_logs: config.browser_logs,
+            worker_limits: config.spawn.cloudflare_worker_limits,
         }
     }
 }
@@ -92,6 +101,7 @@ pub(super) struct CloudflareSession {
     termination_rx: watch::Receiver<Option<DriverTermination>>,
     poller_shutdown_tx: Option<oneshot::Sender<()>>,
     poller_task: Option<JoinHandle<()>>,
+    timings: PhaseTimings,
 }
 
 fn emit_log_message(participant_name: &str, level: &str, message: impl ToString) {
@@ -191,6 +201,7 @@ impl CloudflareSession {
             termination_rx,
             poller_shutdown_tx: None,
             poller_task: None,
+            timings: PhaseTimings::default(),
         }
     }
 
@@ -240,11 +251,43 @@ impl CloudflareSession {
             );
         }
 
-        if let FakeMedia::FileOrUrl(source) = &self.launch_options.fake_media {
+        if let source @ (FakeMedia::FileOrUrl(_) | FakeMedia::TestPattern | FakeMedia::NoiseMix(_)) =
+            &self.launch_options.fake_media
+        {
+            self.log_message(
+                "warn",
+                format!(
+                    "Cloudflare backend ignores local fake media source `{source}` because worker sessions cannot install the fake media script, and will use worker-provided media instead"
+                ),
+            );
+        }
+
+        if !self.launch_spec.settings.screenshare_source.is_default() {
+            self.log_message(
+                "warn",
+                format!(
+                    "Cloudflare backend ignores the screenshare source `{}` and shares worker-provided media instead",
+                    self.launch_spec.settings.screenshare_source
+                ),
+            );
+        }
+
+        if !self.launch_spec.settings.network.is_default() {
             self.log_message(
                 "warn",
                 format!(
-                    "Cloudflare backend ignores local fake media source `{source}` and will use worker-provided media instead"
+                    "Cloudflare backend ignores the {} network profile, only local browsers emulate network conditions",
+                    self.launch_spec.settings.network.profile
+                ),
+            );
+        }
+
+        if !self.launch_spec.settings.device.is_default() {
+            self.log_message(
+                "warn",
+                format!(
+                    "Cloudflare backend ignores the {} device emulation, only local browsers emulate devices",
+                    self.launch_spec.settings.device
                 ),
             );
         }
@@ -272,8 +315,12 @@ impl CloudflareSession {
             CloudflareAuth::HyperCore { cookie, cookie_manager } => {
                 if cookie.is_none() {
                     *cookie = Some(
-                        cookie_manager
-                            .give_or_fetch_cookie(self.launch_spec.base_url(), &self.launch_spec.username)
+                        self.timings
+                            .time(
+                                StartPhase::Auth,
+                                cookie_manager
+                                    .give_or_fetch_cookie(self.launch_spec.base_url(), &self.launch_spec.username),
+                            )
                             .await?,
                     );
                 }
@@ -309,8 +356,8 @@ impl CloudflareSession {
         })
     }
 
-    fn command_request(message: ParticipantMessage) -> types::SessionCommandRequest {
-        match message {
+    fn command_request(message: ParticipantMessage) -> Result<types::SessionCommandRequest> {
+        let request = match message {
             ParticipantMessage::Join => types::SessionCommandRequest::Join,
             ParticipantMessage::Leave => types::SessionCommandRequest::Leave,
             ParticipantMessage::Close => types::SessionCommandRequest::Leave,
@@ -337,7 +384,19 @@ impl CloudflareSession {
                 }
             }
             ParticipantMessage::ToggleBackgroundBlur => types::SessionCommandRequest::ToggleBackgroundBlur,
-        }
+            ParticipantMessage::SetAudioEnabled(_)
+            | ParticipantMessage::SetVideoEnabled(_)
+            | ParticipantMessage::SetScreenshare(_)
+            | ParticipantMessage::SetBlur(_)
+            | ParticipantMessage::SetAutoGainControl(_) => {
+                bail!("Set-style commands have no worker request, they are sent as the matching toggle")
+            }
+            ParticipantMessage::SetNetwork(_) => bail!("Cloudflare backend does not support network emulation"),
+            ParticipantMessage::StartRecording | ParticipantMessage::StopRecording => {
+                bail!("Cloudflare backend cannot return recordings of the received media")
+            }
+        };
+        Ok(request)
     }
 
     fn cached_state(&self) -> ParticipantState {
@@ -466,6 +525,35 @@ impl CloudflareSession {
         Ok(())
     }
 
+    async fn wait_for_worker_capacity(&mut self) -> Result<()> {
+        if !self.launch_options.worker_limits {
+            return Ok(());
+        }
+
+        let client = self.worker_client()?;
+        loop {
+            let response = client
+                .get_limits()
+                .await
+                .wrap_err("Failed to fetch Cloudflare worker limits")?;
+            let Some(wait) = worker_capacity_wait(&response.limits) else {
+                return Ok(());
+            };
+
+            self.log_message(
+                "info",
+                format!(
+                    "Cloudflare worker has no browser capacity ({} of {} sessions active, {} acquisitions allowed); retrying in {}ms",
+                    response.limits.active_sessions.len(),
+                    response.limits.max_concurrent_sessions,
+                    response.limits.allowed_browser_acquisitions,
+                    wait.as_millis()
+                ),
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
     async fn close_inner(&mut self) -> Result<()> {
         self.stop_termination_poller().await;
 
@@ -498,7 +586,17 @@ impl CloudflareSession {
             .session_id
             .clone()
             .ok_or_else(|| eyre!("Cloudflare session is not started"))?;
-        let request = Self::command_request(message);
+        let message = match set_command_toggle(&message) {
+            Some((toggle, value, current)) => {
+                if current(&self.fetch_state(&session_id).await?) == value {
+                    self.log_message("debug", format!("Skipped {message}, the worker already has the value"));
+                    return Ok(());
+                }
+                toggle
+            }
+            None => message,
+        };
+        let request = Self::command_request(message)?;
         let response = self.worker_client()?.command_session(&session_id, &request).await?;
         self.log_worker_entries(&response.log);
         self.log_worker_browser_entries(&response.browser_log);
@@ -506,6 +604,14 @@ impl CloudflareSession {
         Ok(())
     }
 
+    async fn fetch_state(&self, session_id: &str) -> Result<ParticipantState> {
+        let response = self.worker_client()?.get_session_state(session_id).await?;
+        self.log_worker_entries(&response.log);
+        self.log_worker_browser_entries(&response.browser_log);
+        self.update_cached_state(&response.state);
+        Ok(self.cached_state())
+    }
+
     async fn wait_for_termination_inner(&mut self) -> DriverTermination {
         loop {
             if let Some(termination) = self.termination_rx.borrow().clone() {
@@ -528,6 +634,10 @@ impl ParticipantDriverSession for CloudflareSession {
         self.start_inner().boxed()
     }
 
+    fn wait_for_start_capacity(&mut self) -> BoxFuture<'_, Result<()>> {
+        self.wait_for_worker_capacity().boxed()
+    }
+
     fn handle_command(&mut self, message: ParticipantMessage) -> BoxFuture<'_, Result<()>> {
         self.handle_command_inner(message).boxed()
     }
@@ -543,6 +653,50 @@ impl ParticipantDriverSession for CloudflareSession {
     fn wait_for_termination(&mut self) -> BoxFuture<'_, DriverTermination> {
         self.wait_for_termination_inner().boxed()
     }
+
+    fn take_phase_timings(&mut self) -> Vec<(StartPhase, Duration)> {
+        self.timings.take()
+    }
+}
+
+/// Returns how long to wait before the worker can launch another browser, or `None` when it can right away.
+fn worker_capacity_wait(limits: &types::Limits) -> Option<Duration> {
+    if limits.allowed_browser_acquisitions >= 1.0
+        && (limits.active_sessions.len() as f64) < limits.max_concurrent_sessions
+    {
+        return None;
+    }
+
+    let retry_after_ms = limits.time_until_next_allowed_browser_acquisition;
+    if retry_after_ms > 0.0 {
+        Some(Duration::from_millis(retry_after_ms as u64))
+    } else {
+        Some(WORKER_CAPACITY_POLL_INTERVAL)
+    }
+}
+
+/// The worker only knows toggles. Set-style messages are sent as the matching toggle, along with the requested value
+/// and how to read the current one from the worker's state, so they can be skipped when nothing would change.
+fn set_command_toggle(
+    message: &ParticipantMessage,
+) -> Option<(ParticipantMessage, bool, fn(&ParticipantState) -> bool)> {
+    let toggle: (ParticipantMessage, bool, fn(&ParticipantState) -> bool) = match *message {
+        ParticipantMessage::SetAudioEnabled(value) => (ParticipantMessage::ToggleAudio, value, |state| !state.muted),
+        ParticipantMessage::SetVideoEnabled(value) => {
+            (ParticipantMessage::ToggleVideo, value, |state| state.video_activated)
+        }
+        ParticipantMessage::SetScreenshare(value) => (ParticipantMessage::ToggleScreenshare, value, |state| {
+            state.screenshare_activated
+        }),
+        ParticipantMessage::SetBlur(value) => (ParticipantMessage::ToggleBackgroundBlur, value, |state| {
+            state.background_blur
+        }),
+        ParticipantMessage::SetAutoGainControl(value) => (ParticipantMessage::ToggleAutoGainControl, value, |state| {
+            state.auto_gain_control
+        }),
+        _ => return None,
+    };
+    Some(toggle)
 }
 
 fn map_frontend_kind(frontend_kind: ResolvedFrontendKind) -> types::SessionCreateRequestFrontendKind {
@@ -620,8 +774,10 @@ fn map_state(state: &types::ParticipantState) -> ParticipantState {
     ParticipantState {
         username: String::new(),
         running: state.running,
+        pending: false,
         warning: None,
         joined: state.joined,
+        call_status: Default::default(),
         muted: state.muted,
         video_activated: state.video_activated,
         auto_gain_control: state.auto_gain_control,
@@ -681,6 +837,14 @@ fn map_state(state: &types::ParticipantState) -> ParticipantState {
         video_max_concurrent_tracks: state.video_max_concurrent_tracks.map(|value| value as usize),
         background_blur: state.background_blur,
         screenshare_activated: state.screenshare_activated,
+        network: client_simulator_config::NetworkConfig::default(),
+        device: client_simulator_config::DeviceConfig::default(),
+        fake_media: Default::default(),
+        webrtc_stats: None,
+        received_media: None,
+        glass_to_glass: Vec::new(),
+        recording: false,
+        last_command: None,
     }
 }
 
@@ -875,8 +1039,10 @@ pub(crate) fn take_spawned_participants_for_test() -> Vec<String> {
 mod tests {
     use super::{
         map_worker_browser_entry,
+        worker_capacity_wait,
         CloudflareLaunchOptions,
         CloudflareSession,
+        WORKER_CAPACITY_POLL_INTERVAL,
     };
     use crate::{
         auth::HyperSessionCookieManger,
@@ -898,6 +1064,10 @@ mod tests {
         TransportMode,
         VideoConstraint,
     };
+    use cloudflare_worker_client::types::{
+        ActiveSessionRef,
+        Limits,
+    };
     use serde_json::{
         json,
         Value,
@@ -1591,6 +1761,102 @@ mod tests {
         assert_eq!(requests[10].path, "/sessions/cf-session-commands/close");
     }
 
+    #[tokio::test]
+    async fn set_commands_only_toggle_when_the_worker_state_differs() {
+        let state_response = |muted, background_blur| {
+            MockResponse::json(
+                200,
+                json!({
+                    "ok": true,
+                    "sessionId": "cf-session-set",
+                    "state": worker_state_json(true, muted, false, false, true, "none", "none", background_blur),
+                    "log": [],
+                }),
+            )
+        };
+        let responses = VecDeque::from(vec![
+            state_response(false, false),
+            state_response(false, false),
+            state_response(true, false),
+            state_response(true, false),
+            state_response(true, false),
+            state_response(true, true),
+            MockResponse::json(
+                200,
+                json!({
+                    "ok": true,
+                    "sessionId": "cf-session-set",
+                    "log": [],
+                }),
+            ),
+        ]);
+        let (base_url, requests, server) = spawn_http_server(responses).await;
+        let cookie_manager = HyperSessionCookieManger::new(unique_temp_dir().join("cookies.json"));
+        let mut session = CloudflareSession::new_for_test(
+            launch_spec(ResolvedFrontendKind::HyperLite, &format!("{base_url}/room/demo")),
+            launch_options(false, FakeMedia::None),
+            CloudflareConfig {
+                base_url: Url::parse(&base_url).unwrap(),
+                request_timeout_seconds: 5,
+                session_timeout_ms: 120_000,
+                navigation_timeout_ms: 30_000,
+                selector_timeout_ms: 10_000,
+                debug: false,
+                health_poll_interval_ms: 60_000,
+            },
+            None,
+            cookie_manager,
+        );
+
+        session.start().await.unwrap();
+        session
+            .handle_command(ParticipantMessage::SetAudioEnabled(false))
+            .await
+            .unwrap();
+        assert!(session.refresh_state().await.unwrap().muted);
+        session
+            .handle_command(ParticipantMessage::SetAudioEnabled(false))
+            .await
+            .unwrap();
+        session.handle_command(ParticipantMessage::SetBlur(true)).await.unwrap();
+        assert!(session.refresh_state().await.unwrap().background_blur);
+        session.close().await.unwrap();
+        server.abort();
+
+        let requests = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| {
+                let body = serde_json::from_str::<Value>(&request.body).unwrap_or(Value::Null);
+                (request.method.clone(), request.path.clone(), body)
+            })
+            .collect::<Vec<_>>();
+        let state = (
+            "GET".to_string(),
+            "/sessions/cf-session-set/state".to_string(),
+            Value::Null,
+        );
+        let command = |body| {
+            (
+                "POST".to_string(),
+                "/sessions/cf-session-set/commands".to_string(),
+                body,
+            )
+        };
+        assert_eq!(
+            requests[1..6],
+            [
+                state.clone(),
+                command(json!({ "type": "toggle-audio" })),
+                state.clone(),
+                state,
+                command(json!({ "type": "toggle-background-blur" })),
+            ]
+        );
+        assert_eq!(requests[6].1, "/sessions/cf-session-set/close");
+    }
+
     #[tokio::test]
     async fn start_normalizes_webtransport_to_webrtc_for_cloudflare() {
         let captured_logs = CapturedLogs::new();
@@ -1765,11 +2031,109 @@ mod tests {
         assert_eq!(requests[1].path, "/sessions/cf-session-terminated/keep-alive");
     }
 
+    #[tokio::test]
+    async fn wait_for_start_capacity_polls_worker_limits_until_a_browser_is_available() {
+        let responses = VecDeque::from(vec![
+            MockResponse::json(
+                200,
+                json!({
+                    "ok": true,
+                    "limits": {
+                        "activeSessions": [{ "id": "cf-session-busy" }],
+                        "maxConcurrentSessions": 1,
+                        "allowedBrowserAcquisitions": 0,
+                        "timeUntilNextAllowedBrowserAcquisition": 5
+                    },
+                    "docs": limits_docs_json(),
+                }),
+            ),
+            MockResponse::json(
+                200,
+                json!({
+                    "ok": true,
+                    "limits": {
+                        "activeSessions": [],
+                        "maxConcurrentSessions": 1,
+                        "allowedBrowserAcquisitions": 1,
+                        "timeUntilNextAllowedBrowserAcquisition": 0
+                    },
+                    "docs": limits_docs_json(),
+                }),
+            ),
+        ]);
+        let (base_url, requests, server) = spawn_http_server(responses).await;
+        let cookie_manager = HyperSessionCookieManger::new(unique_temp_dir().join("cookies.json"));
+        let mut session = CloudflareSession::new_for_test(
+            launch_spec(ResolvedFrontendKind::HyperLite, &format!("{base_url}/room/demo")),
+            CloudflareLaunchOptions {
+                worker_limits: true,
+                ..launch_options(true, FakeMedia::None)
+            },
+            CloudflareConfig {
+                base_url: Url::parse(&base_url).unwrap(),
+                request_timeout_seconds: 5,
+                session_timeout_ms: 120_000,
+                navigation_timeout_ms: 30_000,
+                selector_timeout_ms: 10_000,
+                debug: false,
+                health_poll_interval_ms: 60_000,
+            },
+            None,
+            cookie_manager,
+        );
+
+        tokio::time::timeout(Duration::from_secs(1), session.wait_for_start_capacity())
+            .await
+            .expect("timed out waiting for worker capacity")
+            .unwrap();
+        server.abort();
+
+        let requests = requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .all(|request| request.method == "GET" && request.path == "/limits"));
+    }
+
+    #[test]
+    fn worker_capacity_wait_respects_session_and_acquisition_limits() {
+        let limits = |active: usize, max: f64, allowed: f64, retry_after_ms: f64| Limits {
+            active_sessions: (0..active)
+                .map(|index| ActiveSessionRef {
+                    id: format!("cf-session-{index}"),
+                })
+                .collect(),
+            max_concurrent_sessions: max,
+            allowed_browser_acquisitions: allowed,
+            time_until_next_allowed_browser_acquisition: retry_after_ms,
+        };
+
+        assert_eq!(worker_capacity_wait(&limits(1, 2.0, 1.0, 0.0)), None);
+        assert_eq!(
+            worker_capacity_wait(&limits(2, 2.0, 1.0, 0.0)),
+            Some(WORKER_CAPACITY_POLL_INTERVAL)
+        );
+        assert_eq!(
+            worker_capacity_wait(&limits(0, 2.0, 0.0, 1_500.0)),
+            Some(Duration::from_millis(1_500))
+        );
+    }
+
+    fn limits_docs_json() -> Value {
+        json!({
+            "activeSessions": "active",
+            "maxConcurrentSessions": "max",
+            "allowedBrowserAcquisitions": "allowed",
+            "timeUntilNextAllowedBrowserAcquisition": "wait"
+        })
+    }
+
     fn launch_options(headless: bool, fake_media: FakeMedia) -> CloudflareLaunchOptions {
         CloudflareLaunchOptions {
             headless,
             fake_media,
             browser_logs: false,
+            worker_limits: false,
         }
     }
 
@@ -1782,6 +2146,7 @@ mod tests {
                 audio_enabled: true,
                 video_enabled: true,
                 screenshare_enabled: false,
+                screenshare_source: Default::default(),
                 auto_gain_control: true,
                 noise_suppression: NoiseSuppression::RNNoise,
                 transport: TransportMode::WebRTC,
@@ -1789,6 +2154,11 @@ mod tests {
                 video_constraint_subscribe: VideoConstraint::None,
                 video_max_concurrent_tracks: None,
                 blur: true,
+                network: Default::default(),
+                device: Default::default(),
+                fake_media: Default::default(),
+                webrtc_stats_interval: None,
+                received_media_timeout: None,
             },
         }
     }
@@ -1832,8 +2202,10 @@ mod tests {
         ParticipantState {
             username: String::new(),
             running: true,
+            pending: false,
             warning: None,
             joined,
+            call_status: Default::default(),
             muted,
             video_activated,
             auto_gain_control,
@@ -1844,6 +2216,14 @@ mod tests {
             video_max_concurrent_tracks: None,
             background_blur,
             screenshare_activated,
+            network: Default::default(),
+            device: Default::default(),
+            fake_media: Default::default(),
+            webrtc_stats: None,
+            received_media: None,
+            glass_to_glass: Vec::new(),
+            recording: false,
+            last_command: None,
         }
     }
 
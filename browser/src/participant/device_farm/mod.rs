@@ -9,10 +9,12 @@ use crate::{
     },
     participant::{
         frontend::{
+            install_fake_media,
             FrontendAuth,
             FrontendAutomation,
             FrontendContext,
             FrontendKindBuilder,
+            InjectedFakeMedia,
         },
         shared::{
             browser_log::{
@@ -29,12 +31,18 @@ use crate::{
             ParticipantDriverSession,
             ParticipantLaunchSpec,
             ParticipantState,
+            PhaseTimings,
+            StartPhase,
+            WebRtcStatsSample,
         },
     },
 };
 pub use aws_sdk_devicefarm::types::TestGridSessionStatus;
 use client_simulator_config::{
-    media::FakeMedia,
+    media::{
+        FakeMedia,
+        ScreenshareSource,
+    },
     DeviceFarmConfig,
 };
 pub use control::{
@@ -183,6 +191,7 @@ pub(super) struct DeviceFarmSession {
     termination_rx: watch::Receiver<Option<DriverTermination>>,
     poller_shutdown_tx: Option<oneshot::Sender<()>>,
     poller_task: Option<JoinHandle<()>>,
+    timings: PhaseTimings,
 }
 
 #[allow(dead_code)]
@@ -214,6 +223,7 @@ impl DeviceFarmSession {
             termination_rx,
             poller_shutdown_tx: None,
             poller_task: None,
+            timings: PhaseTimings::default(),
         }
     }
 
@@ -222,17 +232,37 @@ impl DeviceFarmSession {
     }
 
     fn log_backend_limitations(&self) {
-        if matches!(self.launch_options.fake_media, FakeMedia::FileOrUrl(_)) {
+        if matches!(
+            self.launch_options.fake_media,
+            FakeMedia::FileOrUrl(_) | FakeMedia::NoiseMix(_)
+        ) && InjectedFakeMedia::camera_source(&self.launch_options.fake_media, &self.launch_spec.username).is_none()
+        {
+            self.log_message(
+                "warn",
+                "Device Farm backend cannot use local fake-media files or noise mixes; using the synthetic fake device instead",
+            );
+        }
+
+        if let source @ ScreenshareSource::Tab(_) = &self.launch_spec.settings.screenshare_source {
             self.log_message(
                 "warn",
-                "Device Farm backend cannot use a local fake-media file/URL; using the synthetic fake device instead",
+                format!("Device Farm backend cannot open the screenshare tab `{source}`; sharing the synthetic screen instead"),
             );
         }
     }
 
+    /// URLs and test patterns are served by a page script, Device Farm has no access to local files.
+    fn injected_fake_media(&self) -> Option<InjectedFakeMedia> {
+        InjectedFakeMedia::for_participant(
+            Some(&self.launch_options.fake_media),
+            &self.launch_spec.settings.screenshare_source,
+            &self.launch_spec.username,
+        )
+    }
+
     fn build_capabilities(config: &DeviceFarmConfig, browser_logs: bool) -> Result<ChromeCapabilities> {
         let mut caps = DesiredCapabilities::chrome();
-        // Synthetic fake media only. Device Farm has no access to local fake-media files.
+        // Synthetic fake media unless the fake media script replaces it.
         caps.add_arg("--use-fake-ui-for-media-stream")?;
         caps.add_arg("--use-fake-device-for-media-stream")?;
         caps.insert_base_capability(
@@ -290,19 +320,33 @@ impl DeviceFarmSession {
                 self.config.project_arn
             ),
         );
-        let driver = Self::connect(
-            Arc::clone(&self.api),
-            self.config.clone(),
-            self.launch_options.browser_logs,
-        )
-        .await?;
+        let driver = self
+            .timings
+            .time(
+                StartPhase::BrowserLaunch,
+                Self::connect(
+                    Arc::clone(&self.api),
+                    self.config.clone(),
+                    self.launch_options.browser_logs,
+                ),
+            )
+            .await?;
         self.webdriver = Some(driver.clone());
         let webdriver_driver = WebDriverDriver::new(driver);
+        if let Some(media) = self.injected_fake_media() {
+            if let Err(err) = install_fake_media(&webdriver_driver, &media).await {
+                self.log_message(
+                    "warn",
+                    format!("Failed to inject fake media, using the synthetic fake device instead: {err:#}"),
+                );
+            }
+        }
 
         let auth = self.auth.take().context("device farm auth already consumed")?;
         let context = FrontendContext {
             launch_spec: self.launch_spec.clone(),
             driver: Box::new(webdriver_driver),
+            timings: self.timings.clone(),
         };
         let mut automation = FrontendKindBuilder::build(context, auth).await?;
 
@@ -603,6 +647,28 @@ impl ParticipantDriverSession for DeviceFarmSession {
             format!("Device Farm browser session stopped responding while refreshing state: {err}"),
         ))
     }
+
+    fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<Option<WebRtcStatsSample>>> {
+        async move {
+            match self.automation.as_mut() {
+                Some(automation) => automation.sample_webrtc_stats().await.map(Some),
+                None => Ok(None),
+            }
+        }
+        .boxed()
+    }
+
+    fn webrtc_stats_interval(&self) -> Option<Duration> {
+        self.launch_spec.settings.webrtc_stats_interval
+    }
+
+    fn received_media_timeout(&self) -> Option<Duration> {
+        self.launch_spec.settings.received_media_timeout
+    }
+
+    fn take_phase_timings(&mut self) -> Vec<(StartPhase, Duration)> {
+        self.timings.take()
+    }
 }
 
 #[cfg(test)]
@@ -709,6 +775,7 @@ mod tests {
             termination_rx,
             poller_shutdown_tx: None,
             poller_task: None,
+            timings: PhaseTimings::default(),
         };
 
         session.close_inner().await.unwrap();
@@ -761,6 +828,14 @@ mod tests {
             }
             .boxed()
         }
+
+        fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<WebRtcStatsSample>> {
+            async { Ok(WebRtcStatsSample::default()) }.boxed()
+        }
+
+        fn state_changes(&self) -> Option<tokio::sync::watch::Receiver<()>> {
+            None
+        }
     }
 
     struct UnusedTestGridApi;
@@ -9,10 +9,12 @@ use futures::{
 };
 use std::time::Duration;
 use thirtyfour::{
+    extensions::cdp::ChromeDevTools,
     By,
     Cookie,
     WebDriver,
 };
+use tokio::sync::watch;
 
 /// `BrowserDriver` implementation backed by a remote Selenium `WebDriver`
 /// (AWS Device Farm Test Grid endpoint).
@@ -152,4 +154,25 @@ impl BrowserDriver for WebDriverDriver {
         }
         .boxed()
     }
+
+    fn add_init_script(&self, script: &str) -> BoxFuture<'_, Result<()>> {
+        let script = script.to_owned();
+        async move {
+            // Classic WebDriver has no init scripts, chromedriver forwards the CDP command.
+            ChromeDevTools::new(self.driver.handle.clone())
+                .execute_cdp_with_params(
+                    "Page.addScriptToEvaluateOnNewDocument",
+                    serde_json::json!({ "source": script }),
+                )
+                .await
+                .context("failed to add init script")?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn add_binding(&self, _name: &str) -> BoxFuture<'_, Result<Option<watch::Receiver<()>>>> {
+        // Classic WebDriver cannot deliver CDP events, the Device Farm session polls the state instead.
+        async { Ok(None) }.boxed()
+    }
 }
@@ -11,7 +11,10 @@ use crate::{
         BorrowedCookie,
         HyperSessionCookieManger,
     },
-    participant::shared::ResolvedFrontendKind,
+    participant::shared::{
+        ResolvedFrontendKind,
+        StartPhase,
+    },
 };
 use eyre::Result;
 
@@ -50,8 +53,12 @@ impl FrontendKindBuilder {
                 let cookie = if let Some(cookie) = cookie {
                     cookie
                 } else {
-                    cookie_manager
-                        .fetch_new_cookie(context.launch_spec.base_url(), context.participant_name())
+                    context
+                        .timings
+                        .time(
+                            StartPhase::Auth,
+                            cookie_manager.fetch_new_cookie(context.launch_spec.base_url(), context.participant_name()),
+                        )
                         .await?
                 };
                 Ok(Box::new(ParticipantInner::new(context, cookie)))
@@ -0,0 +1,74 @@
+//! Call conditions shown by the frontends, detected through `BrowserDriver::eval`.
+//!
+//! Only alerts, dialogs, toasts and status regions are searched for the wording of the frontends' banners, so chat
+//! messages and participant names never match. Denied camera or microphone permissions are also read from the
+//! Permissions API. When several conditions show at once, the one that ends the call wins.
+
+use super::{
+    super::shared::CallStatus,
+    driver::BrowserDriver,
+};
+use eyre::{
+    Context as _,
+    Result,
+};
+
+const CALL_STATUS_DETECT: &str = r#"
+const PATTERNS = [
+    ['removed_by_host', ['removed you from', 'you have been removed', 'you were removed', 'you have been kicked']],
+    ['meeting_ended', ['meeting has ended', 'meeting ended', 'call has ended', 'space has ended', 'ended the meeting for everyone']],
+    ['media_permission_denied', ['permission denied', 'permission was denied', 'camera is blocked', 'microphone is blocked', 'allow access to your camera', 'allow access to your microphone']],
+    ['reconnecting', ['reconnecting', 'connection lost', 'trying to reconnect']],
+    ['transport_fallback', ['falling back to webrtc', 'switched to webrtc', 'webtransport is not available', 'webtransport unavailable']],
+];
+const SELECTOR = '[role="alert"], [role="alertdialog"], [role="dialog"], [role="status"], [data-sonner-toast], [aria-live="assertive"]';
+const text = [...document.querySelectorAll(SELECTOR)]
+    .map((element) => (element.innerText || element.textContent || '').toLowerCase())
+    .join('\n');
+const shown = PATTERNS.find(([, phrases]) => phrases.some((phrase) => text.includes(phrase)));
+const permissionDenied = (name) => navigator.permissions
+    ? navigator.permissions.query({ name }).then((status) => status.state === 'denied', () => false)
+    : Promise.resolve(false);
+return Promise.all([permissionDenied('camera'), permissionDenied('microphone')]).then((denied) => {
+    const status = shown ? shown[0] : 'normal';
+    if (denied.some(Boolean) && !['removed_by_host', 'meeting_ended'].includes(status)) return 'media_permission_denied';
+    return status;
+});
+"#;
+
+pub(super) async fn detect_call_status(driver: &dyn BrowserDriver) -> Result<CallStatus> {
+    let value = driver.eval(CALL_STATUS_DETECT, None).await?;
+    serde_json::from_value(value).context("failed to read call status from eval result")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::commands::tests::RecordingDriver,
+        *,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn reads_the_status_shown_by_the_page() {
+        let driver = RecordingDriver::with_result(json!("removed_by_host"));
+
+        assert_eq!(detect_call_status(&driver).await.unwrap(), CallStatus::RemovedByHost);
+        assert_eq!(driver.calls(), vec![(CALL_STATUS_DETECT.to_string(), None)]);
+    }
+
+    #[test]
+    fn every_detected_status_deserializes() {
+        for status in [
+            "normal",
+            "removed_by_host",
+            "meeting_ended",
+            "media_permission_denied",
+            "reconnecting",
+            "transport_fallback",
+        ] {
+            assert!(CALL_STATUS_DETECT.contains(&format!("'{status}'")));
+            serde_json::from_value::<CallStatus>(json!(status)).unwrap();
+        }
+    }
+}
@@ -127,24 +127,41 @@ pub(in crate::participant::frontend) mod tests {
         sync::Mutex,
         time::Duration,
     };
+    use tokio::sync::watch;
 
     #[derive(Default)]
     pub(in crate::participant::frontend) struct RecordingDriver {
         calls: Mutex<Vec<(String, Option<serde_json::Value>)>>,
         next_result: Mutex<serde_json::Value>,
+        init_scripts: Mutex<Vec<String>>,
+        bindings: Mutex<Vec<String>>,
     }
 
     impl RecordingDriver {
-        fn with_result(value: serde_json::Value) -> Self {
+        pub(in crate::participant::frontend) fn with_result(value: serde_json::Value) -> Self {
             Self {
                 calls: Mutex::new(Vec::new()),
                 next_result: Mutex::new(value),
+                init_scripts: Mutex::new(Vec::new()),
+                bindings: Mutex::new(Vec::new()),
             }
         }
 
-        fn calls(&self) -> Vec<(String, Option<serde_json::Value>)> {
+        pub(in crate::participant::frontend) fn set_result(&self, value: serde_json::Value) {
+            *self.next_result.lock().unwrap() = value;
+        }
+
+        pub(in crate::participant::frontend) fn calls(&self) -> Vec<(String, Option<serde_json::Value>)> {
             self.calls.lock().unwrap().clone()
         }
+
+        pub(in crate::participant::frontend) fn init_scripts(&self) -> Vec<String> {
+            self.init_scripts.lock().unwrap().clone()
+        }
+
+        pub(in crate::participant::frontend) fn bindings(&self) -> Vec<String> {
+            self.bindings.lock().unwrap().clone()
+        }
     }
 
     impl BrowserDriver for RecordingDriver {
@@ -181,6 +198,16 @@ pub(in crate::participant::frontend) mod tests {
         fn set_cookie(&self, _domain: &str, _name: &str, _value: &str) -> BoxFuture<'_, Result<()>> {
             async { Ok(()) }.boxed()
         }
+
+        fn add_init_script(&self, script: &str) -> BoxFuture<'_, Result<()>> {
+            self.init_scripts.lock().unwrap().push(script.to_string());
+            async { Ok(()) }.boxed()
+        }
+
+        fn add_binding(&self, name: &str) -> BoxFuture<'_, Result<Option<watch::Receiver<()>>>> {
+            self.bindings.lock().unwrap().push(name.to_string());
+            async { Ok(Some(watch::channel(()).1)) }.boxed()
+        }
     }
 
     #[tokio::test]
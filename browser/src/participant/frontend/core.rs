@@ -4,7 +4,10 @@ use super::{
     super::shared::{
         messages::ParticipantMessage,
         ParticipantState,
+        StartPhase,
+        WebRtcStatsSample,
     },
+    call_status::detect_call_status,
     commands::{
         get_auto_gain_control,
         get_background_blur,
@@ -26,7 +29,14 @@ use super::{
         FrontendAutomation,
         FrontendContext,
     },
+    glass_to_glass::sample_glass_to_glass,
+    received_media::sample_received_media,
     selectors::classic,
+    state_changes::observe_state_changes,
+    stats::{
+        install_peer_connection_tracker,
+        sample_webrtc_stats,
+    },
 };
 use crate::auth::BorrowedCookie;
 use client_simulator_config::{
@@ -35,6 +45,8 @@ use client_simulator_config::{
     VideoConstraint,
 };
 use eyre::{
+    bail,
+    eyre,
     Context as _,
     Result,
 };
@@ -42,18 +54,27 @@ use futures::{
     future::BoxFuture,
     FutureExt as _,
 };
-use std::time::Duration;
+use std::time::{
+    Duration,
+    Instant,
+};
+use tokio::sync::watch;
 
 /// Local frontend automation for the hyper.video ("hyper core") UI.
 #[derive(Debug)]
 pub(super) struct ParticipantInner {
     context: FrontendContext,
     auth: BorrowedCookie,
+    state_changes: Option<watch::Receiver<()>>,
 }
 
 impl ParticipantInner {
     pub(super) fn new(context: FrontendContext, auth: BorrowedCookie) -> Self {
-        Self { context, auth }
+        Self {
+            context,
+            auth,
+            state_changes: None,
+        }
     }
 
     async fn set_cookie(&self) -> Result<()> {
@@ -85,13 +106,27 @@ impl ParticipantInner {
         self.set_cookie().await?;
 
         self.context
-            .driver
-            .goto(self.context.launch_spec.session_url.as_str())
+            .timings
+            .time(
+                StartPhase::Navigation,
+                self.context.driver.goto(self.context.launch_spec.session_url.as_str()),
+            )
             .await
             .context("failed to wait for navigation response")?;
 
         self.context.log_message("debug", "Navigated to page");
 
+        if self.context.launch_spec.settings.webrtc_stats_interval.is_some() {
+            if let Err(err) = install_peer_connection_tracker(self.context.driver.as_ref()).await {
+                self.context.log_message(
+                    "warn",
+                    format!("Failed to track peer connections for WebRTC stats: {err}"),
+                );
+            }
+        }
+        observe_state_changes(&self.context, &mut self.state_changes).await;
+
+        let lobby_started = Instant::now();
         self.context
             .driver
             .wait_for(classic::NAME_INPUT, Duration::from_secs(30))
@@ -122,18 +157,24 @@ impl ParticipantInner {
                 "Failed to apply settings before joining: {err}"
             );
         }
+        self.context.timings.record(StartPhase::Lobby, lobby_started.elapsed());
 
         self.context
-            .driver
-            .click(classic::JOIN_BUTTON)
+            .timings
+            .time(StartPhase::JoinClick, self.context.driver.click(classic::JOIN_BUTTON))
             .await
             .context("failed to click join button")?;
 
         self.context.log_message("debug", "Clicked on the join button");
 
         self.context
-            .driver
-            .wait_for(classic::LEAVE_BUTTON, Duration::from_secs(30))
+            .timings
+            .time(
+                StartPhase::LeaveButtonVisible,
+                self.context
+                    .driver
+                    .wait_for(classic::LEAVE_BUTTON, Duration::from_secs(30)),
+            )
             .await
             .context("We haven't joined the space, cannot find the leave button")?;
 
@@ -228,6 +269,38 @@ impl ParticipantInner {
             .map(|_| ())
     }
 
+    async fn set_audio_enabled_inner(&self, enabled: bool) -> Result<()> {
+        if self.button_active(classic::MUTE_BUTTON, "microphone").await? != enabled {
+            self.toggle_audio_inner().await?;
+        }
+        Ok(())
+    }
+
+    async fn set_video_enabled_inner(&self, enabled: bool) -> Result<()> {
+        if self.button_active(classic::VIDEO_BUTTON, "camera").await? != enabled {
+            self.toggle_video_inner().await?;
+        }
+        Ok(())
+    }
+
+    async fn set_screen_share_enabled_inner(&self, enabled: bool) -> Result<()> {
+        if self.button_active(classic::SCREEN_SHARE_BUTTON, "screen share").await? != enabled {
+            self.toggle_screen_share_inner().await?;
+        }
+        Ok(())
+    }
+
+    /// Whether the control behind `selector` is switched on, read from the button right before changing it.
+    async fn button_active(&self, selector: &str, control: &str) -> Result<bool> {
+        let value = self
+            .context
+            .driver
+            .attribute(selector, "data-test-state")
+            .await
+            .with_context(|| format!("Could not read the state of the {control} button"))?;
+        decode_test_state(value).ok_or_else(|| eyre!("The {control} button does not expose its state"))
+    }
+
     async fn set_video_constraint_publish_webcam_inner(&self, value: VideoConstraint) -> Result<()> {
         info!(participant = %self.participant_name(), "Changing outgoing webcam video constraint to {value}");
         set_video_constraint_publish_webcam(self.context.driver.as_ref(), value)
@@ -269,6 +342,18 @@ impl ParticipantInner {
         Ok(())
     }
 
+    async fn set_auto_gain_control_inner(&self, value: bool) -> Result<()> {
+        set_auto_gain_control(self.context.driver.as_ref(), value)
+            .await
+            .context("Failed to set auto gain control")
+    }
+
+    async fn set_background_blur_inner(&self, value: bool) -> Result<()> {
+        set_background_blur(self.context.driver.as_ref(), value)
+            .await
+            .context("Failed to set background blur")
+    }
+
     async fn toggle_background_blur_inner(&self) -> Result<()> {
         let driver = self.context.driver.as_ref();
         let background_blur = get_background_blur(driver).await?;
@@ -336,6 +421,11 @@ impl ParticipantInner {
             state.background_blur = blur;
         }
 
+        if let Ok(status) = detect_call_status(driver).await {
+            state.call_status = status;
+            state.warning = status.warning();
+        }
+
         Ok(state)
     }
 }
@@ -370,6 +460,15 @@ impl FrontendAutomation for ParticipantInner {
                 }
                 ParticipantMessage::SetNoiseSuppression(value) => self.set_noise_suppression_inner(value).await,
                 ParticipantMessage::ToggleBackgroundBlur => self.toggle_background_blur_inner().await,
+                ParticipantMessage::SetAudioEnabled(value) => self.set_audio_enabled_inner(value).await,
+                ParticipantMessage::SetVideoEnabled(value) => self.set_video_enabled_inner(value).await,
+                ParticipantMessage::SetScreenshare(value) => self.set_screen_share_enabled_inner(value).await,
+                ParticipantMessage::SetBlur(value) => self.set_background_blur_inner(value).await,
+                ParticipantMessage::SetAutoGainControl(value) => self.set_auto_gain_control_inner(value).await,
+                ParticipantMessage::SetNetwork(_) => bail!("Network emulation needs a local Chromium browser"),
+                ParticipantMessage::StartRecording | ParticipantMessage::StopRecording => {
+                    bail!("Recording received media needs a local Chromium browser")
+                }
             }
         }
         .boxed()
@@ -378,4 +477,19 @@ impl FrontendAutomation for ParticipantInner {
     fn refresh_state(&mut self) -> BoxFuture<'_, Result<ParticipantState>> {
         async move { self.refresh_state_inner().await }.boxed()
     }
+
+    fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<WebRtcStatsSample>> {
+        async move {
+            let driver = self.context.driver.as_ref();
+            let mut sample = sample_webrtc_stats(driver).await?;
+            sample.received_media = Some(sample_received_media(driver).await?);
+            sample.glass_to_glass = sample_glass_to_glass(driver).await?;
+            Ok(sample)
+        }
+        .boxed()
+    }
+
+    fn state_changes(&self) -> Option<watch::Receiver<()>> {
+        self.state_changes.clone()
+    }
 }
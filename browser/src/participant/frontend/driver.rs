@@ -5,10 +5,13 @@ use super::super::shared::{
     },
     ParticipantLaunchSpec,
     ParticipantState,
+    PhaseTimings,
+    WebRtcStatsSample,
 };
 use eyre::Result;
 use futures::future::BoxFuture;
 use std::time::Duration;
+use tokio::sync::watch;
 
 /// Driver-agnostic browser operations used by the Hyper frontend automation.
 ///
@@ -33,12 +36,20 @@ pub(in crate::participant) trait BrowserDriver: Send + Sync {
     /// Set a cookie for `domain`. Drivers that require being on-origin first
     /// (WebDriver) must navigate to the origin before setting it.
     fn set_cookie(&self, domain: &str, name: &str, value: &str) -> BoxFuture<'_, Result<()>>;
+    /// Run `script` in every document loaded from now on, before the page's own scripts.
+    fn add_init_script(&self, script: &str) -> BoxFuture<'_, Result<()>>;
+    /// Expose `window[name](payload)` to every document, now and loaded later. Every call marks the returned receiver
+    /// as changed. `Ok(None)` if the driver cannot be called back from the page (WebDriver), which then has to be
+    /// polled instead.
+    fn add_binding(&self, name: &str) -> BoxFuture<'_, Result<Option<watch::Receiver<()>>>>;
 }
 
 /// Context shared by every frontend automation, parameterised over the driver.
 pub(in crate::participant) struct FrontendContext {
     pub(in crate::participant) launch_spec: ParticipantLaunchSpec,
     pub(in crate::participant) driver: Box<dyn BrowserDriver>,
+    /// Start phases observed by the frontend, read by the owning session.
+    pub(in crate::participant) timings: PhaseTimings,
 }
 
 impl std::fmt::Debug for FrontendContext {
@@ -65,6 +76,10 @@ pub(in crate::participant) trait FrontendAutomation: Send {
     fn leave(&mut self) -> BoxFuture<'_, Result<()>>;
     fn handle_command(&mut self, message: ParticipantMessage) -> BoxFuture<'_, Result<()>>;
     fn refresh_state(&mut self) -> BoxFuture<'_, Result<ParticipantState>>;
+    fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<WebRtcStatsSample>>;
+    /// Changed whenever the page reports a change that may affect the state, see `frontend::state_changes`. `None`
+    /// before joining or if the driver cannot report changes.
+    fn state_changes(&self) -> Option<watch::Receiver<()>>;
 }
 
 /// Decode the legacy `data-test-state="true"|"false"` attribute.
@@ -116,6 +131,7 @@ mod tests {
                 app_config: Config::default(),
             }),
             driver: Box::new(RecordingDriver::default()),
+            timings: Default::default(),
         };
         let output = Arc::new(Mutex::new(Vec::new()));
         let writer_output = Arc::clone(&output);
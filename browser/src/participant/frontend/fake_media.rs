@@ -0,0 +1,299 @@
+//! Fake media served by the page itself, for backends that cannot pass files to Chrome.
+//!
+//! An init script replaces `getUserMedia` and `getDisplayMedia` before the frontend loads. Camera and microphone
+//! play a URL through a hidden `<video>` element or a generated canvas/WebAudio test pattern, so the same fake media
+//! choice behaves the same on every backend that can install init scripts. The screen test pattern is served the same
+//! way. Sources left out keep the browser's own capture. Cloudflare worker sessions cannot install init scripts and
+//! keep the worker's media.
+//!
+//! URLs are loaded with `crossOrigin = 'anonymous'`, so their server has to send CORS headers for the page origin.
+//!
+//! The test pattern carries the sender's clock and id as a block code for glass-to-glass measurements, see
+//! `frontend::glass_to_glass`.
+
+use super::{
+    super::shared::media_sender_id,
+    driver::BrowserDriver,
+};
+use client_simulator_config::media::{
+    FakeMedia,
+    FakeMediaFileOrUrl,
+    ScreenshareSource,
+};
+use eyre::{
+    Context as _,
+    Result,
+};
+use serde::Serialize;
+
+const FAKE_MEDIA_SHIM: &str = r#"
+(() => {
+    const sources = window.__hyperSimulatorFakeMedia;
+    const mediaDevices = navigator.mediaDevices;
+    if (!sources || !mediaDevices || mediaDevices.__hyperSimulatorFakeMedia) return;
+    mediaDevices.__hyperSimulatorFakeMedia = true;
+
+    const WIDTH = 640;
+    const HEIGHT = 360;
+    const FPS = 30;
+    const MARKER_MS = 100;
+    // Block code along the top edge: the low 32 bits of `Date.now()`, the sender id and a checksum byte, most
+    // significant bit first. Must match `GLASS_TO_GLASS_SAMPLE`.
+    const CODE_COLUMNS = 24;
+    const CODE_ROWS = 3;
+    const CODE_ROW_HEIGHT = 14;
+
+    const drawCode = (context, senderId) => {
+        const now = Date.now() >>> 0;
+        const bytes = [now >>> 24, now >>> 16, now >>> 8, now, senderId >>> 24, senderId >>> 16, senderId >>> 8, senderId]
+            .map((byte) => byte & 0xff);
+        bytes.push(bytes.reduce((sum, byte) => sum + byte, 0) & 0xff);
+        const cellWidth = WIDTH / CODE_COLUMNS;
+        bytes.forEach((byte, byteIndex) => {
+            for (let bit = 0; bit < 8; bit += 1) {
+                const index = byteIndex * 8 + bit;
+                context.fillStyle = (byte >> (7 - bit)) & 1 ? '#ffffff' : '#000000';
+                context.fillRect(
+                    (index % CODE_COLUMNS) * cellWidth,
+                    Math.floor(index / CODE_COLUMNS) * CODE_ROW_HEIGHT,
+                    cellWidth,
+                    CODE_ROW_HEIGHT,
+                );
+            }
+        });
+    };
+
+    // Colour bars, the label, the frame counter and the elapsed time under the block code. A marker square lights up
+    // at the start of every wall clock second while the audio beeps, like the native test pattern.
+    const patternStream = (source, withAudio) => {
+        const canvas = document.createElement('canvas');
+        canvas.width = WIDTH;
+        canvas.height = HEIGHT;
+        const context = canvas.getContext('2d');
+        const colors = ['#c0c0c0', '#c0c000', '#00c0c0', '#00c000', '#c000c0', '#c00000', '#0000c0'];
+        const started = performance.now();
+        let frame = 0;
+        const draw = () => {
+            const elapsedMs = performance.now() - started;
+            colors.forEach((color, index) => {
+                context.fillStyle = color;
+                context.fillRect((index * WIDTH) / colors.length, 0, WIDTH / colors.length + 1, HEIGHT);
+            });
+            context.fillStyle = 'rgba(0, 0, 0, 0.6)';
+            context.fillRect(0, HEIGHT / 2 - 60, WIDTH, 120);
+            context.fillStyle = '#ffffff';
+            context.textAlign = 'center';
+            context.font = 'bold 40px sans-serif';
+            context.fillText(source.label, WIDTH / 2, HEIGHT / 2 - 10);
+            context.font = '24px monospace';
+            context.fillText(`frame ${frame}  ${(elapsedMs / 1000).toFixed(2)}s`, WIDTH / 2, HEIGHT / 2 + 35);
+            context.fillStyle = Date.now() % 1000 < MARKER_MS ? '#ffffff' : '#000000';
+            context.fillRect(WIDTH - 50, HEIGHT - 50, 40, 40);
+            drawCode(context, source.senderId);
+            frame += 1;
+        };
+        draw();
+        setInterval(draw, 1000 / FPS);
+        const stream = canvas.captureStream(FPS);
+
+        if (withAudio) {
+            const audio = new AudioContext();
+            const oscillator = audio.createOscillator();
+            const gain = audio.createGain();
+            const destination = audio.createMediaStreamDestination();
+            // The pitch tells participants apart by ear, and glass-to-glass measurements whose beep cannot be matched
+            // to a video tile.
+            oscillator.frequency.value = 400 + (source.senderId % 800);
+            gain.gain.value = 0;
+            oscillator.connect(gain).connect(destination);
+            oscillator.start();
+            const scheduleBeep = () => {
+                const untilSecondMs = 1000 - (Date.now() % 1000);
+                const at = audio.currentTime + untilSecondMs / 1000;
+                gain.gain.setValueAtTime(0.5, at);
+                gain.gain.setValueAtTime(0, at + MARKER_MS / 1000);
+                setTimeout(scheduleBeep, untilSecondMs + MARKER_MS);
+            };
+            scheduleBeep();
+            audio.resume().catch(() => {});
+            destination.stream.getAudioTracks().forEach((track) => stream.addTrack(track));
+        }
+        return stream;
+    };
+
+    // Muted elements still capture their audio and may autoplay without a user gesture.
+    const urlStream = async (source) => {
+        const video = document.createElement('video');
+        video.crossOrigin = 'anonymous';
+        video.loop = true;
+        video.muted = true;
+        video.playsInline = true;
+        video.src = source.url;
+        await video.play();
+        return video.captureStream();
+    };
+
+    const streams = {};
+    const streamOf = (name, withAudio) => {
+        const source = sources[name];
+        if (!streams[name]) {
+            streams[name] = source.kind === 'url' ? urlStream(source) : Promise.resolve(patternStream(source, withAudio));
+        }
+        return streams[name];
+    };
+
+    const clonedTracks = (stream, constraints) => {
+        const tracks = [];
+        if (constraints && constraints.audio) tracks.push(...stream.getAudioTracks().map((track) => track.clone()));
+        if (constraints && constraints.video) tracks.push(...stream.getVideoTracks().map((track) => track.clone()));
+        if (!tracks.length) throw new DOMException('Requested device not found', 'NotFoundError');
+        return new MediaStream(tracks);
+    };
+
+    if (sources.screen) {
+        mediaDevices.getDisplayMedia = async (constraints) =>
+            clonedTracks(await streamOf('screen', false), { video: true, audio: constraints && constraints.audio });
+    }
+    if (!sources.camera) return;
+    mediaDevices.getUserMedia = async (constraints) => clonedTracks(await streamOf('camera', true), constraints);
+
+    // Device pickers should list the fake devices even when the browser has no real ones.
+    const enumerateDevices = mediaDevices.enumerateDevices.bind(mediaDevices);
+    mediaDevices.enumerateDevices = async () => {
+        const devices = (await enumerateDevices()).filter((device) => device.kind === 'audiooutput');
+        const fakeDevice = (kind, label) => ({ deviceId: 'hyper-simulator', groupId: 'hyper-simulator', kind, label });
+        return [
+            fakeDevice('audioinput', 'Hyper simulator microphone'),
+            fakeDevice('videoinput', 'Hyper simulator camera'),
+            ...devices,
+        ];
+    };
+})();
+"#;
+
+/// What the fake camera, microphone or screen of the page plays.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(in crate::participant) enum InjectedMediaSource {
+    /// Audio and video of a file the browser can fetch.
+    Url { url: String },
+    /// Generated test pattern showing `label`, its code and beep identify `sender_id`.
+    Pattern {
+        label: String,
+        #[serde(rename = "senderId")]
+        sender_id: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(in crate::participant) struct InjectedFakeMedia {
+    pub(in crate::participant) camera: Option<InjectedMediaSource>,
+    pub(in crate::participant) screen: Option<InjectedMediaSource>,
+}
+
+impl InjectedFakeMedia {
+    /// What the page serves to participant `username`, `None` if it serves nothing. Pass `media` only when the page
+    /// should replace Chrome's camera and microphone, see [`Self::camera_source`].
+    pub(in crate::participant) fn for_participant(
+        media: Option<&FakeMedia>,
+        screenshare: &ScreenshareSource,
+        username: &str,
+    ) -> Option<Self> {
+        let camera = media.and_then(|media| Self::camera_source(media, username));
+        let screen = match screenshare {
+            ScreenshareSource::TestPattern => Some(InjectedMediaSource::Pattern {
+                label: format!("{username} screen"),
+                sender_id: media_sender_id(username),
+            }),
+            ScreenshareSource::Builtin | ScreenshareSource::Tab(_) => None,
+        };
+        (camera.is_some() || screen.is_some()).then_some(Self { camera, screen })
+    }
+
+    /// How the page serves `media`. `None` for Chrome's builtin media and for media that only exists on this machine,
+    /// local files and noise mixes, which need Chrome's capture flags.
+    pub(in crate::participant) fn camera_source(media: &FakeMedia, username: &str) -> Option<InjectedMediaSource> {
+        match media {
+            FakeMedia::TestPattern => Some(InjectedMediaSource::Pattern {
+                label: username.to_string(),
+                sender_id: media_sender_id(username),
+            }),
+            FakeMedia::FileOrUrl(source) => match source.parse().ok()? {
+                FakeMediaFileOrUrl::Url(url) => Some(InjectedMediaSource::Url { url: url.to_string() }),
+                FakeMediaFileOrUrl::File(_) => None,
+            },
+            FakeMedia::None | FakeMedia::Builtin | FakeMedia::NoiseMix(_) => None,
+        }
+    }
+
+    fn script(&self) -> Result<String> {
+        let sources = serde_json::to_string(self).context("failed to encode injected fake media")?;
+        Ok(format!(
+            "window.__hyperSimulatorFakeMedia = {sources};\n{FAKE_MEDIA_SHIM}"
+        ))
+    }
+}
+
+/// Installs the fake media shim for every document loaded from now on. Must run before navigating to the space.
+pub(in crate::participant) async fn install_fake_media(
+    driver: &dyn BrowserDriver,
+    media: &InjectedFakeMedia,
+) -> Result<()> {
+    driver
+        .add_init_script(&media.script()?)
+        .await
+        .context("failed to install fake media script")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::commands::tests::RecordingDriver,
+        *,
+    };
+
+    #[test]
+    fn only_media_the_browser_can_reach_is_injected() {
+        assert_eq!(
+            InjectedFakeMedia::camera_source(&FakeMedia::TestPattern, "alice"),
+            Some(InjectedMediaSource::Pattern {
+                label: "alice".to_string(),
+                sender_id: 92_903_040,
+            })
+        );
+        assert_eq!(
+            InjectedFakeMedia::camera_source(&FakeMedia::from("https://share.dev.hyper.video/sp.mp4"), "alice"),
+            Some(InjectedMediaSource::Url {
+                url: "https://share.dev.hyper.video/sp.mp4".to_string()
+            })
+        );
+        assert_eq!(
+            InjectedFakeMedia::camera_source(&FakeMedia::from("/tmp/local.mp4"), "alice"),
+            None
+        );
+        assert_eq!(InjectedFakeMedia::camera_source(&FakeMedia::Builtin, "alice"), None);
+
+        let builtin = ScreenshareSource::Builtin;
+        assert!(InjectedFakeMedia::for_participant(Some(&FakeMedia::Builtin), &builtin, "alice").is_none());
+        let media = InjectedFakeMedia::for_participant(None, &ScreenshareSource::TestPattern, "alice").unwrap();
+        assert_eq!(media.camera, None);
+        assert!(media.screen.is_some());
+    }
+
+    #[tokio::test]
+    async fn installs_the_sources_ahead_of_the_shim() {
+        let driver = RecordingDriver::default();
+        let media =
+            InjectedFakeMedia::for_participant(Some(&FakeMedia::TestPattern), &ScreenshareSource::TestPattern, "alice")
+                .unwrap();
+
+        install_fake_media(&driver, &media).await.unwrap();
+
+        let scripts = driver.init_scripts();
+        assert_eq!(scripts.len(), 1);
+        assert!(scripts[0].starts_with(
+            r#"window.__hyperSimulatorFakeMedia = {"camera":{"kind":"pattern","label":"alice","senderId":92903040},"screen":{"kind":"pattern","label":"alice screen","senderId":92903040}};"#
+        ));
+        assert!(scripts[0].contains("mediaDevices.getUserMedia ="));
+    }
+}
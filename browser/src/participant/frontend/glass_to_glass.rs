@@ -0,0 +1,200 @@
+//! Glass-to-glass measurements through `BrowserDriver::eval`.
+//!
+//! Each remote video tile is decoded for the block code of the injected test pattern, see `frontend::fake_media`;
+//! its video latency is the receiver's clock minus the sender's clock in the code. Beeps start at every full second
+//! of the sender's clock, so a detector polling the remote audio tracks takes the audio latency from how far past the
+//! full second a beep starts. A beep is attributed to the sender decoded from the video of its track's stream or of
+//! the tile its audio element sits in. Beeps on tracks that match no tile fall back to the sender whose pitch is
+//! closest, among the senders seen in the video.
+
+use super::{
+    super::shared::GlassToGlassSample,
+    driver::BrowserDriver,
+};
+use eyre::{
+    Context as _,
+    Result,
+};
+
+const GLASS_TO_GLASS_SAMPLE: &str = r#"
+// Must match the test pattern in `FAKE_MEDIA_SHIM`.
+const WIDTH = 640;
+const HEIGHT = 360;
+const CODE_COLUMNS = 24;
+const CODE_ROWS = 3;
+const CODE_ROW_HEIGHT = 14;
+const CELL = 8;
+const MAX_LATENCY_MS = 60000;
+const ONSET_RMS = 0.05;
+const RELEASE_RMS = 0.02;
+const MAX_PITCH_ERROR_HZ = 30;
+
+const state = window.__hyperSimulatorGlassToGlass || (window.__hyperSimulatorGlassToGlass = {
+    canvas: document.createElement('canvas'),
+    detectors: new Map(),
+    onsets: [],
+    senders: new Set(),
+    videoSenders: new WeakMap(),
+    streamSenders: new Map(),
+    trackSenders: new Map(),
+    audio: null,
+});
+const connections = (window.__hyperSimulatorPeerConnections || []).filter((pc) => pc.connectionState !== 'closed');
+const remoteTracks = new Set();
+connections.forEach((pc) => pc.getReceivers().forEach((receiver) => {
+    const track = receiver.track;
+    if (!track || track.readyState !== 'live') return;
+    remoteTracks.add(track.id);
+    if (track.kind !== 'audio' || state.detectors.has(track.id)) return;
+    state.audio = state.audio || new AudioContext();
+    state.audio.resume().catch(() => {});
+    const analyser = state.audio.createAnalyser();
+    analyser.fftSize = 2048;
+    state.audio.createMediaStreamSource(new MediaStream([track])).connect(analyser);
+    state.detectors.set(track.id, {
+        analyser,
+        samples: new Float32Array(analyser.fftSize),
+        spectrum: new Float32Array(analyser.frequencyBinCount),
+        loud: false,
+    });
+}));
+if (!state.timer) {
+    state.timer = setInterval(() => {
+        for (const [trackId, detector] of state.detectors) {
+            detector.analyser.getFloatTimeDomainData(detector.samples);
+            const rms = Math.sqrt(detector.samples.reduce((sum, sample) => sum + sample * sample, 0) / detector.samples.length);
+            if (!detector.loud && rms > ONSET_RMS) {
+                detector.loud = true;
+                detector.analyser.getFloatFrequencyData(detector.spectrum);
+                let peak = 0;
+                detector.spectrum.forEach((level, bin) => {
+                    if (level > detector.spectrum[peak]) peak = bin;
+                });
+                const frequency = (peak * state.audio.sampleRate) / detector.analyser.fftSize;
+                state.onsets.push({ at: Date.now(), frequency, trackId });
+            } else if (detector.loud && rms < RELEASE_RMS) {
+                detector.loud = false;
+            }
+        }
+    }, 5);
+}
+
+const latencies = new Map();
+const latencyOf = (senderId) => {
+    if (!latencies.has(senderId)) latencies.set(senderId, { senderId, videoLatencyMs: null, audio: [] });
+    return latencies.get(senderId);
+};
+
+state.canvas.width = CODE_COLUMNS * CELL;
+state.canvas.height = CODE_ROWS * CELL;
+const context = state.canvas.getContext('2d', { willReadFrequently: true });
+document.querySelectorAll('video').forEach((video) => {
+    const stream = video.srcObject;
+    if (!(stream instanceof MediaStream) || !stream.getVideoTracks().some((track) => remoteTracks.has(track.id))) return;
+    if (!video.videoWidth || !video.videoHeight) return;
+    const now = Date.now() >>> 0;
+    const codeHeight = (video.videoHeight * CODE_ROWS * CODE_ROW_HEIGHT) / HEIGHT;
+    context.drawImage(video, 0, 0, video.videoWidth, codeHeight, 0, 0, state.canvas.width, state.canvas.height);
+    const pixels = context.getImageData(0, 0, state.canvas.width, state.canvas.height).data;
+    const bytes = [];
+    for (let index = 0; index < CODE_COLUMNS * CODE_ROWS; index += 1) {
+        const x = (index % CODE_COLUMNS) * CELL + CELL / 2;
+        const y = Math.floor(index / CODE_COLUMNS) * CELL + CELL / 2;
+        const offset = (y * state.canvas.width + x) * 4;
+        const bit = 0.2126 * pixels[offset] + 0.7152 * pixels[offset + 1] + 0.0722 * pixels[offset + 2] > 128 ? 1 : 0;
+        bytes[index >> 3] = ((bytes[index >> 3] || 0) << 1) | bit;
+    }
+    const checksum = bytes.slice(0, 8).reduce((sum, byte) => sum + byte, 0) & 0xff;
+    if (checksum !== bytes[8]) return;
+    const sentAt = ((bytes[0] << 24) | (bytes[1] << 16) | (bytes[2] << 8) | bytes[3]) >>> 0;
+    const senderId = ((bytes[4] << 24) | (bytes[5] << 16) | (bytes[6] << 8) | bytes[7]) >>> 0;
+    // Signed difference of the low 32 bits of both clocks.
+    const latencyMs = (now - sentAt) | 0;
+    if (Math.abs(latencyMs) > MAX_LATENCY_MS) return;
+    state.senders.add(senderId);
+    state.videoSenders.set(video, senderId);
+    state.streamSenders.set(stream.id, senderId);
+    latencyOf(senderId).videoLatencyMs = latencyMs;
+});
+
+// Audio either shares the stream of its sender's video or plays from its own element, then it belongs to the tile
+// around it: the closest ancestor with decoded video, as long as that video shows a single sender.
+document.querySelectorAll('audio, video').forEach((element) => {
+    const stream = element.srcObject;
+    if (!(stream instanceof MediaStream)) return;
+    const tracks = stream.getAudioTracks().filter((track) => remoteTracks.has(track.id));
+    if (!tracks.length) return;
+    let senderId = state.streamSenders.get(stream.id);
+    for (let tile = element.parentElement; senderId === undefined && tile && tile !== document.body; tile = tile.parentElement) {
+        const senders = new Set();
+        tile.querySelectorAll('video').forEach((video) => {
+            if (state.videoSenders.has(video)) senders.add(state.videoSenders.get(video));
+        });
+        if (senders.size > 1) break;
+        if (senders.size === 1) [senderId] = senders;
+    }
+    if (senderId !== undefined) tracks.forEach((track) => state.trackSenders.set(track.id, senderId));
+});
+for (const trackId of state.trackSenders.keys()) {
+    if (!remoteTracks.has(trackId)) state.trackSenders.delete(trackId);
+}
+
+for (const onset of state.onsets.splice(0)) {
+    let senderId = state.trackSenders.get(onset.trackId);
+    if (senderId === undefined) {
+        let closest = null;
+        for (const candidate of state.senders) {
+            const error = Math.abs(400 + (candidate % 800) - onset.frequency);
+            if (error <= MAX_PITCH_ERROR_HZ && (!closest || error < closest.error)) closest = { senderId: candidate, error };
+        }
+        senderId = closest ? closest.senderId : undefined;
+    }
+    if (senderId !== undefined) latencyOf(senderId).audio.push(onset.at % 1000);
+}
+
+return [...latencies.values()].map(({ senderId, videoLatencyMs, audio }) => ({
+    senderId,
+    videoLatencyMs,
+    audioLatencyMs: audio.length ? audio.reduce((sum, latency) => sum + latency, 0) / audio.length : null,
+}));
+"#;
+
+pub(super) async fn sample_glass_to_glass(driver: &dyn BrowserDriver) -> Result<Vec<GlassToGlassSample>> {
+    let value = driver.eval(GLASS_TO_GLASS_SAMPLE, None).await?;
+    serde_json::from_value(value).context("failed to read glass-to-glass latencies from eval result")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::commands::tests::RecordingDriver,
+        *,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn reads_latencies_per_sender_from_the_page() {
+        let driver = RecordingDriver::with_result(json!([
+            { "senderId": 92903040, "videoLatencyMs": 182, "audioLatencyMs": 141.5 },
+            { "senderId": 7, "videoLatencyMs": null, "audioLatencyMs": 90 },
+        ]));
+
+        let samples = sample_glass_to_glass(&driver).await.unwrap();
+
+        assert_eq!(
+            samples,
+            vec![
+                GlassToGlassSample {
+                    sender_id: 92_903_040,
+                    video_latency_ms: Some(182.0),
+                    audio_latency_ms: Some(141.5),
+                },
+                GlassToGlassSample {
+                    sender_id: 7,
+                    video_latency_ms: None,
+                    audio_latency_ms: Some(90.0),
+                },
+            ]
+        );
+    }
+}
@@ -4,14 +4,24 @@ use super::{
     super::shared::{
         messages::ParticipantMessage,
         ParticipantState,
+        StartPhase,
+        WebRtcStatsSample,
     },
+    call_status::detect_call_status,
     driver::{
         decode_test_state,
         BrowserDriver,
         FrontendAutomation,
         FrontendContext,
     },
+    glass_to_glass::sample_glass_to_glass,
+    received_media::sample_received_media,
     selectors::lite,
+    state_changes::observe_state_changes,
+    stats::{
+        install_peer_connection_tracker,
+        sample_webrtc_stats,
+    },
 };
 use client_simulator_config::{
     NoiseSuppression,
@@ -19,6 +29,8 @@ use client_simulator_config::{
     VideoConstraint,
 };
 use eyre::{
+    bail,
+    eyre,
     Context as _,
     Result,
 };
@@ -30,11 +42,13 @@ use std::time::{
     Duration,
     Instant,
 };
+use tokio::sync::watch;
 
 /// Local frontend automation for the hyper-lite UI.
 #[derive(Debug)]
 pub(super) struct ParticipantInnerLite {
     context: FrontendContext,
+    state_changes: Option<watch::Receiver<()>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -45,7 +59,10 @@ enum LiteEntryPoint {
 
 impl ParticipantInnerLite {
     pub(super) fn new(context: FrontendContext) -> Self {
-        Self { context }
+        Self {
+            context,
+            state_changes: None,
+        }
     }
 
     fn participant_name(&self) -> &str {
@@ -54,23 +71,38 @@ impl ParticipantInnerLite {
 
     async fn join_session(&mut self) -> Result<()> {
         self.context
-            .driver
-            .goto(self.context.launch_spec.session_url.as_str())
+            .timings
+            .time(
+                StartPhase::Navigation,
+                self.context.driver.goto(self.context.launch_spec.session_url.as_str()),
+            )
             .await
             .context("failed to wait for navigation response")?;
 
         self.context.log_message("debug", "Navigated to page");
 
+        if self.context.launch_spec.settings.webrtc_stats_interval.is_some() {
+            if let Err(err) = install_peer_connection_tracker(self.context.driver.as_ref()).await {
+                self.context.log_message(
+                    "warn",
+                    format!("Failed to track peer connections for WebRTC stats: {err}"),
+                );
+            }
+        }
+        observe_state_changes(&self.context, &mut self.state_changes).await;
+
+        let lobby_started = Instant::now();
         match self.wait_for_entry_point(Duration::from_secs(30)).await? {
             LiteEntryPoint::InCall => {
                 self.context.log_message("debug", "Lite session is already in-call");
             }
             LiteEntryPoint::Lobby => {
                 self.prepare_lobby().await?;
+                self.context.timings.record(StartPhase::Lobby, lobby_started.elapsed());
 
                 self.context
-                    .driver
-                    .click(lite::JOIN_BUTTON)
+                    .timings
+                    .time(StartPhase::JoinClick, self.context.driver.click(lite::JOIN_BUTTON))
                     .await
                     .context("failed to click join button")?;
 
@@ -79,8 +111,13 @@ impl ParticipantInnerLite {
         }
 
         self.context
-            .driver
-            .wait_for(lite::LEAVE_BUTTON, Duration::from_secs(30))
+            .timings
+            .time(
+                StartPhase::LeaveButtonVisible,
+                self.context
+                    .driver
+                    .wait_for(lite::LEAVE_BUTTON, Duration::from_secs(30)),
+            )
             .await
             .context("We haven't joined the space, cannot find the leave button")?;
 
@@ -228,27 +265,24 @@ impl ParticipantInnerLite {
     }
 
     async fn set_audio_enabled_inner(&self, enabled: bool) -> Result<()> {
-        match self.audio_enabled().await? {
-            Some(current) if current == enabled => Ok(()),
-            None if enabled => Ok(()),
-            _ => self.toggle_audio_inner().await,
+        if known_state(self.audio_enabled().await?, "microphone")? != enabled {
+            self.toggle_audio_inner().await?;
         }
+        Ok(())
     }
 
     async fn set_video_enabled_inner(&self, enabled: bool) -> Result<()> {
-        match self.video_enabled().await? {
-            Some(current) if current == enabled => Ok(()),
-            None if enabled => Ok(()),
-            _ => self.toggle_video_inner().await,
+        if known_state(self.video_enabled().await?, "camera")? != enabled {
+            self.toggle_video_inner().await?;
         }
+        Ok(())
     }
 
     async fn set_screen_share_enabled_inner(&self, enabled: bool) -> Result<()> {
-        match self.screen_share_enabled().await? {
-            Some(current) if current == enabled => Ok(()),
-            None if !enabled => Ok(()),
-            Some(_) | None => self.toggle_screen_share_inner().await,
+        if known_state(self.screen_share_enabled().await?, "screen share")? != enabled {
+            self.toggle_screen_share_inner().await?;
         }
+        Ok(())
     }
 
     async fn toggle_auto_gain_control_inner(&self) -> Result<()> {
@@ -360,6 +394,11 @@ impl ParticipantInnerLite {
             state.screenshare_activated = value;
         }
 
+        if let Ok(status) = detect_call_status(self.context.driver.as_ref()).await {
+            state.call_status = status;
+            state.warning = status.warning();
+        }
+
         Ok(state)
     }
 }
@@ -377,6 +416,12 @@ async fn aria_label(driver: &dyn BrowserDriver, selector: &str) -> Option<String
     driver.attribute(selector, "aria-label").await.ok().flatten()
 }
 
+/// The state of a control read right before changing it. Guessing an unknown state would turn set commands into
+/// toggles.
+fn known_state(state: Option<bool>, control: &str) -> Result<bool> {
+    state.ok_or_else(|| eyre!("The {control} button does not expose its state"))
+}
+
 fn audio_enabled_from_button_state(
     data_test_state: Option<bool>,
     aria_pressed: Option<bool>,
@@ -447,6 +492,15 @@ impl FrontendAutomation for ParticipantInnerLite {
                 }
                 ParticipantMessage::SetNoiseSuppression(value) => self.set_noise_suppression_inner(value).await,
                 ParticipantMessage::ToggleBackgroundBlur => self.toggle_background_blur_inner().await,
+                ParticipantMessage::SetAudioEnabled(value) => self.set_audio_enabled_inner(value).await,
+                ParticipantMessage::SetVideoEnabled(value) => self.set_video_enabled_inner(value).await,
+                ParticipantMessage::SetScreenshare(value) => self.set_screen_share_enabled_inner(value).await,
+                ParticipantMessage::SetBlur(_) => self.toggle_background_blur_inner().await,
+                ParticipantMessage::SetAutoGainControl(_) => self.toggle_auto_gain_control_inner().await,
+                ParticipantMessage::SetNetwork(_) => bail!("Network emulation needs a local Chromium browser"),
+                ParticipantMessage::StartRecording | ParticipantMessage::StopRecording => {
+                    bail!("Recording received media needs a local Chromium browser")
+                }
             }
         }
         .boxed()
@@ -455,15 +509,40 @@ impl FrontendAutomation for ParticipantInnerLite {
     fn refresh_state(&mut self) -> BoxFuture<'_, Result<ParticipantState>> {
         async move { self.refresh_state_inner().await }.boxed()
     }
+
+    fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<WebRtcStatsSample>> {
+        async move {
+            let driver = self.context.driver.as_ref();
+            let mut sample = sample_webrtc_stats(driver).await?;
+            sample.received_media = Some(sample_received_media(driver).await?);
+            sample.glass_to_glass = sample_glass_to_glass(driver).await?;
+            Ok(sample)
+        }
+        .boxed()
+    }
+
+    fn state_changes(&self) -> Option<watch::Receiver<()>> {
+        self.state_changes.clone()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
         audio_enabled_from_button_state,
+        known_state,
         video_enabled_from_button_state,
     };
 
+    #[test]
+    fn unknown_button_states_fail_instead_of_being_guessed() {
+        assert!(known_state(Some(false), "microphone").is_ok_and(|enabled| !enabled));
+        let error = known_state(None, "screen share").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("screen share button does not expose its state"));
+    }
+
     #[test]
     fn audio_state_prefers_legacy_data_test_state() {
         assert_eq!(
@@ -2,11 +2,18 @@
 //! and Device Farm (WebDriver) backends.
 
 mod builder;
+mod call_status;
 mod commands;
 mod core;
 mod driver;
+mod fake_media;
+mod glass_to_glass;
 mod lite;
+mod received_media;
+mod recording;
 mod selectors;
+mod state_changes;
+mod stats;
 
 pub(in crate::participant) use builder::{
     FrontendAuth,
@@ -17,3 +24,8 @@ pub(in crate::participant) use driver::{
     FrontendAutomation,
     FrontendContext,
 };
+pub(in crate::participant) use fake_media::{
+    install_fake_media,
+    InjectedFakeMedia,
+};
+pub(in crate::participant) use recording::ReceivedMediaRecording;
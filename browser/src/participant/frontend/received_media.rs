@@ -0,0 +1,119 @@
+//! Received media inspection through `BrowserDriver::eval`.
+//!
+//! Remote tracks are told apart from local previews by the receivers of the tracked peer connections, see
+//! `frontend::stats`. Each video element playing a remote track is a tile. Its frame counter is compared with the
+//! previous inspection, and a tile with new frames is scaled down to a few pixels to tell black video apart. Audio
+//! energy comes from the cumulative `inbound-rtp` counters.
+
+use super::{
+    super::shared::ReceivedMediaSample,
+    driver::BrowserDriver,
+};
+use eyre::{
+    Context as _,
+    Result,
+};
+
+const RECEIVED_MEDIA_SAMPLE: &str = r#"
+const BLACK_LUMA = 16;
+const SIZE = 8;
+const state = window.__hyperSimulatorReceivedMedia || (window.__hyperSimulatorReceivedMedia = {
+    frames: new WeakMap(),
+    canvas: document.createElement('canvas'),
+});
+const connections = (window.__hyperSimulatorPeerConnections || []).filter((pc) => pc.connectionState !== 'closed');
+const remoteTracks = new Set();
+connections.forEach((pc) => pc.getReceivers().forEach((receiver) => {
+    if (receiver.track && receiver.track.readyState === 'live') remoteTracks.add(receiver.track.id);
+}));
+const sample = {
+    videoTiles: 0,
+    advancingVideoTiles: 0,
+    frozenVideoTiles: 0,
+    blackVideoTiles: 0,
+    audioTracks: 0,
+    totalAudioEnergy: 0,
+    totalSamplesDuration: 0,
+};
+state.canvas.width = SIZE;
+state.canvas.height = SIZE;
+const context = state.canvas.getContext('2d', { willReadFrequently: true });
+document.querySelectorAll('video').forEach((video) => {
+    const stream = video.srcObject;
+    if (!(stream instanceof MediaStream) || !stream.getVideoTracks().some((track) => remoteTracks.has(track.id))) return;
+    sample.videoTiles += 1;
+    const frames = video.getVideoPlaybackQuality().totalVideoFrames;
+    const previous = state.frames.get(video);
+    state.frames.set(video, frames);
+    if (previous === undefined ? frames === 0 : frames <= previous) {
+        sample.frozenVideoTiles += 1;
+        return;
+    }
+    context.drawImage(video, 0, 0, SIZE, SIZE);
+    const pixels = context.getImageData(0, 0, SIZE, SIZE).data;
+    let luma = 0;
+    for (let index = 0; index < pixels.length; index += 4) {
+        luma += 0.2126 * pixels[index] + 0.7152 * pixels[index + 1] + 0.0722 * pixels[index + 2];
+    }
+    if (luma / (SIZE * SIZE) < BLACK_LUMA) {
+        sample.blackVideoTiles += 1;
+    } else {
+        sample.advancingVideoTiles += 1;
+    }
+});
+return Promise.all(connections.map((pc) => pc.getStats().catch(() => null))).then((reports) => {
+    for (const report of reports) {
+        if (!report) continue;
+        report.forEach((stat) => {
+            if (stat.type !== 'inbound-rtp' || stat.kind !== 'audio') return;
+            sample.audioTracks += 1;
+            sample.totalAudioEnergy += stat.totalAudioEnergy || 0;
+            sample.totalSamplesDuration += stat.totalSamplesDuration || 0;
+        });
+    }
+    return sample;
+});
+"#;
+
+pub(super) async fn sample_received_media(driver: &dyn BrowserDriver) -> Result<ReceivedMediaSample> {
+    let value = driver.eval(RECEIVED_MEDIA_SAMPLE, None).await?;
+    serde_json::from_value(value).context("failed to read received media from eval result")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::commands::tests::RecordingDriver,
+        *,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn reads_received_media_from_the_page() {
+        let driver = RecordingDriver::with_result(json!({
+            "videoTiles": 3,
+            "advancingVideoTiles": 1,
+            "frozenVideoTiles": 1,
+            "blackVideoTiles": 1,
+            "audioTracks": 2,
+            "totalAudioEnergy": 0.5,
+            "totalSamplesDuration": 12.0,
+        }));
+
+        let sample = sample_received_media(&driver).await.unwrap();
+
+        assert_eq!(
+            sample,
+            ReceivedMediaSample {
+                video_tiles: 3,
+                advancing_video_tiles: 1,
+                frozen_video_tiles: 1,
+                black_video_tiles: 1,
+                audio_tracks: 2,
+                total_audio_energy: 0.5,
+                total_samples_duration: 12.0,
+            }
+        );
+        assert_eq!(driver.calls(), vec![(RECEIVED_MEDIA_SAMPLE.to_string(), None)]);
+    }
+}
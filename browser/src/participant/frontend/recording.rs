@@ -0,0 +1,492 @@
+//! Recording of the received media through `BrowserDriver::eval`.
+//!
+//! Remote tracks are told apart from local previews by the receivers of the tracked peer connections, see
+//! `frontend::stats`. Every media element playing remote tracks, and every remote track not played by an element, gets
+//! a recording, so there is one recording per sender. Senders that show up while recording are picked up every second.
+//! Recordings with video are kept as WebM from a `MediaRecorder`, audio-only ones are captured as 16 bit PCM and
+//! stored as WAV. What was recorded is pulled back in slices while recording and after stopping, which keeps every
+//! eval result and the page's memory small.
+//!
+//! Files are named after the participant whose sender id the glass-to-glass measurement decoded from the sender's tile,
+//! see `frontend::glass_to_glass`, and after the stream or track id otherwise.
+
+use super::{
+    super::shared::media_sender_name,
+    driver::BrowserDriver,
+};
+use base64::{
+    prelude::BASE64_STANDARD,
+    Engine as _,
+};
+use chrono::Utc;
+use eyre::{
+    bail,
+    Context as _,
+    Result,
+};
+use std::{
+    io::SeekFrom,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+use tokio::{
+    fs::{
+        self,
+        OpenOptions,
+    },
+    io::{
+        AsyncSeekExt as _,
+        AsyncWriteExt as _,
+    },
+};
+
+/// Upper bound of the recorded bytes pulled back per eval.
+const DRAIN_MAX_BYTES: usize = 4 * 1024 * 1024;
+const WAV_HEADER_BYTES: u64 = 44;
+
+const RECORDING_START: &str = r#"
+// A sender's first data waits this long for its id, which names its file.
+const NAME_GRACE_MS = 5000;
+const connections = window.__hyperSimulatorPeerConnections;
+if (!connections) return false;
+const state = window.__hyperSimulatorRecording || (window.__hyperSimulatorRecording = {
+    recordings: new Map(),
+    pending: [],
+    timer: null,
+    audio: null,
+});
+const senderIdOf = (recording) => {
+    const measured = window.__hyperSimulatorGlassToGlass;
+    if (!measured) return null;
+    if (measured.streamSenders.has(recording.key)) return measured.streamSenders.get(recording.key);
+    const track = recording.trackIds.find((id) => measured.trackSenders.has(id));
+    return track === undefined ? null : measured.trackSenders.get(track);
+};
+// Moves what was recorded so far to the drain queue.
+state.collect = (all) => state.recordings.forEach((recording) => {
+    if (recording.senderId === null) recording.senderId = senderIdOf(recording);
+    if (!all && recording.senderId === null && Date.now() - recording.started < NAME_GRACE_MS) return;
+    if (!recording.chunks.length) return;
+    state.pending.push({
+        key: recording.key,
+        senderId: recording.senderId,
+        format: recording.format,
+        sampleRate: recording.sampleRate,
+        blob: new Blob(recording.chunks.splice(0)),
+        offset: 0,
+    });
+});
+const record = (key, tracks) => {
+    const recording = { key, trackIds: tracks.map((track) => track.id), started: Date.now(), senderId: null, chunks: [] };
+    if (tracks.some((track) => track.kind === 'video')) {
+        const recorder = new MediaRecorder(new MediaStream(tracks), { mimeType: 'video/webm' });
+        const stopped = new Promise((resolve) => {
+            recorder.onstop = resolve;
+        });
+        recorder.ondataavailable = (event) => {
+            if (event.data.size) recording.chunks.push(event.data);
+        };
+        recorder.start(1000);
+        recording.format = 'webm';
+        recording.sampleRate = null;
+        recording.stop = () => {
+            if (recorder.state !== 'inactive') recorder.stop();
+            return stopped;
+        };
+        return recording;
+    }
+    // Mono 16 bit PCM in the platform's byte order, little endian everywhere Chrome runs. The simulator writes the WAV
+    // header around it.
+    state.audio = state.audio || new AudioContext();
+    state.audio.resume().catch(() => {});
+    const source = state.audio.createMediaStreamSource(new MediaStream(tracks));
+    const processor = state.audio.createScriptProcessor(4096, 1, 1);
+    const mute = state.audio.createGain();
+    mute.gain.value = 0;
+    processor.onaudioprocess = (event) => {
+        const samples = event.inputBuffer.getChannelData(0);
+        const pcm = new Int16Array(samples.length);
+        samples.forEach((sample, index) => {
+            const clamped = Math.max(-1, Math.min(1, sample));
+            pcm[index] = clamped < 0 ? clamped * 0x8000 : clamped * 0x7fff;
+        });
+        recording.chunks.push(new Blob([pcm.buffer]));
+    };
+    source.connect(processor).connect(mute).connect(state.audio.destination);
+    recording.format = 'wav';
+    recording.sampleRate = state.audio.sampleRate;
+    recording.stop = async () => {
+        source.disconnect();
+        processor.disconnect();
+        mute.disconnect();
+    };
+    return recording;
+};
+const attach = () => {
+    const remoteTracks = new Map();
+    connections.filter((pc) => pc.connectionState !== 'closed').forEach((pc) => pc.getReceivers().forEach((receiver) => {
+        if (receiver.track && receiver.track.readyState === 'live') remoteTracks.set(receiver.track.id, receiver.track);
+    }));
+    const senders = new Map();
+    document.querySelectorAll('video, audio').forEach((element) => {
+        const stream = element.srcObject;
+        if (!(stream instanceof MediaStream)) return;
+        const tracks = stream.getTracks().filter((track) => remoteTracks.has(track.id));
+        if (!tracks.length) return;
+        senders.set(stream.id, tracks);
+        tracks.forEach((track) => remoteTracks.delete(track.id));
+    });
+    remoteTracks.forEach((track) => senders.set(track.id, [track]));
+    senders.forEach((tracks, key) => {
+        if (!state.recordings.has(key)) state.recordings.set(key, record(key, tracks));
+    });
+};
+if (!state.timer) {
+    attach();
+    state.timer = setInterval(attach, 1000);
+}
+return true;
+"#;
+
+const RECORDING_STOP: &str = r#"
+const state = window.__hyperSimulatorRecording;
+if (!state || !state.timer) return 0;
+clearInterval(state.timer);
+state.timer = null;
+const recordings = [...state.recordings.values()];
+return Promise.all(recordings.map((recording) => recording.stop())).then(() => {
+    state.collect(true);
+    state.recordings.clear();
+    return recordings.length;
+});
+"#;
+
+const RECORDING_DRAIN: &str = r#"
+const maxBytes = arguments[0];
+const state = window.__hyperSimulatorRecording;
+if (!state) return { chunks: [], done: true };
+if (state.timer) state.collect(false);
+const pending = state.pending;
+const slices = [];
+let bytes = 0;
+while (pending.length && bytes < maxBytes) {
+    const recording = pending[0];
+    const end = Math.min(recording.blob.size, recording.offset + maxBytes - bytes);
+    slices.push({ ...recording, blob: recording.blob.slice(recording.offset, end) });
+    bytes += end - recording.offset;
+    recording.offset = end;
+    if (recording.offset >= recording.blob.size) pending.shift();
+}
+const base64 = (blob) => new Promise((resolve, reject) => {
+    const reader = new FileReader();
+    reader.onload = () => resolve(reader.result.slice(reader.result.indexOf(',') + 1));
+    reader.onerror = () => reject(reader.error);
+    reader.readAsDataURL(blob);
+});
+return Promise.all(slices.map(async ({ key, senderId, format, sampleRate, blob }) => ({
+    key,
+    senderId,
+    format,
+    sampleRate,
+    data: await base64(blob),
+}))).then((chunks) => ({ chunks, done: pending.length === 0 }));
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+enum RecordingFormat {
+    Webm,
+    Wav,
+}
+
+/// The next slice of a recording, base64 encoded.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordingChunk {
+    /// Id of the stream or track recorded.
+    key: String,
+    /// Decoded from the sender's test pattern, see `shared::media_sender_id`.
+    sender_id: Option<u32>,
+    format: RecordingFormat,
+    /// Of the PCM in WAV recordings.
+    sample_rate: Option<u32>,
+    data: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct DrainedRecordings {
+    chunks: Vec<RecordingChunk>,
+    done: bool,
+}
+
+#[derive(Debug)]
+struct RecordingFile {
+    key: String,
+    path: PathBuf,
+    format: RecordingFormat,
+    /// PCM written after the header of WAV files.
+    data_bytes: u64,
+}
+
+/// Received media being recorded into a directory, one file per sender.
+#[derive(Debug)]
+pub(in crate::participant) struct ReceivedMediaRecording {
+    dir: PathBuf,
+    files: Vec<RecordingFile>,
+}
+
+impl ReceivedMediaRecording {
+    /// A new directory below `data_dir/recordings` for a recording of `participant` that starts now.
+    pub(in crate::participant) fn new_dir(data_dir: &Path, participant: &str) -> PathBuf {
+        data_dir
+            .join("recordings")
+            .join(file_stem(participant, "participant"))
+            .join(Utc::now().format("%Y%m%d-%H%M%S").to_string())
+    }
+
+    /// Starts recording every sender the page receives media from into `dir`. Does nothing in the page while already
+    /// recording.
+    pub(in crate::participant) async fn start(driver: &dyn BrowserDriver, dir: PathBuf) -> Result<Self> {
+        let value = driver.eval(RECORDING_START, None).await?;
+        let started: bool = serde_json::from_value(value).context("failed to read recording start from eval result")?;
+        if !started {
+            bail!("Recording received media needs the peer connection tracker, set webrtc_stats_interval_ms above 0");
+        }
+        Ok(Self { dir, files: Vec::new() })
+    }
+
+    pub(in crate::participant) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes what was recorded so far.
+    pub(in crate::participant) async fn drain(&mut self, driver: &dyn BrowserDriver) -> Result<()> {
+        loop {
+            let value = driver.eval(RECORDING_DRAIN, Some(DRAIN_MAX_BYTES.into())).await?;
+            let drained: DrainedRecordings =
+                serde_json::from_value(value).context("failed to read recorded media from eval result")?;
+            for chunk in drained.chunks {
+                self.write(chunk).await?;
+            }
+            if drained.done {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Stops recording, writes the rest and returns the written files, none if nothing was received.
+    pub(in crate::participant) async fn stop(mut self, driver: &dyn BrowserDriver) -> Result<Vec<PathBuf>> {
+        driver.eval(RECORDING_STOP, None).await?;
+        self.drain(driver).await?;
+        Ok(self.files.into_iter().map(|file| file.path).collect())
+    }
+
+    async fn write(&mut self, chunk: RecordingChunk) -> Result<()> {
+        let bytes = BASE64_STANDARD
+            .decode(&chunk.data)
+            .with_context(|| format!("failed to decode the recording of {}", chunk.key))?;
+        let index = match self.files.iter().position(|file| file.key == chunk.key) {
+            Some(index) => index,
+            None => self.create(&chunk).await?,
+        };
+        let file = &mut self.files[index];
+
+        let mut out = OpenOptions::new()
+            .append(true)
+            .open(&file.path)
+            .await
+            .with_context(|| format!("failed to open {:?}", file.path))?;
+        out.write_all(&bytes)
+            .await
+            .with_context(|| format!("failed to write {:?}", file.path))?;
+        if file.format == RecordingFormat::Wav {
+            // Kept up to date, so the file plays even if the recording is never stopped.
+            file.data_bytes += bytes.len() as u64;
+            let data_bytes = u32::try_from(file.data_bytes).unwrap_or(u32::MAX);
+            let riff_bytes = data_bytes.saturating_add(WAV_HEADER_BYTES as u32 - 8);
+            let mut out = OpenOptions::new()
+                .write(true)
+                .open(&file.path)
+                .await
+                .with_context(|| format!("failed to open {:?}", file.path))?;
+            out.seek(SeekFrom::Start(4)).await?;
+            out.write_all(&riff_bytes.to_le_bytes()).await?;
+            out.seek(SeekFrom::Start(WAV_HEADER_BYTES - 4)).await?;
+            out.write_all(&data_bytes.to_le_bytes()).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates the file of the recording `chunk` belongs to and returns its index.
+    async fn create(&mut self, chunk: &RecordingChunk) -> Result<usize> {
+        let name = match chunk.sender_id {
+            Some(sender_id) => media_sender_name(sender_id).unwrap_or_else(|| format!("{sender_id:08x}")),
+            None => chunk.key.clone(),
+        };
+        // Several recordings may get the same name, e.g. the camera and screen of one participant, or stream ids that
+        // only differ in characters a file name cannot hold.
+        let stem = file_stem(&name, "sender");
+        let mut path = self.dir.join(format!("{stem}.{}", chunk.format));
+        for suffix in 2.. {
+            if !self.files.iter().any(|file| file.path == path) {
+                break;
+            }
+            path = self.dir.join(format!("{stem}-{suffix}.{}", chunk.format));
+        }
+
+        fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("failed to create {:?}", self.dir))?;
+        let header = match chunk.format {
+            RecordingFormat::Wav => wav_header(chunk.sample_rate.unwrap_or(48_000)).to_vec(),
+            RecordingFormat::Webm => Vec::new(),
+        };
+        fs::write(&path, header)
+            .await
+            .with_context(|| format!("failed to write {path:?}"))?;
+        self.files.push(RecordingFile {
+            key: chunk.key.clone(),
+            path,
+            format: chunk.format,
+            data_bytes: 0,
+        });
+        Ok(self.files.len() - 1)
+    }
+}
+
+/// Header of a mono 16 bit PCM WAV file without data, [`ReceivedMediaRecording::write`] fills in the sizes.
+fn wav_header(sample_rate: u32) -> [u8; WAV_HEADER_BYTES as usize] {
+    let mut header = [0; WAV_HEADER_BYTES as usize];
+    header[0..4].copy_from_slice(b"RIFF");
+    header[4..8].copy_from_slice(&(WAV_HEADER_BYTES as u32 - 8).to_le_bytes());
+    header[8..12].copy_from_slice(b"WAVE");
+    header[12..16].copy_from_slice(b"fmt ");
+    header[16..20].copy_from_slice(&16u32.to_le_bytes());
+    header[20..22].copy_from_slice(&1u16.to_le_bytes());
+    header[22..24].copy_from_slice(&1u16.to_le_bytes());
+    header[24..28].copy_from_slice(&sample_rate.to_le_bytes());
+    header[28..32].copy_from_slice(&(sample_rate * 2).to_le_bytes());
+    header[32..34].copy_from_slice(&2u16.to_le_bytes());
+    header[34..36].copy_from_slice(&16u16.to_le_bytes());
+    header[36..40].copy_from_slice(b"data");
+    header
+}
+
+/// Names and stream ids are not chosen by us, keep only what is safe in a file name.
+fn file_stem(name: &str, fallback: &str) -> String {
+    let stem = name
+        .chars()
+        .map(|char| match char {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => char,
+            _ => '_',
+        })
+        .collect::<String>();
+    let stem = stem.trim_matches('_');
+    if stem.is_empty() {
+        fallback.to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            super::shared::{
+                media_sender_id,
+                register_media_sender,
+            },
+            commands::tests::RecordingDriver,
+        },
+        *,
+    };
+    use serde_json::json;
+    use std::time::{
+        SystemTime,
+        UNIX_EPOCH,
+    };
+
+    #[test]
+    fn recording_dirs_stay_below_the_data_dir() {
+        let data_dir = Path::new("/data");
+
+        let dir = ReceivedMediaRecording::new_dir(data_dir, "../../etc/sim user");
+
+        assert_eq!(dir.parent().unwrap(), data_dir.join("recordings").join("etc_sim_user"));
+        assert_eq!(
+            ReceivedMediaRecording::new_dir(data_dir, "..").parent().unwrap(),
+            data_dir.join("recordings").join("participant")
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_without_the_peer_connection_tracker() {
+        let driver = RecordingDriver::with_result(json!(false));
+
+        let err = ReceivedMediaRecording::start(&driver, PathBuf::from("unused"))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("webrtc_stats_interval_ms"));
+    }
+
+    #[tokio::test]
+    async fn writes_one_file_per_sender_while_recording() {
+        let nonce = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("hyper-browser-simulator-recording-{nonce}"));
+        register_media_sender("recorded-alice");
+        let alice = media_sender_id("recorded-alice");
+        let driver = RecordingDriver::with_result(json!(true));
+        let mut recording = ReceivedMediaRecording::start(&driver, dir.clone()).await.unwrap();
+
+        driver.set_result(json!({
+            "chunks": [
+                { "key": "stream-1", "senderId": alice, "format": "webm", "sampleRate": null, "data": "AQI=" },
+                { "key": "{a}", "senderId": null, "format": "webm", "sampleRate": null, "data": "" },
+                { "key": "a", "senderId": null, "format": "webm", "sampleRate": null, "data": "" },
+                { "key": "audio/2", "senderId": 7, "format": "wav", "sampleRate": 16000, "data": "AQI=" },
+            ],
+            "done": true,
+        }));
+        recording.drain(&driver).await.unwrap();
+        driver.set_result(json!({
+            "chunks": [
+                { "key": "stream-1", "senderId": alice, "format": "webm", "sampleRate": null, "data": "Aw==" },
+                { "key": "audio/2", "senderId": 7, "format": "wav", "sampleRate": 16000, "data": "AwQ=" },
+            ],
+            "done": true,
+        }));
+        let files = recording.stop(&driver).await.unwrap();
+
+        assert_eq!(
+            files,
+            vec![
+                dir.join("recorded-alice.webm"),
+                dir.join("a.webm"),
+                dir.join("a-2.webm"),
+                dir.join("00000007.wav"),
+            ]
+        );
+        assert_eq!(std::fs::read(&files[0]).unwrap(), vec![1, 2, 3]);
+        let wav = std::fs::read(&files[3]).unwrap();
+        let mut expected = wav_header(16_000).to_vec();
+        expected[4..8].copy_from_slice(&40u32.to_le_bytes());
+        expected[40..44].copy_from_slice(&4u32.to_le_bytes());
+        expected.extend([1, 2, 3, 4]);
+        assert_eq!(wav, expected);
+        assert_eq!(
+            driver.calls(),
+            vec![
+                (RECORDING_START.to_string(), None),
+                (RECORDING_DRAIN.to_string(), Some(json!(DRAIN_MAX_BYTES))),
+                (RECORDING_STOP.to_string(), None),
+                (RECORDING_DRAIN.to_string(), Some(json!(DRAIN_MAX_BYTES))),
+            ]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
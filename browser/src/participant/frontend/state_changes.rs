@@ -0,0 +1,143 @@
+//! Pushes changes of the page back to the simulator, so the participant state is refreshed without waiting for the
+//! next command.
+//!
+//! A `MutationObserver` watches the attributes the frontends expose their state through and elements being added or
+//! removed, e.g. the leave button or a dialog. Text changes such as the call timer are ignored. Bursts of changes are
+//! throttled into one call of a runtime binding. Drivers without bindings (WebDriver) get no observer, their sessions
+//! keep polling the state.
+//!
+//! The observer is registered as an init script, so every document loaded later (reloads, redirects to the space) arms
+//! it again, and is evaluated once in the document already loaded when joining.
+
+use super::driver::{
+    BrowserDriver,
+    FrontendContext,
+};
+use eyre::{
+    Context as _,
+    Result,
+};
+use tokio::sync::watch;
+
+const STATE_CHANGE_BINDING: &str = "__hyperSimulatorStateChanged";
+
+/// Installs the observer into the current document. `false` if the binding is missing.
+const STATE_CHANGE_OBSERVER: &str = r#"() => {
+const THROTTLE_MS = 100;
+const notify = window.__hyperSimulatorStateChanged;
+if (typeof notify !== 'function') return false;
+if (window.__hyperSimulatorStateObserver) return true;
+let scheduled = null;
+const changed = () => {
+    if (scheduled) return;
+    scheduled = setTimeout(() => {
+        scheduled = null;
+        notify('');
+    }, THROTTLE_MS);
+};
+const isElement = (node) => node.nodeType === Node.ELEMENT_NODE;
+window.__hyperSimulatorStateObserver = new MutationObserver((records) => {
+    const relevant = records.some((record) =>
+        record.type === 'attributes' || [...record.addedNodes].some(isElement) || [...record.removedNodes].some(isElement));
+    if (relevant) changed();
+});
+window.__hyperSimulatorStateObserver.observe(document, {
+    subtree: true,
+    childList: true,
+    attributes: true,
+    attributeFilter: ['aria-label', 'aria-pressed', 'aria-checked', 'data-state', 'data-test-state', 'disabled', 'open'],
+});
+window.addEventListener('popstate', changed);
+return true;
+}"#;
+
+/// Runs before the page's own scripts, when `document` exists but has no content yet; observing it covers everything
+/// added later.
+fn state_change_observer_init_script() -> String {
+    format!("({STATE_CHANGE_OBSERVER})();")
+}
+
+fn state_change_observer_install() -> String {
+    format!("return ({STATE_CHANGE_OBSERVER})();")
+}
+
+/// Exposes the binding and registers the observer for later documents into `state_changes` on first use, then installs
+/// the observer into the page that was just loaded. Failures only cost the immediate refreshes, so they are logged.
+pub(super) async fn observe_state_changes(context: &FrontendContext, state_changes: &mut Option<watch::Receiver<()>>) {
+    let driver = context.driver.as_ref();
+    if state_changes.is_none() {
+        match expose_state_change_binding(driver).await {
+            Ok(changes) => *state_changes = changes,
+            Err(err) => context.log_message("warn", format!("Failed to observe state changes of the page: {err}")),
+        }
+    }
+    if state_changes.is_some() {
+        if let Err(err) = install_state_change_observer(driver).await {
+            context.log_message("warn", format!("Failed to observe state changes of the page: {err}"));
+        }
+    }
+}
+
+/// `Ok(None)` if the driver cannot be called back from the page.
+async fn expose_state_change_binding(driver: &dyn BrowserDriver) -> Result<Option<watch::Receiver<()>>> {
+    let Some(changes) = driver
+        .add_binding(STATE_CHANGE_BINDING)
+        .await
+        .context("failed to expose the state change binding")?
+    else {
+        return Ok(None);
+    };
+    driver
+        .add_init_script(&state_change_observer_init_script())
+        .await
+        .context("failed to observe state changes of later documents")?;
+    Ok(Some(changes))
+}
+
+async fn install_state_change_observer(driver: &dyn BrowserDriver) -> Result<()> {
+    let installed = driver.eval(&state_change_observer_install(), None).await?;
+    if installed != serde_json::Value::Bool(true) {
+        eyre::bail!("the state change binding is not available on this page");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::commands::tests::RecordingDriver,
+        *,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn exposes_the_binding_the_observer_calls() {
+        let driver = RecordingDriver::with_result(json!(true));
+
+        assert!(expose_state_change_binding(&driver).await.unwrap().is_some());
+        install_state_change_observer(&driver).await.unwrap();
+
+        assert_eq!(driver.bindings(), vec![STATE_CHANGE_BINDING.to_string()]);
+        assert!(STATE_CHANGE_OBSERVER.contains(&format!("window.{STATE_CHANGE_BINDING}")));
+        assert_eq!(driver.calls(), vec![(state_change_observer_install(), None)]);
+    }
+
+    #[tokio::test]
+    async fn arms_the_observer_in_every_later_document() {
+        let driver = RecordingDriver::default();
+
+        expose_state_change_binding(&driver).await.unwrap();
+
+        assert_eq!(driver.init_scripts(), vec![state_change_observer_init_script()]);
+        assert!(!driver.init_scripts()[0].contains("return ("));
+    }
+
+    #[tokio::test]
+    async fn installing_the_observer_fails_without_the_binding() {
+        let driver = RecordingDriver::with_result(json!(false));
+
+        let error = install_state_change_observer(&driver).await.unwrap_err();
+
+        assert!(error.to_string().contains("binding is not available"));
+    }
+}
@@ -0,0 +1,170 @@
+//! WebRTC stats sampling through `BrowserDriver::eval`.
+//!
+//! The page does not expose its peer connections, so the tracker wraps the `RTCPeerConnection` constructor and keeps
+//! every instance created afterwards. It has to be installed before the frontend starts its call.
+
+use super::{
+    super::shared::WebRtcStatsSample,
+    driver::BrowserDriver,
+};
+use eyre::{
+    Context as _,
+    Result,
+};
+
+const PEER_CONNECTION_TRACKER_INSTALL: &str = r#"
+if (!window.__hyperSimulatorPeerConnections && window.RTCPeerConnection) {
+    const connections = [];
+    const NativePeerConnection = window.RTCPeerConnection;
+    window.__hyperSimulatorPeerConnections = connections;
+    window.RTCPeerConnection = class extends NativePeerConnection {
+        constructor(...args) {
+            super(...args);
+            connections.push(this);
+        }
+    };
+}
+return Boolean(window.__hyperSimulatorPeerConnections);
+"#;
+
+// Sums the RTP counters of all open peer connections. Resolution and fps are taken from the largest received video
+// track, RTT from the nominated candidate pairs.
+const WEBRTC_STATS_SAMPLE: &str = r#"
+const connections = (window.__hyperSimulatorPeerConnections || []).filter((pc) => pc.connectionState !== 'closed');
+const sample = {
+    timestampMs: Date.now(),
+    peerConnections: connections.length,
+    bytesSent: 0,
+    bytesReceived: 0,
+    packetsReceived: 0,
+    packetsLost: 0,
+    framesDecoded: 0,
+    framesDropped: 0,
+};
+const jitter = [];
+const roundTripTimes = [];
+let largestVideo = null;
+return Promise.all(connections.map((pc) => pc.getStats().catch(() => null))).then((reports) => {
+    for (const report of reports) {
+        if (!report) continue;
+        report.forEach((stat) => {
+            if (stat.type === 'outbound-rtp') {
+                sample.bytesSent += stat.bytesSent || 0;
+            } else if (stat.type === 'inbound-rtp') {
+                sample.bytesReceived += stat.bytesReceived || 0;
+                sample.packetsReceived += stat.packetsReceived || 0;
+                sample.packetsLost += stat.packetsLost || 0;
+                if (typeof stat.jitter === 'number') jitter.push(stat.jitter);
+                if (stat.kind === 'video') {
+                    sample.framesDecoded += stat.framesDecoded || 0;
+                    sample.framesDropped += stat.framesDropped || 0;
+                    const pixels = (stat.frameWidth || 0) * (stat.frameHeight || 0);
+                    if (pixels > 0 && (!largestVideo || pixels > largestVideo.frameWidth * largestVideo.frameHeight)) {
+                        largestVideo = stat;
+                    }
+                }
+            } else if (stat.type === 'candidate-pair' && stat.nominated && typeof stat.currentRoundTripTime === 'number') {
+                roundTripTimes.push(stat.currentRoundTripTime);
+            }
+        });
+    }
+    const average = (values) => (values.length ? values.reduce((sum, value) => sum + value, 0) / values.length : null);
+    sample.jitterSeconds = average(jitter);
+    sample.roundTripTimeSeconds = average(roundTripTimes);
+    if (largestVideo) {
+        sample.frameWidth = largestVideo.frameWidth;
+        sample.frameHeight = largestVideo.frameHeight;
+        sample.framesPerSecond = largestVideo.framesPerSecond ?? null;
+    }
+    return sample;
+});
+"#;
+
+pub(super) async fn install_peer_connection_tracker(driver: &dyn BrowserDriver) -> Result<()> {
+    let installed = driver.eval(PEER_CONNECTION_TRACKER_INSTALL, None).await?;
+    if installed != serde_json::Value::Bool(true) {
+        eyre::bail!("RTCPeerConnection is not available on this page");
+    }
+    Ok(())
+}
+
+pub(super) async fn sample_webrtc_stats(driver: &dyn BrowserDriver) -> Result<WebRtcStatsSample> {
+    let value = driver.eval(WEBRTC_STATS_SAMPLE, None).await?;
+    serde_json::from_value(value).context("failed to read WebRTC stats from eval result")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::commands::tests::RecordingDriver,
+        *,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn installing_the_tracker_fails_without_peer_connection_support() {
+        let driver = RecordingDriver::with_result(json!(false));
+
+        let error = install_peer_connection_tracker(&driver).await.unwrap_err();
+
+        assert!(error.to_string().contains("RTCPeerConnection is not available"));
+        assert_eq!(driver.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reads_a_sample_from_the_page() {
+        let driver = RecordingDriver::with_result(json!({
+            "timestampMs": 1_700_000_000_000.0,
+            "peerConnections": 2,
+            "bytesSent": 1_000,
+            "bytesReceived": 4_000,
+            "packetsReceived": 40,
+            "packetsLost": 1,
+            "framesDecoded": 300,
+            "framesDropped": 2,
+            "jitterSeconds": 0.004,
+            "roundTripTimeSeconds": null,
+            "frameWidth": 1280,
+            "frameHeight": 720,
+            "framesPerSecond": 29.5,
+        }));
+
+        let sample = sample_webrtc_stats(&driver).await.unwrap();
+
+        assert_eq!(
+            sample,
+            WebRtcStatsSample {
+                timestamp_ms: 1_700_000_000_000.0,
+                peer_connections: 2,
+                bytes_sent: 1_000,
+                bytes_received: 4_000,
+                packets_received: 40,
+                packets_lost: 1,
+                jitter_seconds: Some(0.004),
+                round_trip_time_seconds: None,
+                frames_decoded: 300,
+                frames_dropped: 2,
+                frame_width: Some(1280),
+                frame_height: Some(720),
+                frames_per_second: Some(29.5),
+                received_media: None,
+                glass_to_glass: Vec::new(),
+            }
+        );
+        assert_eq!(driver.calls(), vec![(WEBRTC_STATS_SAMPLE.to_string(), None)]);
+    }
+
+    #[tokio::test]
+    async fn a_page_without_peer_connections_yields_an_empty_sample() {
+        let driver = RecordingDriver::with_result(json!({
+            "timestampMs": 1_000.0,
+            "peerConnections": 0,
+        }));
+
+        let sample = sample_webrtc_stats(&driver).await.unwrap();
+
+        assert_eq!(sample.peer_connections, 0);
+        assert_eq!(sample.bytes_received, 0);
+        assert_eq!(sample.frame_width, None);
+    }
+}
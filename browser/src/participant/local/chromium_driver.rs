@@ -1,8 +1,10 @@
 use crate::participant::frontend::BrowserDriver;
 use chromiumoxide::{
     cdp::js_protocol::runtime::{
+        AddBindingParams,
         CallArgument,
         CallFunctionOnParams,
+        EventBindingCalled,
     },
     js::Evaluation,
     Page,
@@ -14,11 +16,13 @@ use eyre::{
 use futures::{
     future::BoxFuture,
     FutureExt as _,
+    StreamExt as _,
 };
 use std::time::{
     Duration,
     Instant,
 };
+use tokio::sync::watch;
 
 /// `BrowserDriver` implementation backed by a local chromiumoxide `Page` (CDP).
 pub(crate) struct ChromiumDriver {
@@ -155,4 +159,42 @@ impl BrowserDriver for ChromiumDriver {
         }
         .boxed()
     }
+
+    fn add_init_script(&self, script: &str) -> BoxFuture<'_, Result<()>> {
+        let script = script.to_owned();
+        async move {
+            self.page
+                .evaluate_on_new_document(script)
+                .await
+                .context("failed to add init script")?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn add_binding(&self, name: &str) -> BoxFuture<'_, Result<Option<watch::Receiver<()>>>> {
+        let name = name.to_owned();
+        async move {
+            let mut calls = self
+                .page
+                .event_listener::<EventBindingCalled>()
+                .await
+                .context("failed to listen for binding calls")?;
+            self.page
+                .execute(AddBindingParams::new(name.clone()))
+                .await
+                .context("failed to add binding")?;
+            let (changes_tx, changes_rx) = watch::channel(());
+            // Ends with the page, or once nobody listens anymore.
+            tokio::spawn(async move {
+                while let Some(call) = calls.next().await {
+                    if call.name == name && changes_tx.send(()).is_err() {
+                        break;
+                    }
+                }
+            });
+            Ok(Some(changes_rx))
+        }
+        .boxed()
+    }
 }
@@ -4,12 +4,16 @@ use crate::{
         BorrowedCookie,
         HyperSessionCookieManger,
     },
+    media::FakeMediaPreparations,
     participant::{
         frontend::{
+            install_fake_media,
             FrontendAuth,
             FrontendAutomation,
             FrontendContext,
             FrontendKindBuilder,
+            InjectedFakeMedia,
+            ReceivedMediaRecording,
         },
         shared::{
             browser_log::{
@@ -25,6 +29,10 @@ use crate::{
             DriverTermination,
             ParticipantDriverSession,
             ParticipantLaunchSpec,
+            ParticipantWarning,
+            PhaseTimings,
+            StartPhase,
+            WebRtcStatsSample,
         },
     },
 };
@@ -32,7 +40,17 @@ use chromiumoxide::{
     browser,
     cdp::{
         browser_protocol::{
+            browser::{
+                GrantPermissionsParams,
+                PermissionType,
+            },
+            emulation::{
+                SetCpuThrottlingRateParams,
+                SetDeviceMetricsOverrideParams,
+                SetUserAgentOverrideParams,
+            },
             log::EventEntryAdded,
+            network::EmulateNetworkConditionsParams,
             target::{
                 CreateTargetParams,
                 EventDetachedFromTarget,
@@ -55,6 +73,8 @@ use client_simulator_config::{
         FakeMediaFiles,
     },
     BrowserConfig,
+    DeviceConfig,
+    NetworkConfig,
 };
 use eyre::{
     bail,
@@ -79,13 +99,17 @@ use std::{
         },
         Arc,
     },
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 use tokio::{
     sync::watch,
     task::JoinHandle,
     time::timeout,
 };
+use url::Url;
 
 pub(crate) struct LocalChromiumSession {
     launch_spec: ParticipantLaunchSpec,
@@ -100,6 +124,12 @@ pub(crate) struct LocalChromiumSession {
     termination_tx: watch::Sender<Option<DriverTermination>>,
     termination_rx: watch::Receiver<Option<DriverTermination>>,
     closing: Arc<AtomicBool>,
+    timings: PhaseTimings,
+    network: NetworkConfig,
+    /// Set when the configured fake media could not be prepared, overlaid on every refreshed state.
+    fake_media_warning: Option<ParticipantWarning>,
+    /// The received media being recorded, drained with every WebRTC stats sample.
+    recording: Option<ReceivedMediaRecording>,
 }
 
 impl LocalChromiumSession {
@@ -112,6 +142,7 @@ impl LocalChromiumSession {
         let frontend_builder = FrontendAuth::for_kind(launch_spec.frontend_kind, auth, cookie_manager);
         let (termination_tx, termination_rx) = watch::channel(None);
         let closing = Arc::new(AtomicBool::new(false));
+        let network = launch_spec.settings.network;
 
         Self {
             launch_spec,
@@ -126,6 +157,10 @@ impl LocalChromiumSession {
             termination_tx,
             termination_rx,
             closing,
+            timings: PhaseTimings::default(),
+            network,
+            fake_media_warning: None,
+            recording: None,
         }
     }
 
@@ -135,7 +170,24 @@ impl LocalChromiumSession {
 
     async fn start_inner(&mut self) -> Result<()> {
         self.closing.store(false, Ordering::SeqCst);
-        let (mut browser, handler) = create_browser(&self.browser_config).await?;
+        let injected_fake_media = self.injected_fake_media();
+        let fake_media = match injected_fake_media.as_ref().and_then(|media| media.camera.as_ref()) {
+            Some(_) => None,
+            None => self.prepare_fake_media().await,
+        };
+        let screenshare_tab = match self.launch_spec.settings.screenshare_source.tab_url() {
+            Ok(url) => url,
+            Err(err) => {
+                self.log_message(
+                    "warn",
+                    format!("Sharing Chrome's fake screen instead of the screenshare source: {err:#}"),
+                );
+                None
+            }
+        };
+        let launch_started = Instant::now();
+        let (mut browser, handler) =
+            create_browser(&self.browser_config, fake_media, screenshare_tab.is_some()).await?;
         let browser_event_task = drive_browser_events(
             &self.launch_spec.username,
             handler,
@@ -143,6 +195,27 @@ impl LocalChromiumSession {
             Arc::clone(&self.closing),
         );
         let page = create_page_retry(&self.launch_spec, &mut browser).await?;
+        if let Some(url) = &screenshare_tab {
+            open_screenshare_tab(&browser, url).await?;
+            grant_media_permissions(&browser, &self.launch_spec).await?;
+            page.bring_to_front()
+                .await
+                .context("failed to bring the participant tab to the front")?;
+        }
+        let driver = ChromiumDriver::new(page.clone());
+        if let Some(media) = &injected_fake_media {
+            install_fake_media(&driver, media).await?;
+        }
+        if !self.network.is_default() {
+            emulate_network(&page, &self.network).await?;
+            self.log_message("info", format!("Emulating {} network", self.network));
+        }
+        let device = &self.launch_spec.settings.device;
+        if !device.is_default() {
+            emulate_device(&page, device).await?;
+            self.log_message("info", format!("Emulating {device} device"));
+        }
+        self.timings.record(StartPhase::BrowserLaunch, launch_started.elapsed());
         let browser_log_streams = if self.browser_config.app_config.browser_logs {
             Some(open_browser_log_streams(&page).await?)
         } else {
@@ -163,7 +236,8 @@ impl LocalChromiumSession {
         let automation = FrontendKindBuilder::build(
             FrontendContext {
                 launch_spec: self.launch_spec.clone(),
-                driver: Box::new(ChromiumDriver::new(page.clone())),
+                driver: Box::new(driver),
+                timings: self.timings.clone(),
             },
             auth,
         )
@@ -186,6 +260,129 @@ impl LocalChromiumSession {
         Ok(())
     }
 
+    /// The page draws the screen test pattern, and with `inject_fake_media` also serves URLs and test patterns as
+    /// camera and microphone like on remote backends. Other media still needs Chrome's capture flags.
+    fn injected_fake_media(&self) -> Option<InjectedFakeMedia> {
+        let app_config = &self.browser_config.app_config;
+        let media = app_config.fake_media();
+        InjectedFakeMedia::for_participant(
+            app_config.inject_fake_media.then_some(&media),
+            &self.launch_spec.settings.screenshare_source,
+            &self.launch_spec.username,
+        )
+    }
+
+    /// Falls back to Chrome's builtin fake media and reports a warning if the configured media cannot be used.
+    async fn prepare_fake_media(&mut self) -> Option<FakeMediaFiles> {
+        let source = self.browser_config.app_config.fake_media_with_description();
+        let prepared = FakeMediaPreparations::global()
+            .prepare(
+                &source,
+                &self.launch_spec.username,
+                &self.browser_config.cache_dir,
+                self.browser_config.app_config.fake_media_cache.max_size(),
+            )
+            .await;
+        let (files, error) = match prepared {
+            Ok(files) => {
+                let error = files.as_ref().and_then(FakeMediaFiles::error_summary);
+                (files, error)
+            }
+            Err(err) => (None, Some(format!("{err:#}"))),
+        };
+
+        self.fake_media_warning = error.map(|error| {
+            ParticipantWarning::new(
+                "Fake media unavailable",
+                format!(
+                    "{} could not be prepared, falling back to the builtin fake media: {error}",
+                    source.fake_media()
+                ),
+            )
+        });
+        if let Some(warning) = &self.fake_media_warning {
+            self.log_message("warn", &warning.message);
+        }
+        files
+    }
+
+    async fn handle_command_inner(&mut self, message: ParticipantMessage) -> Result<()> {
+        match message {
+            ParticipantMessage::SetNetwork(network) => {
+                emulate_network(self.page()?, &network).await?;
+                self.network = network;
+                self.log_message("info", format!("Emulating {network} network"));
+                Ok(())
+            }
+            ParticipantMessage::StartRecording => self.start_recording_inner().await,
+            ParticipantMessage::StopRecording => self.stop_recording_inner().await,
+            message => self.automation_mut()?.handle_command(message).await,
+        }
+    }
+
+    /// Records into `<data dir>/recordings/<participant>/<start time>`.
+    async fn start_recording_inner(&mut self) -> Result<()> {
+        if self.recording.is_some() {
+            return Ok(());
+        }
+
+        let dir =
+            ReceivedMediaRecording::new_dir(self.browser_config.app_config.data_dir(), &self.launch_spec.username);
+        let recording = ReceivedMediaRecording::start(&ChromiumDriver::new(self.page()?.clone()), dir).await?;
+        self.log_message(
+            "info",
+            format!("Recording received media into {}", recording.dir().display()),
+        );
+        self.recording = Some(recording);
+        Ok(())
+    }
+
+    async fn stop_recording_inner(&mut self) -> Result<()> {
+        let Some(recording) = self.recording.take() else {
+            return Ok(());
+        };
+
+        let dir = recording.dir().to_path_buf();
+        let files = recording.stop(&ChromiumDriver::new(self.page()?.clone())).await?;
+        self.log_message(
+            "info",
+            format!(
+                "Wrote {} recordings of received media to {}",
+                files.len(),
+                dir.display()
+            ),
+        );
+        Ok(())
+    }
+
+    async fn sample_webrtc_stats_inner(&mut self) -> Result<Option<WebRtcStatsSample>> {
+        let sample = self.automation_mut()?.sample_webrtc_stats().await?;
+        if let Some(recording) = self.recording.as_mut() {
+            let driver = ChromiumDriver::new(self.page.clone().context("local browser page not started")?);
+            let drained = recording.drain(&driver).await;
+            if let Err(err) = drained {
+                self.log_message("warn", format!("Failed writing the recorded media: {err:#}"));
+            }
+        }
+        Ok(Some(sample))
+    }
+
+    fn page(&self) -> Result<&Page> {
+        self.page.as_ref().context("local browser page not started")
+    }
+
+    async fn refresh_state_inner(&mut self) -> Result<crate::participant::shared::ParticipantState> {
+        let mut state = self.automation_mut()?.refresh_state().await?;
+        state.network = self.network;
+        state.device = self.launch_spec.settings.device.clone();
+        state.fake_media = self.launch_spec.settings.fake_media.clone();
+        state.recording = self.recording.is_some();
+        if state.warning.is_none() {
+            state.warning = self.fake_media_warning.clone();
+        }
+        Ok(state)
+    }
+
     fn automation_mut(&mut self) -> Result<&mut (dyn FrontendAutomation + 'static)> {
         self.automation
             .as_deref_mut()
@@ -199,6 +396,13 @@ impl LocalChromiumSession {
             handle.abort();
         }
 
+        if let Err(err) = self.stop_recording_inner().await {
+            self.log_message(
+                "error",
+                format!("Failed saving the recordings while closing browser: {err}"),
+            );
+        }
+
         let should_leave = if let Some(automation) = self.automation.as_mut() {
             match automation.refresh_state().await {
                 Ok(state) => state.joined,
@@ -334,11 +538,11 @@ impl ParticipantDriverSession for LocalChromiumSession {
     }
 
     fn handle_command(&mut self, message: ParticipantMessage) -> BoxFuture<'_, Result<()>> {
-        async move { self.automation_mut()?.handle_command(message).await }.boxed()
+        async move { self.handle_command_inner(message).await }.boxed()
     }
 
     fn refresh_state(&mut self) -> BoxFuture<'_, Result<crate::participant::shared::ParticipantState>> {
-        async move { self.automation_mut()?.refresh_state().await }.boxed()
+        async move { self.refresh_state_inner().await }.boxed()
     }
 
     fn close(&mut self) -> BoxFuture<'_, Result<()>> {
@@ -348,11 +552,34 @@ impl ParticipantDriverSession for LocalChromiumSession {
     fn wait_for_termination(&mut self) -> BoxFuture<'_, DriverTermination> {
         async move { self.wait_for_termination_inner().await }.boxed()
     }
+
+    fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<Option<WebRtcStatsSample>>> {
+        async move { self.sample_webrtc_stats_inner().await }.boxed()
+    }
+
+    fn webrtc_stats_interval(&self) -> Option<Duration> {
+        self.launch_spec.settings.webrtc_stats_interval
+    }
+
+    fn received_media_timeout(&self) -> Option<Duration> {
+        self.launch_spec.settings.received_media_timeout
+    }
+
+    fn take_phase_timings(&mut self) -> Vec<(StartPhase, Duration)> {
+        self.timings.take()
+    }
+
+    fn state_changes(&mut self) -> Option<watch::Receiver<()>> {
+        self.automation
+            .as_ref()
+            .and_then(|automation| automation.state_changes())
+    }
 }
 
 const CHROME_BINARY_NAMES: &[&str] = &["chromium", "google-chrome", "google-chrome-stable", "chrome"];
 const BROWSER_CLOSE_COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
 const BROWSER_EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+const SCREENSHARE_TAB_TITLE: &str = "Hyper simulator screenshare";
 #[cfg(any(test, target_os = "macos"))]
 const MACOS_GOOGLE_CHROME_APP_BINARY: &str = "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome";
 #[cfg(any(test, target_os = "macos"))]
@@ -577,40 +804,24 @@ fn browser_log_entry(participant: &str, event: &EventEntryAdded) -> BrowserLogEn
     )
 }
 
-async fn create_browser(browser_config: &BrowserConfig) -> Result<(Browser, Handler)> {
+/// `fake_media` are the prepared files of the configured fake media, if it has any. `screenshare_tab` lets Chrome's
+/// picker share the tab opened by [`open_screenshare_tab`].
+async fn create_browser(
+    browser_config: &BrowserConfig,
+    fake_media: Option<FakeMediaFiles>,
+    screenshare_tab: bool,
+) -> Result<(Browser, Handler)> {
     let binary = get_binary()?;
 
     let mut chrome_args = vec!["no-startup-window".to_string()];
-    match &browser_config.app_config.fake_media() {
-        FakeMedia::None => {}
-        FakeMedia::Builtin => {
-            add_builtin_fake_media_args(&mut chrome_args);
-        }
-        FakeMedia::FileOrUrl(file_or_url) => {
-            add_builtin_fake_media_args(&mut chrome_args);
-
-            let fake_media = tokio::task::block_in_place(move || {
-                match file_or_url
-                    .parse()
-                    .and_then(|input| FakeMediaFiles::from_file_or_url(input, &browser_config.cache_dir))
-                {
-                    Ok(media) => Some(media),
-                    Err(err) => {
-                        error!("Unable to read custom fake media from {file_or_url:?}: {err}");
-                        None
-                    }
-                }
-            });
-
-            if let Some(media) = fake_media {
-                if let Some(audio) = media.audio {
-                    chrome_args.push(chrome_arg_value("use-file-for-fake-audio-capture", audio.display()));
-                }
-                if let Some(video) = media.video {
-                    chrome_args.push(chrome_arg_value("use-file-for-fake-video-capture", video.display()));
-                }
-            }
-        }
+    if browser_config.app_config.fake_media() != FakeMedia::None {
+        add_builtin_fake_media_args(&mut chrome_args);
+    }
+    if let Some(media) = fake_media {
+        add_fake_media_file_args(&mut chrome_args, media);
+    }
+    if screenshare_tab {
+        add_screenshare_tab_args(&mut chrome_args);
     }
 
     let mut config = browser::BrowserConfig::builder();
@@ -640,6 +851,67 @@ fn add_builtin_fake_media_args(chrome_args: &mut Vec<String>) {
     ]);
 }
 
+fn add_fake_media_file_args(chrome_args: &mut Vec<String>, media: FakeMediaFiles) {
+    if let Some(audio) = media.audio {
+        chrome_args.push(chrome_arg_value("use-file-for-fake-audio-capture", audio.display()));
+    }
+    if let Some(video) = media.video {
+        chrome_args.push(chrome_arg_value("use-file-for-fake-video-capture", video.display()));
+    }
+}
+
+/// Chrome's fake UI shares a fake screen without asking, so the real picker selects the screenshare tab by its title
+/// instead. Camera and microphone are then allowed by [`grant_media_permissions`].
+fn add_screenshare_tab_args(chrome_args: &mut Vec<String>) {
+    chrome_args.retain(|arg| arg != "use-fake-ui-for-media-stream");
+    chrome_args.push(chrome_arg_value(
+        "auto-select-tab-capture-source-by-title",
+        SCREENSHARE_TAB_TITLE,
+    ));
+}
+
+/// Opens `url` in its own tab, titled so Chrome's picker selects it once the participant starts a screenshare. Videos
+/// opened directly play muted in a loop.
+async fn open_screenshare_tab(browser: &Browser, url: &Url) -> Result<Page> {
+    let tab = browser
+        .new_page(url.as_str())
+        .await
+        .with_context(|| format!("failed to open the screenshare tab {url}"))?;
+    let setup = format!(
+        r#"(() => {{
+            const title = {SCREENSHARE_TAB_TITLE:?};
+            document.title = title;
+            setInterval(() => {{ if (document.title !== title) document.title = title; }}, 1000);
+            document.querySelectorAll('video').forEach((video) => {{
+                video.loop = true;
+                video.muted = true;
+                video.play().catch(() => {{}});
+            }});
+        }})()"#
+    );
+    tab.evaluate(setup)
+        .await
+        .context("failed to set up the screenshare tab")?;
+    Ok(tab)
+}
+
+async fn grant_media_permissions(browser: &Browser, launch_spec: &ParticipantLaunchSpec) -> Result<()> {
+    let params = GrantPermissionsParams::builder()
+        .permissions([
+            PermissionType::AudioCapture,
+            PermissionType::VideoCapture,
+            PermissionType::DisplayCapture,
+        ])
+        .origin(launch_spec.base_url().origin().ascii_serialization())
+        .build()
+        .map_err(|err| eyre::eyre!("invalid media permissions: {err}"))?;
+    browser
+        .execute(params)
+        .await
+        .context("failed to grant media permissions")?;
+    Ok(())
+}
+
 fn chrome_arg_value(key: &str, value: impl std::fmt::Display) -> String {
     format!("{key}={value}")
 }
@@ -717,6 +989,24 @@ mod tests {
         assert!(args.iter().all(|arg| !arg.starts_with("--")));
     }
 
+    #[test]
+    fn screenshare_tab_args_replace_the_fake_ui_with_tab_selection() {
+        let mut args = vec!["no-startup-window".to_string()];
+        add_builtin_fake_media_args(&mut args);
+
+        add_screenshare_tab_args(&mut args);
+
+        assert_eq!(
+            args,
+            vec![
+                "no-startup-window",
+                "no-sandbox",
+                "use-fake-device-for-media-stream",
+                "auto-select-tab-capture-source-by-title=Hyper simulator screenshare",
+            ]
+        );
+    }
+
     #[test]
     fn fake_media_file_args_use_name_value_format_without_shell_prefix() {
         let audio_arg = chrome_arg_value("use-file-for-fake-audio-capture", "/tmp/audio.wav");
@@ -987,6 +1277,56 @@ async fn create_page(launch_spec: &ParticipantLaunchSpec, browser: &mut Browser)
     Ok(page)
 }
 
+/// Applies `network` to all requests of `page`, including its WebRTC traffic. The default config lifts a previous
+/// emulation again.
+async fn emulate_network(page: &Page, network: &NetworkConfig) -> Result<()> {
+    let conditions = network.conditions();
+    let throughput = |kbps: Option<u64>| kbps.map_or(-1.0, |kbps| kbps as f64 * 1000.0 / 8.0);
+    // Deprecated in favor of `emulateNetworkConditionsByRule`, which current Chromium releases do not all support yet.
+    #[allow(deprecated)]
+    let params = EmulateNetworkConditionsParams::builder()
+        .offline(false)
+        .latency(conditions.map_or(0.0, |conditions| conditions.latency_ms as f64))
+        .download_throughput(throughput(conditions.and_then(|conditions| conditions.download_kbps)))
+        .upload_throughput(throughput(conditions.and_then(|conditions| conditions.upload_kbps)))
+        .packet_loss(conditions.map_or(0.0, |conditions| conditions.packet_loss_percent))
+        .build()
+        .map_err(|err| eyre::eyre!("invalid network conditions: {err}"))?;
+    page.execute(params)
+        .await
+        .context("failed to emulate network conditions")?;
+    Ok(())
+}
+
+/// Throttles the CPU and overrides viewport and user agent of `page` before the frontend navigates to the space.
+async fn emulate_device(page: &Page, device: &DeviceConfig) -> Result<()> {
+    let Some(emulation) = device.emulation() else {
+        return Ok(());
+    };
+
+    page.execute(SetCpuThrottlingRateParams::new(emulation.cpu_throttling_rate))
+        .await
+        .context("failed to throttle the CPU")?;
+    if let Some(metrics) = emulation.metrics {
+        let params = SetDeviceMetricsOverrideParams::builder()
+            .width(metrics.width)
+            .height(metrics.height)
+            .device_scale_factor(metrics.device_scale_factor)
+            .mobile(metrics.mobile)
+            .build()
+            .map_err(|err| eyre::eyre!("invalid device metrics: {err}"))?;
+        page.execute(params)
+            .await
+            .context("failed to override device metrics")?;
+    }
+    if let Some(user_agent) = emulation.user_agent {
+        page.execute(SetUserAgentOverrideParams::new(user_agent))
+            .await
+            .context("failed to override the user agent")?;
+    }
+    Ok(())
+}
+
 async fn create_page_retry(launch_spec: &ParticipantLaunchSpec, browser: &mut Browser) -> Result<Page> {
     let mut backoff = PageRetryBackoff::default();
     let mut attempt = 0;
@@ -6,11 +6,18 @@ use crate::participant::{
     local::session::LocalChromiumSession,
     remote_stub::RemoteStubSession,
     shared::{
-        messages::ParticipantMessage,
+        messages::{
+            CommandError,
+            ParticipantCommand,
+            ParticipantMessage,
+        },
+        register_media_sender,
         run_participant_runtime,
+        spawn_behavior,
         ParticipantDriverSession,
         ParticipantLaunchSpec,
         ResolvedFrontendKind,
+        StartGate,
     },
 };
 use chrono::Utc;
@@ -20,6 +27,8 @@ use client_simulator_config::{
     ParticipantConfig,
 };
 use eyre::{
+    bail,
+    eyre,
     OptionExt as _,
     Result,
 };
@@ -32,6 +41,7 @@ use futures::{
 };
 use std::{
     fmt,
+    future::Future,
     sync::Arc,
 };
 use tokio::{
@@ -58,13 +68,34 @@ mod cloudflare;
 pub mod device_farm;
 mod frontend;
 mod local;
+mod remote;
 mod remote_stub;
 pub mod shared;
 
+pub use remote::{
+    AgentAccess,
+    RemoteAgent,
+};
 pub use shared::{
+    media_sender_id,
+    CallStatus,
+    CommandResult,
+    GlassToGlass,
+    GlassToGlassSummary,
+    GlassToGlassWindow,
+    LatencyMetric,
+    LatencySummary,
+    ParticipantEvent,
+    ParticipantEventKind,
+    ParticipantHistory,
     ParticipantState,
     ParticipantStore,
+    ParticipantTiming,
     ParticipantWarning,
+    ReceivedMedia,
+    SpawnScheduler,
+    StartPhase,
+    WebRtcStats,
 };
 
 #[cfg(not(test))]
@@ -77,9 +108,11 @@ const PARTICIPANT_CLOSE_PROGRESS_INTERVAL: Duration = Duration::from_millis(10);
 pub struct Participant {
     pub name: String,
     pub created: chrono::DateTime<chrono::Utc>,
+    pub backend: ParticipantBackendKind,
     pub state: watch::Receiver<ParticipantState>,
+    pub history: ParticipantHistory,
     participant_task: ParticipantTaskControl,
-    sender: UnboundedSender<ParticipantMessage>,
+    sender: UnboundedSender<ParticipantCommand>,
     close_strategy: CloseStrategy,
 }
 
@@ -134,45 +167,100 @@ impl ParticipantTaskControl {
 
 impl Participant {
     pub fn spawn_with_app_config(config: &Config, cookie_manager: HyperSessionCookieManger) -> Result<Self> {
+        Self::spawn_local_session(
+            config,
+            cookie_manager,
+            SpawnScheduler::default().gate(ParticipantBackendKind::Local),
+        )
+    }
+
+    fn spawn_local_session(
+        config: &Config,
+        cookie_manager: HyperSessionCookieManger,
+        start_gate: StartGate,
+    ) -> Result<Self> {
         let session_url = config.url.clone().ok_or_eyre("No session URL provided in the config")?;
         let base_url = session_url.origin().unicode_serialization();
         let cookie = cookie_manager.give_cookie(&base_url);
         let name = cookie.as_ref().map(BorrowedCookie::username);
         let participant_config = ParticipantConfig::new(config, name)?;
         debug!("Participant config: {:#?}", participant_config);
-        Self::with_participant_config(participant_config, cookie, cookie_manager)
+        Self::with_participant_config_and_gate(participant_config, cookie, cookie_manager, start_gate)
     }
 
     pub fn spawn(config: &Config, cookie_manager: HyperSessionCookieManger) -> Result<Self> {
-        match config.backend {
-            ParticipantBackendKind::Local => Self::spawn_with_app_config(config, cookie_manager),
-            ParticipantBackendKind::Cloudflare => Self::spawn_cloudflare(config, cookie_manager),
-            ParticipantBackendKind::RemoteStub => Self::spawn_remote_stub(config, cookie_manager),
-            ParticipantBackendKind::AwsDeviceFarm => Self::spawn_device_farm(config, cookie_manager),
-        }
+        Self::spawn_scheduled(config, cookie_manager, &SpawnScheduler::default())
+    }
+
+    /// Like [`Participant::spawn`], but the backend session only starts once `scheduler` hands out a slot. Until
+    /// then the participant is reported as pending.
+    pub fn spawn_scheduled(
+        config: &Config,
+        cookie_manager: HyperSessionCookieManger,
+        scheduler: &SpawnScheduler,
+    ) -> Result<Self> {
+        let start_gate = scheduler.gate(config.backend);
+        let participant = match config.backend {
+            ParticipantBackendKind::Local => Self::spawn_local_session(config, cookie_manager, start_gate),
+            ParticipantBackendKind::Cloudflare => Self::spawn_cloudflare_session(config, cookie_manager, start_gate),
+            ParticipantBackendKind::RemoteStub => Self::spawn_remote_stub_session(config, cookie_manager, start_gate),
+            ParticipantBackendKind::Remote => Self::spawn_remote_session(config, start_gate),
+            ParticipantBackendKind::AwsDeviceFarm => {
+                let api = Arc::new(crate::participant::device_farm::AwsTestGrid::new(
+                    &config.device_farm.region,
+                ));
+                Self::spawn_device_farm_session(config, cookie_manager, api, start_gate)
+            }
+        }?;
+
+        spawn_behavior(
+            participant.name.clone(),
+            config,
+            participant.state.clone(),
+            participant.sender.clone(),
+            participant.participant_task.cancellation_token.clone(),
+        );
+        Ok(participant)
     }
 
     pub fn with_participant_config(
         participant_config: ParticipantConfig,
         cookie: Option<BorrowedCookie>,
         cookie_manager: HyperSessionCookieManger,
+    ) -> Result<Self> {
+        Self::with_participant_config_and_gate(
+            participant_config,
+            cookie,
+            cookie_manager,
+            SpawnScheduler::default().gate(ParticipantBackendKind::Local),
+        )
+    }
+
+    fn with_participant_config_and_gate(
+        participant_config: ParticipantConfig,
+        cookie: Option<BorrowedCookie>,
+        cookie_manager: HyperSessionCookieManger,
+        start_gate: StartGate,
     ) -> Result<Self> {
         let launch_spec = ParticipantLaunchSpec::from(participant_config.clone());
         let browser_config = client_simulator_config::BrowserConfig::from(&participant_config);
         let name = launch_spec.username.clone();
 
-        let (sender_tx, receiver_tx) = unbounded_channel::<ParticipantMessage>();
+        let (sender_tx, receiver_tx) = unbounded_channel::<ParticipantCommand>();
 
-        let (state_receiver, task_guard) = spawn_session(
+        let (state_receiver, history, task_guard) = spawn_session(
             name.clone(),
             receiver_tx,
             LocalChromiumSession::new(launch_spec, browser_config, cookie, cookie_manager),
+            start_gate,
         );
 
         Ok(Self {
             name,
             created: chrono::Utc::now(),
+            backend: ParticipantBackendKind::Local,
             state: state_receiver,
+            history,
             participant_task: task_guard,
             sender: sender_tx,
             close_strategy: CloseStrategy::DriverCloseOnly,
@@ -180,6 +268,18 @@ impl Participant {
     }
 
     pub fn spawn_remote_stub(config: &Config, cookie_manager: HyperSessionCookieManger) -> Result<Self> {
+        Self::spawn_remote_stub_session(
+            config,
+            cookie_manager,
+            SpawnScheduler::default().gate(ParticipantBackendKind::RemoteStub),
+        )
+    }
+
+    fn spawn_remote_stub_session(
+        config: &Config,
+        cookie_manager: HyperSessionCookieManger,
+        start_gate: StartGate,
+    ) -> Result<Self> {
         let session_url = config.url.clone().ok_or_eyre("No session URL provided in the config")?;
         let base_url = session_url.origin().unicode_serialization();
         let cookie = cookie_manager.give_cookie(&base_url);
@@ -188,13 +288,46 @@ impl Participant {
         let launch_spec = ParticipantLaunchSpec::from(participant_config);
         let name = launch_spec.username.clone();
 
-        let (sender, receiver) = unbounded_channel::<ParticipantMessage>();
-        let (state_receiver, task_guard) = spawn_session(name.clone(), receiver, RemoteStubSession::new(launch_spec));
+        let (sender, receiver) = unbounded_channel::<ParticipantCommand>();
+        let (state_receiver, history, task_guard) =
+            spawn_session(name.clone(), receiver, RemoteStubSession::new(launch_spec), start_gate);
+
+        Ok(Self {
+            name,
+            created: Utc::now(),
+            backend: ParticipantBackendKind::RemoteStub,
+            state: state_receiver,
+            history,
+            participant_task: task_guard,
+            sender,
+            close_strategy: CloseStrategy::DriverCloseOnly,
+        })
+    }
+
+    /// Spawns a participant on the next agent from `remote.agents`. Agents fetch their own session cookies.
+    pub fn spawn_remote(config: &Config) -> Result<Self> {
+        Self::spawn_remote_session(config, SpawnScheduler::default().gate(ParticipantBackendKind::Remote))
+    }
+
+    fn spawn_remote_session(config: &Config, start_gate: StartGate) -> Result<Self> {
+        let agent = remote::next_agent(&config.remote)?;
+        let participant_config = ParticipantConfig::new(config, None::<String>)?;
+        let name = participant_config.username.clone();
+
+        let (sender, receiver) = unbounded_channel::<ParticipantCommand>();
+        let (state_receiver, history, task_guard) = spawn_session(
+            name.clone(),
+            receiver,
+            remote::RemoteSession::new(name.clone(), config.clone(), agent),
+            start_gate,
+        );
 
         Ok(Self {
             name,
             created: Utc::now(),
+            backend: ParticipantBackendKind::Remote,
             state: state_receiver,
+            history,
             participant_task: task_guard,
             sender,
             close_strategy: CloseStrategy::DriverCloseOnly,
@@ -202,6 +335,18 @@ impl Participant {
     }
 
     pub fn spawn_cloudflare(config: &Config, cookie_manager: HyperSessionCookieManger) -> Result<Self> {
+        Self::spawn_cloudflare_session(
+            config,
+            cookie_manager,
+            SpawnScheduler::default().gate(ParticipantBackendKind::Cloudflare),
+        )
+    }
+
+    fn spawn_cloudflare_session(
+        config: &Config,
+        cookie_manager: HyperSessionCookieManger,
+        start_gate: StartGate,
+    ) -> Result<Self> {
         let session_url = config.url.clone().ok_or_eyre("No session URL provided in the config")?;
         let frontend_kind = ResolvedFrontendKind::from_session_url(&session_url);
         let base_url = session_url.origin().unicode_serialization();
@@ -213,8 +358,8 @@ impl Participant {
         let launch_spec = ParticipantLaunchSpec::from(participant_config);
         let name = launch_spec.username.clone();
 
-        let (sender, receiver) = unbounded_channel::<ParticipantMessage>();
-        let (state_receiver, task_guard) = spawn_session(
+        let (sender, receiver) = unbounded_channel::<ParticipantCommand>();
+        let (state_receiver, history, task_guard) = spawn_session(
             name.clone(),
             receiver,
             cloudflare::CloudflareSession::new(
@@ -224,12 +369,15 @@ impl Participant {
                 cookie,
                 cookie_manager,
             ),
+            start_gate,
         );
 
         Ok(Self {
             name,
             created: Utc::now(),
+            backend: ParticipantBackendKind::Cloudflare,
             state: state_receiver,
+            history,
             participant_task: task_guard,
             sender,
             close_strategy: CloseStrategy::LeaveBeforeClose,
@@ -249,6 +397,20 @@ impl Participant {
         config: &Config,
         cookie_manager: HyperSessionCookieManger,
         api: Arc<dyn crate::testing::TestGridApi>,
+    ) -> Result<Self> {
+        Self::spawn_device_farm_session(
+            config,
+            cookie_manager,
+            api,
+            SpawnScheduler::default().gate(ParticipantBackendKind::AwsDeviceFarm),
+        )
+    }
+
+    fn spawn_device_farm_session(
+        config: &Config,
+        cookie_manager: HyperSessionCookieManger,
+        api: Arc<dyn crate::testing::TestGridApi>,
+        start_gate: StartGate,
     ) -> Result<Self> {
         use crate::participant::device_farm::{
             DeviceFarmLaunchOptions,
@@ -269,9 +431,9 @@ impl Participant {
         let device_farm_config = config.device_farm.clone();
         let launch_options = DeviceFarmLaunchOptions::from(config);
 
-        let (sender, receiver) = unbounded_channel::<ParticipantMessage>();
+        let (sender, receiver) = unbounded_channel::<ParticipantCommand>();
 
-        let (state_receiver, task_guard) = spawn_session(
+        let (state_receiver, history, task_guard) = spawn_session(
             name.clone(),
             receiver,
             DeviceFarmSession::new(
@@ -282,12 +444,15 @@ impl Participant {
                 cookie_manager,
                 api,
             ),
+            start_gate,
         );
 
         Ok(Self {
             name,
             created: Utc::now(),
+            backend: ParticipantBackendKind::AwsDeviceFarm,
             state: state_receiver,
+            history,
             participant_task: task_guard,
             sender,
             close_strategy: CloseStrategy::DriverCloseOnly,
@@ -297,19 +462,36 @@ impl Participant {
 
 fn spawn_session<S>(
     name: String,
-    receiver: UnboundedReceiver<ParticipantMessage>,
+    receiver: UnboundedReceiver<ParticipantCommand>,
     session: S,
-) -> (watch::Receiver<ParticipantState>, ParticipantTaskControl)
+    start_gate: StartGate,
+) -> (
+    watch::Receiver<ParticipantState>,
+    ParticipantHistory,
+    ParticipantTaskControl,
+)
 where
     S: ParticipantDriverSession + 'static,
 {
+    // Recordings of other participants name the media they received from this one after it.
+    register_media_sender(&name);
     let task_cancellation_token = CancellationToken::new();
     let task_token = task_cancellation_token.clone();
     let task_cancellation_guard = task_cancellation_token.clone().drop_guard();
     let (state_sender, state_receiver) = watch::channel(Default::default());
+    let history = ParticipantHistory::default();
+    let task_history = history.clone();
 
     let handle = tokio::task::spawn(async move {
-        let result = run_participant_runtime(receiver, state_sender, session, task_token.clone()).await;
+        let result = run_participant_runtime(
+            receiver,
+            state_sender,
+            task_history,
+            session,
+            task_token.clone(),
+            start_gate,
+        )
+        .await;
 
         if let Err(err) = result {
             error!(participant = %name, "Failed to create participant: {err}");
@@ -320,6 +502,7 @@ where
 
     (
         state_receiver,
+        history,
         ParticipantTaskControl::new(task_cancellation_token, task_cancellation_guard, handle),
     )
 }
@@ -350,7 +533,7 @@ impl Participant {
         }
 
         if self.close_strategy == CloseStrategy::LeaveBeforeClose && initial_state.joined {
-            if self.sender.send(ParticipantMessage::Leave).is_err() {
+            if self.sender.send(ParticipantMessage::Leave.into()).is_err() {
                 error!(participant = %self.name, "Was not able to send ParticipantMessage::Leave message");
             } else {
                 match timeout(
@@ -370,7 +553,7 @@ impl Participant {
             }
         }
 
-        if self.sender.send(ParticipantMessage::Close).is_ok() {
+        if self.sender.send(ParticipantMessage::Close.into()).is_ok() {
             // The timeout is only a progress heartbeat. Returning while the
             // participant task is still running lets the TUI exit and drop
             // driver state during Tokio shutdown.
@@ -401,11 +584,15 @@ impl Participant {
             debug!(self.name, "Already closed the browser");
             return;
         }
+        if state.pending {
+            debug!(self.name, "Still waiting to start");
+            return;
+        }
         if state.joined {
             debug!(self.name, "Already joined");
             return;
         }
-        if self.sender.send(ParticipantMessage::Join).is_err() {
+        if self.sender.send(ParticipantMessage::Join.into()).is_err() {
             error!("Was not able to send ParticipantMessage::Join message")
         }
     }
@@ -424,13 +611,58 @@ impl Participant {
             debug!(self.name, "Cannot send message {}, not in the space yet", &message);
             return;
         }
-        if self.sender.send(message.clone()).is_err() {
+        if self.sender.send(message.clone().into()).is_err() {
             error!("Was not able to send message: {message}")
         }
 
         debug!("Sent message {message:?}");
     }
 
+    /// Like [`Participant::send_message`], but reports why a message could not be delivered instead of only
+    /// logging it.
+    pub fn try_send_message(&self, message: ParticipantMessage) -> Result<()> {
+        if let Err(err) = self.check_message(&message) {
+            bail!("Cannot send message {message} to `{}`: {err}", self.name);
+        }
+
+        self.sender
+            .send(message.into())
+            .map_err(|err| eyre!("Was not able to send message {} to `{}`", err.0.message, self.name))
+    }
+
+    /// Sends `message` and resolves once the participant ran it, or with why it was rejected or failed. Unlike
+    /// [`Participant::try_send_message`], the outcome of the backend is awaited as well.
+    pub fn run_command(
+        &self,
+        message: ParticipantMessage,
+    ) -> impl Future<Output = std::result::Result<(), CommandError>> + Send + 'static {
+        let sent = self.check_message(&message).and_then(|()| {
+            let (command, reply) = ParticipantCommand::with_reply(message);
+            self.sender
+                .send(command)
+                .map(|()| reply)
+                .map_err(|_| CommandError::NotRunning)
+        });
+        async move { sent?.await.unwrap_or(Err(CommandError::Dropped)) }
+    }
+
+    /// Whether the participant can take `message` in its current state.
+    fn check_message(&self, message: &ParticipantMessage) -> std::result::Result<(), CommandError> {
+        let state = self.state.borrow();
+        if !state.running {
+            return Err(CommandError::NotRunning);
+        }
+        if state.pending {
+            return Err(CommandError::Pending);
+        }
+        match message {
+            ParticipantMessage::Join if state.joined => Err(CommandError::AlreadyJoined),
+            ParticipantMessage::Join | ParticipantMessage::Close => Ok(()),
+            _ if !state.joined => Err(CommandError::NotJoined),
+            _ => Ok(()),
+        }
+    }
+
     pub fn leave(&self) {
         self.send_message(ParticipantMessage::Leave);
     }
@@ -474,6 +706,38 @@ impl Participant {
     pub fn toggle_background_blur(&self) {
         self.send_message(ParticipantMessage::ToggleBackgroundBlur);
     }
+
+    pub fn set_audio_enabled(&self, value: bool) {
+        self.send_message(ParticipantMessage::SetAudioEnabled(value));
+    }
+
+    pub fn set_video_enabled(&self, value: bool) {
+        self.send_message(ParticipantMessage::SetVideoEnabled(value));
+    }
+
+    pub fn set_screenshare(&self, value: bool) {
+        self.send_message(ParticipantMessage::SetScreenshare(value));
+    }
+
+    pub fn set_blur(&self, value: bool) {
+        self.send_message(ParticipantMessage::SetBlur(value));
+    }
+
+    pub fn set_auto_gain_control(&self, value: bool) {
+        self.send_message(ParticipantMessage::SetAutoGainControl(value));
+    }
+
+    pub fn set_network(&self, value: client_simulator_config::NetworkConfig) {
+        self.send_message(ParticipantMessage::SetNetwork(value));
+    }
+
+    pub fn start_recording(&self) {
+        self.send_message(ParticipantMessage::StartRecording);
+    }
+
+    pub fn stop_recording(&self) {
+        self.send_message(ParticipantMessage::StopRecording);
+    }
 }
 
 #[cfg(test)]
@@ -555,19 +819,22 @@ mod tests {
         let (command_tx, command_rx) = unbounded_channel();
         let commands = Arc::new(Mutex::new(Vec::new()));
         let close_count = Arc::new(AtomicUsize::new(0));
-        let (state, task_guard) = spawn_session(
+        let (state, history, task_guard) = spawn_session(
             "sim-user".to_string(),
             command_rx,
             RecordingCloseDriver {
                 commands: Arc::clone(&commands),
                 close_count: Arc::clone(&close_count),
             },
+            SpawnScheduler::default().gate(ParticipantBackendKind::Local),
         );
 
         let participant = Participant {
             name: "sim-user".to_string(),
             created: Utc::now(),
+            backend: ParticipantBackendKind::Local,
             state,
+            history,
             participant_task: task_guard,
             sender: command_tx,
             close_strategy: CloseStrategy::DriverCloseOnly,
@@ -633,18 +900,21 @@ mod tests {
     async fn close_keeps_waiting_after_progress_timeout_until_driver_stops() {
         let (command_tx, command_rx) = unbounded_channel();
         let (close_tx, close_rx) = oneshot::channel();
-        let (state, task_guard) = spawn_session(
+        let (state, history, task_guard) = spawn_session(
             "sim-user".to_string(),
             command_rx,
             BlockingCloseDriver {
                 close_rx: Some(close_rx),
             },
+            SpawnScheduler::default().gate(ParticipantBackendKind::Local),
         );
 
         let participant = Participant {
             name: "sim-user".to_string(),
             created: Utc::now(),
+            backend: ParticipantBackendKind::Local,
             state,
+            history,
             participant_task: task_guard,
             sender: command_tx,
             close_strategy: CloseStrategy::DriverCloseOnly,
@@ -752,7 +1022,7 @@ mod tests {
         let (start_tx, start_rx) = oneshot::channel();
         let start_cancel_count = Arc::new(AtomicUsize::new(0));
         let close_count = Arc::new(AtomicUsize::new(0));
-        let (state, task_guard) = spawn_session(
+        let (state, history, task_guard) = spawn_session(
             "sim-user".to_string(),
             command_rx,
             BlockingStartDriver {
@@ -760,12 +1030,15 @@ mod tests {
                 start_cancel_count: Arc::clone(&start_cancel_count),
                 close_count: Arc::clone(&close_count),
             },
+            SpawnScheduler::default().gate(ParticipantBackendKind::Local),
         );
 
         let participant = Participant {
             name: "sim-user".to_string(),
             created: Utc::now(),
+            backend: ParticipantBackendKind::Local,
             state: state.clone(),
+            history,
             participant_task: task_guard,
             sender: command_tx,
             close_strategy: CloseStrategy::DriverCloseOnly,
@@ -837,18 +1110,21 @@ mod tests {
     async fn close_cleans_up_participant_before_running_state_is_published() {
         let (command_tx, command_rx) = unbounded_channel();
         let close_count = Arc::new(AtomicUsize::new(0));
-        let (state, task_guard) = spawn_session(
+        let (state, history, task_guard) = spawn_session(
             "sim-user".to_string(),
             command_rx,
             PendingStartDriver {
                 close_count: Arc::clone(&close_count),
             },
+            SpawnScheduler::default().gate(ParticipantBackendKind::Local),
         );
 
         let participant = Participant {
             name: "sim-user".to_string(),
             created: Utc::now(),
+            backend: ParticipantBackendKind::Local,
             state: state.clone(),
+            history,
             participant_task: task_guard,
             sender: command_tx,
             close_strategy: CloseStrategy::DriverCloseOnly,
@@ -876,7 +1152,9 @@ mod tests {
         let participant = Participant {
             name: "sim-user".to_string(),
             created: Utc::now(),
+            backend: ParticipantBackendKind::Local,
             state,
+            history: ParticipantHistory::default(),
             participant_task,
             sender: command_tx,
             close_strategy: CloseStrategy::DriverCloseOnly,
@@ -893,4 +1171,110 @@ mod tests {
         timeout(Duration::from_secs(1), close_task_one).await.unwrap().unwrap();
         timeout(Duration::from_secs(1), close_task_two).await.unwrap().unwrap();
     }
+
+    #[tokio::test]
+    async fn try_send_message_rejects_messages_the_participant_cannot_handle() {
+        let (command_tx, mut command_rx) = unbounded_channel();
+        let (state_tx, state) = watch::channel(ParticipantState::default());
+        let task_cancellation_token = CancellationToken::new();
+        let task_cancellation_guard = task_cancellation_token.clone().drop_guard();
+        let participant = Participant {
+            name: "sim-user".to_string(),
+            created: Utc::now(),
+            backend: ParticipantBackendKind::Local,
+            state,
+            history: ParticipantHistory::default(),
+            participant_task: ParticipantTaskControl::new(
+                task_cancellation_token,
+                task_cancellation_guard,
+                tokio::spawn(async {}),
+            ),
+            sender: command_tx,
+            close_strategy: CloseStrategy::DriverCloseOnly,
+        };
+
+        let error = participant
+            .try_send_message(ParticipantMessage::ToggleAudio)
+            .expect_err("stopped participant should reject messages");
+        assert!(error.to_string().contains("not running"), "{error}");
+
+        state_tx.send_modify(|state| {
+            state.running = true;
+            state.pending = true;
+        });
+        let error = participant
+            .try_send_message(ParticipantMessage::Join)
+            .expect_err("pending participant should reject messages");
+        assert!(error.to_string().contains("still waiting to start"), "{error}");
+
+        state_tx.send_modify(|state| state.pending = false);
+        let error = participant
+            .try_send_message(ParticipantMessage::ToggleAudio)
+            .expect_err("participant outside the space should reject messages");
+        assert!(error.to_string().contains("not in the space yet"), "{error}");
+        participant
+            .try_send_message(ParticipantMessage::Join)
+            .expect("join should be accepted");
+
+        state_tx.send_modify(|state| state.joined = true);
+        let error = participant
+            .try_send_message(ParticipantMessage::Join)
+            .expect_err("joined participant should reject join");
+        assert!(error.to_string().contains("already joined"), "{error}");
+        participant
+            .try_send_message(ParticipantMessage::ToggleAudio)
+            .expect("joined participant should accept messages");
+
+        assert!(matches!(
+            command_rx.recv().await.map(|command| command.message),
+            Some(ParticipantMessage::Join)
+        ));
+        assert!(matches!(
+            command_rx.recv().await.map(|command| command.message),
+            Some(ParticipantMessage::ToggleAudio)
+        ));
+    }
+
+    #[tokio::test]
+    async fn run_command_resolves_with_the_outcome_of_the_command() {
+        let (command_tx, mut command_rx) = unbounded_channel();
+        let (state_tx, state) = watch::channel(ParticipantState {
+            running: true,
+            ..Default::default()
+        });
+        let task_cancellation_token = CancellationToken::new();
+        let task_cancellation_guard = task_cancellation_token.clone().drop_guard();
+        let participant = Participant {
+            name: "sim-user".to_string(),
+            created: Utc::now(),
+            backend: ParticipantBackendKind::Local,
+            state,
+            history: ParticipantHistory::default(),
+            participant_task: ParticipantTaskControl::new(
+                task_cancellation_token,
+                task_cancellation_guard,
+                tokio::spawn(async {}),
+            ),
+            sender: command_tx,
+            close_strategy: CloseStrategy::DriverCloseOnly,
+        };
+
+        assert_eq!(
+            participant.run_command(ParticipantMessage::ToggleAudio).await,
+            Err(CommandError::NotJoined)
+        );
+
+        state_tx.send_modify(|state| state.joined = true);
+        let outcome = tokio::spawn(participant.run_command(ParticipantMessage::ToggleAudio));
+        let mut command = command_rx.recv().await.unwrap();
+        command.reply(Err(CommandError::Failed("button missing".to_string())));
+        assert_eq!(
+            outcome.await.unwrap(),
+            Err(CommandError::Failed("button missing".to_string()))
+        );
+
+        let outcome = participant.run_command(ParticipantMessage::ToggleVideo);
+        drop(command_rx.recv().await.unwrap());
+        assert_eq!(outcome.await, Err(CommandError::Dropped));
+    }
 }
@@ -0,0 +1,408 @@
+use super::protocol::{
+    write_line,
+    AgentRequest,
+    AgentResponse,
+    PhaseSample,
+    TerminationMessage,
+};
+use crate::{
+    auth::{
+        HyperSessionCookieManger,
+        HyperSessionCookieStash,
+    },
+    participant::{
+        local::session::LocalChromiumSession,
+        remote_stub::RemoteStubSession,
+        shared::{
+            DriverTermination,
+            ParticipantDriverSession,
+            ParticipantLaunchSpec,
+        },
+    },
+};
+use client_simulator_config::{
+    media::{
+        FakeMedia,
+        FakeMediaFileOrUrl,
+        ScreenshareSource,
+    },
+    BrowserConfig,
+    Config,
+    ParticipantBackendKind,
+    ParticipantConfig,
+};
+use eyre::{
+    bail,
+    Context as _,
+    OptionExt as _,
+    Result,
+};
+use std::{
+    future::pending,
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    io::{
+        AsyncBufReadExt as _,
+        BufReader,
+    },
+    net::{
+        TcpListener,
+        TcpStream,
+    },
+    sync::watch,
+    task::{
+        JoinHandle,
+        JoinSet,
+    },
+};
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+type HostedSession = Box<dyn ParticipantDriverSession>;
+
+/// Hosts participant sessions for simulators using the `remote` backend, one session per TCP connection.
+///
+/// Dropping a connection closes its session, so participants never outlive the simulator that started them.
+#[derive(Debug)]
+pub struct RemoteAgent {
+    local_addr: SocketAddr,
+    shutdown: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+/// Who may start participants on an agent, and what the simulators may make them open.
+#[derive(Debug, Clone, Default)]
+pub struct AgentAccess {
+    /// Shared secret every simulator sends when it starts a participant, see
+    /// [`client_simulator_config::RemoteConfig::token`].
+    pub token: String,
+    /// Hosts the session URL and fake media or screenshare URLs may point to. Empty allows any host.
+    pub allowed_hosts: Vec<String>,
+    /// Lets simulators use fake media and screenshare files on the agent's machine.
+    pub allow_local_files: bool,
+}
+
+impl AgentAccess {
+    /// Compares in constant time, so the token cannot be guessed byte by byte.
+    fn accepts(&self, token: &str) -> bool {
+        let (expected, actual) = (self.token.as_bytes(), token.as_bytes());
+        expected.len() == actual.len()
+            && expected
+                .iter()
+                .zip(actual)
+                .fold(0, |diff, (expected, actual)| diff | (expected ^ actual))
+                == 0
+    }
+
+    /// Rejects the parts of a simulator's config that would let it reach beyond the session: other URL schemes,
+    /// hosts that are not allowed and, unless allowed, files on this machine.
+    fn check_config(&self, config: &Config, session_url: &Url) -> Result<()> {
+        self.check_url(session_url, "session URL")?;
+        match config.fake_media() {
+            FakeMedia::None | FakeMedia::Builtin | FakeMedia::TestPattern => {}
+            FakeMedia::FileOrUrl(source) => self.check_source(&source, "fake media")?,
+            FakeMedia::NoiseMix(mix) => {
+                self.check_source(&mix.speech, "fake media speech")?;
+                self.check_source(&mix.noise, "fake media noise")?;
+            }
+        }
+        match &config.screenshare_source {
+            ScreenshareSource::Builtin | ScreenshareSource::TestPattern => {}
+            ScreenshareSource::Tab(source) => self.check_source(source, "screenshare source")?,
+        }
+        Ok(())
+    }
+
+    fn check_source(&self, source: &str, what: &str) -> Result<()> {
+        match source.parse::<FakeMediaFileOrUrl>() {
+            Ok(FakeMediaFileOrUrl::Url(url)) => self.check_url(&url, what),
+            // Files that do not exist here fail to parse, which must not let them pass.
+            Ok(FakeMediaFileOrUrl::File(_)) | Err(_) if self.allow_local_files => Ok(()),
+            Ok(FakeMediaFileOrUrl::File(_)) | Err(_) => {
+                bail!("The agent does not allow local files as {what}, start it with --allow-local-files")
+            }
+        }
+    }
+
+    fn check_url(&self, url: &Url, what: &str) -> Result<()> {
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("The agent only allows http and https URLs as {what}, not {url}");
+        }
+        let host = url.host_str().unwrap_or_default();
+        if !self.allowed_hosts.is_empty() && !self.allowed_hosts.iter().any(|allowed| allowed == host) {
+            bail!("The agent does not allow the host of the {what} {url}");
+        }
+        Ok(())
+    }
+}
+
+struct AgentHost {
+    config: Config,
+    backend: ParticipantBackendKind,
+    cookies: HyperSessionCookieManger,
+    access: AgentAccess,
+}
+
+impl RemoteAgent {
+    /// Binds `addr` and hosts `backend` sessions, which must be `local` or `remote-stub`. `config` provides the data
+    /// directory, the rest of the participant config comes from the simulator, within the limits of `access`.
+    pub async fn start(
+        addr: SocketAddr,
+        config: Config,
+        backend: ParticipantBackendKind,
+        access: AgentAccess,
+    ) -> Result<Self> {
+        if !matches!(
+            backend,
+            ParticipantBackendKind::Local | ParticipantBackendKind::RemoteStub
+        ) {
+            bail!("Agents can only host local or remote-stub participants, not {backend}");
+        }
+        if access.token.is_empty() {
+            bail!("Agents need a token that simulators must send to start participants");
+        }
+
+        let listener = TcpListener::bind(addr)
+            .await
+            .wrap_err_with(|| format!("Failed to bind the agent to {addr}"))?;
+        let local_addr = listener.local_addr()?;
+        let shutdown = CancellationToken::new();
+        let host = Arc::new(AgentHost {
+            cookies: HyperSessionCookieStash::load_from_data_dir(config.data_dir()).into(),
+            config,
+            backend,
+            access,
+        });
+
+        let task = tokio::spawn(accept_connections(listener, host, shutdown.clone()));
+        info!("Agent hosting {backend} participants on {local_addr}");
+
+        Ok(Self {
+            local_addr,
+            shutdown,
+            task,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stops accepting connections and closes all hosted sessions.
+    pub async fn shutdown(self) {
+        self.shutdown.cancel();
+        if let Err(err) = self.task.await {
+            error!("Agent task failed: {err}");
+        }
+    }
+}
+
+async fn accept_connections(listener: TcpListener, host: Arc<AgentHost>, shutdown: CancellationToken) {
+    let mut connections = JoinSet::new();
+
+    loop {
+        let accepted = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+            accepted = listener.accept() => accepted,
+        };
+        match accepted {
+            Ok((stream, peer)) => {
+                debug!("Agent accepted connection from {peer}");
+                connections.spawn(serve_connection(stream, peer, host.clone(), shutdown.clone()));
+            }
+            Err(err) => {
+                error!("Agent failed to accept a connection: {err}");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+
+    while connections.join_next().await.is_some() {}
+}
+
+/// Runs the requests of one simulator participant against its hosted session until the connection closes.
+async fn serve_connection(stream: TcpStream, peer: SocketAddr, host: Arc<AgentHost>, shutdown: CancellationToken) {
+    let _ = stream.set_nodelay(true);
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut session: Option<HostedSession> = None;
+    let mut state_changes: Option<watch::Receiver<()>> = None;
+    let mut terminated = false;
+    let mut authorized = false;
+
+    loop {
+        let line = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            termination = wait_for_termination(&mut session), if !terminated => {
+                terminated = true;
+                let response = AgentResponse::Terminated(TerminationMessage::from(&termination));
+                if write_line(&mut writer, &response).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            _ = state_changed(&mut state_changes) => {
+                if write_line(&mut writer, &AgentResponse::StateChanged).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            line = lines.next_line() => line,
+        };
+        let request = match line {
+            Ok(Some(line)) => match serde_json::from_str::<AgentRequest>(&line) {
+                Ok(request) => request,
+                Err(err) => {
+                    warn!("Agent received an invalid request from {peer}: {err}");
+                    break;
+                }
+            },
+            Ok(None) => break,
+            Err(err) => {
+                warn!("Agent lost the connection to {peer}: {err}");
+                break;
+            }
+        };
+        if let AgentRequest::Start { token, .. } = &request {
+            authorized = host.access.accepts(token);
+        }
+        if !authorized {
+            warn!("Agent rejected {peer}, it did not start with the agent's token");
+            let _ = write_line(&mut writer, &failed("The agent token is missing or wrong")).await;
+            break;
+        }
+
+        let response = host.handle(&mut session, request).await;
+        if write_line(&mut writer, &response).await.is_err() {
+            break;
+        }
+        match session.as_mut() {
+            Some(session) if state_changes.is_none() => state_changes = session.state_changes(),
+            Some(_) => {}
+            None => state_changes = None,
+        }
+    }
+
+    if let Some(mut session) = session {
+        if let Err(err) = session.close().await {
+            error!(participant = %session.participant_name(), "Failed closing hosted participant: {err}");
+        }
+    }
+    debug!("Agent closed connection from {peer}");
+}
+
+async fn wait_for_termination(session: &mut Option<HostedSession>) -> DriverTermination {
+    match session {
+        Some(session) => session.wait_for_termination().await,
+        None => pending().await,
+    }
+}
+
+async fn state_changed(state_changes: &mut Option<watch::Receiver<()>>) {
+    match state_changes {
+        Some(changes) if changes.changed().await.is_ok() => {}
+        _ => pending().await,
+    }
+}
+
+impl AgentHost {
+    async fn handle(&self, session: &mut Option<HostedSession>, request: AgentRequest) -> AgentResponse {
+        if let AgentRequest::Start { username, config, .. } = request {
+            if session.is_some() {
+                return failed("A participant was already started on this connection");
+            }
+            let hosted = match self.session(username, *config) {
+                Ok(hosted) => session.insert(hosted),
+                Err(err) => return failed(err),
+            };
+            return match hosted.start().await {
+                Ok(()) => AgentResponse::Started {
+                    phases: PhaseSample::from_phases(hosted.take_phase_timings()),
+                    state_refresh_interval_ms: hosted.state_refresh_interval().map(|value| value.as_millis() as u64),
+                    webrtc_stats_interval_ms: hosted.webrtc_stats_interval().map(|value| value.as_millis() as u64),
+                },
+                Err(err) => AgentResponse::Failed {
+                    error: format!("{err:#}"),
+                    warning: hosted.start_error_warning(&err),
+                    termination: None,
+                },
+            };
+        }
+
+        let Some(hosted) = session.as_mut() else {
+            if let AgentRequest::Close = request {
+                return AgentResponse::Done { phases: Vec::new() };
+            }
+            return failed("No participant was started on this connection");
+        };
+
+        match request {
+            AgentRequest::Start { .. } => unreachable!("handled above"),
+            AgentRequest::Command { message } => match hosted.handle_command(message).await {
+                Ok(()) => AgentResponse::Done {
+                    phases: PhaseSample::from_phases(hosted.take_phase_timings()),
+                },
+                Err(err) => failed(err),
+            },
+            AgentRequest::RefreshState => match hosted.refresh_state().await {
+                Ok(state) => AgentResponse::State { state },
+                Err(err) => AgentResponse::Failed {
+                    error: format!("{err:#}"),
+                    warning: None,
+                    termination: hosted
+                        .state_refresh_error_termination(&err)
+                        .as_ref()
+                        .map(TerminationMessage::from),
+                },
+            },
+            AgentRequest::SampleWebRtcStats => match hosted.sample_webrtc_stats().await {
+                Ok(sample) => AgentResponse::WebRtcStats { sample },
+                Err(err) => failed(err),
+            },
+            AgentRequest::Close => {
+                let result = hosted.close().await;
+                *session = None;
+                match result {
+                    Ok(()) => AgentResponse::Done { phases: Vec::new() },
+                    Err(err) => failed(err),
+                }
+            }
+        }
+    }
+
+    fn session(&self, username: String, mut config: Config) -> Result<HostedSession> {
+        config.app_config = self.config.app_config.clone();
+        config.backend = self.backend;
+        let session_url = config.url.clone().ok_or_eyre("No session URL provided in the config")?;
+        self.access.check_config(&config, &session_url)?;
+        let participant_config = ParticipantConfig {
+            username,
+            session_url,
+            app_config: config,
+        };
+        let launch_spec = ParticipantLaunchSpec::from(participant_config.clone());
+        info!(participant = %launch_spec.username, "Agent starting {} participant", self.backend);
+
+        Ok(match self.backend {
+            ParticipantBackendKind::RemoteStub => Box::new(RemoteStubSession::new(launch_spec)),
+            _ => Box::new(LocalChromiumSession::new(
+                launch_spec,
+                BrowserConfig::from(&participant_config),
+                None,
+                self.cookies.clone(),
+            )),
+        })
+    }
+}
+
+fn failed(error: impl std::fmt::Display) -> AgentResponse {
+    AgentResponse::Failed {
+        error: format!("{error:#}"),
+        warning: None,
+        termination: None,
+    }
+}
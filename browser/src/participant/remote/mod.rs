@@ -0,0 +1,399 @@
+//! The `remote` backend: participants run on agent processes (see [`RemoteAgent`]) on other machines, while the
+//! participant runtime stays in this process. [`RemoteSession`] forwards every driver call over a TCP connection to
+//! the agent, which runs the actual session.
+
+mod agent;
+mod protocol;
+
+use crate::participant::shared::{
+    messages::{
+        ParticipantLogMessage,
+        ParticipantMessage,
+    },
+    DriverTermination,
+    ParticipantDriverSession,
+    ParticipantState,
+    ParticipantWarning,
+    StartPhase,
+    WebRtcStatsSample,
+};
+pub use agent::{
+    AgentAccess,
+    RemoteAgent,
+};
+use client_simulator_config::{
+    Config,
+    RemoteConfig,
+    AGENT_TOKEN_ENV,
+};
+use eyre::{
+    bail,
+    eyre,
+    Context as _,
+    OptionExt as _,
+    Result,
+};
+use futures::{
+    future::BoxFuture,
+    FutureExt as _,
+};
+use protocol::{
+    write_line,
+    AgentRequest,
+    AgentResponse,
+    PhaseSample,
+};
+use std::{
+    future::pending,
+    sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{
+        AsyncBufReadExt as _,
+        BufReader,
+    },
+    net::{
+        tcp::OwnedWriteHalf,
+        TcpStream,
+    },
+    sync::{
+        mpsc::{
+            unbounded_channel,
+            UnboundedReceiver,
+        },
+        watch,
+    },
+    task::JoinHandle,
+    time::timeout,
+};
+
+/// Round-robin position over `remote.agents`, shared by all remote participants of the process.
+static NEXT_AGENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Picks the agent for the next remote participant.
+pub(super) fn next_agent(config: &RemoteConfig) -> Result<String> {
+    if config.agents.is_empty() {
+        bail!("The remote backend needs at least one agent in `remote.agents`");
+    }
+    let index = NEXT_AGENT.fetch_add(1, Ordering::Relaxed) % config.agents.len();
+    Ok(config.agents[index].clone())
+}
+
+pub(super) struct RemoteSession {
+    username: String,
+    config: Config,
+    agent: String,
+    connect_timeout: Duration,
+    connection: Option<AgentConnection>,
+    phases: Vec<(StartPhase, Duration)>,
+    state_refresh_interval: Option<Duration>,
+    webrtc_stats_interval: Option<Duration>,
+    /// What the agent derived from the last failed request, see [`AgentResponse::Failed`].
+    last_warning: Option<ParticipantWarning>,
+    last_termination: Option<DriverTermination>,
+}
+
+impl RemoteSession {
+    pub(super) fn new(username: String, config: Config, agent: String) -> Self {
+        Self {
+            username,
+            connect_timeout: Duration::from_millis(config.remote.connect_timeout_ms),
+            config,
+            agent,
+            connection: None,
+            phases: Vec::new(),
+            state_refresh_interval: None,
+            webrtc_stats_interval: None,
+            last_warning: None,
+            last_termination: None,
+        }
+    }
+
+    fn log_message(&self, level: &str, message: impl ToString) {
+        ParticipantLogMessage::new(level, &self.username, message).write();
+    }
+
+    async fn start_inner(&mut self) -> Result<()> {
+        let token = self.config.remote.resolved_token().ok_or_else(|| {
+            eyre!("The remote backend needs the token of the agents in `remote.token` or {AGENT_TOKEN_ENV}")
+        })?;
+        let stream = match timeout(self.connect_timeout, TcpStream::connect(&self.agent)).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(err)) => {
+                self.last_warning = Some(agent_unavailable_warning(&self.agent));
+                return Err(err).wrap_err_with(|| format!("Failed to connect to agent {}", self.agent));
+            }
+            Err(_) => {
+                self.last_warning = Some(agent_unavailable_warning(&self.agent));
+                bail!("Timed out connecting to agent {}", self.agent);
+            }
+        };
+        stream.set_nodelay(true)?;
+        self.connection = Some(AgentConnection::new(stream, self.agent.clone()));
+        self.log_message("debug", format!("Connected to agent {}", self.agent));
+
+        let mut config = self.config.clone();
+        config.remote.token = None;
+        let request = AgentRequest::Start {
+            token,
+            username: self.username.clone(),
+            config: Box::new(config),
+        };
+        match self.request(request).await? {
+            AgentResponse::Started {
+                phases,
+                state_refresh_interval_ms,
+                webrtc_stats_interval_ms,
+            } => {
+                self.phases.extend(PhaseSample::into_phases(phases));
+                self.state_refresh_interval = state_refresh_interval_ms.map(Duration::from_millis);
+                self.webrtc_stats_interval = webrtc_stats_interval_ms.map(Duration::from_millis);
+                Ok(())
+            }
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn handle_command_inner(&mut self, message: ParticipantMessage) -> Result<()> {
+        if matches!(
+            message,
+            ParticipantMessage::StartRecording | ParticipantMessage::StopRecording
+        ) {
+            bail!("Remote agents cannot return recordings of the received media");
+        }
+        match self.request(AgentRequest::Command { message }).await? {
+            AgentResponse::Done { phases } => {
+                self.phases.extend(PhaseSample::into_phases(phases));
+                Ok(())
+            }
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn refresh_state_inner(&mut self) -> Result<ParticipantState> {
+        match self.request(AgentRequest::RefreshState).await? {
+            AgentResponse::State { state } => Ok(state),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn sample_webrtc_stats_inner(&mut self) -> Result<Option<WebRtcStatsSample>> {
+        match self.request(AgentRequest::SampleWebRtcStats).await? {
+            AgentResponse::WebRtcStats { sample } => Ok(sample),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn close_inner(&mut self) -> Result<()> {
+        let Some(mut connection) = self.connection.take() else {
+            return Ok(());
+        };
+        if connection.is_closed() {
+            return Ok(());
+        }
+        match connection.request(AgentRequest::Close).await? {
+            AgentResponse::Done { .. } => Ok(()),
+            AgentResponse::Failed { error, .. } => Err(eyre!(error)),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    /// Sends `request` and turns a `Failed` response into an error, remembering the warning and termination the agent
+    /// derived from it.
+    async fn request(&mut self, request: AgentRequest) -> Result<AgentResponse> {
+        let connection = self.connection.as_mut().ok_or_eyre("Not connected to an agent")?;
+        match connection.request(request).await? {
+            AgentResponse::Failed {
+                error,
+                warning,
+                termination,
+            } => {
+                self.last_warning = warning;
+                self.last_termination = termination.map(DriverTermination::from);
+                Err(eyre!(error))
+            }
+            response => Ok(response),
+        }
+    }
+}
+
+impl ParticipantDriverSession for RemoteSession {
+    fn participant_name(&self) -> &str {
+        &self.username
+    }
+
+    fn start(&mut self) -> BoxFuture<'_, Result<()>> {
+        async move { self.start_inner().await }.boxed()
+    }
+
+    fn handle_command(&mut self, message: ParticipantMessage) -> BoxFuture<'_, Result<()>> {
+        async move { self.handle_command_inner(message).await }.boxed()
+    }
+
+    fn refresh_state(&mut self) -> BoxFuture<'_, Result<ParticipantState>> {
+        async move { self.refresh_state_inner().await }.boxed()
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<()>> {
+        async move { self.close_inner().await }.boxed()
+    }
+
+    fn wait_for_termination(&mut self) -> BoxFuture<'_, DriverTermination> {
+        async move {
+            match self.connection.as_mut() {
+                Some(connection) => connection.wait_for_termination().await,
+                None => pending().await,
+            }
+        }
+        .boxed()
+    }
+
+    fn start_error_warning(&self, _err: &eyre::Report) -> Option<ParticipantWarning> {
+        self.last_warning.clone()
+    }
+
+    fn state_refresh_interval(&self) -> Option<Duration> {
+        self.state_refresh_interval
+    }
+
+    fn state_refresh_error_termination(&self, _err: &eyre::Report) -> Option<DriverTermination> {
+        self.last_termination.clone()
+    }
+
+    fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<Option<WebRtcStatsSample>>> {
+        async move { self.sample_webrtc_stats_inner().await }.boxed()
+    }
+
+    fn webrtc_stats_interval(&self) -> Option<Duration> {
+        self.webrtc_stats_interval
+    }
+
+    fn received_media_timeout(&self) -> Option<Duration> {
+        (self.config.received_media_timeout_secs > 0)
+            .then(|| Duration::from_secs(self.config.received_media_timeout_secs))
+    }
+
+    fn take_phase_timings(&mut self) -> Vec<(StartPhase, Duration)> {
+        std::mem::take(&mut self.phases)
+    }
+
+    fn state_changes(&mut self) -> Option<watch::Receiver<()>> {
+        self.connection
+            .as_ref()
+            .map(|connection| connection.state_changes.clone())
+    }
+}
+
+/// TCP connection to the agent. A reader task splits what the agent sends into responses, state changes and
+/// terminations.
+struct AgentConnection {
+    agent: String,
+    writer: OwnedWriteHalf,
+    responses: UnboundedReceiver<AgentResponse>,
+    state_changes: watch::Receiver<()>,
+    terminations: UnboundedReceiver<DriverTermination>,
+    /// Requests whose response was not read yet, because the caller stopped waiting for it.
+    unanswered: usize,
+    reader_task: JoinHandle<()>,
+}
+
+impl AgentConnection {
+    fn new(stream: TcpStream, agent: String) -> Self {
+        let (reader, writer) = stream.into_split();
+        let (responses_tx, responses) = unbounded_channel();
+        let (state_changes_tx, state_changes) = watch::channel(());
+        let (terminations_tx, terminations) = unbounded_channel();
+
+        let reader_task = tokio::spawn({
+            let agent = agent.clone();
+            async move {
+                let mut lines = BufReader::new(reader).lines();
+                let message = loop {
+                    let line = match lines.next_line().await {
+                        Ok(Some(line)) => line,
+                        Ok(None) => break format!("Agent {agent} closed the connection"),
+                        Err(err) => break format!("Lost connection to agent {agent}: {err}"),
+                    };
+                    match serde_json::from_str::<AgentResponse>(&line) {
+                        Ok(AgentResponse::StateChanged) => {
+                            state_changes_tx.send_replace(());
+                        }
+                        Ok(AgentResponse::Terminated(termination)) => {
+                            let _ = terminations_tx.send(termination.into());
+                        }
+                        Ok(response) => {
+                            let _ = responses_tx.send(response);
+                        }
+                        Err(err) => break format!("Agent {agent} sent an invalid message: {err}"),
+                    }
+                };
+                let _ = terminations_tx.send(DriverTermination::new("error", message));
+            }
+        });
+
+        Self {
+            agent,
+            writer,
+            responses,
+            state_changes,
+            terminations,
+            unanswered: 0,
+            reader_task,
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.reader_task.is_finished()
+    }
+
+    async fn request(&mut self, request: AgentRequest) -> Result<AgentResponse> {
+        // A start that was interrupted by a close still gets its answer first.
+        while self.unanswered > 0 {
+            self.next_response().await?;
+            self.unanswered -= 1;
+        }
+
+        write_line(&mut self.writer, &request)
+            .await
+            .wrap_err_with(|| format!("Failed to send request to agent {}", self.agent))?;
+        self.unanswered += 1;
+        let response = self.next_response().await?;
+        self.unanswered -= 1;
+        Ok(response)
+    }
+
+    async fn next_response(&mut self) -> Result<AgentResponse> {
+        self.responses
+            .recv()
+            .await
+            .ok_or_else(|| eyre!("Connection to agent {} closed", self.agent))
+    }
+
+    async fn wait_for_termination(&mut self) -> DriverTermination {
+        match self.terminations.recv().await {
+            Some(termination) => termination,
+            None => pending().await,
+        }
+    }
+}
+
+impl Drop for AgentConnection {
+    fn drop(&mut self) {
+        self.reader_task.abort();
+    }
+}
+
+fn agent_unavailable_warning(agent: &str) -> ParticipantWarning {
+    ParticipantWarning::new(
+        "Remote agent unavailable",
+        format!("Could not reach the agent at {agent}. Is `hyper-client-simulator agent` running there?"),
+    )
+}
+
+fn unexpected_response(response: AgentResponse) -> eyre::Report {
+    eyre!("Unexpected response from agent: {response:?}")
+}
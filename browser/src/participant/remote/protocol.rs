@@ -0,0 +1,175 @@
+//! Wire format between a [`super::RemoteSession`] and the agent hosting its participant: one JSON object per line
+//! over TCP. Every request is answered by exactly one response, in order. `StateChanged` and `Terminated` are the only
+//! messages an agent sends on its own.
+
+use crate::participant::shared::{
+    messages::ParticipantMessage,
+    DriverTermination,
+    ParticipantState,
+    ParticipantWarning,
+    StartPhase,
+    WebRtcStatsSample,
+};
+use client_simulator_config::Config;
+use eyre::{
+    Context as _,
+    Result,
+};
+use std::time::Duration;
+use tokio::io::{
+    AsyncWrite,
+    AsyncWriteExt as _,
+};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub(super) enum AgentRequest {
+    /// Create the participant on the agent and start it. The agent replaces the data directory of `config` with its
+    /// own. `token` must match the token the agent was started with, otherwise the agent closes the connection.
+    Start {
+        token: String,
+        username: String,
+        config: Box<Config>,
+    },
+    Command {
+        message: ParticipantMessage,
+    },
+    RefreshState,
+    SampleWebRtcStats,
+    Close,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub(super) enum AgentResponse {
+    Started {
+        phases: Vec<PhaseSample>,
+        state_refresh_interval_ms: Option<u64>,
+        webrtc_stats_interval_ms: Option<u64>,
+    },
+    Done {
+        phases: Vec<PhaseSample>,
+    },
+    State {
+        state: ParticipantState,
+    },
+    WebRtcStats {
+        sample: Option<WebRtcStatsSample>,
+    },
+    /// The request failed. `warning` and `termination` carry what the hosted session derived from the error.
+    Failed {
+        error: String,
+        warning: Option<ParticipantWarning>,
+        termination: Option<TerminationMessage>,
+    },
+    /// The hosted session noticed that its state may have changed, see
+    /// [`crate::participant::shared::ParticipantDriverSession::state_changes`].
+    StateChanged,
+    Terminated(TerminationMessage),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(super) struct TerminationMessage {
+    pub(super) level: String,
+    pub(super) message: String,
+}
+
+impl From<&DriverTermination> for TerminationMessage {
+    fn from(termination: &DriverTermination) -> Self {
+        Self {
+            level: termination.level.to_string(),
+            message: termination.message.clone(),
+        }
+    }
+}
+
+impl From<TerminationMessage> for DriverTermination {
+    fn from(termination: TerminationMessage) -> Self {
+        let level = match termination.level.as_str() {
+            "trace" => "trace",
+            "debug" => "debug",
+            "info" => "info",
+            "warn" => "warn",
+            _ => "error",
+        };
+        Self::new(level, termination.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(super) struct PhaseSample {
+    pub(super) phase: StartPhase,
+    pub(super) duration_ms: u64,
+}
+
+impl PhaseSample {
+    pub(super) fn from_phases(phases: Vec<(StartPhase, Duration)>) -> Vec<Self> {
+        phases
+            .into_iter()
+            .map(|(phase, duration)| Self {
+                phase,
+                duration_ms: duration.as_millis() as u64,
+            })
+            .collect()
+    }
+
+    pub(super) fn into_phases(samples: Vec<Self>) -> Vec<(StartPhase, Duration)> {
+        samples
+            .into_iter()
+            .map(|sample| (sample.phase, Duration::from_millis(sample.duration_ms)))
+            .collect()
+    }
+}
+
+pub(super) async fn write_line<W, T>(writer: &mut W, message: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: serde::Serialize,
+{
+    let mut line = serde_json::to_vec(message).context("Failed to encode agent message")?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_and_responses_are_single_json_lines() {
+        let request = AgentRequest::Command {
+            message: ParticipantMessage::SetVideoMaxConcurrentTracks(Some(2)),
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            json,
+            r#"{"request":"command","message":{"SetVideoMaxConcurrentTracks":2}}"#
+        );
+
+        let response = AgentResponse::Terminated(TerminationMessage {
+            level: "warn".to_string(),
+            message: "browser closed".to_string(),
+        });
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            r#"{"response":"terminated","level":"warn","message":"browser closed"}"#
+        );
+        assert!(matches!(
+            serde_json::from_str::<AgentResponse>(&json).unwrap(),
+            AgentResponse::Terminated(termination) if termination.message == "browser closed"
+        ));
+    }
+
+    #[test]
+    fn unknown_termination_levels_become_errors() {
+        let termination = DriverTermination::from(TerminationMessage {
+            level: "fatal".to_string(),
+            message: "agent crashed".to_string(),
+        });
+
+        assert_eq!(termination, DriverTermination::new("error", "agent crashed"));
+    }
+}
@@ -46,8 +46,10 @@ impl ParticipantDriverSession for RemoteStubSession {
             self.state = ParticipantState {
                 username: self.launch_spec.username.clone(),
                 running: true,
+                pending: false,
                 warning: None,
                 joined: true,
+                call_status: Default::default(),
                 muted: !self.launch_spec.settings.audio_enabled,
                 video_activated: self.launch_spec.settings.video_enabled,
                 auto_gain_control: self.launch_spec.settings.auto_gain_control,
@@ -58,6 +60,14 @@ impl ParticipantDriverSession for RemoteStubSession {
                 video_max_concurrent_tracks: self.launch_spec.settings.video_max_concurrent_tracks,
                 background_blur: self.launch_spec.settings.blur,
                 screenshare_activated: self.launch_spec.settings.screenshare_enabled,
+                network: self.launch_spec.settings.network,
+                device: self.launch_spec.settings.device.clone(),
+                fake_media: self.launch_spec.settings.fake_media.clone(),
+                webrtc_stats: None,
+                received_media: None,
+                glass_to_glass: Vec::new(),
+                recording: false,
+                last_command: None,
             };
 
             self.log_message("warn", "remote backend is a local stub; commands are simulated locally");
@@ -121,6 +131,38 @@ impl ParticipantDriverSession for RemoteStubSession {
                     self.state.background_blur = !self.state.background_blur;
                     self.log_message("debug", "remote stub toggled background blur");
                 }
+                ParticipantMessage::SetAudioEnabled(value) => {
+                    self.state.muted = !value;
+                    self.log_message("debug", format!("remote stub set audio enabled to {value}"));
+                }
+                ParticipantMessage::SetVideoEnabled(value) => {
+                    self.state.video_activated = value;
+                    self.log_message("debug", format!("remote stub set video enabled to {value}"));
+                }
+                ParticipantMessage::SetScreenshare(value) => {
+                    self.state.screenshare_activated = value;
+                    self.log_message("debug", format!("remote stub set screenshare to {value}"));
+                }
+                ParticipantMessage::SetBlur(value) => {
+                    self.state.background_blur = value;
+                    self.log_message("debug", format!("remote stub set background blur to {value}"));
+                }
+                ParticipantMessage::SetAutoGainControl(value) => {
+                    self.state.auto_gain_control = value;
+                    self.log_message("debug", format!("remote stub set auto gain control to {value}"));
+                }
+                ParticipantMessage::SetNetwork(value) => {
+                    self.state.network = value;
+                    self.log_message("debug", format!("remote stub set network to {value}"));
+                }
+                ParticipantMessage::StartRecording => {
+                    self.state.recording = true;
+                    self.log_message("debug", "remote stub started recording");
+                }
+                ParticipantMessage::StopRecording => {
+                    self.state.recording = false;
+                    self.log_message("debug", "remote stub stopped recording");
+                }
             }
 
             Ok(())
@@ -137,6 +179,7 @@ impl ParticipantDriverSession for RemoteStubSession {
             self.state.running = false;
             self.state.joined = false;
             self.state.screenshare_activated = false;
+            self.state.recording = false;
             self.log_message("debug", "remote stub closed");
             Ok(())
         }
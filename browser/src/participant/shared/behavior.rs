@@ -0,0 +1,267 @@
+use super::{
+    messages::{
+        ParticipantCommand,
+        ParticipantMessage,
+    },
+    ParticipantState,
+};
+use client_simulator_config::{
+    BehaviorProfile,
+    Config,
+};
+use rand::{
+    rngs::StdRng,
+    Rng as _,
+    SeedableRng as _,
+};
+use std::{
+    ops::Range,
+    time::Duration,
+};
+use tokio::sync::{
+    mpsc::UnboundedSender,
+    watch,
+};
+use tokio_util::sync::CancellationToken;
+
+/// Starts the scripted behavior configured in `config` for a participant.
+///
+/// The behavior waits until the participant joined the space the first time and then sends messages through `sender`
+/// until `cancellation_token` fires or the participant runtime stops.
+pub(in crate::participant) fn spawn_behavior(
+    name: String,
+    config: &Config,
+    state: watch::Receiver<ParticipantState>,
+    sender: UnboundedSender<ParticipantCommand>,
+    cancellation_token: CancellationToken,
+) {
+    if config.behavior.is_none() {
+        return;
+    }
+
+    let seed = config.behavior_seed.unwrap_or_else(rand::random);
+    info!(participant = %name, "Running {} behavior with seed {seed}", config.behavior);
+    let planner = BehaviorPlanner::new(config.behavior, seed, Duration::from_millis(config.behavior_period_ms));
+
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = cancellation_token.cancelled() => {}
+            _ = run_behavior(&name, planner, state, sender) => {}
+        }
+        debug!(participant = %name, "Behavior stopped");
+    });
+}
+
+async fn run_behavior(
+    name: &str,
+    mut planner: BehaviorPlanner,
+    mut state: watch::Receiver<ParticipantState>,
+    sender: UnboundedSender<ParticipantCommand>,
+) {
+    // Participants join on their own once started, the behavior only takes over afterwards.
+    if state.wait_for(|state| state.joined).await.is_err() {
+        return;
+    }
+
+    loop {
+        let step = planner.next_step(&state.borrow_and_update());
+        let Some(step) = step else {
+            if state.changed().await.is_err() {
+                return;
+            }
+            continue;
+        };
+
+        tokio::time::sleep(step.delay).await;
+
+        // Somebody else may have changed the participant in the meantime, plan again from the current state.
+        let before = message_flag(&step.message, &state.borrow_and_update());
+        if before != Some(step.flag) {
+            continue;
+        }
+
+        debug!(participant = %name, "Behavior sends {}", step.message);
+        if sender.send(step.message.clone().into()).is_err() {
+            return;
+        }
+
+        // Toggles only show up with the next state refresh. Wait for it so the same toggle is not sent twice.
+        let applied = state.wait_for(|state| message_flag(&step.message, state) != Some(step.flag));
+        match tokio::time::timeout(planner.period, applied).await {
+            Ok(Err(_)) => return,
+            Ok(Ok(_)) => {}
+            Err(_) => warn!(participant = %name, "Behavior did not observe the effect of {}", step.message),
+        }
+    }
+}
+
+/// The part of `state` that `message` flips, `None` for messages a behavior never sends.
+fn message_flag(message: &ParticipantMessage, state: &ParticipantState) -> Option<bool> {
+    match message {
+        ParticipantMessage::Join | ParticipantMessage::Leave => Some(state.joined),
+        ParticipantMessage::ToggleAudio => Some(state.muted),
+        ParticipantMessage::ToggleVideo => Some(state.video_activated),
+        ParticipantMessage::ToggleScreenshare => Some(state.screenshare_activated),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+struct BehaviorStep {
+    delay: Duration,
+    message: ParticipantMessage,
+    /// Value of [`message_flag`] the step was planned for.
+    flag: bool,
+}
+
+/// Decides what a [`BehaviorProfile`] does next. All randomness comes from the seed, so the same seed and states
+/// produce the same steps.
+#[derive(Debug)]
+struct BehaviorPlanner {
+    profile: BehaviorProfile,
+    period: Duration,
+    rng: StdRng,
+}
+
+impl BehaviorPlanner {
+    fn new(profile: BehaviorProfile, seed: u64, period: Duration) -> Self {
+        Self {
+            profile,
+            period,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The next message to send for the given state, `None` if the profile waits for the state to change.
+    fn next_step(&mut self, state: &ParticipantState) -> Option<BehaviorStep> {
+        if !state.running {
+            return None;
+        }
+        if !state.joined {
+            return match self.profile {
+                BehaviorProfile::Flapper => Some(self.step(ParticipantMessage::Join, state, 0.1..0.3)),
+                _ => None,
+            };
+        }
+
+        match self.profile {
+            BehaviorProfile::None => None,
+            BehaviorProfile::Talker if state.muted => Some(self.step(ParticipantMessage::ToggleAudio, state, 0.5..2.0)),
+            BehaviorProfile::Talker => Some(self.step(ParticipantMessage::ToggleAudio, state, 0.3..1.5)),
+            BehaviorProfile::Lurker if !state.muted => {
+                Some(self.step(ParticipantMessage::ToggleAudio, state, 0.0..0.1))
+            }
+            BehaviorProfile::Lurker if state.video_activated => {
+                Some(self.step(ParticipantMessage::ToggleVideo, state, 0.0..0.1))
+            }
+            BehaviorProfile::Lurker => None,
+            BehaviorProfile::Flapper => Some(self.step(ParticipantMessage::Leave, state, 0.75..1.25)),
+            BehaviorProfile::ScreenSharer if state.screenshare_activated => {
+                Some(self.step(ParticipantMessage::ToggleScreenshare, state, 1.0..3.0))
+            }
+            BehaviorProfile::ScreenSharer => Some(self.step(ParticipantMessage::ToggleScreenshare, state, 0.5..1.0)),
+        }
+    }
+
+    /// Plans `message` after a random delay of `periods` times the configured period.
+    fn step(&mut self, message: ParticipantMessage, state: &ParticipantState, periods: Range<f64>) -> BehaviorStep {
+        let delay = self.period.mul_f64(self.rng.random_range(periods));
+        let flag = message_flag(&message, state).expect("behaviors only send messages with a state flag");
+        BehaviorStep { delay, message, flag }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BehaviorPlanner;
+    use crate::participant::shared::{
+        messages::ParticipantMessage,
+        ParticipantState,
+    };
+    use client_simulator_config::BehaviorProfile;
+    use std::time::Duration;
+
+    const PERIOD: Duration = Duration::from_secs(10);
+
+    fn joined() -> ParticipantState {
+        ParticipantState {
+            running: true,
+            joined: true,
+            ..Default::default()
+        }
+    }
+
+    fn talker_delays(seed: u64) -> Vec<Duration> {
+        let mut planner = BehaviorPlanner::new(BehaviorProfile::Talker, seed, PERIOD);
+        let mut state = joined();
+        (0..8)
+            .map(|_| {
+                let step = planner.next_step(&state).expect("talker always has a next step");
+                state.muted = !state.muted;
+                step.delay
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_plans_the_same_steps() {
+        assert_eq!(talker_delays(7), talker_delays(7));
+        assert_ne!(talker_delays(7), talker_delays(8));
+    }
+
+    #[test]
+    fn flapper_leaves_and_rejoins() {
+        let mut planner = BehaviorPlanner::new(BehaviorProfile::Flapper, 1, PERIOD);
+
+        let leave = planner.next_step(&joined()).unwrap();
+        assert!(matches!(leave.message, ParticipantMessage::Leave));
+        assert!((PERIOD.mul_f64(0.75)..PERIOD.mul_f64(1.25)).contains(&leave.delay));
+
+        let left = ParticipantState {
+            joined: false,
+            ..joined()
+        };
+        let join = planner.next_step(&left).unwrap();
+        assert!(matches!(join.message, ParticipantMessage::Join));
+        assert!(join.delay < PERIOD.mul_f64(0.3));
+    }
+
+    #[test]
+    fn lurker_turns_off_media_and_then_idles() {
+        let mut planner = BehaviorPlanner::new(BehaviorProfile::Lurker, 1, PERIOD);
+        let mut state = ParticipantState {
+            video_activated: true,
+            ..joined()
+        };
+
+        let mute = planner.next_step(&state).unwrap();
+        assert!(matches!(mute.message, ParticipantMessage::ToggleAudio));
+        state.muted = true;
+
+        let camera_off = planner.next_step(&state).unwrap();
+        assert!(matches!(camera_off.message, ParticipantMessage::ToggleVideo));
+        state.video_activated = false;
+
+        assert!(planner.next_step(&state).is_none());
+    }
+
+    #[test]
+    fn profiles_wait_until_the_participant_is_in_the_space() {
+        let left = ParticipantState {
+            running: true,
+            ..Default::default()
+        };
+        for profile in [
+            BehaviorProfile::None,
+            BehaviorProfile::Talker,
+            BehaviorProfile::Lurker,
+            BehaviorProfile::ScreenSharer,
+        ] {
+            let step = BehaviorPlanner::new(profile, 1, PERIOD).next_step(&left);
+            assert!(step.is_none(), "{profile} should not act outside the space");
+        }
+        assert!(BehaviorPlanner::new(BehaviorProfile::Flapper, 1, PERIOD)
+            .next_step(&ParticipantState::default())
+            .is_none());
+    }
+}
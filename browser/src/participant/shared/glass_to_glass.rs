@@ -0,0 +1,300 @@
+//! End-to-end latency from a sender's test pattern to a receiver's rendered video and played audio.
+//!
+//! The injected test pattern draws the sender's wall clock time and id as a block code into every frame and beeps at
+//! every full second, see `frontend::fake_media`. Receivers decode the code from the remote video tiles and detect the
+//! beeps on the remote audio tracks, see `frontend::glass_to_glass`. Both sides read their own clock, so the results
+//! are only meaningful for participants whose clocks are in sync, e.g. on the same machine.
+
+use super::timings::percentile;
+use crate::participant::Participant;
+use client_simulator_config::TransportMode;
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+        VecDeque,
+    },
+    sync::{
+        Mutex,
+        OnceLock,
+    },
+};
+
+/// Measurements kept per sender and receiver, half an hour at the default WebRTC stats interval.
+const GLASS_TO_GLASS_WINDOW: usize = 900;
+
+/// Identifies the media of participant `name` in the test pattern code and sets the pitch of its beep. Same hash as
+/// the page uses.
+pub fn media_sender_id(name: &str) -> u32 {
+    name.chars().fold(0u32, |hash, char| {
+        let mut units = [0; 2];
+        let unit = char.encode_utf16(&mut units)[0];
+        hash.wrapping_mul(31).wrapping_add(unit.into())
+    })
+}
+
+fn media_senders() -> &'static Mutex<HashMap<u32, String>> {
+    static SENDERS: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+    SENDERS.get_or_init(Default::default)
+}
+
+/// Remembers participant `name` of this process, so media carrying its sender id can be named after it.
+pub(in crate::participant) fn register_media_sender(name: &str) {
+    media_senders()
+        .lock()
+        .unwrap()
+        .insert(media_sender_id(name), name.to_string());
+}
+
+/// The participant of this process whose media carries `sender_id`, see [`register_media_sender`].
+pub(in crate::participant) fn media_sender_name(sender_id: u32) -> Option<String> {
+    media_senders().lock().unwrap().get(&sender_id).cloned()
+}
+
+/// Latency of the media of one sender as seen by a receiver. Audio latency is measured modulo one second, from the
+/// full second a beep was sent at.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct GlassToGlass {
+    pub sender_id: u32,
+    pub video_latency_ms: Option<i64>,
+    pub audio_latency_ms: Option<i64>,
+    /// How much later the audio arrives than the video, negative if it arrives earlier.
+    pub av_offset_ms: Option<i64>,
+}
+
+/// Per sender measurements as returned by the page.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(in crate::participant) struct GlassToGlassSample {
+    pub(in crate::participant) sender_id: u32,
+    pub(in crate::participant) video_latency_ms: Option<f64>,
+    pub(in crate::participant) audio_latency_ms: Option<f64>,
+}
+
+impl From<GlassToGlassSample> for GlassToGlass {
+    fn from(sample: GlassToGlassSample) -> Self {
+        let video_latency_ms = sample.video_latency_ms.map(|ms| ms.round() as i64);
+        let audio_latency_ms = sample.audio_latency_ms.map(|ms| ms.round() as i64);
+        Self {
+            sender_id: sample.sender_id,
+            video_latency_ms,
+            audio_latency_ms,
+            av_offset_ms: audio_latency_ms
+                .zip(video_latency_ms)
+                .map(|(audio, video)| audio - video),
+        }
+    }
+}
+
+/// The latest measurements of one sender at one receiver, at most [`GLASS_TO_GLASS_WINDOW`], so long runs neither grow
+/// the history nor slow down the summaries.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GlassToGlassWindow {
+    latencies: VecDeque<GlassToGlass>,
+    count: usize,
+}
+
+impl GlassToGlassWindow {
+    pub(in crate::participant) fn push(&mut self, latency: GlassToGlass) {
+        if self.latencies.len() == GLASS_TO_GLASS_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+        self.count += 1;
+    }
+
+    /// Oldest first.
+    pub fn latencies(&self) -> impl Iterator<Item = &GlassToGlass> {
+        self.latencies.iter()
+    }
+
+    /// All measurements ever pushed, including those that left the window.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Latency percentiles of the media of one sender at one receiver, `None` where nothing was measured. The percentiles
+/// cover the latest measurements only, see [`GlassToGlassWindow`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct GlassToGlassSummary {
+    pub sender: String,
+    pub receiver: String,
+    /// Transport of the receiver.
+    pub transport: TransportMode,
+    /// All measurements, including those older than the percentiles cover.
+    pub count: usize,
+    pub video_p50_ms: Option<i64>,
+    pub video_p95_ms: Option<i64>,
+    pub audio_p50_ms: Option<i64>,
+    pub audio_p95_ms: Option<i64>,
+    pub av_offset_p50_ms: Option<i64>,
+}
+
+impl GlassToGlassSummary {
+    /// Groups the measurements recorded by `participants` by sender and receiver, ordered by receiver, then sender.
+    /// Senders that are not among `participants` are named by their id.
+    pub fn from_participants(participants: &[Participant]) -> Vec<Self> {
+        let names = participants
+            .iter()
+            .map(|participant| (media_sender_id(&participant.name), participant.name.clone()))
+            .collect::<HashMap<_, _>>();
+        participants
+            .iter()
+            .flat_map(|participant| {
+                let transport = participant.state.borrow().transport_mode;
+                Self::from_windows(
+                    &participant.name,
+                    transport,
+                    participant.history.glass_to_glass(),
+                    &names,
+                )
+            })
+            .collect()
+    }
+
+    fn from_windows(
+        receiver: &str,
+        transport: TransportMode,
+        windows: HashMap<u32, GlassToGlassWindow>,
+        names: &HashMap<u32, String>,
+    ) -> Vec<Self> {
+        let senders = windows
+            .into_iter()
+            .map(|(sender_id, window)| {
+                let sender = names
+                    .get(&sender_id)
+                    .cloned()
+                    .unwrap_or_else(|| format!("{sender_id:08x}"));
+                (sender, window)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        senders
+            .into_iter()
+            .map(|(sender, window)| {
+                let sorted = |value: fn(&GlassToGlass) -> Option<i64>| {
+                    let mut samples = window.latencies().filter_map(value).collect::<Vec<_>>();
+                    samples.sort_unstable();
+                    samples
+                };
+                let video = sorted(|latency| latency.video_latency_ms);
+                let audio = sorted(|latency| latency.audio_latency_ms);
+                let av_offset = sorted(|latency| latency.av_offset_ms);
+                let at = |samples: &[i64], percent| (!samples.is_empty()).then(|| percentile(samples, percent));
+                Self {
+                    sender,
+                    receiver: receiver.to_string(),
+                    transport,
+                    count: window.count(),
+                    video_p50_ms: at(&video, 50),
+                    video_p95_ms: at(&video, 95),
+                    audio_p50_ms: at(&audio, 50),
+                    audio_p95_ms: at(&audio, 95),
+                    av_offset_p50_ms: at(&av_offset, 50),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latency(sender_id: u32, video_latency_ms: f64, audio_latency_ms: Option<f64>) -> GlassToGlass {
+        GlassToGlassSample {
+            sender_id,
+            video_latency_ms: Some(video_latency_ms),
+            audio_latency_ms,
+        }
+        .into()
+    }
+
+    #[test]
+    fn sender_ids_match_the_page_hash() {
+        // `[...'alice'].reduce((hash, char) => (hash * 31 + char.charCodeAt(0)) >>> 0, 0)`
+        assert_eq!(media_sender_id("alice"), 92_903_040);
+        assert_eq!(media_sender_id(""), 0);
+    }
+
+    #[test]
+    fn audio_video_offset_is_audio_minus_video_latency() {
+        let latency = GlassToGlass::from(GlassToGlassSample {
+            sender_id: 1,
+            video_latency_ms: Some(180.4),
+            audio_latency_ms: Some(120.0),
+        });
+
+        assert_eq!(latency.video_latency_ms, Some(180));
+        assert_eq!(latency.av_offset_ms, Some(-60));
+        assert_eq!(
+            GlassToGlass::from(GlassToGlassSample {
+                sender_id: 1,
+                video_latency_ms: Some(180.0),
+                audio_latency_ms: None,
+            })
+            .av_offset_ms,
+            None
+        );
+    }
+
+    #[test]
+    fn summarizes_percentiles_per_sender() {
+        let names = HashMap::from([(media_sender_id("alice"), "alice".to_string())]);
+        let mut windows = HashMap::<u32, GlassToGlassWindow>::new();
+        for index in 1..=10 {
+            windows.entry(media_sender_id("alice")).or_default().push(latency(
+                media_sender_id("alice"),
+                index as f64 * 10.0,
+                Some(50.0),
+            ));
+        }
+        windows
+            .entry(0xdead_beef)
+            .or_default()
+            .push(latency(0xdead_beef, 300.0, None));
+
+        let summaries = GlassToGlassSummary::from_windows("bob", TransportMode::WebTransport, windows, &names);
+
+        assert_eq!(
+            summaries,
+            vec![
+                GlassToGlassSummary {
+                    sender: "alice".to_string(),
+                    receiver: "bob".to_string(),
+                    transport: TransportMode::WebTransport,
+                    count: 10,
+                    video_p50_ms: Some(50),
+                    video_p95_ms: Some(100),
+                    audio_p50_ms: Some(50),
+                    audio_p95_ms: Some(50),
+                    av_offset_p50_ms: Some(0),
+                },
+                GlassToGlassSummary {
+                    sender: "deadbeef".to_string(),
+                    receiver: "bob".to_string(),
+                    transport: TransportMode::WebTransport,
+                    count: 1,
+                    video_p50_ms: Some(300),
+                    video_p95_ms: Some(300),
+                    audio_p50_ms: None,
+                    audio_p95_ms: None,
+                    av_offset_p50_ms: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn windows_keep_the_latest_measurements() {
+        let mut window = GlassToGlassWindow::default();
+        for index in 0..GLASS_TO_GLASS_WINDOW + 5 {
+            window.push(latency(1, index as f64, None));
+        }
+
+        assert_eq!(window.count(), GLASS_TO_GLASS_WINDOW + 5);
+        assert_eq!(window.latencies().count(), GLASS_TO_GLASS_WINDOW);
+        assert_eq!(window.latencies().next().unwrap().video_latency_ms, Some(5));
+    }
+}
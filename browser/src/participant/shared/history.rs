@@ -0,0 +1,137 @@
+use super::{
+    glass_to_glass::{
+        GlassToGlass,
+        GlassToGlassWindow,
+    },
+    timings::{
+        LatencyMetric,
+        ParticipantTiming,
+        StartPhase,
+    },
+    CallStatus,
+    DriverTermination,
+    ParticipantState,
+};
+use chrono::{
+    DateTime,
+    Utc,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// Something that happened to a participant during its lifetime.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ParticipantEventKind {
+    /// Waiting for a spawn slot or backend capacity.
+    Pending,
+    /// The backend session started.
+    Started,
+    StartFailed {
+        error: String,
+    },
+    Joined,
+    Left,
+    /// The frontend showed a different call condition, e.g. a reconnecting banner or the meeting ending.
+    CallStatusChanged {
+        status: CallStatus,
+    },
+    CommandFailed {
+        command: String,
+        error: String,
+    },
+    /// The backend reported that the session ended without being asked to.
+    Terminated {
+        level: String,
+        message: String,
+    },
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ParticipantEvent {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: ParticipantEventKind,
+}
+
+/// Timestamped events and latencies recorded by the participant runtime, readable while and after the participant
+/// runs.
+#[derive(Debug, Clone, Default)]
+pub struct ParticipantHistory {
+    events: Arc<Mutex<Vec<ParticipantEvent>>>,
+    timings: Arc<Mutex<Vec<ParticipantTiming>>>,
+    /// By sender id.
+    glass_to_glass: Arc<Mutex<HashMap<u32, GlassToGlassWindow>>>,
+}
+
+impl ParticipantHistory {
+    pub fn events(&self) -> Vec<ParticipantEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    pub fn timings(&self) -> Vec<ParticipantTiming> {
+        self.timings.lock().unwrap().clone()
+    }
+
+    /// The latest glass-to-glass latencies of each sender, by sender id.
+    pub fn glass_to_glass(&self) -> HashMap<u32, GlassToGlassWindow> {
+        self.glass_to_glass.lock().unwrap().clone()
+    }
+
+    pub(in crate::participant) fn record_timing(&self, metric: LatencyMetric, duration: Duration) {
+        self.timings
+            .lock()
+            .unwrap()
+            .push(ParticipantTiming::new(metric, duration));
+    }
+
+    pub(in crate::participant) fn record_glass_to_glass(&self, latency: GlassToGlass) {
+        self.glass_to_glass
+            .lock()
+            .unwrap()
+            .entry(latency.sender_id)
+            .or_default()
+            .push(latency);
+    }
+
+    pub(in crate::participant) fn record_phases(&self, phases: Vec<(StartPhase, Duration)>) {
+        for (phase, duration) in phases {
+            self.record_timing(LatencyMetric::Phase(phase), duration);
+        }
+    }
+
+    pub(in crate::participant) fn record(&self, kind: ParticipantEventKind) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(ParticipantEvent { at: Utc::now(), kind });
+    }
+
+    pub(in crate::participant) fn record_termination(&self, termination: &DriverTermination) {
+        self.record(ParticipantEventKind::Terminated {
+            level: termination.level.to_string(),
+            message: termination.message.clone(),
+        });
+    }
+
+    /// Records a join or leave when the published state changes `joined`, and changes of the call status.
+    pub(in crate::participant) fn record_transition(&self, previous: &ParticipantState, next: &ParticipantState) {
+        match (previous.joined, next.joined) {
+            (false, true) => self.record(ParticipantEventKind::Joined),
+            (true, false) => self.record(ParticipantEventKind::Left),
+            _ => {}
+        }
+        if previous.call_status != next.call_status {
+            self.record(ParticipantEventKind::CallStatusChanged {
+                status: next.call_status,
+            });
+        }
+    }
+}
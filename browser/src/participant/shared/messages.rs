@@ -1,10 +1,12 @@
 use client_simulator_config::{
+    NetworkConfig,
     NoiseSuppression,
     VideoConstraint,
 };
 use std::fmt;
+use tokio::sync::oneshot;
 
-#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug, strum::IntoStaticStr)]
 pub enum ParticipantMessage {
     Join,
     Leave,
@@ -18,6 +20,16 @@ pub enum ParticipantMessage {
     SetVideoConstraintSubscribe(VideoConstraint),
     SetVideoMaxConcurrentTracks(Option<usize>),
     ToggleBackgroundBlur,
+    /// Unlike the toggles, the `Set*(bool)` messages do nothing if the participant already has the value.
+    SetAudioEnabled(bool),
+    SetVideoEnabled(bool),
+    SetScreenshare(bool),
+    SetBlur(bool),
+    SetAutoGainControl(bool),
+    SetNetwork(NetworkConfig),
+    /// Records the media received from every sender into the data dir, until `StopRecording`.
+    StartRecording,
+    StopRecording,
 }
 
 impl fmt::Display for ParticipantMessage {
@@ -26,6 +38,70 @@ impl fmt::Display for ParticipantMessage {
     }
 }
 
+/// Why a command sent with [`crate::participant::Participant::run_command`] was not carried out.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "reason", content = "error", rename_all = "snake_case")]
+pub enum CommandError {
+    NotRunning,
+    /// Still waiting for a spawn slot.
+    Pending,
+    /// Only `Join` and `Close` can be sent before the participant is in the space.
+    NotJoined,
+    AlreadyJoined,
+    /// The backend failed to carry out the command.
+    Failed(String),
+    /// The participant stopped before it ran the command.
+    Dropped,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotRunning => write!(f, "participant is not running"),
+            Self::Pending => write!(f, "participant is still waiting to start"),
+            Self::NotJoined => write!(f, "rejected: not joined, participant is not in the space yet"),
+            Self::AlreadyJoined => write!(f, "participant already joined"),
+            Self::Failed(error) => write!(f, "{error}"),
+            Self::Dropped => write!(f, "participant stopped before running the command"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// A message for the participant runtime, and whom to tell whether it was carried out.
+#[derive(Debug)]
+pub(in crate::participant) struct ParticipantCommand {
+    pub(in crate::participant) message: ParticipantMessage,
+    reply: Option<oneshot::Sender<Result<(), CommandError>>>,
+}
+
+impl ParticipantCommand {
+    pub(in crate::participant) fn with_reply(
+        message: ParticipantMessage,
+    ) -> (Self, oneshot::Receiver<Result<(), CommandError>>) {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let command = Self {
+            message,
+            reply: Some(reply_tx),
+        };
+        (command, reply_rx)
+    }
+
+    /// Reports the outcome, if the sender waits for it. Later calls do nothing.
+    pub(in crate::participant) fn reply(&mut self, result: Result<(), CommandError>) {
+        if let Some(reply) = self.reply.take() {
+            let _ = reply.send(result);
+        }
+    }
+}
+
+impl From<ParticipantMessage> for ParticipantCommand {
+    fn from(message: ParticipantMessage) -> Self {
+        Self { message, reply: None }
+    }
+}
+
 pub(in crate::participant) struct ParticipantLogMessage {
     participant: String,
     level: String,
@@ -1,22 +1,65 @@
+mod behavior;
 pub(in crate::participant) mod browser_log;
+mod glass_to_glass;
+mod history;
 pub mod messages;
+mod received_media;
 mod runtime;
+mod scheduler;
 mod spec;
 mod state;
+mod stats;
 mod store;
+mod timings;
 
+pub(in crate::participant) use behavior::spawn_behavior;
+pub use glass_to_glass::{
+    media_sender_id,
+    GlassToGlass,
+    GlassToGlassSummary,
+    GlassToGlassWindow,
+};
+pub(in crate::participant) use glass_to_glass::{
+    media_sender_name,
+    register_media_sender,
+    GlassToGlassSample,
+};
+pub use history::{
+    ParticipantEvent,
+    ParticipantEventKind,
+    ParticipantHistory,
+};
+pub use received_media::ReceivedMedia;
+pub(in crate::participant) use received_media::{
+    ReceivedMediaMonitor,
+    ReceivedMediaSample,
+    ReceivingChange,
+};
 pub(in crate::participant) use runtime::{
     run_participant_runtime,
     DriverTermination,
     ParticipantDriverSession,
 };
+pub use scheduler::SpawnScheduler;
+pub(in crate::participant) use scheduler::StartGate;
 pub(in crate::participant) use spec::{
     ParticipantLaunchSpec,
     ParticipantSettings,
     ResolvedFrontendKind,
 };
 pub use state::{
+    CallStatus,
+    CommandResult,
     ParticipantState,
     ParticipantWarning,
 };
+pub use stats::WebRtcStats;
+pub(in crate::participant) use stats::WebRtcStatsSample;
 pub use store::ParticipantStore;
+pub(in crate::participant) use timings::PhaseTimings;
+pub use timings::{
+    LatencyMetric,
+    LatencySummary,
+    ParticipantTiming,
+    StartPhase,
+};
@@ -0,0 +1,229 @@
+//! Whether a participant actually receives the media of the others, derived from the remote `<video>` tiles and remote
+//! audio of its page, see `frontend::received_media`.
+
+use super::ParticipantWarning;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Remote audio at or above this level counts as received, about -60 dBFS.
+const AUDIBLE_LEVEL: f64 = 0.001;
+
+/// What a participant receives, inspected along with its WebRTC stats.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReceivedMedia {
+    /// Video elements playing a remote track.
+    pub video_tiles: u32,
+    /// Tiles that showed new frames since the previous inspection.
+    pub advancing_video_tiles: u32,
+    /// Tiles without new frames since the previous inspection.
+    pub frozen_video_tiles: u32,
+    /// Tiles with new frames that are black.
+    pub black_video_tiles: u32,
+    pub audio_tracks: u32,
+    /// Root mean square level of the remote audio since the previous inspection, from 0 to 1. `None` without remote
+    /// audio tracks.
+    pub audio_level: Option<f64>,
+    /// How long the joined participant has received neither advancing video nor audible audio.
+    pub nothing_received_secs: f64,
+    /// Nothing was received for longer than `received_media_timeout_secs`.
+    pub stalled: bool,
+}
+
+impl ReceivedMedia {
+    pub fn is_receiving(&self) -> bool {
+        self.advancing_video_tiles > 0 || self.audio_level.is_some_and(|level| level >= AUDIBLE_LEVEL)
+    }
+}
+
+/// Raw counts as returned by the page. Audio counters are cumulative.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(in crate::participant) struct ReceivedMediaSample {
+    pub(in crate::participant) video_tiles: u32,
+    pub(in crate::participant) advancing_video_tiles: u32,
+    pub(in crate::participant) frozen_video_tiles: u32,
+    pub(in crate::participant) black_video_tiles: u32,
+    pub(in crate::participant) audio_tracks: u32,
+    pub(in crate::participant) total_audio_energy: f64,
+    pub(in crate::participant) total_samples_duration: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(in crate::participant) enum ReceivingChange {
+    /// Nothing was received for the given time, which exceeds the timeout.
+    Stalled(Duration),
+    Recovered,
+}
+
+/// Tracks for how long a joined participant received nothing across consecutive samples.
+#[derive(Debug, Default)]
+pub(in crate::participant) struct ReceivedMediaMonitor {
+    timeout: Option<Duration>,
+    previous: Option<ReceivedMediaSample>,
+    nothing_received_since: Option<Instant>,
+    stalled: bool,
+    /// Raised when the participant stalls, cleared once it receives media again.
+    warning: Option<ParticipantWarning>,
+}
+
+impl ReceivedMediaMonitor {
+    /// `timeout` is how long a joined participant may receive nothing before it counts as stalled, `None` to never.
+    pub(in crate::participant) fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            timeout,
+            ..Default::default()
+        }
+    }
+
+    /// Turns `sample` into what the participant receives and reports when it stalls or recovers. A participant that
+    /// is not joined is not expected to receive anything.
+    pub(in crate::participant) fn observe(
+        &mut self,
+        sample: ReceivedMediaSample,
+        joined: bool,
+        now: Instant,
+    ) -> (ReceivedMedia, Option<ReceivingChange>) {
+        let mut media = ReceivedMedia {
+            video_tiles: sample.video_tiles,
+            advancing_video_tiles: sample.advancing_video_tiles,
+            frozen_video_tiles: sample.frozen_video_tiles,
+            black_video_tiles: sample.black_video_tiles,
+            audio_tracks: sample.audio_tracks,
+            audio_level: self.audio_level(&sample),
+            ..Default::default()
+        };
+        self.previous = Some(sample);
+
+        let receiving = media.is_receiving();
+        if !joined || receiving {
+            self.nothing_received_since = None;
+            let was_stalled = std::mem::take(&mut self.stalled);
+            self.warning = None;
+            return (media, (was_stalled && receiving).then_some(ReceivingChange::Recovered));
+        }
+
+        let nothing_received_for = now - *self.nothing_received_since.get_or_insert(now);
+        let timed_out = self.timeout.is_some_and(|timeout| nothing_received_for >= timeout);
+        let change = (timed_out && !self.stalled).then_some(ReceivingChange::Stalled(nothing_received_for));
+        if change.is_some() {
+            self.warning = Some(ParticipantWarning::new(
+                "Receiving nothing",
+                format!("Received no video or audio for {}s", nothing_received_for.as_secs()),
+            ));
+        }
+        self.stalled |= timed_out;
+        media.nothing_received_secs = nothing_received_for.as_secs_f64();
+        media.stalled = self.stalled;
+        (media, change)
+    }
+
+    /// Set while the participant is stalled.
+    pub(in crate::participant) fn warning(&self) -> Option<&ParticipantWarning> {
+        self.warning.as_ref()
+    }
+
+    /// The level since the previous sample. Without one, or when the counters restarted, the level over the whole
+    /// session.
+    fn audio_level(&self, sample: &ReceivedMediaSample) -> Option<f64> {
+        if sample.audio_tracks == 0 {
+            return None;
+        }
+        let (energy, duration) = match &self.previous {
+            Some(previous) if sample.total_samples_duration >= previous.total_samples_duration => (
+                sample.total_audio_energy - previous.total_audio_energy,
+                sample.total_samples_duration - previous.total_samples_duration,
+            ),
+            _ => (sample.total_audio_energy, sample.total_samples_duration),
+        };
+        // The energy sums the squared level of every sample times its duration.
+        Some(if duration > 0.0 {
+            (energy.max(0.0) / duration).sqrt()
+        } else {
+            0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silent_video(video_tiles: u32) -> ReceivedMediaSample {
+        ReceivedMediaSample {
+            video_tiles,
+            frozen_video_tiles: video_tiles,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn audio_level_covers_the_interval_since_the_previous_sample() {
+        let mut monitor = ReceivedMediaMonitor::new(None);
+        let now = Instant::now();
+        let first = ReceivedMediaSample {
+            audio_tracks: 1,
+            total_audio_energy: 0.25,
+            total_samples_duration: 1.0,
+            ..Default::default()
+        };
+        let second = ReceivedMediaSample {
+            total_audio_energy: 0.25,
+            total_samples_duration: 3.0,
+            ..first.clone()
+        };
+
+        let (media, _) = monitor.observe(first, true, now);
+        assert_eq!(media.audio_level, Some(0.5));
+        assert!(media.is_receiving());
+
+        let (media, _) = monitor.observe(second, true, now);
+        assert_eq!(media.audio_level, Some(0.0));
+        assert!(!media.is_receiving());
+
+        let (media, _) = monitor.observe(silent_video(2), true, now);
+        assert_eq!(media.audio_level, None);
+        assert_eq!(media.frozen_video_tiles, 2);
+    }
+
+    #[test]
+    fn a_joined_participant_receiving_nothing_stalls_once_and_recovers() {
+        let mut monitor = ReceivedMediaMonitor::new(Some(Duration::from_secs(10)));
+        let start = Instant::now();
+
+        let (media, change) = monitor.observe(silent_video(1), true, start);
+        assert_eq!((media.nothing_received_secs, media.stalled, change), (0.0, false, None));
+
+        let (media, change) = monitor.observe(silent_video(1), true, start + Duration::from_secs(12));
+        assert!(media.stalled);
+        assert_eq!(change, Some(ReceivingChange::Stalled(Duration::from_secs(12))));
+        assert_eq!(
+            monitor.warning().map(|warning| warning.message.as_str()),
+            Some("Received no video or audio for 12s")
+        );
+
+        let (media, change) = monitor.observe(silent_video(1), true, start + Duration::from_secs(14));
+        assert_eq!((media.nothing_received_secs, media.stalled, change), (14.0, true, None));
+
+        let advancing = ReceivedMediaSample {
+            video_tiles: 1,
+            advancing_video_tiles: 1,
+            ..Default::default()
+        };
+        let (media, change) = monitor.observe(advancing, true, start + Duration::from_secs(16));
+        assert_eq!((media.nothing_received_secs, media.stalled), (0.0, false));
+        assert_eq!(change, Some(ReceivingChange::Recovered));
+        assert_eq!(monitor.warning(), None);
+    }
+
+    #[test]
+    fn participants_that_are_not_joined_never_stall() {
+        let mut monitor = ReceivedMediaMonitor::new(Some(Duration::from_secs(10)));
+        let start = Instant::now();
+
+        monitor.observe(silent_video(0), false, start);
+        let (media, change) = monitor.observe(silent_video(0), false, start + Duration::from_secs(60));
+
+        assert!(!media.stalled);
+        assert_eq!(change, None);
+    }
+}
@@ -1,13 +1,37 @@
 use super::{
-    messages::ParticipantMessage,
+    history::{
+        ParticipantEventKind,
+        ParticipantHistory,
+    },
+    messages::{
+        CommandError,
+        ParticipantCommand,
+        ParticipantMessage,
+    },
+    scheduler::StartGate,
+    timings::{
+        LatencyMetric,
+        StartPhase,
+    },
+    CallStatus,
+    CommandResult,
+    GlassToGlass,
     ParticipantState,
     ParticipantWarning,
+    ReceivedMediaMonitor,
+    ReceivingChange,
+    WebRtcStats,
+    WebRtcStatsSample,
 };
+use chrono::Utc;
 use eyre::{
     Report,
     Result,
 };
-use futures::future::BoxFuture;
+use futures::{
+    future::BoxFuture,
+    FutureExt as _,
+};
 use std::{
     future::pending,
     time::Duration,
@@ -49,6 +73,11 @@ pub(in crate::participant) trait ParticipantDriverSession: Send {
     fn close(&mut self) -> BoxFuture<'_, Result<()>>;
     fn wait_for_termination(&mut self) -> BoxFuture<'_, DriverTermination>;
 
+    /// Waits until the backend has capacity for another session. The participant stays pending until this returns.
+    fn wait_for_start_capacity(&mut self) -> BoxFuture<'_, Result<()>> {
+        async { Ok(()) }.boxed()
+    }
+
     fn start_error_warning(&self, _err: &Report) -> Option<ParticipantWarning> {
         None
     }
@@ -60,23 +89,113 @@ pub(in crate::participant) trait ParticipantDriverSession: Send {
     fn state_refresh_error_termination(&self, _err: &Report) -> Option<DriverTermination> {
         None
     }
+
+    /// Changed whenever the backend notices on its own that the state may have changed, e.g. because the page was
+    /// kicked from the space, so the runtime refreshes it right away. Read after the start and after every command
+    /// until it returns a receiver.
+    fn state_changes(&mut self) -> Option<watch::Receiver<()>> {
+        None
+    }
+
+    /// Reads the cumulative WebRTC counters of the session, `Ok(None)` if the backend cannot sample them.
+    fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<Option<WebRtcStatsSample>>> {
+        async { Ok(None) }.boxed()
+    }
+
+    fn webrtc_stats_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// How long a joined participant may receive no media before the runtime warns, `None` to never warn.
+    fn received_media_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Durations of the start phases the backend could observe since the last call.
+    fn take_phase_timings(&mut self) -> Vec<(StartPhase, Duration)> {
+        Vec::new()
+    }
 }
 
 /// Drive one participant session by translating runtime messages into backend operations.
 pub(in crate::participant) async fn run_participant_runtime<D>(
-    mut receiver: UnboundedReceiver<ParticipantMessage>,
+    mut receiver: UnboundedReceiver<ParticipantCommand>,
     state: watch::Sender<ParticipantState>,
+    history: ParticipantHistory,
     mut driver: D,
     cancellation_token: CancellationToken,
+    start_gate: StartGate,
 ) -> Result<()>
 where
     D: ParticipantDriverSession,
 {
+    let participant_name = driver.participant_name().to_string();
     state.send_modify(|current| {
-        current.username = driver.participant_name().to_string();
+        current.username = participant_name.clone();
         current.running = true;
+        current.pending = true;
     });
+    history.record(ParticipantEventKind::Pending);
+
+    // Answered once the participant is stopped.
+    let mut close_command = None;
+    let start_slot = {
+        let wait = async {
+            let permit = start_gate.wait().await;
+            driver.wait_for_start_capacity().await.map(|()| permit)
+        };
+        tokio::pin!(wait);
+
+        loop {
+            tokio::select! {
+                biased;
 
+                _ = cancellation_token.cancelled() => break None,
+                command = receiver.recv() => match command {
+                    Some(command) if matches!(command.message, ParticipantMessage::Close) => {
+                        close_command = Some((command, Ok(())));
+                        break None;
+                    }
+                    None => break None,
+                    Some(mut command) => {
+                        log_runtime_message(
+                            "warn",
+                            &participant_name,
+                            format!("Ignoring {} while waiting to start", command.message),
+                        );
+                        command.reply(Err(CommandError::Pending));
+                    }
+                },
+                result = &mut wait => break Some(result),
+            }
+        }
+    };
+
+    let mut start_permit = match start_slot {
+        Some(Ok(permit)) => permit,
+        Some(Err(err)) => {
+            history.record(ParticipantEventKind::StartFailed { error: err.to_string() });
+            log_runtime_message(
+                "error",
+                &participant_name,
+                format!("Failed waiting for backend capacity: {err}"),
+            );
+            mark_stopped(&state, &history);
+            return Ok(());
+        }
+        None => {
+            log_runtime_message("debug", &participant_name, "Participant closed before it started");
+            mark_stopped(&state, &history);
+            reply_to_close(close_command);
+            return Ok(());
+        }
+    };
+
+    state.send_modify(|current| {
+        current.pending = false;
+    });
+
+    let start_started = Instant::now();
     let start_result = tokio::select! {
         biased;
 
@@ -87,13 +206,17 @@ where
                     format!("Failed closing participant after task cancellation: {err}"),
                 );
             }
-            mark_stopped(&state);
+            mark_stopped(&state, &history);
             return Ok(());
         }
         result = driver.start() => result,
     };
+    // Only the start slot is released here, the backend slot stays taken until the runtime returns.
+    start_permit.started();
+    history.record_phases(driver.take_phase_timings());
 
     if let Err(err) = start_result {
+        history.record(ParticipantEventKind::StartFailed { error: err.to_string() });
         let warning = driver.start_error_warning(&err);
         if let Some(warning) = warning.clone() {
             state.send_modify(|current| {
@@ -117,12 +240,16 @@ where
                 format!("Failed to clean up participant after start failure: {close_err}"),
             );
         }
-        mark_stopped(&state);
+        mark_stopped(&state, &history);
         return Ok(());
     }
+    history.record_timing(LatencyMetric::Start, start_started.elapsed());
+    history.record(ParticipantEventKind::Started);
 
-    if let Some(termination) = sync_state(&mut driver, &state).await {
+    let mut received_media_monitor = ReceivedMediaMonitor::new(driver.received_media_timeout());
+    if let Some(termination) = sync_state(&mut driver, &state, &history, &received_media_monitor).await {
         let participant_name = driver.participant_name().to_string();
+        history.record_termination(&termination);
         log_runtime_message(termination.level, &participant_name, termination.message);
         if let Err(err) = driver.close().await {
             log_runtime_message(
@@ -131,22 +258,22 @@ where
                 format!("Failed closing participant after state refresh failure: {err}"),
             );
         }
-        mark_stopped(&state);
+        mark_stopped(&state, &history);
         return Ok(());
     }
 
-    let mut state_refresh_ticker = driver.state_refresh_interval().map(|interval| {
-        let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
-        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
-        ticker
-    });
+    let mut state_refresh_ticker = driver.state_refresh_interval().map(delayed_ticker);
+    let mut state_changes = driver.state_changes();
+    let mut stats_ticker = driver.webrtc_stats_interval().map(delayed_ticker);
+    let mut previous_stats_sample = None;
 
     loop {
         enum RuntimeEvent {
-            Command(ParticipantMessage),
+            Command(ParticipantCommand),
             ChannelClosed,
             Terminated(DriverTermination),
             RefreshState,
+            SampleStats,
             Cancelled,
         }
 
@@ -156,7 +283,7 @@ where
             _ = cancellation_token.cancelled() => RuntimeEvent::Cancelled,
             termination = driver.wait_for_termination() => RuntimeEvent::Terminated(termination),
             message = receiver.recv() => match message {
-                Some(message) => RuntimeEvent::Command(message),
+                Some(command) => RuntimeEvent::Command(command),
                 None => RuntimeEvent::ChannelClosed,
             },
             _ = async {
@@ -166,11 +293,25 @@ where
                     pending::<()>().await;
                 }
             } => RuntimeEvent::RefreshState,
+            _ = async {
+                match state_changes.as_mut() {
+                    Some(changes) if changes.changed().await.is_ok() => {}
+                    _ => pending::<()>().await,
+                }
+            } => RuntimeEvent::RefreshState,
+            _ = async {
+                if let Some(ticker) = stats_ticker.as_mut() {
+                    ticker.tick().await;
+                } else {
+                    pending::<()>().await;
+                }
+            } => RuntimeEvent::SampleStats,
         };
 
         match event {
             RuntimeEvent::Terminated(termination) => {
                 let participant_name = driver.participant_name().to_string();
+                history.record_termination(&termination);
                 log_runtime_message(termination.level, &participant_name, termination.message);
                 if let Err(err) = driver.close().await {
                     log_runtime_message(
@@ -202,8 +343,9 @@ where
                 break;
             }
             RuntimeEvent::RefreshState => {
-                if let Some(termination) = sync_state(&mut driver, &state).await {
+                if let Some(termination) = sync_state(&mut driver, &state, &history, &received_media_monitor).await {
                     let participant_name = driver.participant_name().to_string();
+                    history.record_termination(&termination);
                     log_runtime_message(termination.level, &participant_name, termination.message);
                     if let Err(err) = driver.close().await {
                         log_runtime_message(
@@ -215,27 +357,72 @@ where
                     break;
                 }
             }
-            RuntimeEvent::Command(ParticipantMessage::Close) => {
-                if let Err(err) = driver.close().await {
+            RuntimeEvent::SampleStats => {
+                sync_webrtc_stats(
+                    &mut driver,
+                    &state,
+                    &history,
+                    &mut previous_stats_sample,
+                    &mut received_media_monitor,
+                )
+                .await;
+            }
+            RuntimeEvent::Command(command) if matches!(command.message, ParticipantMessage::Close) => {
+                let result = driver.close().await.map_err(|err| {
                     log_runtime_message(
                         "error",
                         driver.participant_name(),
                         format!("Failed closing participant: {err}"),
                     );
-                }
+                    CommandError::Failed(err.to_string())
+                });
+                close_command = Some((command, result));
                 break;
             }
-            RuntimeEvent::Command(message) => {
-                if let Err(err) = driver.handle_command(message.clone()).await {
-                    log_runtime_message(
-                        "error",
-                        driver.participant_name(),
-                        format!("Running action {message} failed with error: {err}."),
-                    );
+            RuntimeEvent::Command(mut command) => {
+                let message = command.message.clone();
+                let command_started = Instant::now();
+                let command_name = <&'static str>::from(&message);
+                let error = match driver.handle_command(message.clone()).await {
+                    Ok(()) => {
+                        history.record_timing(
+                            LatencyMetric::Command(command_name.to_string()),
+                            command_started.elapsed(),
+                        );
+                        None
+                    }
+                    Err(err) => {
+                        history.record(ParticipantEventKind::CommandFailed {
+                            command: message.to_string(),
+                            error: err.to_string(),
+                        });
+                        log_runtime_message(
+                            "error",
+                            driver.participant_name(),
+                            format!("Running action {message} failed with error: {err}."),
+                        );
+                        Some(err.to_string())
+                    }
+                };
+                state.send_modify(|current| {
+                    current.last_command = Some(CommandResult {
+                        command: command_name.to_string(),
+                        at: Utc::now(),
+                        error: error.clone(),
+                    });
+                });
+                // A `Join` after a `Leave` goes through the start phases again.
+                history.record_phases(driver.take_phase_timings());
+                if state_changes.is_none() {
+                    state_changes = driver.state_changes();
                 }
 
-                if let Some(termination) = sync_state(&mut driver, &state).await {
+                let termination = sync_state(&mut driver, &state, &history, &received_media_monitor).await;
+                // Answered after the refresh, so the caller sees the state the command left behind.
+                command.reply(error.map_or(Ok(()), |error| Err(CommandError::Failed(error))));
+                if let Some(termination) = termination {
                     let participant_name = driver.participant_name().to_string();
+                    history.record_termination(&termination);
                     log_runtime_message(termination.level, &participant_name, termination.message);
                     if let Err(err) = driver.close().await {
                         log_runtime_message(
@@ -250,11 +437,18 @@ where
         }
     }
 
-    mark_stopped(&state);
+    mark_stopped(&state, &history);
+    reply_to_close(close_command);
 
     Ok(())
 }
 
+fn reply_to_close(close_command: Option<(ParticipantCommand, Result<(), CommandError>)>) {
+    if let Some((mut command, result)) = close_command {
+        command.reply(result);
+    }
+}
+
 fn start_error_message(err: &Report, warning: Option<&ParticipantWarning>) -> String {
     match warning {
         Some(warning) => format!(
@@ -265,8 +459,20 @@ fn start_error_message(err: &Report, warning: Option<&ParticipantWarning>) -> St
     }
 }
 
-/// Refresh the shared participant state from the backend and publish it to watchers.
-async fn sync_state<D>(driver: &mut D, state: &watch::Sender<ParticipantState>) -> Option<DriverTermination>
+fn delayed_ticker(interval: Duration) -> tokio::time::Interval {
+    let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ticker
+}
+
+/// Refresh the shared participant state from the backend and publish it to watchers. A stall of the received media
+/// is overlaid as the warning unless the backend reports one of its own.
+async fn sync_state<D>(
+    driver: &mut D,
+    state: &watch::Sender<ParticipantState>,
+    history: &ParticipantHistory,
+    received_media_monitor: &ReceivedMediaMonitor,
+) -> Option<DriverTermination>
 where
     D: ParticipantDriverSession,
 {
@@ -274,9 +480,43 @@ where
         Ok(mut next_state) => {
             next_state.username = driver.participant_name().to_string();
             next_state.running = true;
+            if next_state.warning.is_none() {
+                next_state.warning = received_media_monitor.warning().cloned();
+            }
+            let mut previous_call_status = next_state.call_status;
             state.send_modify(|current| {
+                previous_call_status = current.call_status;
+                // Stats are sampled on their own schedule, keep the latest ones across refreshes.
+                if next_state.webrtc_stats.is_none() {
+                    next_state.webrtc_stats = current.webrtc_stats.take();
+                }
+                if next_state.received_media.is_none() {
+                    next_state.received_media = current.received_media.take();
+                }
+                if next_state.glass_to_glass.is_empty() {
+                    next_state.glass_to_glass = std::mem::take(&mut current.glass_to_glass);
+                }
+                if next_state.last_command.is_none() {
+                    next_state.last_command = current.last_command.take();
+                }
+                history.record_transition(current, &next_state);
                 *current = next_state;
             });
+            let call_status = state.borrow().call_status;
+            if call_status != previous_call_status {
+                match call_status.warning() {
+                    Some(warning) => log_runtime_message(
+                        "warn",
+                        driver.participant_name(),
+                        format!("{}: {}", warning.title, warning.message),
+                    ),
+                    None => log_runtime_message(
+                        "info",
+                        driver.participant_name(),
+                        format!("Call recovered from {previous_call_status}"),
+                    ),
+                }
+            }
             None
         }
         Err(err) => {
@@ -293,13 +533,92 @@ where
     }
 }
 
+/// Sample WebRTC stats and publish the rates derived from the previous sample, along with the received media and the
+/// glass-to-glass latencies, which are also recorded in the history. Raises the warning of a stall and clears it on
+/// recovery.
+async fn sync_webrtc_stats<D>(
+    driver: &mut D,
+    state: &watch::Sender<ParticipantState>,
+    history: &ParticipantHistory,
+    previous_sample: &mut Option<WebRtcStatsSample>,
+    received_media_monitor: &mut ReceivedMediaMonitor,
+) where
+    D: ParticipantDriverSession,
+{
+    match driver.sample_webrtc_stats().await {
+        Ok(Some(mut sample)) => {
+            let previous_warning = received_media_monitor.warning().cloned();
+            let received_media = sample.received_media.take().map(|received| {
+                let joined = state.borrow().joined;
+                let (media, change) = received_media_monitor.observe(received, joined, Instant::now());
+                match change {
+                    Some(ReceivingChange::Stalled(duration)) => log_runtime_message(
+                        "warn",
+                        driver.participant_name(),
+                        format!("Received no video or audio for {}s", duration.as_secs()),
+                    ),
+                    Some(ReceivingChange::Recovered) => {
+                        log_runtime_message("info", driver.participant_name(), "Receiving media again")
+                    }
+                    None => {}
+                }
+                media
+            });
+            let glass_to_glass = std::mem::take(&mut sample.glass_to_glass)
+                .into_iter()
+                .map(GlassToGlass::from)
+                .collect::<Vec<_>>();
+            for latency in &glass_to_glass {
+                history.record_glass_to_glass(latency.clone());
+            }
+            let stats = WebRtcStats::from_samples(previous_sample.as_ref(), &sample);
+            *previous_sample = Some(sample);
+            let warning = received_media_monitor.warning();
+            state.send_modify(|current| {
+                current.webrtc_stats = Some(stats);
+                current.received_media = received_media;
+                // Warnings of the backend take precedence and are left alone.
+                if current.warning.as_ref() == previous_warning.as_ref() {
+                    current.warning = warning.cloned();
+                }
+                for latency in glass_to_glass {
+                    match current
+                        .glass_to_glass
+                        .iter_mut()
+                        .find(|current| current.sender_id == latency.sender_id)
+                    {
+                        Some(current) => *current = latency,
+                        None => current.glass_to_glass.push(latency),
+                    }
+                }
+            });
+        }
+        Ok(None) => {}
+        Err(err) => log_runtime_message(
+            "debug",
+            driver.participant_name(),
+            format!("Failed sampling WebRTC stats: {err}"),
+        ),
+    }
+}
+
 /// Mark the participant as no longer running after the runtime loop exits.
-fn mark_stopped(state: &watch::Sender<ParticipantState>) {
+fn mark_stopped(state: &watch::Sender<ParticipantState>, history: &ParticipantHistory) {
     state.send_modify(|current| {
+        if current.joined {
+            history.record(ParticipantEventKind::Left);
+        }
         current.running = false;
+        current.pending = false;
         current.joined = false;
+        current.call_status = CallStatus::Normal;
         current.screenshare_activated = false;
+        current.webrtc_stats = None;
+        current.received_media = None;
+        current.glass_to_glass.clear();
+        current.recording = false;
     });
+    history.record(ParticipantEventKind::Stopped);
 }
 
 /// Log a runtime message through tracing.
@@ -324,9 +643,31 @@ mod tests {
         ParticipantDriverSession,
     };
     use crate::participant::shared::{
-        messages::ParticipantMessage,
+        history::{
+            ParticipantEventKind,
+            ParticipantHistory,
+        },
+        messages::{
+            CommandError,
+            ParticipantCommand,
+            ParticipantMessage,
+        },
+        scheduler::{
+            SpawnScheduler,
+            StartGate,
+        },
+        timings::{
+            LatencyMetric,
+            StartPhase,
+        },
         ParticipantState,
         ParticipantWarning,
+        ReceivedMediaSample,
+        WebRtcStatsSample,
+    };
+    use client_simulator_config::{
+        ParticipantBackendKind,
+        SpawnConfig,
     };
     use eyre::Result;
     use futures::{
@@ -337,11 +678,13 @@ mod tests {
         future::pending,
         sync::{
             atomic::{
+                AtomicBool,
                 AtomicUsize,
                 Ordering,
             },
             Arc,
         },
+        time::Duration,
     };
     use tokio::sync::{
         mpsc::unbounded_channel,
@@ -349,12 +692,17 @@ mod tests {
     };
     use tokio_util::sync::CancellationToken;
 
+    fn start_gate() -> StartGate {
+        SpawnScheduler::default().gate(ParticipantBackendKind::Local)
+    }
+
     struct FakeDriver {
         name: String,
         joined: bool,
         muted: bool,
         close_count: usize,
         commands: Vec<ParticipantMessage>,
+        phase_timings: Vec<(StartPhase, Duration)>,
     }
 
     impl FakeDriver {
@@ -365,6 +713,7 @@ mod tests {
                 muted: false,
                 close_count: 0,
                 commands: Vec::new(),
+                phase_timings: vec![(StartPhase::Navigation, Duration::from_millis(120))],
             }
         }
     }
@@ -395,6 +744,7 @@ mod tests {
                     ParticipantMessage::Join => {
                         self.joined = true;
                     }
+                    ParticipantMessage::StartRecording => eyre::bail!("no peer connection tracker"),
                     ParticipantMessage::Close
                     | ParticipantMessage::ToggleVideo
                     | ParticipantMessage::ToggleScreenshare
@@ -403,7 +753,14 @@ mod tests {
                     | ParticipantMessage::SetVideoConstraintPublishWebcam(_)
                     | ParticipantMessage::SetVideoConstraintSubscribe(_)
                     | ParticipantMessage::SetVideoMaxConcurrentTracks(_)
-                    | ParticipantMessage::ToggleBackgroundBlur => {}
+                    | ParticipantMessage::ToggleBackgroundBlur
+                    | ParticipantMessage::SetAudioEnabled(_)
+                    | ParticipantMessage::SetVideoEnabled(_)
+                    | ParticipantMessage::SetScreenshare(_)
+                    | ParticipantMessage::SetBlur(_)
+                    | ParticipantMessage::SetAutoGainControl(_)
+                    | ParticipantMessage::SetNetwork(_)
+                    | ParticipantMessage::StopRecording => {}
                 }
                 Ok(())
             }
@@ -435,6 +792,10 @@ mod tests {
         fn wait_for_termination(&mut self) -> BoxFuture<'_, DriverTermination> {
             async move { pending::<DriverTermination>().await }.boxed()
         }
+
+        fn take_phase_timings(&mut self) -> Vec<(StartPhase, Duration)> {
+            std::mem::take(&mut self.phase_timings)
+        }
     }
 
     #[tokio::test]
@@ -445,30 +806,205 @@ mod tests {
         let runtime = tokio::spawn(run_participant_runtime(
             message_rx,
             state_tx,
+            ParticipantHistory::default(),
             FakeDriver::new("sim-user"),
             CancellationToken::new(),
+            start_gate(),
         ));
 
-        message_tx.send(ParticipantMessage::ToggleAudio).unwrap();
+        message_tx.send(ParticipantMessage::ToggleAudio.into()).unwrap();
         state_rx
             .clone()
             .wait_for(|state| state.running && state.joined && state.muted)
             .await
             .unwrap();
 
-        message_tx.send(ParticipantMessage::Leave).unwrap();
+        message_tx.send(ParticipantMessage::Leave.into()).unwrap();
         state_rx
             .clone()
             .wait_for(|state| state.running && !state.joined && state.muted)
             .await
             .unwrap();
 
-        message_tx.send(ParticipantMessage::Close).unwrap();
+        message_tx.send(ParticipantMessage::Close.into()).unwrap();
         runtime.await.unwrap().unwrap();
 
         assert!(!state_rx.borrow().running);
     }
 
+    #[tokio::test]
+    async fn runtime_answers_commands_and_keeps_the_last_result() {
+        let (message_tx, message_rx) = unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(ParticipantState::default());
+
+        let runtime = tokio::spawn(run_participant_runtime(
+            message_rx,
+            state_tx,
+            ParticipantHistory::default(),
+            FakeDriver::new("sim-user"),
+            CancellationToken::new(),
+            start_gate(),
+        ));
+        state_rx.clone().wait_for(|state| state.joined).await.unwrap();
+
+        let (command, reply) = ParticipantCommand::with_reply(ParticipantMessage::ToggleAudio);
+        message_tx.send(command).unwrap();
+        assert_eq!(reply.await.unwrap(), Ok(()));
+        assert!(state_rx.borrow().muted);
+
+        let (command, reply) = ParticipantCommand::with_reply(ParticipantMessage::StartRecording);
+        message_tx.send(command).unwrap();
+        assert_eq!(
+            reply.await.unwrap(),
+            Err(CommandError::Failed("no peer connection tracker".to_string()))
+        );
+        let last_command = state_rx.borrow().last_command.clone().unwrap();
+        assert_eq!(last_command.command, "StartRecording");
+        assert_eq!(last_command.error.as_deref(), Some("no peer connection tracker"));
+
+        let (command, reply) = ParticipantCommand::with_reply(ParticipantMessage::Close);
+        message_tx.send(command).unwrap();
+        assert_eq!(reply.await.unwrap(), Ok(()));
+        assert!(!state_rx.borrow().running);
+        runtime.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn runtime_records_lifecycle_events_in_history() {
+        let (message_tx, message_rx) = unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(ParticipantState::default());
+        let history = ParticipantHistory::default();
+
+        let runtime = tokio::spawn(run_participant_runtime(
+            message_rx,
+            state_tx,
+            history.clone(),
+            FakeDriver::new("sim-user"),
+            CancellationToken::new(),
+            start_gate(),
+        ));
+
+        state_rx.clone().wait_for(|state| state.joined).await.unwrap();
+        message_tx.send(ParticipantMessage::Leave.into()).unwrap();
+        message_tx.send(ParticipantMessage::Join.into()).unwrap();
+        message_tx.send(ParticipantMessage::Close.into()).unwrap();
+        runtime.await.unwrap().unwrap();
+
+        let events = history.events().into_iter().map(|event| event.kind).collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                ParticipantEventKind::Pending,
+                ParticipantEventKind::Started,
+                ParticipantEventKind::Joined,
+                ParticipantEventKind::Left,
+                ParticipantEventKind::Joined,
+                ParticipantEventKind::Left,
+                ParticipantEventKind::Stopped,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn runtime_records_start_phase_and_command_timings() {
+        let (message_tx, message_rx) = unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(ParticipantState::default());
+        let history = ParticipantHistory::default();
+
+        let runtime = tokio::spawn(run_participant_runtime(
+            message_rx,
+            state_tx,
+            history.clone(),
+            FakeDriver::new("sim-user"),
+            CancellationToken::new(),
+            start_gate(),
+        ));
+
+        state_rx.clone().wait_for(|state| state.joined).await.unwrap();
+        message_tx.send(ParticipantMessage::ToggleAudio.into()).unwrap();
+        message_tx.send(ParticipantMessage::Close.into()).unwrap();
+        runtime.await.unwrap().unwrap();
+
+        let timings = history.timings();
+        let metrics = timings.iter().map(|timing| timing.metric.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            metrics,
+            vec![
+                LatencyMetric::Phase(StartPhase::Navigation),
+                LatencyMetric::Start,
+                LatencyMetric::Command("ToggleAudio".to_string()),
+            ]
+        );
+        assert_eq!(timings[0].duration_ms, 120);
+    }
+
+    #[tokio::test]
+    async fn runtime_stays_pending_until_the_scheduler_lets_it_start() {
+        let scheduler = SpawnScheduler::new(&SpawnConfig {
+            max_concurrent_starts: Some(1),
+            ..Default::default()
+        });
+        let mut blocking_start = scheduler.gate(ParticipantBackendKind::Local).wait().await;
+
+        let (message_tx, message_rx) = unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(ParticipantState::default());
+        let runtime = tokio::spawn(run_participant_runtime(
+            message_rx,
+            state_tx,
+            ParticipantHistory::default(),
+            FakeDriver::new("sim-user"),
+            CancellationToken::new(),
+            scheduler.gate(ParticipantBackendKind::Local),
+        ));
+
+        state_rx
+            .clone()
+            .wait_for(|state| state.running && state.pending && !state.joined)
+            .await
+            .unwrap();
+
+        blocking_start.started();
+        state_rx
+            .clone()
+            .wait_for(|state| state.running && !state.pending && state.joined)
+            .await
+            .unwrap();
+
+        message_tx.send(ParticipantMessage::Close.into()).unwrap();
+        runtime.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn runtime_closes_pending_participant_without_starting_it() {
+        let scheduler = SpawnScheduler::new(&SpawnConfig {
+            max_concurrent_starts: Some(1),
+            ..Default::default()
+        });
+        let _blocking_start = scheduler.gate(ParticipantBackendKind::Local).wait().await;
+
+        let (message_tx, message_rx) = unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(ParticipantState::default());
+        let runtime = tokio::spawn(run_participant_runtime(
+            message_rx,
+            state_tx,
+            ParticipantHistory::default(),
+            FakeDriver::new("sim-user"),
+            CancellationToken::new(),
+            scheduler.gate(ParticipantBackendKind::Local),
+        ));
+
+        state_rx.clone().wait_for(|state| state.pending).await.unwrap();
+        message_tx.send(ParticipantMessage::ToggleAudio.into()).unwrap();
+        message_tx.send(ParticipantMessage::Close.into()).unwrap();
+        runtime.await.unwrap().unwrap();
+
+        let state = state_rx.borrow();
+        assert!(!state.running);
+        assert!(!state.pending);
+        assert!(!state.joined);
+        assert!(!state.muted);
+    }
+
     #[tokio::test]
     async fn runtime_marks_participant_stopped_when_driver_terminates() {
         struct TerminatingDriver {
@@ -530,12 +1066,14 @@ mod tests {
         run_participant_runtime(
             message_rx,
             state_tx,
+            ParticipantHistory::default(),
             TerminatingDriver {
                 close_count: Arc::clone(&close_count),
                 name: "sim-user".to_string(),
                 terminated: false,
             },
             CancellationToken::new(),
+            start_gate(),
         )
         .await
         .unwrap();
@@ -597,10 +1135,12 @@ mod tests {
         let runtime = tokio::spawn(run_participant_runtime(
             message_rx,
             state_tx,
+            ParticipantHistory::default(),
             CancelAwareDriver {
                 close_count: Arc::clone(&close_count),
             },
             cancellation_token.clone(),
+            start_gate(),
         ));
 
         state_rx.clone().wait_for(|state| state.running).await.unwrap();
@@ -660,10 +1200,12 @@ mod tests {
         run_participant_runtime(
             message_rx,
             state_tx,
+            ParticipantHistory::default(),
             FailingStartDriver {
                 close_count: Arc::clone(&close_count),
             },
             CancellationToken::new(),
+            start_gate(),
         )
         .await
         .unwrap();
@@ -679,4 +1221,273 @@ mod tests {
             Some("Run setup-auth")
         );
     }
+
+    #[tokio::test]
+    async fn runtime_publishes_webrtc_stats_and_keeps_them_across_state_refreshes() {
+        struct SamplingDriver {
+            samples: usize,
+            muted: bool,
+        }
+
+        impl ParticipantDriverSession for SamplingDriver {
+            fn participant_name(&self) -> &str {
+                "sim-user"
+            }
+
+            fn start(&mut self) -> BoxFuture<'_, Result<()>> {
+                async move { Ok(()) }.boxed()
+            }
+
+            fn handle_command(&mut self, _message: ParticipantMessage) -> BoxFuture<'_, Result<()>> {
+                async move {
+                    self.muted = true;
+                    Ok(())
+                }
+                .boxed()
+            }
+
+            fn refresh_state(&mut self) -> BoxFuture<'_, Result<ParticipantState>> {
+                async move {
+                    Ok(ParticipantState {
+                        joined: true,
+                        muted: self.muted,
+                        ..Default::default()
+                    })
+                }
+                .boxed()
+            }
+
+            fn close(&mut self) -> BoxFuture<'_, Result<()>> {
+                async move { Ok(()) }.boxed()
+            }
+
+            fn wait_for_termination(&mut self) -> BoxFuture<'_, DriverTermination> {
+                async move { pending::<DriverTermination>().await }.boxed()
+            }
+
+            fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<Option<WebRtcStatsSample>>> {
+                async move {
+                    // Only two samples, so stats seen afterwards must have survived the state refreshes.
+                    if self.samples == 2 {
+                        return Ok(None);
+                    }
+                    self.samples += 1;
+                    Ok(Some(WebRtcStatsSample {
+                        timestamp_ms: self.samples as f64 * 1_000.0,
+                        peer_connections: 1,
+                        bytes_received: self.samples as u64 * 10_000,
+                        ..Default::default()
+                    }))
+                }
+                .boxed()
+            }
+
+            fn webrtc_stats_interval(&self) -> Option<Duration> {
+                Some(Duration::from_millis(10))
+            }
+        }
+
+        let (message_tx, message_rx) = unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(ParticipantState::default());
+        let runtime = tokio::spawn(run_participant_runtime(
+            message_rx,
+            state_tx,
+            ParticipantHistory::default(),
+            SamplingDriver {
+                samples: 0,
+                muted: false,
+            },
+            CancellationToken::new(),
+            start_gate(),
+        ));
+
+        state_rx
+            .clone()
+            .wait_for(|state| {
+                state
+                    .webrtc_stats
+                    .as_ref()
+                    .is_some_and(|stats| stats.inbound_bitrate_kbps == 80.0)
+            })
+            .await
+            .unwrap();
+
+        message_tx.send(ParticipantMessage::ToggleAudio.into()).unwrap();
+        state_rx.clone().wait_for(|state| state.muted).await.unwrap();
+        assert_eq!(
+            state_rx
+                .borrow()
+                .webrtc_stats
+                .as_ref()
+                .map(|stats| stats.inbound_bitrate_kbps),
+            Some(80.0)
+        );
+
+        message_tx.send(ParticipantMessage::Close.into()).unwrap();
+        runtime.await.unwrap().unwrap();
+
+        assert!(!state_rx.borrow().running);
+        assert_eq!(state_rx.borrow().webrtc_stats, None);
+    }
+
+    #[tokio::test]
+    async fn runtime_marks_joined_participants_that_receive_nothing_as_stalled() {
+        struct SilentDriver;
+
+        impl ParticipantDriverSession for SilentDriver {
+            fn participant_name(&self) -> &str {
+                "sim-user"
+            }
+
+            fn start(&mut self) -> BoxFuture<'_, Result<()>> {
+                async move { Ok(()) }.boxed()
+            }
+
+            fn handle_command(&mut self, _message: ParticipantMessage) -> BoxFuture<'_, Result<()>> {
+                async move { Ok(()) }.boxed()
+            }
+
+            fn refresh_state(&mut self) -> BoxFuture<'_, Result<ParticipantState>> {
+                async move {
+                    Ok(ParticipantState {
+                        joined: true,
+                        ..Default::default()
+                    })
+                }
+                .boxed()
+            }
+
+            fn close(&mut self) -> BoxFuture<'_, Result<()>> {
+                async move { Ok(()) }.boxed()
+            }
+
+            fn wait_for_termination(&mut self) -> BoxFuture<'_, DriverTermination> {
+                async move { pending::<DriverTermination>().await }.boxed()
+            }
+
+            fn sample_webrtc_stats(&mut self) -> BoxFuture<'_, Result<Option<WebRtcStatsSample>>> {
+                async move {
+                    Ok(Some(WebRtcStatsSample {
+                        received_media: Some(ReceivedMediaSample {
+                            video_tiles: 2,
+                            frozen_video_tiles: 2,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }))
+                }
+                .boxed()
+            }
+
+            fn webrtc_stats_interval(&self) -> Option<Duration> {
+                Some(Duration::from_millis(10))
+            }
+
+            fn received_media_timeout(&self) -> Option<Duration> {
+                Some(Duration::ZERO)
+            }
+        }
+
+        let (message_tx, message_rx) = unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(ParticipantState::default());
+        let runtime = tokio::spawn(run_participant_runtime(
+            message_rx,
+            state_tx,
+            ParticipantHistory::default(),
+            SilentDriver,
+            CancellationToken::new(),
+            start_gate(),
+        ));
+
+        state_rx
+            .clone()
+            .wait_for(|state| state.received_media.as_ref().is_some_and(|media| media.stalled))
+            .await
+            .unwrap();
+        assert_eq!(
+            state_rx
+                .borrow()
+                .received_media
+                .as_ref()
+                .map(|media| media.frozen_video_tiles),
+            Some(2)
+        );
+
+        message_tx.send(ParticipantMessage::Close.into()).unwrap();
+        runtime.await.unwrap().unwrap();
+
+        assert_eq!(state_rx.borrow().received_media, None);
+    }
+
+    #[tokio::test]
+    async fn runtime_refreshes_state_when_the_driver_reports_a_change() {
+        struct ObservedDriver {
+            joined: Arc<AtomicBool>,
+            changes: watch::Receiver<()>,
+        }
+
+        impl ParticipantDriverSession for ObservedDriver {
+            fn participant_name(&self) -> &str {
+                "sim-user"
+            }
+
+            fn start(&mut self) -> BoxFuture<'_, Result<()>> {
+                async move { Ok(()) }.boxed()
+            }
+
+            fn handle_command(&mut self, _message: ParticipantMessage) -> BoxFuture<'_, Result<()>> {
+                async move { Ok(()) }.boxed()
+            }
+
+            fn refresh_state(&mut self) -> BoxFuture<'_, Result<ParticipantState>> {
+                let joined = self.joined.load(Ordering::SeqCst);
+                async move {
+                    Ok(ParticipantState {
+                        joined,
+                        ..Default::default()
+                    })
+                }
+                .boxed()
+            }
+
+            fn close(&mut self) -> BoxFuture<'_, Result<()>> {
+                async move { Ok(()) }.boxed()
+            }
+
+            fn wait_for_termination(&mut self) -> BoxFuture<'_, DriverTermination> {
+                async move { pending::<DriverTermination>().await }.boxed()
+            }
+
+            fn state_changes(&mut self) -> Option<watch::Receiver<()>> {
+                Some(self.changes.clone())
+            }
+        }
+
+        let joined = Arc::new(AtomicBool::new(true));
+        let (changes_tx, changes_rx) = watch::channel(());
+        let (message_tx, message_rx) = unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(ParticipantState::default());
+        let runtime = tokio::spawn(run_participant_runtime(
+            message_rx,
+            state_tx,
+            ParticipantHistory::default(),
+            ObservedDriver {
+                joined: joined.clone(),
+                changes: changes_rx,
+            },
+            CancellationToken::new(),
+            start_gate(),
+        ));
+
+        state_rx.clone().wait_for(|state| state.joined).await.unwrap();
+        joined.store(false, Ordering::SeqCst);
+        changes_tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), state_rx.clone().wait_for(|state| !state.joined))
+            .await
+            .expect("state was not refreshed after the change")
+            .unwrap();
+
+        message_tx.send(ParticipantMessage::Close.into()).unwrap();
+        runtime.await.unwrap().unwrap();
+    }
 }
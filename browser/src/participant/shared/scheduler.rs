@@ -0,0 +1,208 @@
+use client_simulator_config::{
+    ParticipantBackendKind,
+    SpawnConfig,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::Duration,
+};
+use strum::IntoEnumIterator as _;
+use tokio::{
+    sync::{
+        OwnedSemaphorePermit,
+        Semaphore,
+    },
+    time::Instant,
+};
+
+/// Paces participant starts according to the configured [`SpawnConfig`].
+///
+/// Every participant spawned through the same scheduler shares its start slots, inter-spawn delay, and per-backend
+/// caps.
+#[derive(Debug, Clone)]
+pub struct SpawnScheduler {
+    inner: Arc<SpawnSchedulerInner>,
+}
+
+#[derive(Debug)]
+struct SpawnSchedulerInner {
+    starts: Option<Arc<Semaphore>>,
+    interval: Duration,
+    next_start: Mutex<Option<Instant>>,
+    running: HashMap<ParticipantBackendKind, Arc<Semaphore>>,
+}
+
+impl Default for SpawnScheduler {
+    fn default() -> Self {
+        Self::new(&SpawnConfig::default())
+    }
+}
+
+impl SpawnScheduler {
+    pub fn new(config: &SpawnConfig) -> Self {
+        let running = ParticipantBackendKind::iter()
+            .filter_map(|backend| {
+                let limit = config.max_running(backend)?;
+                Some((backend, Arc::new(Semaphore::new(limit))))
+            })
+            .collect();
+
+        Self {
+            inner: Arc::new(SpawnSchedulerInner {
+                starts: config
+                    .max_concurrent_starts
+                    .map(|limit| Arc::new(Semaphore::new(limit))),
+                interval: Duration::from_millis(config.interval_ms),
+                next_start: Mutex::new(None),
+                running,
+            }),
+        }
+    }
+
+    pub(in crate::participant) fn gate(&self, backend: ParticipantBackendKind) -> StartGate {
+        StartGate {
+            scheduler: self.clone(),
+            backend,
+        }
+    }
+
+    /// Reserves the next start time, keeping at least the configured interval between two starts.
+    fn reserve_start_time(&self) -> Instant {
+        let now = Instant::now();
+        let mut next_start = self.inner.next_start.lock().unwrap();
+        let at = next_start.map_or(now, |next| next.max(now));
+        *next_start = Some(at + self.inner.interval);
+        at
+    }
+}
+
+/// The scheduler slot a single participant waits on before its backend session starts.
+pub(in crate::participant) struct StartGate {
+    scheduler: SpawnScheduler,
+    backend: ParticipantBackendKind,
+}
+
+impl StartGate {
+    /// Waits until the participant may start.
+    ///
+    /// The backend cap is acquired first so a participant blocked on a full backend does not hold a start slot
+    /// that participants of other backends could use.
+    pub(in crate::participant) async fn wait(&self) -> StartPermit {
+        let inner = &self.scheduler.inner;
+
+        let running = acquire(inner.running.get(&self.backend)).await;
+        let start = acquire(inner.starts.as_ref()).await;
+
+        let start_at = self.scheduler.reserve_start_time();
+        if start_at > Instant::now() {
+            tokio::time::sleep_until(start_at).await;
+        }
+
+        StartPermit {
+            _running: running,
+            start,
+        }
+    }
+}
+
+async fn acquire(semaphore: Option<&Arc<Semaphore>>) -> Option<OwnedSemaphorePermit> {
+    let semaphore = Arc::clone(semaphore?);
+    Some(
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("spawn scheduler semaphores are never closed"),
+    )
+}
+
+/// Scheduler slots held by a participant.
+///
+/// The start slot is released once the backend finished starting, the backend slot when the participant stops.
+pub(in crate::participant) struct StartPermit {
+    _running: Option<OwnedSemaphorePermit>,
+    start: Option<OwnedSemaphorePermit>,
+}
+
+impl StartPermit {
+    pub(in crate::participant) fn started(&mut self) {
+        self.start = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpawnScheduler;
+    use client_simulator_config::{
+        ParticipantBackendKind,
+        SpawnConfig,
+    };
+    use std::time::Duration;
+    use tokio::time::{
+        timeout,
+        Instant,
+    };
+
+    #[tokio::test]
+    async fn limits_concurrent_starts_until_a_start_finishes() {
+        let scheduler = SpawnScheduler::new(&SpawnConfig {
+            max_concurrent_starts: Some(1),
+            ..Default::default()
+        });
+        let first_gate = scheduler.gate(ParticipantBackendKind::Local);
+        let second_gate = scheduler.gate(ParticipantBackendKind::Cloudflare);
+
+        let mut first = first_gate.wait().await;
+        assert!(timeout(Duration::from_millis(20), second_gate.wait()).await.is_err());
+
+        first.started();
+        timeout(Duration::from_millis(20), second_gate.wait())
+            .await
+            .expect("second participant should start once the first one started");
+    }
+
+    #[tokio::test]
+    async fn backend_cap_is_held_until_the_permit_is_dropped() {
+        let scheduler = SpawnScheduler::new(&SpawnConfig {
+            max_remote_stub: Some(1),
+            ..Default::default()
+        });
+        let gate = scheduler.gate(ParticipantBackendKind::RemoteStub);
+
+        let mut first = gate.wait().await;
+        first.started();
+        assert!(timeout(Duration::from_millis(20), gate.wait()).await.is_err());
+
+        timeout(
+            Duration::from_millis(20),
+            scheduler.gate(ParticipantBackendKind::Local).wait(),
+        )
+        .await
+        .expect("other backends are not capped");
+
+        drop(first);
+        timeout(Duration::from_millis(20), gate.wait())
+            .await
+            .expect("participant should start once the first one stopped");
+    }
+
+    #[tokio::test]
+    async fn spaces_starts_by_the_configured_interval() {
+        let scheduler = SpawnScheduler::new(&SpawnConfig {
+            interval_ms: 50,
+            ..Default::default()
+        });
+        let gate = scheduler.gate(ParticipantBackendKind::Local);
+        let started = Instant::now();
+
+        gate.wait().await;
+        assert!(started.elapsed() < Duration::from_millis(50));
+        gate.wait().await;
+        gate.wait().await;
+
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -1,9 +1,16 @@
 use client_simulator_config::{
+    media::{
+        FakeMediaWithDescription,
+        ScreenshareSource,
+    },
+    DeviceConfig,
+    NetworkConfig,
     NoiseSuppression,
     ParticipantConfig,
     TransportMode,
     VideoConstraint,
 };
+use std::time::Duration;
 use url::Url;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,6 +35,7 @@ pub(in crate::participant) struct ParticipantSettings {
     pub(in crate::participant) audio_enabled: bool,
     pub(in crate::participant) video_enabled: bool,
     pub(in crate::participant) screenshare_enabled: bool,
+    pub(in crate::participant) screenshare_source: ScreenshareSource,
     pub(in crate::participant) auto_gain_control: bool,
     pub(in crate::participant) noise_suppression: NoiseSuppression,
     pub(in crate::participant) transport: TransportMode,
@@ -35,6 +43,11 @@ pub(in crate::participant) struct ParticipantSettings {
     pub(in crate::participant) video_constraint_subscribe: VideoConstraint,
     pub(in crate::participant) video_max_concurrent_tracks: Option<usize>,
     pub(in crate::participant) blur: bool,
+    pub(in crate::participant) network: NetworkConfig,
+    pub(in crate::participant) device: DeviceConfig,
+    pub(in crate::participant) fake_media: FakeMediaWithDescription,
+    pub(in crate::participant) webrtc_stats_interval: Option<Duration>,
+    pub(in crate::participant) received_media_timeout: Option<Duration>,
 }
 
 impl From<&ParticipantConfig> for ParticipantSettings {
@@ -44,6 +57,7 @@ impl From<&ParticipantConfig> for ParticipantSettings {
             audio_enabled: app_config.audio_enabled,
             video_enabled: app_config.video_enabled,
             screenshare_enabled: app_config.screenshare_enabled,
+            screenshare_source: app_config.screenshare_source.clone(),
             auto_gain_control: app_config.auto_gain_control,
             noise_suppression: app_config.noise_suppression,
             transport: app_config.transport,
@@ -51,6 +65,13 @@ impl From<&ParticipantConfig> for ParticipantSettings {
             video_constraint_subscribe: app_config.video_constraint_subscribe,
             video_max_concurrent_tracks: app_config.video_max_concurrent_tracks,
             blur: app_config.blur,
+            network: app_config.network,
+            device: app_config.device.clone(),
+            fake_media: app_config.fake_media_with_description(),
+            webrtc_stats_interval: (app_config.webrtc_stats_interval_ms > 0)
+                .then(|| Duration::from_millis(app_config.webrtc_stats_interval_ms)),
+            received_media_timeout: (app_config.received_media_timeout_secs > 0)
+                .then(|| Duration::from_secs(app_config.received_media_timeout_secs)),
         }
     }
 }
@@ -90,11 +111,14 @@ mod tests {
     };
     use client_simulator_config::{
         Config,
+        NetworkConfig,
+        NetworkProfile,
         NoiseSuppression,
         ParticipantConfig,
         TransportMode,
         VideoConstraint,
     };
+    use std::time::Duration;
     use url::Url;
 
     #[test]
@@ -113,6 +137,12 @@ mod tests {
                 video_constraint_subscribe: VideoConstraint::P720,
                 video_max_concurrent_tracks: Some(2),
                 blur: true,
+                network: NetworkConfig {
+                    profile: NetworkProfile::Fast3g,
+                    ..Default::default()
+                },
+                webrtc_stats_interval_ms: 0,
+                received_media_timeout_secs: 30,
                 ..Default::default()
             },
         };
@@ -132,5 +162,8 @@ mod tests {
         assert_eq!(spec.settings.video_constraint_subscribe, VideoConstraint::P720);
         assert_eq!(spec.settings.video_max_concurrent_tracks, Some(2));
         assert!(spec.settings.blur);
+        assert_eq!(spec.settings.network.profile, NetworkProfile::Fast3g);
+        assert_eq!(spec.settings.webrtc_stats_interval, None);
+        assert_eq!(spec.settings.received_media_timeout, Some(Duration::from_secs(30)));
     }
 }
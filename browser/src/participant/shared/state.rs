@@ -1,4 +1,16 @@
+use super::{
+    GlassToGlass,
+    ReceivedMedia,
+    WebRtcStats,
+};
+use chrono::{
+    DateTime,
+    Utc,
+};
 use client_simulator_config::{
+    media::FakeMediaWithDescription,
+    DeviceConfig,
+    NetworkConfig,
     NoiseSuppression,
     TransportMode,
     VideoConstraint,
@@ -19,12 +31,67 @@ impl ParticipantWarning {
     }
 }
 
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+/// Outcome of the last command the participant ran.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CommandResult {
+    /// Name of the `ParticipantMessage` variant.
+    pub command: String,
+    pub at: DateTime<Utc>,
+    /// Why the command failed, `None` if it succeeded.
+    pub error: Option<String>,
+}
+
+/// Condition of the call as shown by the frontend, beyond being joined or not.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum CallStatus {
+    #[default]
+    Normal,
+    /// The frontend lost its connection to the space and tries to get it back.
+    Reconnecting,
+    RemovedByHost,
+    MeetingEnded,
+    /// Camera or microphone access was denied.
+    MediaPermissionDenied,
+    /// The frontend could not use WebTransport and fell back to WebRTC.
+    TransportFallback,
+}
+
+impl CallStatus {
+    /// The participant is out of the call and will not get back in on its own.
+    pub fn ends_call(self) -> bool {
+        matches!(self, Self::RemovedByHost | Self::MeetingEnded)
+    }
+
+    /// Warning surfaced in the participant state, `None` for [`CallStatus::Normal`].
+    pub fn warning(self) -> Option<ParticipantWarning> {
+        let (title, message) = match self {
+            Self::Normal => return None,
+            Self::Reconnecting => ("Reconnecting", "The frontend lost its connection and is reconnecting"),
+            Self::RemovedByHost => ("Removed by host", "The participant was removed from the space"),
+            Self::MeetingEnded => ("Meeting ended", "The meeting was ended for everyone"),
+            Self::MediaPermissionDenied => (
+                "Media permission denied",
+                "The frontend reports that camera or microphone access was denied",
+            ),
+            Self::TransportFallback => (
+                "Transport fallback",
+                "The frontend fell back from WebTransport to WebRTC",
+            ),
+        };
+        Some(ParticipantWarning::new(title, message))
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ParticipantState {
     pub username: String,
     pub running: bool,
+    pub pending: bool,
     pub warning: Option<ParticipantWarning>,
     pub joined: bool,
+    pub call_status: CallStatus,
     pub muted: bool,
     pub video_activated: bool,
     pub auto_gain_control: bool,
@@ -35,4 +102,16 @@ pub struct ParticipantState {
     pub video_max_concurrent_tracks: Option<usize>,
     pub background_blur: bool,
     pub screenshare_activated: bool,
+    pub network: NetworkConfig,
+    pub device: DeviceConfig,
+    /// Fake media source the participant publishes.
+    pub fake_media: FakeMediaWithDescription,
+    pub webrtc_stats: Option<WebRtcStats>,
+    /// What the participant receives from the others, inspected along with `webrtc_stats`.
+    pub received_media: Option<ReceivedMedia>,
+    /// Latest latency of the media of every sender whose test pattern this participant decodes.
+    pub glass_to_glass: Vec<GlassToGlass>,
+    /// The received media is being recorded, see `ParticipantMessage::StartRecording`.
+    pub recording: bool,
+    pub last_command: Option<CommandResult>,
 }
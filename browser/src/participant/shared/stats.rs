@@ -0,0 +1,159 @@
+use super::{
+    GlassToGlassSample,
+    ReceivedMediaSample,
+};
+
+/// Media quality of a participant, derived from `RTCPeerConnection.getStats()` of all of its peer connections.
+///
+/// Bitrates and packet loss cover the interval since the previous sample; frame counters are cumulative.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct WebRtcStats {
+    pub peer_connections: u32,
+    pub outbound_bitrate_kbps: f64,
+    pub inbound_bitrate_kbps: f64,
+    pub packets_lost: i64,
+    pub packet_loss_percent: f64,
+    pub jitter_ms: Option<f64>,
+    pub round_trip_time_ms: Option<f64>,
+    pub frames_decoded: u64,
+    pub frames_dropped: u64,
+    pub frame_width: Option<u32>,
+    pub frame_height: Option<u32>,
+    pub frames_per_second: Option<f64>,
+}
+
+/// Raw cumulative counters as returned by the page, see `frontend::stats`.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(in crate::participant) struct WebRtcStatsSample {
+    pub(in crate::participant) timestamp_ms: f64,
+    pub(in crate::participant) peer_connections: u32,
+    pub(in crate::participant) bytes_sent: u64,
+    pub(in crate::participant) bytes_received: u64,
+    pub(in crate::participant) packets_received: u64,
+    pub(in crate::participant) packets_lost: i64,
+    pub(in crate::participant) jitter_seconds: Option<f64>,
+    pub(in crate::participant) round_trip_time_seconds: Option<f64>,
+    pub(in crate::participant) frames_decoded: u64,
+    pub(in crate::participant) frames_dropped: u64,
+    pub(in crate::participant) frame_width: Option<u32>,
+    pub(in crate::participant) frame_height: Option<u32>,
+    pub(in crate::participant) frames_per_second: Option<f64>,
+    /// Inspected separately from the peer connections, see `frontend::received_media`.
+    pub(in crate::participant) received_media: Option<ReceivedMediaSample>,
+    /// Decoded from the test patterns of the other participants, see `frontend::glass_to_glass`.
+    pub(in crate::participant) glass_to_glass: Vec<GlassToGlassSample>,
+}
+
+impl WebRtcStats {
+    /// Turns two consecutive samples into rates. Without a previous sample (or when the page was reloaded and its
+    /// counters restarted) bitrates are zero and packet loss is computed over the whole session.
+    pub(in crate::participant) fn from_samples(
+        previous: Option<&WebRtcStatsSample>,
+        current: &WebRtcStatsSample,
+    ) -> Self {
+        let previous = previous.filter(|previous| {
+            current.timestamp_ms > previous.timestamp_ms
+                && current.bytes_sent >= previous.bytes_sent
+                && current.bytes_received >= previous.bytes_received
+                && current.packets_received >= previous.packets_received
+        });
+
+        let (outbound_bitrate_kbps, inbound_bitrate_kbps, packets_received, packets_lost) = match previous {
+            Some(previous) => {
+                let elapsed_ms = current.timestamp_ms - previous.timestamp_ms;
+                (
+                    // bits per millisecond equal kilobits per second
+                    (current.bytes_sent - previous.bytes_sent) as f64 * 8.0 / elapsed_ms,
+                    (current.bytes_received - previous.bytes_received) as f64 * 8.0 / elapsed_ms,
+                    current.packets_received - previous.packets_received,
+                    (current.packets_lost - previous.packets_lost).max(0),
+                )
+            }
+            None => (0.0, 0.0, current.packets_received, current.packets_lost.max(0)),
+        };
+
+        let packets_expected = packets_received as f64 + packets_lost as f64;
+        let packet_loss_percent = if packets_expected > 0.0 {
+            packets_lost as f64 * 100.0 / packets_expected
+        } else {
+            0.0
+        };
+
+        Self {
+            peer_connections: current.peer_connections,
+            outbound_bitrate_kbps,
+            inbound_bitrate_kbps,
+            packets_lost: current.packets_lost,
+            packet_loss_percent,
+            jitter_ms: current.jitter_seconds.map(|seconds| seconds * 1000.0),
+            round_trip_time_ms: current.round_trip_time_seconds.map(|seconds| seconds * 1000.0),
+            frames_decoded: current.frames_decoded,
+            frames_dropped: current.frames_dropped,
+            frame_width: current.frame_width,
+            frame_height: current.frame_height,
+            frames_per_second: current.frames_per_second,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp_ms: f64, bytes_sent: u64, bytes_received: u64) -> WebRtcStatsSample {
+        WebRtcStatsSample {
+            timestamp_ms,
+            peer_connections: 1,
+            bytes_sent,
+            bytes_received,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn computes_bitrates_and_interval_packet_loss_from_consecutive_samples() {
+        let previous = WebRtcStatsSample {
+            packets_received: 900,
+            packets_lost: 10,
+            ..sample(1_000.0, 100_000, 200_000)
+        };
+        let current = WebRtcStatsSample {
+            packets_received: 1_890,
+            packets_lost: 20,
+            jitter_seconds: Some(0.015_625),
+            round_trip_time_seconds: Some(0.031_25),
+            frame_width: Some(1280),
+            frame_height: Some(720),
+            frames_per_second: Some(30.0),
+            ..sample(3_000.0, 400_000, 1_000_000)
+        };
+
+        let stats = WebRtcStats::from_samples(Some(&previous), &current);
+
+        assert_eq!(stats.outbound_bitrate_kbps, 1_200.0);
+        assert_eq!(stats.inbound_bitrate_kbps, 3_200.0);
+        assert_eq!(stats.packets_lost, 20);
+        assert_eq!(stats.packet_loss_percent, 1.0);
+        assert_eq!(stats.jitter_ms, Some(15.625));
+        assert_eq!(stats.round_trip_time_ms, Some(31.25));
+        assert_eq!(stats.frame_width, Some(1280));
+        assert_eq!(stats.frames_per_second, Some(30.0));
+    }
+
+    #[test]
+    fn restarted_counters_are_treated_like_a_first_sample() {
+        let previous = sample(1_000.0, 500_000, 500_000);
+        let current = WebRtcStatsSample {
+            packets_received: 95,
+            packets_lost: 5,
+            ..sample(2_000.0, 1_000, 2_000)
+        };
+
+        let stats = WebRtcStats::from_samples(Some(&previous), &current);
+
+        assert_eq!(stats.outbound_bitrate_kbps, 0.0);
+        assert_eq!(stats.inbound_bitrate_kbps, 0.0);
+        assert_eq!(stats.packet_loss_percent, 5.0);
+    }
+}
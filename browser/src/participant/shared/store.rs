@@ -4,13 +4,17 @@ use crate::{
         HyperSessionCookieStash,
     },
     participant::{
+        GlassToGlassSummary,
+        LatencySummary,
         Participant,
         ParticipantWarning,
+        SpawnScheduler,
     },
 };
 use client_simulator_config::{
     Config,
     ParticipantBackendKind,
+    SpawnConfig,
 };
 use eyre::Result;
 use futures::future::join_all;
@@ -18,6 +22,10 @@ use std::{
     collections::HashMap,
     path::Path,
     sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
         Arc,
         Mutex,
     },
@@ -28,6 +36,9 @@ use std::{
 #[derive(Debug, Clone)]
 pub struct ParticipantStore {
     cookies: HyperSessionCookieManger,
+    scheduler: SpawnScheduler,
+    /// Participants spawned so far, spreads the `fake_media_pool` of their configs over them.
+    spawned: Arc<AtomicUsize>,
     inner: Arc<Mutex<HashMap<String, Participant>>>,
 }
 
@@ -35,27 +46,41 @@ impl ParticipantStore {
     pub fn new(data_dir: impl AsRef<Path>) -> Self {
         Self {
             cookies: HyperSessionCookieStash::load_from_data_dir(data_dir).into(),
+            scheduler: Default::default(),
+            spawned: Default::default(),
             inner: Default::default(),
         }
     }
 
+    /// Limits how participants spawned through this store are started. Participants waiting for a slot are
+    /// reported as pending.
+    pub fn with_spawn_config(mut self, config: &SpawnConfig) -> Self {
+        self.scheduler = SpawnScheduler::new(config);
+        self
+    }
+
     pub fn cookies(&self) -> &HyperSessionCookieManger {
         &self.cookies
     }
 
-    pub fn spawn(&self, config: &Config) -> Result<()> {
-        let participant = Participant::spawn(config, self.cookies.clone())?;
+    /// Spawns a participant for `config` and returns its name. A `fake_media_pool` in `config` picks the
+    /// participant's fake media.
+    pub fn spawn(&self, config: &Config) -> Result<String> {
+        let mut config = config.clone();
+        config.assign_pooled_fake_media(self.spawned.fetch_add(1, Ordering::Relaxed), rand::random())?;
+        let participant = Participant::spawn_scheduled(&config, self.cookies.clone(), &self.scheduler)?;
+        let name = participant.name.clone();
         self.add(participant);
-        Ok(())
+        Ok(name)
     }
 
-    pub fn spawn_local(&self, config: &Config) -> Result<()> {
+    pub fn spawn_local(&self, config: &Config) -> Result<String> {
         let mut config = config.clone();
         config.backend = ParticipantBackendKind::Local;
         self.spawn(&config)
     }
 
-    pub fn spawn_remote_stub(&self, config: &Config) -> Result<()> {
+    pub fn spawn_remote_stub(&self, config: &Config) -> Result<String> {
         let mut config = config.clone();
         config.backend = ParticipantBackendKind::RemoteStub;
         self.spawn(&config)
@@ -112,6 +137,16 @@ impl ParticipantStore {
             .collect()
     }
 
+    /// Start, phase and command latencies of the participants currently in the store, per backend.
+    pub fn latency_summaries(&self) -> Vec<LatencySummary> {
+        LatencySummary::from_participants(&self.values())
+    }
+
+    /// Glass-to-glass latencies per sender and receiver among the participants currently in the store.
+    pub fn glass_to_glass_summaries(&self) -> Vec<GlassToGlassSummary> {
+        GlassToGlassSummary::from_participants(&self.values())
+    }
+
     pub fn add(&self, participant: Participant) {
         self.inner.lock().unwrap().insert(participant.name.clone(), participant);
     }
@@ -136,8 +171,13 @@ mod tests {
     use super::ParticipantStore;
     use crate::participant::cloudflare::take_spawned_participants_for_test;
     use client_simulator_config::{
+        media::{
+            FakeMediaAssignment,
+            FakeMediaPool,
+        },
         Config,
         ParticipantBackendKind,
+        SpawnConfig,
     };
     use std::{
         fs,
@@ -201,6 +241,82 @@ mod tests {
         assert!(store.is_empty());
     }
 
+    #[tokio::test]
+    async fn spawn_keeps_participants_pending_while_the_backend_cap_is_reached() {
+        let data_dir = unique_test_data_dir();
+        fs::create_dir_all(&data_dir).expect("create temp data dir");
+
+        let store = ParticipantStore::new(&data_dir).with_spawn_config(&SpawnConfig {
+            max_remote_stub: Some(1),
+            ..Default::default()
+        });
+        let config = Config {
+            url: Some(Url::parse("https://example.com/lite/demo").expect("valid url")),
+            ..Default::default()
+        };
+
+        let first_name = store.spawn_remote_stub(&config).expect("spawn first remote stub");
+        let first = store.get(&first_name).expect("first participant handle");
+        first
+            .state
+            .clone()
+            .wait_for(|current| current.joined)
+            .await
+            .expect("first participant should start");
+
+        let second_name = store.spawn_remote_stub(&config).expect("spawn second remote stub");
+        let second = store.get(&second_name).expect("second participant handle");
+        let mut second_state = second.state.clone();
+        second_state
+            .wait_for(|current| current.running && current.pending)
+            .await
+            .expect("second participant should be queued");
+        assert!(!second_state.borrow().joined);
+
+        store
+            .remove(&first_name)
+            .expect("first participant in store")
+            .close()
+            .await;
+
+        second_state
+            .wait_for(|current| !current.pending && current.joined)
+            .await
+            .expect("second participant should start once the first one closed");
+
+        store.shutdown_all().await;
+    }
+
+    #[tokio::test]
+    async fn spawn_assigns_fake_media_from_the_pool_round_robin() {
+        let data_dir = unique_test_data_dir();
+        fs::create_dir_all(&data_dir).expect("create temp data dir");
+
+        let store = ParticipantStore::new(&data_dir);
+        let config = Config {
+            url: Some(Url::parse("https://example.com/lite/demo").expect("valid url")),
+            fake_media_pool: FakeMediaPool {
+                assignment: FakeMediaAssignment::RoundRobin,
+                sources: vec!["Counting (Audio)".to_string(), "<builtin>".to_string()],
+            },
+            ..Default::default()
+        };
+
+        let mut descriptions = Vec::new();
+        for _ in 0..3 {
+            let name = store.spawn_remote_stub(&config).expect("spawn remote stub");
+            let mut receiver = store.get(&name).expect("participant handle").state.clone();
+            let state = receiver
+                .wait_for(|current| current.joined)
+                .await
+                .expect("participant should start");
+            descriptions.push(state.fake_media.description().to_string());
+        }
+
+        assert_eq!(descriptions, ["<builtin>", "Counting (Audio)", "<builtin>"]);
+        store.shutdown_all().await;
+    }
+
     fn unique_test_data_dir() -> PathBuf {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
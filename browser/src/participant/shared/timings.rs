@@ -0,0 +1,247 @@
+use crate::participant::Participant;
+use chrono::{
+    DateTime,
+    Utc,
+};
+use client_simulator_config::ParticipantBackendKind;
+use eyre::Result;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+/// The steps a backend session goes through in `start()` until the participant is in the session.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize, strum::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum StartPhase {
+    /// Launching or connecting to the browser and opening a page.
+    BrowserLaunch,
+    /// Fetching the session cookie (Hyper Core only).
+    Auth,
+    Navigation,
+    /// From the loaded page until the join button can be clicked, including name entry and lobby settings.
+    Lobby,
+    JoinClick,
+    /// From the join click until the leave button shows up, i.e. the participant is in the call.
+    LeaveButtonVisible,
+}
+
+/// What a latency sample measures.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyMetric {
+    /// The whole backend `start()`, from leaving the pending state until the session started.
+    Start,
+    Phase(StartPhase),
+    /// Round trip of a command until the backend handled it, keyed by the message variant.
+    Command(String),
+}
+
+impl fmt::Display for LatencyMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Start => f.write_str("start"),
+            Self::Phase(phase) => write!(f, "{phase}"),
+            Self::Command(command) => f.write_str(command),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ParticipantTiming {
+    pub at: DateTime<Utc>,
+    pub metric: LatencyMetric,
+    pub duration_ms: u64,
+}
+
+impl ParticipantTiming {
+    pub(in crate::participant) fn new(metric: LatencyMetric, duration: Duration) -> Self {
+        Self {
+            at: Utc::now(),
+            metric,
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+}
+
+/// Phase durations recorded by a backend session while it starts. The runtime moves them into the participant
+/// history once `start()` returns.
+#[derive(Debug, Clone, Default)]
+pub(in crate::participant) struct PhaseTimings {
+    phases: Arc<Mutex<Vec<(StartPhase, Duration)>>>,
+}
+
+impl PhaseTimings {
+    pub(in crate::participant) fn record(&self, phase: StartPhase, duration: Duration) {
+        self.phases.lock().unwrap().push((phase, duration));
+    }
+
+    /// Awaits `future` and records how long it took, unless it failed.
+    pub(in crate::participant) async fn time<T>(
+        &self,
+        phase: StartPhase,
+        future: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let started = Instant::now();
+        let output = future.await?;
+        self.record(phase, started.elapsed());
+        Ok(output)
+    }
+
+    pub(in crate::participant) fn take(&self) -> Vec<(StartPhase, Duration)> {
+        std::mem::take(&mut *self.phases.lock().unwrap())
+    }
+}
+
+/// Latency percentiles of one metric across all participants of a backend.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct LatencySummary {
+    pub backend: ParticipantBackendKind,
+    pub metric: LatencyMetric,
+    pub count: usize,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+impl LatencySummary {
+    /// Groups the recorded timings of `participants` by backend and metric, ordered by backend, then metric.
+    pub fn from_participants(participants: &[Participant]) -> Vec<Self> {
+        Self::from_timings(participants.iter().flat_map(|participant| {
+            participant
+                .history
+                .timings()
+                .into_iter()
+                .map(|timing| (participant.backend, timing))
+        }))
+    }
+
+    fn from_timings(timings: impl IntoIterator<Item = (ParticipantBackendKind, ParticipantTiming)>) -> Vec<Self> {
+        let mut histograms = HashMap::<(ParticipantBackendKind, LatencyMetric), Vec<u64>>::new();
+        for (backend, timing) in timings {
+            histograms
+                .entry((backend, timing.metric))
+                .or_default()
+                .push(timing.duration_ms);
+        }
+
+        let mut summaries = histograms
+            .into_iter()
+            .map(|((backend, metric), mut samples)| {
+                samples.sort_unstable();
+                Self {
+                    backend,
+                    metric,
+                    count: samples.len(),
+                    p50_ms: percentile(&samples, 50),
+                    p95_ms: percentile(&samples, 95),
+                    max_ms: samples.last().copied().unwrap_or_default(),
+                }
+            })
+            .collect::<Vec<_>>();
+        summaries.sort_by(|a, b| (a.backend.to_string(), &a.metric).cmp(&(b.backend.to_string(), &b.metric)));
+        summaries
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty `samples`.
+pub(super) fn percentile<T: Copy>(samples: &[T], percent: usize) -> T {
+    let rank = (samples.len() * percent).div_ceil(100).max(1);
+    samples[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(
+        backend: ParticipantBackendKind,
+        metric: LatencyMetric,
+        duration_ms: u64,
+    ) -> (ParticipantBackendKind, ParticipantTiming) {
+        (
+            backend,
+            ParticipantTiming {
+                at: Utc::now(),
+                metric,
+                duration_ms,
+            },
+        )
+    }
+
+    #[test]
+    fn summarizes_percentiles_per_backend_and_metric() {
+        let mut timings = (1..=20)
+            .map(|duration_ms| timing(ParticipantBackendKind::Local, LatencyMetric::Start, duration_ms * 100))
+            .collect::<Vec<_>>();
+        timings.push(timing(ParticipantBackendKind::Cloudflare, LatencyMetric::Start, 4_000));
+        timings.push(timing(
+            ParticipantBackendKind::Local,
+            LatencyMetric::Command("ToggleAudio".to_string()),
+            40,
+        ));
+
+        let summaries = LatencySummary::from_timings(timings);
+
+        assert_eq!(
+            summaries,
+            vec![
+                LatencySummary {
+                    backend: ParticipantBackendKind::Cloudflare,
+                    metric: LatencyMetric::Start,
+                    count: 1,
+                    p50_ms: 4_000,
+                    p95_ms: 4_000,
+                    max_ms: 4_000,
+                },
+                LatencySummary {
+                    backend: ParticipantBackendKind::Local,
+                    metric: LatencyMetric::Start,
+                    count: 20,
+                    p50_ms: 1_000,
+                    p95_ms: 1_900,
+                    max_ms: 2_000,
+                },
+                LatencySummary {
+                    backend: ParticipantBackendKind::Local,
+                    metric: LatencyMetric::Command("ToggleAudio".to_string()),
+                    count: 1,
+                    p50_ms: 40,
+                    p95_ms: 40,
+                    max_ms: 40,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_phases_are_not_recorded() {
+        let timings = PhaseTimings::default();
+
+        timings.time(StartPhase::Navigation, async { Ok(()) }).await.unwrap();
+        let error = timings
+            .time(StartPhase::Lobby, async {
+                Err::<(), _>(eyre::eyre!("join button missing"))
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "join button missing");
+
+        let phases = timings.take().into_iter().map(|(phase, _)| phase).collect::<Vec<_>>();
+        assert_eq!(phases, vec![StartPhase::Navigation]);
+        assert!(timings.take().is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+use client_simulator_browser::{
+    control::ControlServer,
+    participant::ParticipantStore,
+};
+use client_simulator_config::Config;
+use reqwest::StatusCode;
+use serde_json::{
+    json,
+    Value,
+};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{
+        Duration,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+use tokio::time::{
+    sleep,
+    timeout,
+};
+
+#[tokio::test]
+async fn control_api_spawns_commands_and_closes_participants() {
+    let store = ParticipantStore::new(unique_temp_dir());
+    let server = ControlServer::start("127.0.0.1:0".parse().unwrap(), store.clone(), Config::default())
+        .await
+        .unwrap();
+    let base_url = format!("http://{}/participants", server.local_addr());
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&base_url)
+        .json(&json!({
+            "backend": "remote-stub",
+            "url": "https://example.com/space/control-api",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let spawned: Value = response.json().await.unwrap();
+    let name = spawned["name"].as_str().unwrap().to_string();
+    assert_eq!(spawned["backend"], "remote-stub");
+
+    let joined = wait_for_participant(&client, &format!("{base_url}/{name}"), |state| state["joined"] == true).await;
+    assert_eq!(joined["state"]["muted"], false);
+
+    let listed: Vec<Value> = client.get(&base_url).send().await.unwrap().json().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["name"], name);
+
+    let response = client
+        .post(format!("{base_url}/{name}/messages"))
+        .json(&json!("ToggleAudio"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    wait_for_participant(&client, &format!("{base_url}/{name}"), |state| state["muted"] == true).await;
+
+    let response = client.delete(format!("{base_url}/{name}")).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(store.is_empty());
+
+    let response = client.get(format!("{base_url}/{name}")).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn control_api_rejects_invalid_configs_and_messages() {
+    let store = ParticipantStore::new(unique_temp_dir());
+    let server = ControlServer::start("127.0.0.1:0".parse().unwrap(), store.clone(), Config::default())
+        .await
+        .unwrap();
+    let base_url = format!("http://{}/participants", server.local_addr());
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&base_url)
+        .json(&json!({ "backend": "mainframe" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("Invalid participant config"));
+
+    let response = client
+        .post(format!("{base_url}/nobody/messages"))
+        .json(&json!("ToggleAudio"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(store.is_empty());
+
+    server.shutdown().await;
+}
+
+async fn wait_for_participant(client: &reqwest::Client, url: &str, predicate: impl Fn(&Value) -> bool) -> Value {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let participant: Value = client.get(url).send().await.unwrap().json().await.unwrap();
+            if predicate(&participant["state"]) {
+                return participant;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("timed out waiting for participant state")
+}
+
+fn unique_temp_dir() -> PathBuf {
+    let nonce = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let dir = std::env::temp_dir().join(format!("hyper-browser-simulator-control-it-{nonce}"));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,176 @@
+use client_simulator_browser::participant::{
+    AgentAccess,
+    Participant,
+    ParticipantEventKind,
+    ParticipantState,
+    RemoteAgent,
+};
+use client_simulator_config::{
+    Config,
+    ParticipantBackendKind,
+    RemoteConfig,
+};
+use std::{
+    net::SocketAddr,
+    time::Duration,
+};
+use tokio::{
+    sync::watch,
+    time::timeout,
+};
+
+#[tokio::test]
+async fn remote_participants_run_their_session_on_the_agent() {
+    let agent = start_stub_agent().await;
+    let participant = Participant::spawn_remote(&remote_config(&agent.local_addr().to_string())).unwrap();
+    assert!(participant.name.starts_with("remote-"));
+    assert_eq!(participant.backend, ParticipantBackendKind::Remote);
+
+    let joined = wait_for_state(&participant.state, |current| current.running && current.joined).await;
+    assert_eq!(joined.username, participant.name);
+    assert!(!joined.muted);
+
+    participant.toggle_audio();
+    let muted = wait_for_state(&participant.state, |current| current.muted).await;
+    assert!(muted.joined);
+
+    participant.leave();
+    wait_for_state(&participant.state, |current| !current.joined).await;
+
+    let state = participant.state.clone();
+    participant.close().await;
+    assert!(!state.borrow().running);
+
+    agent.shutdown().await;
+}
+
+#[tokio::test]
+async fn stopping_the_agent_terminates_its_participants() {
+    let agent = start_stub_agent().await;
+    let participant = Participant::spawn_remote(&remote_config(&agent.local_addr().to_string())).unwrap();
+    wait_for_state(&participant.state, |current| current.joined).await;
+
+    agent.shutdown().await;
+
+    wait_for_state(&participant.state, |current| !current.running).await;
+    assert!(participant
+        .history
+        .events()
+        .iter()
+        .any(|event| matches!(&event.kind, ParticipantEventKind::Terminated { message, .. } if message.contains("closed the connection"))));
+}
+
+#[tokio::test]
+async fn unreachable_agents_fail_the_start_with_a_warning() {
+    let unused_addr = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    };
+    let participant = Participant::spawn_remote(&remote_config(&unused_addr.to_string())).unwrap();
+
+    let stopped = wait_for_state(&participant.state, |current| {
+        !current.running && !current.pending && current.warning.is_some()
+    })
+    .await;
+    assert_eq!(stopped.warning.unwrap().title, "Remote agent unavailable");
+}
+
+#[test]
+fn remote_participants_need_an_agent() {
+    let mut config = remote_config("127.0.0.1:7171");
+    config.remote.agents.clear();
+
+    let error = Participant::spawn_remote(&config).unwrap_err();
+    assert!(error.to_string().contains("remote.agents"));
+}
+
+#[tokio::test]
+async fn agents_reject_starts_without_their_token() {
+    let agent = start_stub_agent().await;
+    let mut config = remote_config(&agent.local_addr().to_string());
+    config.remote.token = Some("wrong".to_string());
+    let participant = Participant::spawn_remote(&config).unwrap();
+
+    wait_for_state(&participant.state, |current| !current.running && !current.pending).await;
+    assert!(start_failed_with(&participant, "token is missing or wrong"));
+
+    agent.shutdown().await;
+}
+
+#[tokio::test]
+async fn agents_reject_local_files_and_other_hosts() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let access = AgentAccess {
+        allowed_hosts: vec!["example.com".to_string()],
+        ..agent_access()
+    };
+    let agent = RemoteAgent::start(addr, Config::default(), ParticipantBackendKind::RemoteStub, access)
+        .await
+        .unwrap();
+    let agent_addr = agent.local_addr().to_string();
+
+    let mut local_file = remote_config(&agent_addr);
+    local_file.screenshare_source = "/etc/hostname".into();
+    let mut other_host = remote_config(&agent_addr);
+    other_host.url = Some("https://attacker.example.net/space".parse().unwrap());
+
+    for config in [local_file, other_host] {
+        let participant = Participant::spawn_remote(&config).unwrap();
+        wait_for_state(&participant.state, |current| !current.running && !current.pending).await;
+        assert!(start_failed_with(&participant, "does not allow"));
+    }
+
+    agent.shutdown().await;
+}
+
+fn start_failed_with(participant: &Participant, text: &str) -> bool {
+    participant
+        .history
+        .events()
+        .iter()
+        .any(|event| matches!(&event.kind, ParticipantEventKind::StartFailed { error } if error.contains(text)))
+}
+
+async fn start_stub_agent() -> RemoteAgent {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    RemoteAgent::start(
+        addr,
+        Config::default(),
+        ParticipantBackendKind::RemoteStub,
+        agent_access(),
+    )
+    .await
+    .unwrap()
+}
+
+fn agent_access() -> AgentAccess {
+    AgentAccess {
+        token: "test-token".to_string(),
+        ..Default::default()
+    }
+}
+
+fn remote_config(agent: &str) -> Config {
+    Config {
+        url: Some("https://example.com/space/remote".parse().unwrap()),
+        backend: ParticipantBackendKind::Remote,
+        remote: RemoteConfig {
+            agents: vec![agent.to_string()],
+            connect_timeout_ms: 1_000,
+            token: Some("test-token".to_string()),
+        },
+        ..Default::default()
+    }
+}
+
+async fn wait_for_state<F>(state: &watch::Receiver<ParticipantState>, mut predicate: F) -> ParticipantState
+where
+    F: FnMut(&ParticipantState) -> bool,
+{
+    let mut state = state.clone();
+    timeout(Duration::from_secs(5), async move {
+        state.wait_for(|current| predicate(current)).await.unwrap().clone()
+    })
+    .await
+    .expect("timed out waiting for participant state")
+}
@@ -43,7 +43,7 @@ impl From<&super::ParticipantConfig> for BrowserConfig {
     fn from(config: &super::ParticipantConfig) -> Self {
         Self {
             user_data_dir: Default::default(),
-            cache_dir: super::app_config::cache_dir(),
+            cache_dir: super::app_config::get_cache_dir(),
             app_config: config.app_config.clone(),
         }
     }
@@ -52,6 +52,24 @@ pub enum VideoConstraint {
     P2160,
 }
 
+impl VideoConstraint {
+    /// The video height in pixels, `None` for no constraint.
+    pub fn height(self) -> Option<u32> {
+        match self {
+            VideoConstraint::None => None,
+            VideoConstraint::P90 => Some(90),
+            VideoConstraint::P144 => Some(144),
+            VideoConstraint::P240 => Some(240),
+            VideoConstraint::P360 => Some(360),
+            VideoConstraint::P480 => Some(480),
+            VideoConstraint::P720 => Some(720),
+            VideoConstraint::P1080 => Some(1080),
+            VideoConstraint::P1440 => Some(1440),
+            VideoConstraint::P2160 => Some(2160),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, Display, EnumIter, EnumString, PartialEq, Eq)]
 pub enum VideoMaxConcurrentTracksPreset {
     #[default]
@@ -148,7 +166,7 @@ pub enum NoiseSuppression {
     AiCousticsRookL48khz,
 }
 
-#[derive(Debug, Default, Clone, Copy, Display, EnumIter, EnumString, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, Display, EnumIter, EnumString, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
 pub enum ParticipantBackendKind {
@@ -157,6 +175,7 @@ pub enum ParticipantBackendKind {
     Cloudflare,
     RemoteStub,
     AwsDeviceFarm,
+    Remote,
 }
 
 impl ParticipantBackendKind {
@@ -165,9 +184,34 @@ impl ParticipantBackendKind {
     }
 }
 
+/// Scripted behavior that makes a participant send messages on its own once it joined.
+#[derive(Debug, Default, Clone, Copy, Display, EnumIter, EnumString, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum BehaviorProfile {
+    /// Only reacts to commands.
+    #[default]
+    None,
+    /// Unmutes for short bursts of talking and mutes again in between.
+    Talker,
+    /// Turns off microphone and camera after joining and stays quiet.
+    Lurker,
+    /// Leaves the space and rejoins shortly after, over and over.
+    Flapper,
+    /// Starts and stops screensharing.
+    ScreenSharer,
+}
+
+impl BehaviorProfile {
+    pub const fn is_none(&self) -> bool {
+        matches!(self, Self::None)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
+        BehaviorProfile,
         ParticipantBackendKind,
         VideoConstraint,
         VideoMaxConcurrentTracksPreset,
@@ -231,4 +275,13 @@ mod tests {
         assert_eq!(kind.to_string(), "aws-device-farm");
         assert!(!kind.is_local());
     }
+
+    #[test]
+    fn behavior_profiles_round_trip_kebab_case() {
+        let profile = BehaviorProfile::from_str("screen-sharer").unwrap();
+        assert_eq!(profile, BehaviorProfile::ScreenSharer);
+        assert_eq!(profile.to_string(), "screen-sharer");
+        assert_eq!(serde_json::to_value(profile).unwrap(), "screen-sharer");
+        assert!(BehaviorProfile::default().is_none());
+    }
 }
@@ -0,0 +1,203 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::fmt;
+use strum::{
+    Display,
+    EnumIter,
+    EnumString,
+};
+
+const ANDROID_USER_AGENT: &str = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) \
+                                  Chrome/146.0.0.0 Mobile Safari/537.36";
+
+/// Emulated device of a participant's browser: a slower CPU and the screen and user agent of a weaker device. Only
+/// local Chromium participants (also when hosted by a remote agent) apply it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DeviceConfig {
+    pub profile: DeviceProfile,
+    /// Slows the CPU down by this factor instead of the profile's rate. `1` disables throttling.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_throttling_rate: Option<f64>,
+    /// Viewport width for the `custom` profile. `0` keeps the browser window size.
+    pub viewport_width: u32,
+    /// Viewport height for the `custom` profile. `0` keeps the browser window size.
+    pub viewport_height: u32,
+    /// Device pixel ratio for the `custom` profile.
+    pub device_scale_factor: f64,
+    /// Emulate a touch device with a mobile viewport for the `custom` profile.
+    pub mobile: bool,
+    /// User agent for the `custom` profile. Empty keeps the browser's user agent.
+    pub user_agent: String,
+}
+
+impl DeviceConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The emulation to apply, `None` if the browser should run as is.
+    pub fn emulation(&self) -> Option<DeviceEmulation> {
+        let mut emulation = match self.profile {
+            DeviceProfile::None => DeviceEmulation::default(),
+            DeviceProfile::LowEndLaptop => DeviceEmulation {
+                cpu_throttling_rate: 4.0,
+                metrics: Some(DeviceMetrics {
+                    width: 1366,
+                    height: 768,
+                    device_scale_factor: 1.0,
+                    mobile: false,
+                }),
+                user_agent: None,
+            },
+            // Same CPU rates as the Chrome DevTools mid-tier and low-end mobile presets.
+            DeviceProfile::MidRangePhone => DeviceEmulation {
+                cpu_throttling_rate: 4.0,
+                metrics: Some(DeviceMetrics {
+                    width: 412,
+                    height: 915,
+                    device_scale_factor: 2.625,
+                    mobile: true,
+                }),
+                user_agent: Some(ANDROID_USER_AGENT.to_string()),
+            },
+            DeviceProfile::LowEndPhone => DeviceEmulation {
+                cpu_throttling_rate: 6.0,
+                metrics: Some(DeviceMetrics {
+                    width: 360,
+                    height: 640,
+                    device_scale_factor: 2.0,
+                    mobile: true,
+                }),
+                user_agent: Some(ANDROID_USER_AGENT.to_string()),
+            },
+            DeviceProfile::Custom => DeviceEmulation {
+                cpu_throttling_rate: 1.0,
+                metrics: (self.viewport_width > 0 && self.viewport_height > 0).then_some(DeviceMetrics {
+                    width: self.viewport_width,
+                    height: self.viewport_height,
+                    device_scale_factor: self.device_scale_factor,
+                    mobile: self.mobile,
+                }),
+                user_agent: (!self.user_agent.is_empty()).then(|| self.user_agent.clone()),
+            },
+        };
+        if let Some(rate) = self.cpu_throttling_rate {
+            emulation.cpu_throttling_rate = rate.max(1.0);
+        }
+
+        (emulation != DeviceEmulation::default()).then_some(emulation)
+    }
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        Self {
+            profile: DeviceProfile::None,
+            cpu_throttling_rate: None,
+            viewport_width: 0,
+            viewport_height: 0,
+            device_scale_factor: 1.0,
+            mobile: false,
+            user_agent: String::new(),
+        }
+    }
+}
+
+impl fmt::Display for DeviceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.profile)?;
+        if let Some(rate) = self.cpu_throttling_rate {
+            write!(f, " {rate}x cpu")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Display, EnumIter, EnumString, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DeviceProfile {
+    #[default]
+    #[strum(to_string = "none")]
+    #[serde(rename = "none")]
+    None,
+    #[strum(to_string = "low-end-laptop")]
+    #[serde(rename = "low-end-laptop")]
+    LowEndLaptop,
+    #[strum(to_string = "mid-range-phone")]
+    #[serde(rename = "mid-range-phone")]
+    MidRangePhone,
+    #[strum(to_string = "low-end-phone")]
+    #[serde(rename = "low-end-phone")]
+    LowEndPhone,
+    /// Uses the viewport and user agent of the [`DeviceConfig`].
+    #[strum(to_string = "custom")]
+    #[serde(rename = "custom")]
+    Custom,
+}
+
+/// Resolved [`DeviceConfig`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceEmulation {
+    /// `1` runs the CPU at full speed.
+    pub cpu_throttling_rate: f64,
+    /// `None` keeps the browser window size.
+    pub metrics: Option<DeviceMetrics>,
+    /// `None` keeps the browser's user agent.
+    pub user_agent: Option<String>,
+}
+
+impl Default for DeviceEmulation {
+    fn default() -> Self {
+        Self {
+            cpu_throttling_rate: 1.0,
+            metrics: None,
+            user_agent: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeviceMetrics {
+    pub width: u32,
+    pub height: u32,
+    pub device_scale_factor: f64,
+    pub mobile: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DeviceConfig,
+        DeviceProfile,
+    };
+
+    #[test]
+    fn cpu_throttling_rate_overrides_the_profile() {
+        let config = DeviceConfig {
+            profile: DeviceProfile::LowEndPhone,
+            cpu_throttling_rate: Some(2.0),
+            ..Default::default()
+        };
+
+        let emulation = config.emulation().expect("profile emulates a device");
+        assert_eq!(emulation.cpu_throttling_rate, 2.0);
+        assert!(emulation.metrics.unwrap().mobile);
+        assert!(emulation.user_agent.is_some());
+        assert_eq!(config.to_string(), "low-end-phone 2x cpu");
+    }
+
+    #[test]
+    fn throttling_alone_does_not_touch_viewport_or_user_agent() {
+        let config = DeviceConfig {
+            cpu_throttling_rate: Some(3.0),
+            ..Default::default()
+        };
+
+        let emulation = config.emulation().expect("throttling is an emulation");
+        assert_eq!(emulation.metrics, None);
+        assert_eq!(emulation.user_agent, None);
+        assert_eq!(DeviceConfig::default().emulation(), None);
+    }
+}
@@ -6,22 +6,35 @@ mod args;
 mod browser_config;
 mod client_config;
 mod cloudflare_config;
+mod device_config;
 mod device_farm_config;
 pub mod media;
+mod network_config;
 mod participant_config;
+mod remote_config;
+mod spawn_config;
 
 use crate::media::{
+    list_descriptions,
     FakeMedia,
+    FakeMediaCacheConfig,
+    FakeMediaConversion,
+    FakeMediaFileOrUrl,
+    FakeMediaPool,
     FakeMediaWithDescription,
+    ScreenshareSource,
 };
 use app_config::AppConfig;
 pub use app_config::{
+    get_cache_dir,
     get_config_dir,
     get_data_dir,
 };
 pub use args::TuiArgs;
 pub use browser_config::BrowserConfig;
 pub use client_config::{
+    BehaviorProfile,
+    BehaviorProfileIter,
     NoiseSuppression,
     NoiseSuppressionIter,
     ParticipantBackendKind,
@@ -34,6 +47,13 @@ pub use client_config::{
 };
 pub use cloudflare_config::CloudflareConfig;
 use color_eyre::Result;
+pub use device_config::{
+    DeviceConfig,
+    DeviceEmulation,
+    DeviceMetrics,
+    DeviceProfile,
+    DeviceProfileIter,
+};
 pub use device_farm_config::{
     default_device_farm_project_arn,
     default_device_farm_region,
@@ -47,14 +67,25 @@ pub use device_farm_config::{
     DEVICE_FARM_PROJECT_ARN_ENV,
 };
 use eyre::Context as _;
+pub use network_config::{
+    NetworkConditions,
+    NetworkConfig,
+    NetworkProfile,
+    NetworkProfileIter,
+};
 pub use participant_config::{
     generate_random_name,
     ParticipantConfig,
 };
+pub use remote_config::{
+    RemoteConfig,
+    AGENT_TOKEN_ENV,
+};
 use serde::{
     Deserialize,
     Serialize,
 };
+pub use spawn_config::SpawnConfig;
 use std::{
     collections::HashMap,
     path::Path,
@@ -70,6 +101,16 @@ pub struct Config {
     pub fake_media_selected: Option<usize>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub fake_media_sources: Vec<FakeMediaWithDescription>,
+    /// Assigns each spawned participant its own source instead of `fake_media_selected`.
+    #[serde(default, skip_serializing_if = "FakeMediaPool::is_default")]
+    pub fake_media_pool: FakeMediaPool,
+    /// Size cap of the downloaded, converted and generated fake media in the cache dir.
+    #[serde(default, skip_serializing_if = "FakeMediaCacheConfig::is_default")]
+    pub fake_media_cache: FakeMediaCacheConfig,
+    /// Local browsers serve URL and test pattern fake media through the page script remote backends use, instead of
+    /// Chrome's fake capture flags.
+    #[serde(default)]
+    pub inject_fake_media: bool,
     #[serde(default)]
     pub headless: bool,
     #[serde(default)]
@@ -80,12 +121,19 @@ pub struct Config {
     pub cloudflare: CloudflareConfig,
     #[serde(default, skip_serializing_if = "DeviceFarmConfig::is_default")]
     pub device_farm: DeviceFarmConfig,
+    #[serde(default, skip_serializing_if = "RemoteConfig::is_default")]
+    pub remote: RemoteConfig,
+    #[serde(default, skip_serializing_if = "SpawnConfig::is_default")]
+    pub spawn: SpawnConfig,
     #[serde(default)]
     pub audio_enabled: bool,
     #[serde(default)]
     pub video_enabled: bool,
     #[serde(default)]
     pub screenshare_enabled: bool,
+    /// What a screenshare shows, see [`ScreenshareSource`].
+    #[serde(default, skip_serializing_if = "ScreenshareSource::is_default")]
+    pub screenshare_source: ScreenshareSource,
     #[serde(default = "default_auto_gain_control")]
     pub auto_gain_control: bool,
     #[serde(default)]
@@ -100,6 +148,29 @@ pub struct Config {
     pub video_max_concurrent_tracks: Option<usize>,
     #[serde(default)]
     pub blur: bool,
+    #[serde(default, skip_serializing_if = "NetworkConfig::is_default")]
+    pub network: NetworkConfig,
+    #[serde(default, skip_serializing_if = "DeviceConfig::is_default")]
+    pub device: DeviceConfig,
+    /// How often participants sample WebRTC stats from their peer connections. `0` disables sampling.
+    #[serde(default = "default_webrtc_stats_interval_ms")]
+    pub webrtc_stats_interval_ms: u64,
+    /// Warn when a joined participant receives neither moving video nor audible audio for this many seconds. Received
+    /// media is inspected along with the WebRTC stats. `0` disables the warning.
+    #[serde(default = "default_received_media_timeout_secs")]
+    pub received_media_timeout_secs: u64,
+    /// Scripted behavior that sends participant messages on its own once the participant joined.
+    #[serde(default, skip_serializing_if = "BehaviorProfile::is_none")]
+    pub behavior: BehaviorProfile,
+    /// Seed for the random timing of `behavior`. Unset picks a random seed, which is logged so a run can be repeated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub behavior_seed: Option<u64>,
+    /// Base period of `behavior`, e.g. how long a flapper stays in the space before it leaves and rejoins.
+    #[serde(default = "default_behavior_period_ms")]
+    pub behavior_period_ms: u64,
+    /// Address the embedded control API listens on, e.g. `127.0.0.1:7070`. Unset disables the control API.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_bind: Option<std::net::SocketAddr>,
 }
 
 const DEFAULT_CONFIG: &str = include_str!("default-config.yaml");
@@ -108,6 +179,18 @@ const fn default_auto_gain_control() -> bool {
     true
 }
 
+const fn default_webrtc_stats_interval_ms() -> u64 {
+    2_000
+}
+
+const fn default_received_media_timeout_secs() -> u64 {
+    20
+}
+
+const fn default_behavior_period_ms() -> u64 {
+    10_000
+}
+
 impl Default for Config {
     fn default() -> Self {
         let mut config: Self = yaml_serde::from_str(DEFAULT_CONFIG).expect("Failed to parse default config");
@@ -179,14 +262,53 @@ impl config::Source for Config {
                 .into(),
             );
         }
+        if !self.remote.is_default() {
+            let mut remote = HashMap::<String, config::Value>::from_iter([
+                ("agents".to_string(), self.remote.agents.clone().into()),
+                ("connect_timeout_ms".to_string(), self.remote.connect_timeout_ms.into()),
+            ]);
+            if let Some(token) = &self.remote.token {
+                remote.insert("token".to_string(), token.clone().into());
+            }
+            cache.insert("remote".to_string(), config::ValueKind::Table(remote).into());
+        }
+        if !self.spawn.is_default() {
+            let mut spawn = HashMap::<String, config::Value>::from_iter([
+                ("interval_ms".to_string(), self.spawn.interval_ms.into()),
+                (
+                    "cloudflare_worker_limits".to_string(),
+                    self.spawn.cloudflare_worker_limits.into(),
+                ),
+            ]);
+            spawn.extend(
+                [
+                    ("max_concurrent_starts", self.spawn.max_concurrent_starts),
+                    ("max_local", self.spawn.max_local),
+                    ("max_cloudflare", self.spawn.max_cloudflare),
+                    ("max_remote_stub", self.spawn.max_remote_stub),
+                    ("max_aws_device_farm", self.spawn.max_aws_device_farm),
+                    ("max_remote", self.spawn.max_remote),
+                ]
+                .into_iter()
+                .filter_map(|(key, value)| Some((key.to_string(), (value? as u64).into()))),
+            );
+            cache.insert("spawn".to_string(), config::ValueKind::Table(spawn).into());
+        }
         if let Some(url) = &self.url {
             cache.insert("url".to_string(), url.to_string().into());
         }
+        cache.insert("inject_fake_media".to_string(), self.inject_fake_media.into());
         cache.insert("headless".to_string(), (self.headless).into());
         cache.insert("browser_logs".to_string(), self.browser_logs.into());
         cache.insert("audio_enabled".to_string(), self.audio_enabled.into());
         cache.insert("video_enabled".to_string(), self.video_enabled.into());
         cache.insert("screenshare_enabled".to_string(), self.screenshare_enabled.into());
+        if !self.screenshare_source.is_default() {
+            cache.insert(
+                "screenshare_source".to_string(),
+                self.screenshare_source.to_string().into(),
+            );
+        }
         cache.insert("auto_gain_control".to_string(), self.auto_gain_control.into());
         cache.insert(
             "noise_suppression".to_string(),
@@ -205,6 +327,55 @@ impl config::Source for Config {
             cache.insert("video_max_concurrent_tracks".to_string(), (value as i64).into());
         }
         cache.insert("blur".to_string(), self.blur.into());
+        if !self.network.is_default() {
+            cache.insert(
+                "network".to_string(),
+                config::ValueKind::Table(HashMap::from_iter([
+                    ("profile".to_string(), self.network.profile.to_string().into()),
+                    ("latency_ms".to_string(), self.network.latency_ms.into()),
+                    ("download_kbps".to_string(), self.network.download_kbps.into()),
+                    ("upload_kbps".to_string(), self.network.upload_kbps.into()),
+                    (
+                        "packet_loss_percent".to_string(),
+                        self.network.packet_loss_percent.into(),
+                    ),
+                ]))
+                .into(),
+            );
+        }
+        cache.insert(
+            "webrtc_stats_interval_ms".to_string(),
+            self.webrtc_stats_interval_ms.into(),
+        );
+        cache.insert(
+            "received_media_timeout_secs".to_string(),
+            self.received_media_timeout_secs.into(),
+        );
+        if !self.device.is_default() {
+            let mut device = HashMap::<String, config::Value>::from_iter([
+                ("profile".to_string(), self.device.profile.to_string().into()),
+                ("viewport_width".to_string(), self.device.viewport_width.into()),
+                ("viewport_height".to_string(), self.device.viewport_height.into()),
+                (
+                    "device_scale_factor".to_string(),
+                    self.device.device_scale_factor.into(),
+                ),
+                ("mobile".to_string(), self.device.mobile.into()),
+                ("user_agent".to_string(), self.device.user_agent.clone().into()),
+            ]);
+            if let Some(rate) = self.device.cpu_throttling_rate {
+                device.insert("cpu_throttling_rate".to_string(), rate.into());
+            }
+            cache.insert("device".to_string(), config::ValueKind::Table(device).into());
+        }
+        cache.insert("behavior".to_string(), self.behavior.to_string().into());
+        if let Some(seed) = self.behavior_seed {
+            cache.insert("behavior_seed".to_string(), seed.into());
+        }
+        cache.insert("behavior_period_ms".to_string(), self.behavior_period_ms.into());
+        if let Some(addr) = self.control_bind {
+            cache.insert("control_bind".to_string(), addr.to_string().into());
+        }
         if let Some(value) = self.fake_media_selected {
             cache.insert("fake_media_selected".to_string(), (value as u64).into());
         }
@@ -214,15 +385,54 @@ impl config::Source for Config {
                 self.fake_media_sources
                     .iter()
                     .map(|ea| {
-                        config::ValueKind::Table(HashMap::from_iter([
+                        let mut source = HashMap::<String, config::Value>::from_iter([
                             ("description".to_string(), ea.description().to_string().into()),
                             ("fake_media".to_string(), ea.fake_media().to_string().into()),
-                        ]))
+                        ]);
+                        let conversion = ea.conversion();
+                        if !conversion.is_default() {
+                            let mut table = HashMap::<String, config::Value>::from_iter([
+                                ("resolution".to_string(), conversion.resolution.to_string().into()),
+                                ("start_secs".to_string(), conversion.start_secs.into()),
+                                ("loop_friendly".to_string(), conversion.loop_friendly.into()),
+                            ]);
+                            if let Some(fps) = conversion.fps {
+                                table.insert("fps".to_string(), u64::from(fps).into());
+                            }
+                            if let Some(duration) = conversion.max_duration_secs {
+                                table.insert("max_duration_secs".to_string(), duration.into());
+                            }
+                            source.insert("conversion".to_string(), config::ValueKind::Table(table).into());
+                        }
+                        config::ValueKind::Table(source)
                     })
                     .collect::<Vec<_>>()
                     .into(),
             );
         }
+        if !self.fake_media_pool.is_default() {
+            cache.insert(
+                "fake_media_pool".to_string(),
+                config::ValueKind::Table(HashMap::from_iter([
+                    (
+                        "assignment".to_string(),
+                        self.fake_media_pool.assignment.to_string().into(),
+                    ),
+                    ("sources".to_string(), self.fake_media_pool.sources.clone().into()),
+                ]))
+                .into(),
+            );
+        }
+        if !self.fake_media_cache.is_default() {
+            cache.insert(
+                "fake_media_cache".to_string(),
+                config::ValueKind::Table(HashMap::from_iter([(
+                    "max_size_mb".to_string(),
+                    self.fake_media_cache.max_size_mb.into(),
+                )]))
+                .into(),
+            );
+        }
         Ok(cache)
     }
 }
@@ -268,7 +478,7 @@ impl Config {
         let media = if content.trim().is_empty() {
             return None;
         } else {
-            FakeMediaWithDescription::new(FakeMedia::FileOrUrl(content.clone()), Some(content))
+            FakeMediaWithDescription::new(FakeMedia::from(&content), Some(content))
         };
         let fake_media_sources = &mut self.fake_media_sources;
         if fake_media_sources.len() >= 2 {
@@ -280,6 +490,51 @@ impl Config {
         }
     }
 
+    /// Selects the fake media source whose description or file/URL is `name`. Existing files, URLs and noise mixes
+    /// without a source are added as custom sources, any other name is rejected. The selection is kept,
+    /// `fake_media_pool` no longer assigns a source.
+    pub fn select_fake_media(&mut self, name: &str) -> Result<()> {
+        self.fake_media_selected = match self.fake_media_sources.iter().position(|source| source.matches(name)) {
+            Some(index) => Some(index),
+            None if Self::is_custom_fake_media(name) => self.add_custom_fake_media(name.to_string()),
+            None => eyre::bail!(
+                "Unknown fake media `{name}`, expected a file, URL, noise mix or one of: {}",
+                list_descriptions(&self.fake_media_sources)
+            ),
+        };
+        self.fake_media_pool = FakeMediaPool::default();
+        Ok(())
+    }
+
+    fn is_custom_fake_media(name: &str) -> bool {
+        match FakeMedia::from(name) {
+            FakeMedia::FileOrUrl(file_or_url) => file_or_url.parse::<FakeMediaFileOrUrl>().is_ok(),
+            _ => true,
+        }
+    }
+
+    /// Sets how the selected fake media source is converted.
+    pub fn set_fake_media_conversion(&mut self, conversion: FakeMediaConversion) {
+        if let Some(source) = self
+            .fake_media_selected
+            .and_then(|selected| self.fake_media_sources.get_mut(selected))
+        {
+            *source = source.clone().with_conversion(conversion);
+        }
+    }
+
+    /// Selects the source `fake_media_pool` assigns to the `spawn_index`-th participant spawned from this config.
+    /// Fails if the pool names a source that is not in `fake_media_sources`.
+    pub fn assign_pooled_fake_media(&mut self, spawn_index: usize, random: u64) -> Result<()> {
+        if let Some(index) = self
+            .fake_media_pool
+            .pick(&self.fake_media_sources, spawn_index, random)?
+        {
+            self.fake_media_selected = Some(index);
+        }
+        Ok(())
+    }
+
     pub fn data_dir(&self) -> &Path {
         &self.app_config.data_dir
     }
@@ -344,6 +599,7 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::media::FakeMediaAssignment;
 
     #[test]
     fn loads_old_config_file_without_remote_url_fields() {
@@ -407,6 +663,322 @@ cloudflare:
         assert_eq!(config.cloudflare.health_poll_interval_ms, 2_000);
     }
 
+    #[test]
+    fn parses_spawn_limits_and_keeps_them_when_used_as_config_source() {
+        let config: Config = config::Config::builder()
+            .add_source(Config::default())
+            .add_source(config::File::from_str(
+                r#"
+spawn:
+  max_concurrent_starts: 3
+  interval_ms: 500
+  max_cloudflare: 10
+  cloudflare_worker_limits: true
+"#,
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(config.spawn.max_concurrent_starts, Some(3));
+        assert_eq!(config.spawn.interval_ms, 500);
+        assert_eq!(config.spawn.max_running(ParticipantBackendKind::Cloudflare), Some(10));
+        assert_eq!(config.spawn.max_running(ParticipantBackendKind::Local), None);
+        assert!(config.spawn.cloudflare_worker_limits);
+
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(reloaded.spawn, config.spawn);
+        assert!(Config::default().spawn.is_default());
+    }
+
+    #[test]
+    fn parses_remote_backend_and_keeps_agents_when_used_as_config_source() {
+        let config: Config = config::Config::builder()
+            .add_source(Config::default())
+            .add_source(config::File::from_str(
+                r#"
+backend: remote
+remote:
+  agents:
+    - sim-1.internal:7171
+    - sim-2.internal:7171
+  connect_timeout_ms: 2000
+  token: s3cret
+"#,
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(config.backend, ParticipantBackendKind::Remote);
+        assert_eq!(config.remote.agents, vec!["sim-1.internal:7171", "sim-2.internal:7171"]);
+        assert_eq!(config.remote.connect_timeout_ms, 2_000);
+        assert_eq!(config.remote.token.as_deref(), Some("s3cret"));
+
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(reloaded.remote, config.remote);
+        assert!(Config::default().remote.is_default());
+    }
+
+    #[test]
+    fn parses_network_conditions_and_keeps_them_when_used_as_config_source() {
+        let config: Config = config::Config::builder()
+            .add_source(Config::default())
+            .add_source(config::File::from_str(
+                r#"
+network:
+  profile: custom
+  latency_ms: 250
+  upload_kbps: 800
+  packet_loss_percent: 1.5
+"#,
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(config.network.profile, NetworkProfile::Custom);
+        assert_eq!(config.network.latency_ms, 250);
+        assert_eq!(config.network.download_kbps, 0);
+        assert_eq!(config.network.packet_loss_percent, 1.5);
+
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(reloaded.network, config.network);
+        assert!(Config::default().network.is_default());
+    }
+
+    #[test]
+    fn parses_device_emulation_and_keeps_it_when_used_as_config_source() {
+        let config: Config = config::Config::builder()
+            .add_source(Config::default())
+            .add_source(config::File::from_str(
+                r#"
+device:
+  profile: low-end-phone
+  cpu_throttling_rate: 8
+"#,
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(config.device.profile, DeviceProfile::LowEndPhone);
+        assert_eq!(config.device.cpu_throttling_rate, Some(8.0));
+
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(reloaded.device, config.device);
+        assert!(Config::default().device.is_default());
+    }
+
+    #[test]
+    fn parses_fake_media_pool_and_keeps_it_when_used_as_config_source() {
+        let config: Config = config::Config::builder()
+            .add_source(Config::default())
+            .add_source(config::File::from_str(
+                r#"
+fake_media_pool:
+  assignment: round-robin
+  sources:
+    - Counting (Audio)
+    - <builtin>
+"#,
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(config.fake_media_pool.assignment, FakeMediaAssignment::RoundRobin);
+        let picks = (0..3)
+            .map(|index| {
+                let mut participant = config.clone();
+                participant.assign_pooled_fake_media(index, 0).unwrap();
+                participant.fake_media_with_description().description().to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(picks, ["Counting (Audio)", "<builtin>", "Counting (Audio)"]);
+
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(reloaded.fake_media_pool, config.fake_media_pool);
+        assert!(Config::default().fake_media_pool.is_default());
+    }
+
+    #[test]
+    fn parses_fake_media_conversion_and_keeps_it_when_used_as_config_source() {
+        let mut config: Config = config::Config::builder()
+            .add_source(Config::default())
+            .add_source(config::File::from_str(
+                r#"
+fake_media_selected: 0
+fake_media_sources:
+  - description: Talking head
+    fake_media: talking-head.mp4
+    conversion:
+      resolution: 360p
+      fps: 15
+      max_duration_secs: 10
+"#,
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(
+            config.fake_media_with_description().conversion().to_string(),
+            "360p, 15fps, 10s"
+        );
+
+        config.set_fake_media_conversion("720p, from 2s, loop".parse().unwrap());
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(
+            reloaded.fake_media_with_description().conversion().to_string(),
+            "720p, from 2s, loop"
+        );
+    }
+
+    #[test]
+    fn parses_fake_media_cache_limit_and_keeps_it_when_used_as_config_source() {
+        let config: Config = config::Config::builder()
+            .add_source(Config::default())
+            .add_source(config::File::from_str(
+                r#"
+fake_media_cache:
+  max_size_mb: 0
+"#,
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(config.fake_media_cache.max_size(), None);
+
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(reloaded.fake_media_cache, config.fake_media_cache);
+        assert_eq!(Config::default().fake_media_cache.max_size(), Some(10_000_000_000));
+    }
+
+    #[test]
+    fn selecting_fake_media_by_name_takes_the_participant_out_of_the_pool() {
+        let mut config = Config {
+            fake_media_pool: FakeMediaPool {
+                assignment: FakeMediaAssignment::Random,
+                sources: Vec::new(),
+            },
+            ..Default::default()
+        };
+
+        config.select_fake_media("IT Crowd (Video + Audio)").unwrap();
+        assert_eq!(
+            config.fake_media(),
+            FakeMedia::from("https://share.dev.hyper.video/itcrowd.mkv")
+        );
+        assert!(config.fake_media_pool.is_default());
+
+        config.select_fake_media("https://example.com/voice.wav").unwrap();
+        assert_eq!(config.fake_media(), FakeMedia::from("https://example.com/voice.wav"));
+    }
+
+    #[test]
+    fn selecting_unknown_fake_media_lists_the_sources() {
+        let mut config = Config::default();
+        let selected = config.fake_media_selected;
+
+        let error = config.select_fake_media("IT Crowd").unwrap_err().to_string();
+
+        assert!(error.contains("Unknown fake media `IT Crowd`"), "{error}");
+        assert!(error.contains("IT Crowd (Video + Audio)"), "{error}");
+        assert_eq!(config.fake_media_selected, selected);
+        assert_eq!(config.fake_media_sources, Config::default().fake_media_sources);
+    }
+
+    #[test]
+    fn parses_behavior_profile_and_keeps_seed_when_used_as_config_source() {
+        let config: Config = config::Config::builder()
+            .add_source(Config::default())
+            .add_source(config::File::from_str(
+                r#"
+behavior: flapper
+behavior_seed: 42
+behavior_period_ms: 5000
+"#,
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(config.behavior, BehaviorProfile::Flapper);
+        assert_eq!(config.behavior_seed, Some(42));
+        assert_eq!(config.behavior_period_ms, 5_000);
+
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(reloaded.behavior, BehaviorProfile::Flapper);
+        assert_eq!(reloaded.behavior_seed, Some(42));
+        assert_eq!(reloaded.behavior_period_ms, 5_000);
+    }
+
     #[test]
     fn parses_aws_device_farm_backend_and_nested_device_farm_config() {
         let config: Config = config::Config::builder()
@@ -488,6 +1060,46 @@ video_max_concurrent_tracks: 2
         assert!(!Config::default().browser_logs);
     }
 
+    #[test]
+    fn parses_screenshare_source_and_keeps_it_when_used_as_config_source() {
+        assert_eq!(Config::default().screenshare_source, ScreenshareSource::Builtin);
+
+        let yaml = include_str!("default-config.yaml").replace(
+            "screenshare_source: <builtin>",
+            "screenshare_source: https://share.dev.hyper.video/slides.html",
+        );
+        let config: Config = yaml_serde::from_str(&yaml).expect("failed to parse config");
+        assert_eq!(
+            config.screenshare_source,
+            ScreenshareSource::Tab("https://share.dev.hyper.video/slides.html".to_string())
+        );
+
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(reloaded.screenshare_source, config.screenshare_source);
+    }
+
+    #[test]
+    fn inject_fake_media_is_off_by_default_and_kept_when_used_as_config_source() {
+        assert!(!Config::default().inject_fake_media);
+
+        let yaml = include_str!("default-config.yaml").replace("inject_fake_media: false", "inject_fake_media: true");
+        let config: Config = yaml_serde::from_str(&yaml).expect("failed to parse config");
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert!(reloaded.inject_fake_media);
+    }
+
     #[test]
     fn old_config_without_browser_logs_still_loads() {
         let yaml = include_str!("default-config.yaml").replace("browser_logs: false\n", "");
@@ -495,4 +1107,57 @@ video_max_concurrent_tracks: 2
 
         assert!(!config.browser_logs);
     }
+
+    #[test]
+    fn old_config_without_webrtc_stats_interval_samples_every_two_seconds() {
+        let yaml = include_str!("default-config.yaml").replace("webrtc_stats_interval_ms: 2000\n", "");
+        let config: Config = yaml_serde::from_str(&yaml).expect("old config should load");
+
+        assert_eq!(config.webrtc_stats_interval_ms, 2_000);
+    }
+
+    #[test]
+    fn received_media_timeout_defaults_to_twenty_seconds_and_can_be_disabled() {
+        let yaml = include_str!("default-config.yaml").replace("received_media_timeout_secs: 20\n", "");
+        let config: Config = yaml_serde::from_str(&yaml).expect("old config should load");
+        assert_eq!(config.received_media_timeout_secs, 20);
+
+        let yaml = include_str!("default-config.yaml")
+            .replace("received_media_timeout_secs: 20\n", "received_media_timeout_secs: 0\n");
+        let config: Config = yaml_serde::from_str(&yaml).expect("failed to parse config");
+        let config: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+        assert_eq!(config.received_media_timeout_secs, 0);
+    }
+
+    #[test]
+    fn control_api_is_disabled_unless_a_bind_address_is_configured() {
+        assert_eq!(Config::default().control_bind, None);
+
+        let config: Config = config::Config::builder()
+            .add_source(Config::default())
+            .add_source(config::File::from_str(
+                "control_bind: 127.0.0.1:7070",
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(config.control_bind, Some("127.0.0.1:7070".parse().unwrap()));
+
+        let reloaded: Config = config::Config::builder()
+            .add_source(config.clone())
+            .build()
+            .expect("failed to build config")
+            .try_deserialize()
+            .expect("failed to deserialize config");
+
+        assert_eq!(reloaded.control_bind, config.control_bind);
+    }
 }
@@ -0,0 +1,444 @@
+//! The fake media cache under the cache dir: downloads in `download-cache` and converted or generated media in
+//! `media-cache`, one directory per source.
+
+use super::custom_fake_media::FakeMediaFiles;
+use eyre::{
+    Context as _,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::{
+    fs::File,
+    io::Read as _,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+use strum::Display;
+
+pub(super) const DOWNLOAD_CACHE: &str = "download-cache";
+pub(super) const MEDIA_CACHE: &str = "media-cache";
+pub(super) const TEST_PATTERN_CACHE: &str = "test-pattern";
+pub(super) const NOISE_MIX_CACHE: &str = "noise-mix";
+/// Written next to the cached files, holds the URL, file or description they were created from.
+const ORIGIN_FILE: &str = "origin.txt";
+
+const MB: u64 = 1_000_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct FakeMediaCacheConfig {
+    /// Least recently used media is removed once the cache grows beyond this. `0` never removes media.
+    pub max_size_mb: u64,
+}
+
+impl FakeMediaCacheConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The size cap in bytes, `None` if the cache may grow without limit. Caps beyond `u64::MAX` bytes saturate.
+    pub fn max_size(&self) -> Option<u64> {
+        (self.max_size_mb > 0).then(|| self.max_size_mb.saturating_mul(MB))
+    }
+}
+
+impl Default for FakeMediaCacheConfig {
+    fn default() -> Self {
+        Self { max_size_mb: 10_000 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Display, PartialEq, Eq)]
+pub enum CacheEntryKind {
+    #[strum(to_string = "download")]
+    Download,
+    /// Audio and video converted from a file or download.
+    #[strum(to_string = "conversion")]
+    Conversion,
+    #[strum(to_string = "test-pattern")]
+    TestPattern,
+    #[strum(to_string = "noise-mix")]
+    NoiseMix,
+}
+
+/// One cached source: a directory with its files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheEntry {
+    pub kind: CacheEntryKind,
+    pub path: PathBuf,
+    /// `None` for media cached before origins were recorded.
+    pub origin: Option<String>,
+    pub size: u64,
+    /// The newest modification time of the files, bumped whenever the cached media is used.
+    pub last_used: SystemTime,
+}
+
+impl CacheEntry {
+    fn read(path: PathBuf, kind: CacheEntryKind) -> Result<Self> {
+        let mut size = 0;
+        let mut last_used = SystemTime::UNIX_EPOCH;
+        for file in std::fs::read_dir(&path)? {
+            let metadata = file?.metadata()?;
+            size += metadata.len();
+            last_used = last_used.max(metadata.modified()?);
+        }
+        let origin = std::fs::read_to_string(path.join(ORIGIN_FILE))
+            .ok()
+            .map(|origin| origin.trim().to_string());
+
+        Ok(Self {
+            kind,
+            path,
+            origin,
+            size,
+            last_used,
+        })
+    }
+
+    pub fn remove(&self) -> Result<()> {
+        std::fs::remove_dir_all(&self.path).wrap_err_with(|| format!("Failed to remove {:?}", self.path))
+    }
+
+    /// Problems with the cached files, empty if they are complete.
+    pub fn verify(&self) -> Result<Vec<String>> {
+        let mut problems = Vec::new();
+        let mut media_files = 0;
+        for file in std::fs::read_dir(&self.path)? {
+            let path = file?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if name == ORIGIN_FILE {
+                continue;
+            }
+            media_files += 1;
+            let problem = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("partial") => Some("unfinished write".to_string()),
+                Some("y4m") => verify_y4m(&path)?,
+                Some("wav") => verify_wav(&path)?,
+                _ if std::fs::metadata(&path)?.len() == 0 => Some("empty".to_string()),
+                _ => None,
+            };
+            problems.extend(problem.map(|problem| format!("{name}: {problem}")));
+        }
+        if media_files == 0 {
+            problems.push("no media files".to_string());
+        }
+        Ok(problems)
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.path)
+    }
+
+    /// Whether a download or conversion is still writing into the entry, possibly in another process.
+    fn is_being_written(&self) -> bool {
+        std::fs::read_dir(&self.path).is_ok_and(|files| {
+            files
+                .flatten()
+                .any(|file| file.path().extension().is_some_and(|ext| ext == "partial"))
+        })
+    }
+}
+
+/// Lists and prunes the fake media cache.
+#[derive(Debug, Clone)]
+pub struct FakeMediaCache {
+    cache_dir: PathBuf,
+}
+
+impl FakeMediaCache {
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            cache_dir: cache_dir.into(),
+        }
+    }
+
+    /// All cached sources, least recently used first.
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let media_cache = self.cache_dir.join(MEDIA_CACHE);
+        let mut entries = Vec::new();
+        read_entries(
+            &self.cache_dir.join(DOWNLOAD_CACHE),
+            CacheEntryKind::Download,
+            &mut entries,
+        )?;
+        read_entries(&media_cache, CacheEntryKind::Conversion, &mut entries)?;
+        read_entries(
+            &media_cache.join(TEST_PATTERN_CACHE),
+            CacheEntryKind::TestPattern,
+            &mut entries,
+        )?;
+        read_entries(
+            &media_cache.join(NOISE_MIX_CACHE),
+            CacheEntryKind::NoiseMix,
+            &mut entries,
+        )?;
+        entries.sort_by_key(|entry| entry.last_used);
+        Ok(entries)
+    }
+
+    /// The entries to remove so that nothing is older than `max_age` and, least recently used first, the cache fits
+    /// into `max_size` bytes. Entries holding one of the `keep` files and entries still being written are never
+    /// picked.
+    pub fn prune_plan(
+        &self,
+        max_age: Option<Duration>,
+        max_size: Option<u64>,
+        keep: &[&Path],
+    ) -> Result<Vec<CacheEntry>> {
+        let now = SystemTime::now();
+        let entries = self.entries()?;
+        let mut size = entries.iter().map(|entry| entry.size).sum::<u64>();
+        let mut plan = Vec::new();
+        for entry in entries {
+            if keep.iter().any(|path| entry.contains(path)) || entry.is_being_written() {
+                continue;
+            }
+            let expired =
+                max_age.is_some_and(|max_age| now.duration_since(entry.last_used).unwrap_or_default() > max_age);
+            let oversized = max_size.is_some_and(|max_size| size > max_size);
+            if expired || oversized {
+                size -= entry.size;
+                plan.push(entry);
+            }
+        }
+        Ok(plan)
+    }
+}
+
+fn read_entries(dir: &Path, kind: CacheEntryKind, entries: &mut Vec<CacheEntry>) -> Result<()> {
+    let Ok(items) = std::fs::read_dir(dir) else {
+        // Nothing cached yet.
+        return Ok(());
+    };
+    for item in items {
+        let item = item?;
+        let nested = kind == CacheEntryKind::Conversion
+            && [TEST_PATTERN_CACHE, NOISE_MIX_CACHE].contains(&item.file_name().to_string_lossy().as_ref());
+        if item.file_type()?.is_dir() && !nested {
+            entries.push(CacheEntry::read(item.path(), kind)?);
+        }
+    }
+    Ok(())
+}
+
+impl FakeMediaFiles {
+    /// Removes least recently used media until the cache fits into `max_size` bytes, keeping these files and the
+    /// `in_use` ones, e.g. the media of running participants.
+    pub fn enforce_cache_limit(
+        &self,
+        cache_dir: impl AsRef<Path>,
+        max_size: Option<u64>,
+        in_use: &[PathBuf],
+    ) -> Result<()> {
+        if max_size.is_none() {
+            return Ok(());
+        }
+        let keep = [&self.audio, &self.video]
+            .into_iter()
+            .flatten()
+            .chain(in_use)
+            .map(PathBuf::as_path)
+            .collect::<Vec<_>>();
+        for entry in FakeMediaCache::new(cache_dir.as_ref()).prune_plan(None, max_size, &keep)? {
+            info!(path = ?entry.path, size = entry.size, "Removing least recently used fake media from the cache");
+            entry.remove()?;
+        }
+        Ok(())
+    }
+}
+
+/// Records what the cached files in `dir` were created from, listed by `media list`.
+pub(super) fn record_origin(dir: &Path, origin: &str) {
+    let path = dir.join(ORIGIN_FILE);
+    if std::fs::read_to_string(&path).is_ok_and(|recorded| recorded == origin) {
+        return;
+    }
+    if let Err(err) = std::fs::write(&path, origin) {
+        debug!(?path, "Failed to record the origin of cached fake media: {err}");
+    }
+}
+
+/// Bumps the modification time of a cached file, which orders the cache for pruning.
+pub(super) fn mark_used(path: &Path) {
+    let result = File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    if let Err(err) = result {
+        debug!(?path, "Failed to mark cached fake media as used: {err}");
+    }
+}
+
+/// Checks that the file holds whole frames of the size its header announces.
+fn verify_y4m(path: &Path) -> Result<Option<String>> {
+    let mut header = Vec::new();
+    File::open(path)?.take(1024).read_to_end(&mut header)?;
+    let Some(header_len) = header.iter().position(|byte| *byte == b'\n') else {
+        return Ok(Some("no Y4M header".to_string()));
+    };
+    let header = String::from_utf8_lossy(&header[..header_len]);
+    let mut params = header.split(' ');
+    if params.next() != Some("YUV4MPEG2") {
+        return Ok(Some("no Y4M header".to_string()));
+    }
+
+    let (mut width, mut height, mut colorspace) = (0u64, 0u64, "420");
+    for param in params {
+        match param.split_at_checked(1) {
+            Some(("W", value)) => width = value.parse().unwrap_or(0),
+            Some(("H", value)) => height = value.parse().unwrap_or(0),
+            Some(("C", value)) => colorspace = value,
+            _ => {}
+        }
+    }
+    let luma = width * height;
+    let frame_len = match colorspace {
+        colorspace if colorspace.starts_with("420") => luma + 2 * width.div_ceil(2) * height.div_ceil(2),
+        colorspace if colorspace.starts_with("422") => luma + 2 * width.div_ceil(2) * height,
+        colorspace if colorspace.starts_with("444") => luma * 3,
+        "mono" => luma,
+        // Unknown layouts are not checked further.
+        _ => return Ok(None),
+    };
+    if frame_len == 0 {
+        return Ok(Some(format!("invalid Y4M header `{header}`")));
+    }
+
+    let data_len = std::fs::metadata(path)?.len() - header_len as u64 - 1;
+    let framed_len = frame_len + "FRAME\n".len() as u64;
+    if data_len == 0 || !data_len.is_multiple_of(framed_len) {
+        return Ok(Some(format!("truncated after {} frames", data_len / framed_len)));
+    }
+    Ok(None)
+}
+
+/// Checks that the file is as long as its RIFF header announces.
+fn verify_wav(path: &Path) -> Result<Option<String>> {
+    let mut header = [0; 12];
+    if File::open(path)?.read_exact(&mut header).is_err() || &header[..4] != b"RIFF" || &header[8..] != b"WAVE" {
+        return Ok(Some("no WAV header".to_string()));
+    }
+    let riff_len = u32::from_le_bytes(header[4..8].try_into()?) as u64;
+    let len = std::fs::metadata(path)?.len();
+    if len < riff_len + 8 {
+        return Ok(Some(format!("truncated at {len} of {} bytes", riff_len + 8)));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_entry(cache_dir: &Path, dir: &str, file: &str, len: usize, age: Duration) -> PathBuf {
+        let dir = cache_dir.join(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(file);
+        std::fs::write(&path, vec![1; len]).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn prune_removes_least_recently_used_entries_first() {
+        let cache_dir = temp_dir::TempDir::new().unwrap();
+        let hour = Duration::from_secs(3_600);
+        write_entry(cache_dir.path(), "download-cache/a", "input.mp4", 100, hour * 3);
+        let kept = write_entry(cache_dir.path(), "media-cache/b", "video.y4m", 100, hour * 2);
+        write_entry(cache_dir.path(), "media-cache/c", "audio.wav", 100, hour);
+        write_entry(
+            cache_dir.path(),
+            "media-cache/test-pattern/d",
+            "audio.wav",
+            100,
+            Duration::ZERO,
+        );
+        let cache = FakeMediaCache::new(cache_dir.path());
+
+        let kinds = cache
+            .entries()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                CacheEntryKind::Download,
+                CacheEntryKind::Conversion,
+                CacheEntryKind::Conversion,
+                CacheEntryKind::TestPattern
+            ]
+        );
+
+        let plan = cache.prune_plan(None, Some(250), &[&kept]).unwrap();
+        let names = plan
+            .iter()
+            .map(|entry| entry.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "c"]);
+
+        let plan = cache.prune_plan(Some(hour * 2 + hour / 2), None, &[]).unwrap();
+        assert_eq!(plan.len(), 1);
+    }
+
+    #[test]
+    fn prune_skips_entries_that_are_still_written() {
+        let cache_dir = temp_dir::TempDir::new().unwrap();
+        let hour = Duration::from_secs(3_600);
+        write_entry(cache_dir.path(), "download-cache/a", "input.1-0.partial", 100, hour * 2);
+        write_entry(cache_dir.path(), "media-cache/b", "video.y4m", 100, hour);
+        let cache = FakeMediaCache::new(cache_dir.path());
+
+        let plan = cache.prune_plan(Some(Duration::ZERO), Some(0), &[]).unwrap();
+        let names = plan
+            .iter()
+            .map(|entry| entry.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["b"]);
+    }
+
+    #[test]
+    fn huge_size_limits_saturate() {
+        let config = FakeMediaCacheConfig { max_size_mb: u64::MAX };
+        assert_eq!(config.max_size(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn verify_detects_truncated_media() {
+        let cache_dir = temp_dir::TempDir::new().unwrap();
+        let dir = cache_dir.path().join("media-cache").join("a");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut y4m = b"YUV4MPEG2 W2 H2 F30:1 C420jpeg\n".to_vec();
+        y4m.extend(b"FRAME\n\0\0\0\0\0\0");
+        std::fs::write(dir.join("video.y4m"), &y4m).unwrap();
+        let entry = CacheEntry::read(dir.clone(), CacheEntryKind::Conversion).unwrap();
+        assert!(entry.verify().unwrap().is_empty());
+
+        std::fs::write(dir.join("video.y4m"), &y4m[..y4m.len() - 1]).unwrap();
+        std::fs::write(dir.join("audio.wav"), b"RIFF\x24\0\0\0WAVEfmt ").unwrap();
+        let problems = entry.verify().unwrap();
+        assert!(
+            problems.contains(&"video.y4m: truncated after 0 frames".to_string()),
+            "{problems:?}"
+        );
+        assert!(
+            problems.contains(&"audio.wav: truncated at 16 of 44 bytes".to_string()),
+            "{problems:?}"
+        );
+    }
+}
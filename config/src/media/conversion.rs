@@ -0,0 +1,240 @@
+//! How files and URLs are converted into fake media: resolution, frame rate and which part of the input is used.
+
+use super::custom_fake_media::string_hash;
+use crate::VideoConstraint;
+use eyre::{
+    bail,
+    eyre,
+    Context as _,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::{
+    fmt,
+    path::Path,
+    process::Command,
+    str::FromStr,
+};
+
+/// Audio is faded in and out this long at the ends of a loop-friendly clip, so the loop does not click.
+const LOOP_FADE_SECS: f64 = 0.05;
+const ORIGINAL: &str = "original";
+
+/// Conversion options of a fake media source. The default converts the whole input at its own resolution and frame
+/// rate.
+///
+/// Written as comma separated options, e.g. `360p, 15fps, from 5s, 10s, loop`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FakeMediaConversion {
+    /// Scales the video to this height, keeping the aspect ratio. `none` keeps the input resolution.
+    pub resolution: VideoConstraint,
+    /// `None` keeps the input frame rate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fps: Option<u32>,
+    /// Skips this many seconds at the start of the input.
+    pub start_secs: f64,
+    /// Cuts the clip after this many seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration_secs: Option<f64>,
+    /// Trims audio and video to the same whole number of seconds and fades the audio at both ends, so the streams
+    /// Chrome loops independently stay in sync and loop without a click.
+    pub loop_friendly: bool,
+}
+
+impl FakeMediaConversion {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The name of the cache directory of `input_hash` converted this way. The default conversion keeps the plain
+    /// input hash, so media converted before conversion options existed stays cached.
+    pub(super) fn cache_key(&self, input_hash: &str) -> Result<String> {
+        if self.is_default() {
+            Ok(input_hash.to_string())
+        } else {
+            string_hash(format!("{input_hash}:{self}"))
+        }
+    }
+
+    /// ffmpeg arguments to place before `-i`.
+    pub(super) fn input_args(&self) -> Vec<String> {
+        if self.start_secs > 0.0 {
+            vec!["-ss".to_string(), self.start_secs.to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// ffmpeg output arguments for the video stream of `input`.
+    pub(super) fn video_args(&self, input: &Path) -> Result<Vec<String>> {
+        let mut args = self.duration_args(input)?;
+        let mut filters = Vec::new();
+        if let Some(height) = self.resolution.height() {
+            // `-2` keeps the width even, which yuv420p requires.
+            filters.push(format!("scale=-2:{height}"));
+        }
+        if let Some(fps) = self.fps {
+            filters.push(format!("fps={fps}"));
+        }
+        if !filters.is_empty() {
+            args.extend(["-vf".to_string(), filters.join(",")]);
+        }
+        Ok(args)
+    }
+
+    /// ffmpeg output arguments for the audio stream of `input`.
+    pub(super) fn audio_args(&self, input: &Path) -> Result<Vec<String>> {
+        let mut args = self.duration_args(input)?;
+        if let Some(duration) = self.loop_duration(input)? {
+            let fade_out = duration - LOOP_FADE_SECS;
+            args.extend([
+                "-af".to_string(),
+                format!("afade=t=in:d={LOOP_FADE_SECS},afade=t=out:st={fade_out}:d={LOOP_FADE_SECS}"),
+            ]);
+        }
+        Ok(args)
+    }
+
+    fn duration_args(&self, input: &Path) -> Result<Vec<String>> {
+        let duration = match self.loop_duration(input)? {
+            Some(duration) => Some(duration),
+            None => self.max_duration_secs,
+        };
+        Ok(duration
+            .map(|duration| vec!["-t".to_string(), duration.to_string()])
+            .unwrap_or_default())
+    }
+
+    /// The whole number of seconds a loop-friendly clip of `input` lasts.
+    fn loop_duration(&self, input: &Path) -> Result<Option<f64>> {
+        if !self.loop_friendly {
+            return Ok(None);
+        }
+        let available = probe_duration(input)? - self.start_secs;
+        let duration = match self.max_duration_secs {
+            Some(max_duration) => available.min(max_duration),
+            None => available,
+        }
+        .floor();
+        if duration < 1.0 {
+            bail!(
+                "{input:?} is shorter than one second after {}s, too short to loop",
+                self.start_secs
+            );
+        }
+        Ok(Some(duration))
+    }
+}
+
+fn probe_duration(input: &Path) -> Result<f64> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "csv=p=0"])
+        .arg(input)
+        .output()
+        .context("Failed to start ffprobe process")?;
+    let duration = String::from_utf8_lossy(&output.stdout);
+    duration
+        .trim()
+        .parse()
+        .map_err(|_| eyre!("ffprobe could not read the duration of {input:?}: {duration}"))
+}
+
+impl fmt::Display for FakeMediaConversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut options = Vec::new();
+        if self.resolution != VideoConstraint::None {
+            options.push(self.resolution.to_string());
+        }
+        if let Some(fps) = self.fps {
+            options.push(format!("{fps}fps"));
+        }
+        if self.start_secs > 0.0 {
+            options.push(format!("from {}s", self.start_secs));
+        }
+        if let Some(duration) = self.max_duration_secs {
+            options.push(format!("{duration}s"));
+        }
+        if self.loop_friendly {
+            options.push("loop".to_string());
+        }
+        if options.is_empty() {
+            options.push(ORIGINAL.to_string());
+        }
+        write!(f, "{}", options.join(", "))
+    }
+}
+
+impl FromStr for FakeMediaConversion {
+    type Err = eyre::Report;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut conversion = Self::default();
+        for option in input.split(',').map(str::trim).filter(|option| !option.is_empty()) {
+            let seconds = |value: &str| -> Result<f64> {
+                value
+                    .trim()
+                    .strip_suffix('s')
+                    .and_then(|value| value.parse().ok())
+                    .filter(|value: &f64| *value >= 0.0)
+                    .ok_or_else(|| eyre!("Invalid duration `{value}`, expected e.g. `10s`"))
+            };
+            if option == ORIGINAL {
+                continue;
+            } else if option == "loop" {
+                conversion.loop_friendly = true;
+            } else if let Some(start) = option.strip_prefix("from ") {
+                conversion.start_secs = seconds(start)?;
+            } else if let Some(fps) = option.strip_suffix("fps") {
+                conversion.fps = Some(fps.trim().parse().map_err(|_| eyre!("Invalid frame rate `{option}`"))?);
+            } else if option.ends_with('p') {
+                conversion.resolution = option.parse().map_err(|_| eyre!("Unknown resolution `{option}`"))?;
+            } else {
+                conversion.max_duration_secs = Some(seconds(option)?);
+            }
+        }
+        Ok(conversion)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversion_round_trips_through_its_text_form() {
+        let conversion: FakeMediaConversion = "360p, 15fps, from 5s, 10s, loop".parse().unwrap();
+        assert_eq!(
+            conversion,
+            FakeMediaConversion {
+                resolution: VideoConstraint::P360,
+                fps: Some(15),
+                start_secs: 5.0,
+                max_duration_secs: Some(10.0),
+                loop_friendly: true,
+            }
+        );
+        assert_eq!(conversion.to_string(), "360p, 15fps, from 5s, 10s, loop");
+        assert_eq!(FakeMediaConversion::default().to_string(), "original");
+        assert!("original".parse::<FakeMediaConversion>().unwrap().is_default());
+        assert!("123p".parse::<FakeMediaConversion>().is_err());
+    }
+
+    #[test]
+    fn only_converted_media_gets_its_own_cache_key() {
+        let conversion = FakeMediaConversion {
+            fps: Some(15),
+            ..Default::default()
+        };
+
+        assert_eq!(FakeMediaConversion::default().cache_key("abc").unwrap(), "abc");
+        assert_ne!(conversion.cache_key("abc").unwrap(), "abc");
+        assert_eq!(
+            conversion.video_args(Path::new("unused")).unwrap(),
+            ["-vf".to_string(), "fps=15".to_string()]
+        );
+    }
+}
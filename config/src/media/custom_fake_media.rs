@@ -1,5 +1,14 @@
 //! Converts audio and video files into inputs suitable for "fake" video and audio inputs for Chrome/Chromium.
 
+use super::{
+    cache::{
+        mark_used,
+        record_origin,
+        DOWNLOAD_CACHE,
+        MEDIA_CACHE,
+    },
+    FakeMediaConversion,
+};
 use eyre::{
     bail,
     Context as _,
@@ -10,14 +19,32 @@ use sha1::{
     Sha1,
 };
 use std::{
+    fs::File,
+    io::{
+        BufWriter,
+        Read as _,
+        Write as _,
+    },
     path::{
         Path,
         PathBuf,
     },
-    process::Command,
+    process::{
+        Command,
+        Stdio,
+    },
+    sync::atomic::{
+        AtomicU64,
+        Ordering,
+    },
+    time::Duration,
 };
+use tokio_util::sync::CancellationToken;
 use url::Url;
 
+/// How often a running ffmpeg is checked for cancellation.
+const FFMPEG_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FakeMediaFileOrUrl {
     /// A file path to a video or audio file.
@@ -51,35 +78,77 @@ pub struct FakeMediaFiles {
 
 impl FakeMediaFiles {
     /// Reads a media file and tries to split its audio and video streams into a wav and y4m file, suitable for serving
-    /// as "fake" media inputs for Chrome/Chromium.
-    pub fn from_file_or_url(input: FakeMediaFileOrUrl, cache_dir: impl AsRef<Path>) -> Result<Self> {
+    /// as "fake" media inputs for Chrome/Chromium. Cancelling `cancel` kills a running ffmpeg and fails the conversion.
+    pub fn from_file_or_url(
+        input: FakeMediaFileOrUrl,
+        conversion: &FakeMediaConversion,
+        cache_dir: impl AsRef<Path>,
+        cancel: &CancellationToken,
+    ) -> Result<Self> {
         let cache_dir = cache_dir.as_ref();
 
-        let input = match input {
-            FakeMediaFileOrUrl::File(path) => path,
+        match input {
+            FakeMediaFileOrUrl::File(path) => Self::from_file(&path, conversion, cache_dir, cancel),
             FakeMediaFileOrUrl::Url(url) => {
-                let name =
-                    infer_filename_from_url(&url).unwrap_or_else(|| PathBuf::from("input.mp4" /* wild guess */));
-                let url_hash = string_hash(&url)?;
-                let cache_dir = cache_dir.join("download-cache").join(&url_hash);
-                let input = cache_dir.join(&name);
-                if !input.exists() {
-                    std::fs::create_dir_all(&cache_dir)?;
+                let input = Self::download_path(&url, cache_dir)?;
+                let download_dir = input.parent().expect("download path has a parent");
+                if input.exists() {
+                    mark_used(&input);
+                } else {
+                    std::fs::create_dir_all(download_dir)?;
                     download_file(&url, &input)?;
                 }
-                input
+                record_origin(download_dir, url.as_str());
+                Self::convert(&input, url.as_str(), conversion, cache_dir, cancel)
             }
-        };
+        }
+    }
 
-        Self::from_file(&input, cache_dir)
+    /// Where [`Self::from_file_or_url`] keeps the download of `url`. A file at this path is used instead of
+    /// downloading again.
+    pub fn download_path(url: &Url, cache_dir: impl AsRef<Path>) -> Result<PathBuf> {
+        let name = infer_filename_from_url(url).unwrap_or_else(|| PathBuf::from("input.mp4" /* wild guess */));
+        let url_hash = string_hash(url)?;
+        Ok(cache_dir.as_ref().join(DOWNLOAD_CACHE).join(url_hash).join(name))
     }
 
-    pub fn from_file(input: impl AsRef<Path>, cache_dir: impl AsRef<Path>) -> Result<Self> {
-        let cache_dir = cache_dir.as_ref().join("media-cache");
+    /// The conversion errors of the audio and video streams, `None` if both converted.
+    pub fn error_summary(&self) -> Option<String> {
+        let errors = [("video", &self.video_error), ("audio", &self.audio_error)]
+            .into_iter()
+            .filter_map(|(kind, error)| Some(format!("{kind}: {}", error.as_deref()?)))
+            .collect::<Vec<_>>();
+        (!errors.is_empty()).then(|| errors.join("; "))
+    }
+
+    pub fn from_file(
+        input: impl AsRef<Path>,
+        conversion: &FakeMediaConversion,
+        cache_dir: impl AsRef<Path>,
+        cancel: &CancellationToken,
+    ) -> Result<Self> {
         let input = input.as_ref();
-        let hash = file_hash(input)?;
+        Self::convert(
+            input,
+            &input.display().to_string(),
+            conversion,
+            cache_dir.as_ref(),
+            cancel,
+        )
+    }
+
+    /// `origin` is the file or URL `input` came from.
+    fn convert(
+        input: &Path,
+        origin: &str,
+        conversion: &FakeMediaConversion,
+        cache_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<Self> {
+        let cache_dir = cache_dir.join(MEDIA_CACHE);
+        let key = conversion.cache_key(&file_hash(input)?)?;
 
-        let (video, video_error) = match ffmpeg_extract(Kind::Video, input, &hash, &cache_dir) {
+        let (video, video_error) = match ffmpeg_extract(Kind::Video, input, &key, conversion, &cache_dir, cancel) {
             Ok(video) => (Some(video), None),
             Err(err) => {
                 warn!("Video conversion failed: {err}");
@@ -87,13 +156,26 @@ impl FakeMediaFiles {
             }
         };
 
-        let (audio, audio_error) = match ffmpeg_extract(Kind::Audio, input, &hash, &cache_dir) {
+        let (audio, audio_error) = match ffmpeg_extract(Kind::Audio, input, &key, conversion, &cache_dir, cancel) {
             Ok(audio) => (Some(audio), None),
             Err(err) => {
                 warn!("Audio conversion failed: {err}");
                 (None, Some(err.to_string()))
             }
         };
+        // A stream that fails on its own is reported, a cancelled conversion is not converted at all.
+        if cancel.is_cancelled() {
+            bail!("Converting {origin} was cancelled");
+        }
+
+        let converted = cache_dir.join(&key);
+        if converted.exists() {
+            let origin = match conversion.is_default() {
+                true => origin.to_string(),
+                false => format!("{origin} ({conversion})"),
+            };
+            record_origin(&converted, &origin);
+        }
 
         Ok(Self {
             audio,
@@ -110,68 +192,110 @@ enum Kind {
     Audio,
 }
 
-fn ffmpeg_extract(kind: Kind, input: &Path, input_hash: &str, cache_dir: &Path) -> Result<PathBuf> {
+/// `key` names the cache directory of the converted input, see [`FakeMediaConversion::cache_key`].
+fn ffmpeg_extract(
+    kind: Kind,
+    input: &Path,
+    key: &str,
+    conversion: &FakeMediaConversion,
+    cache_dir: &Path,
+    cancel: &CancellationToken,
+) -> Result<PathBuf> {
     const AUDIO_FILE: &str = "audio.wav";
     const AUDIO_EXT: &str = "wav";
     const VIDEO_FILE: &str = "video.y4m";
     const VIDEO_EXT: &str = "y4m";
 
     // Check if the input file is already in the correct format
-    let filename = match (input.extension().and_then(|ext| ext.to_str()), kind) {
-        (Some(AUDIO_EXT), Kind::Audio) => return Ok(input.to_path_buf()),
-        (Some(VIDEO_EXT), Kind::Video) => return Ok(input.to_path_buf()),
+    let extension = input.extension().and_then(|ext| ext.to_str());
+    let filename = match (extension, kind) {
+        (Some(AUDIO_EXT), Kind::Audio) if conversion.is_default() => return Ok(input.to_path_buf()),
+        (Some(VIDEO_EXT), Kind::Video) if conversion.is_default() => return Ok(input.to_path_buf()),
         (_, Kind::Audio) => AUDIO_FILE,
         (_, Kind::Video) => VIDEO_FILE,
     };
 
     // Did we already convert this file?
-    let cache_dir = cache_dir.join(input_hash);
+    let cache_dir = cache_dir.join(key);
     let cached = cache_dir.join(filename);
     if cached.exists() {
+        mark_used(&cached);
         return Ok(cached);
     }
 
-    std::fs::create_dir_all(&cache_dir)?;
-
-    let args = match kind {
-        Kind::Audio => vec![
-            "-loglevel",
-            "panic",
-            "-i",
-            input.to_str().expect("invalid input path"),
-            "-y",
-            "-vn",
-            cached.to_str().expect("invalid output path"),
-        ],
-        Kind::Video => vec![
-            "-loglevel",
-            "error",
-            "-i",
-            input.to_str().expect("invalid input path"),
-            "-pix_fmt",
-            "yuv420p",
-            "-an",
-            "-y",
-            cached.to_str().expect("invalid output path"),
-        ],
+    let mut args = match kind {
+        Kind::Audio => vec!["-loglevel".to_string(), "panic".to_string()],
+        Kind::Video => vec!["-loglevel".to_string(), "error".to_string()],
+    };
+    args.extend(conversion.input_args());
+    args.extend([
+        "-i".to_string(),
+        input.to_str().expect("invalid input path").to_string(),
+    ]);
+    match kind {
+        Kind::Audio => {
+            args.extend(conversion.audio_args(input)?);
+            args.push("-vn".to_string());
+        }
+        Kind::Video => {
+            args.extend(conversion.video_args(input)?);
+            args.extend(["-pix_fmt".to_string(), "yuv420p".to_string(), "-an".to_string()]);
+        }
+    }
+    // Written to a partial file first, which keeps the cache pruning away from it and a cancelled or failed run from
+    // leaving a truncated file. ffmpeg cannot infer the format from its name.
+    let partial = partial_path(&cached);
+    let format = match kind {
+        Kind::Audio => "wav",
+        Kind::Video => "yuv4mpegpipe",
     };
+    args.extend([
+        "-f".to_string(),
+        format.to_string(),
+        "-y".to_string(),
+        partial.to_str().expect("invalid output path").to_string(),
+    ]);
 
-    let output = Command::new("ffmpeg")
+    if cancel.is_cancelled() {
+        bail!("Cancelled before creating {filename}");
+    }
+    std::fs::create_dir_all(&cache_dir)?;
+    let mut child = Command::new("ffmpeg")
         .args(args)
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
         .spawn()
-        .context("Failed to start ffmpeg process")?
-        .wait_with_output()
-        .context("Failed to wait for ffmpeg process")?;
+        .context("Failed to start ffmpeg process")?;
+    // Read on its own thread, so ffmpeg never blocks on a full pipe while it is polled below.
+    let mut stderr = child.stderr.take().expect("ffmpeg stderr is piped");
+    let stderr = std::thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stderr.read_to_string(&mut output);
+        output
+    });
+    let mut cancelled = false;
+    while child.try_wait().context("Failed to wait for ffmpeg process")?.is_none() {
+        if cancel.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            cancelled = true;
+            break;
+        }
+        std::thread::sleep(FFMPEG_POLL_INTERVAL);
+    }
+    let stderr = stderr.join().unwrap_or_default();
+    let converted = !cancelled && partial.exists() && std::fs::rename(&partial, &cached).is_ok();
+    let _ = std::fs::remove_file(&partial);
 
-    if !cached.exists() {
+    if !converted {
         // If the conversion failed, remove the potentially empty cache directory.
         if cache_dir.read_dir().is_ok_and(|dir| dir.count() == 0) {
             let _ = std::fs::remove_dir_all(&cache_dir);
         }
-        let err = String::from_utf8_lossy(&output.stderr);
-        bail!("Failed to create {filename} file: {err}");
+        if cancelled {
+            bail!("Cancelled creating {filename}");
+        }
+        bail!("Failed to create {filename} file: {stderr}");
     }
 
     debug!(?cached, "created");
@@ -179,7 +303,7 @@ fn ffmpeg_extract(kind: Kind, input: &Path, input_hash: &str, cache_dir: &Path)
     Ok(cached)
 }
 
-fn file_hash(path: &Path) -> Result<String> {
+pub(super) fn file_hash(path: &Path) -> Result<String> {
     let content = std::fs::read(path)?;
     let mut hasher = Sha1::new();
     hasher.update(content);
@@ -190,7 +314,7 @@ fn file_hash(path: &Path) -> Result<String> {
     }))
 }
 
-fn string_hash(string: impl AsRef<str>) -> Result<String> {
+pub(super) fn string_hash(string: impl AsRef<str>) -> Result<String> {
     let mut hasher = Sha1::new();
     hasher.update(string.as_ref().bytes().collect::<Vec<_>>());
     let bytes = hasher.finalize();
@@ -200,14 +324,36 @@ fn string_hash(string: impl AsRef<str>) -> Result<String> {
     }))
 }
 
+/// A temporary file next to `path` to write it to, unique so concurrent writers of the same file do not clash. Cache
+/// entries holding one are not pruned.
+pub fn partial_path(path: &Path) -> PathBuf {
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+    path.with_extension(format!(
+        "{}-{}.partial",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Writes to a temporary file first, so an interrupted run never leaves a truncated file in the cache.
+pub(super) fn write_atomically(path: &Path, write: impl FnOnce(&mut BufWriter<File>) -> Result<()>) -> Result<()> {
+    let partial = partial_path(path);
+    let mut out = BufWriter::new(File::create(&partial)?);
+    write(&mut out)?;
+    out.flush()?;
+    drop(out);
+    std::fs::rename(&partial, path).wrap_err_with(|| format!("Failed to write {path:?}"))?;
+    debug!(?path, "created");
+    Ok(())
+}
+
 fn download_file(url: &Url, output: &Path) -> Result<()> {
     let response = reqwest::blocking::get(url.as_str())?;
     if !response.status().is_success() {
         bail!("Failed to download file from {url}, status code: {}", response.status());
     }
-    let mut file = std::fs::File::create(output)?;
-    std::io::copy(&mut response.bytes()?.as_ref(), &mut file)?;
-    Ok(())
+    let bytes = response.bytes()?;
+    write_atomically(output, |out| Ok(out.write_all(&bytes)?))
 }
 
 fn infer_filename_from_url(url: &Url) -> Option<PathBuf> {
@@ -1,26 +1,55 @@
+mod cache;
+mod conversion;
 mod custom_fake_media;
+mod noise_mix;
+mod pool;
+mod screenshare;
+mod test_pattern;
+mod wav;
 
+pub use cache::{
+    CacheEntry,
+    CacheEntryKind,
+    FakeMediaCache,
+    FakeMediaCacheConfig,
+};
+pub use conversion::FakeMediaConversion;
 pub use custom_fake_media::{
+    partial_path,
     FakeMediaFileOrUrl,
     FakeMediaFiles,
 };
+pub use noise_mix::NoiseMix;
+pub use pool::{
+    FakeMediaAssignment,
+    FakeMediaAssignmentIter,
+    FakeMediaPool,
+};
+pub use screenshare::ScreenshareSource;
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub enum FakeMedia {
     #[default]
     None,
     Builtin,
+    /// Generated per participant, see [`FakeMediaFiles::test_pattern`].
+    TestPattern,
+    /// Speech mixed with background noise, see [`FakeMediaFiles::noise_mix`].
+    NoiseMix(NoiseMix),
     FileOrUrl(String),
 }
 
 const NONE: &str = "<none>";
 const BUILTIN: &str = "<builtin>";
+const TEST_PATTERN: &str = "<test-pattern>";
 
 impl std::fmt::Display for FakeMedia {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FakeMedia::None => write!(f, "{NONE}"),
             FakeMedia::Builtin => write!(f, "{BUILTIN}"),
+            FakeMedia::TestPattern => write!(f, "{TEST_PATTERN}"),
+            FakeMedia::NoiseMix(mix) => write!(f, "{mix}"),
             FakeMedia::FileOrUrl(file_or_url) => write!(f, "{file_or_url}"),
         }
     }
@@ -31,7 +60,11 @@ impl<T: AsRef<str>> From<T> for FakeMedia {
         match arg.as_ref() {
             NONE => FakeMedia::None,
             BUILTIN => FakeMedia::Builtin,
-            arg => FakeMedia::FileOrUrl(arg.to_string()),
+            TEST_PATTERN => FakeMedia::TestPattern,
+            arg => match arg.parse() {
+                Ok(mix) => FakeMedia::NoiseMix(mix),
+                Err(_) => FakeMedia::FileOrUrl(arg.to_string()),
+            },
         }
     }
 }
@@ -44,6 +77,8 @@ impl serde::Serialize for FakeMedia {
         match self {
             FakeMedia::None => serializer.serialize_str(NONE),
             FakeMedia::Builtin => serializer.serialize_str(BUILTIN),
+            FakeMedia::TestPattern => serializer.serialize_str(TEST_PATTERN),
+            FakeMedia::NoiseMix(mix) => serializer.collect_str(mix),
             FakeMedia::FileOrUrl(file_or_url) => serializer.serialize_str(file_or_url),
         }
     }
@@ -63,6 +98,9 @@ pub struct FakeMediaWithDescription {
     fake_media: FakeMedia,
     #[serde(skip_serializing_if = "Option::is_none")]
     description: Option<String>,
+    /// Applies to files, URLs and the speech of noise mixes.
+    #[serde(default, skip_serializing_if = "FakeMediaConversion::is_default")]
+    conversion: FakeMediaConversion,
 }
 
 impl FakeMediaWithDescription {
@@ -70,13 +108,23 @@ impl FakeMediaWithDescription {
         Self {
             fake_media,
             description,
+            conversion: FakeMediaConversion::default(),
         }
     }
 
+    pub fn with_conversion(mut self, conversion: FakeMediaConversion) -> Self {
+        self.conversion = conversion;
+        self
+    }
+
     pub fn fake_media(&self) -> &FakeMedia {
         &self.fake_media
     }
 
+    pub fn conversion(&self) -> &FakeMediaConversion {
+        &self.conversion
+    }
+
     pub fn description(&self) -> &str {
         if let Some(description) = self.description.as_ref() {
             return description.as_str();
@@ -85,7 +133,23 @@ impl FakeMediaWithDescription {
         match self.fake_media {
             FakeMedia::None => NONE,
             FakeMedia::Builtin => BUILTIN,
-            FakeMedia::FileOrUrl(_) => "",
+            FakeMedia::TestPattern => TEST_PATTERN,
+            FakeMedia::NoiseMix(_) | FakeMedia::FileOrUrl(_) => "",
         }
     }
+
+    /// Whether `name` is the description or the file/URL of this source.
+    pub fn matches(&self, name: &str) -> bool {
+        self.description.as_deref() == Some(name) || self.fake_media.to_string() == name
+    }
+}
+
+/// The descriptions of `sources` for errors about names that match none of them.
+pub(crate) fn list_descriptions(sources: &[FakeMediaWithDescription]) -> String {
+    sources
+        .iter()
+        .map(FakeMediaWithDescription::description)
+        .filter(|description| !description.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
@@ -0,0 +1,361 @@
+//! Mixes clean speech with background noise at a given signal-to-noise ratio, to evaluate noise suppression.
+
+use super::{
+    cache::{
+        mark_used,
+        record_origin,
+        MEDIA_CACHE,
+        NOISE_MIX_CACHE,
+    },
+    custom_fake_media::{
+        file_hash,
+        string_hash,
+        write_atomically,
+        FakeMediaFiles,
+    },
+    wav::{
+        read_wav,
+        write_wav,
+        MonoAudio,
+    },
+    FakeMediaConversion,
+};
+use eyre::{
+    bail,
+    eyre,
+    Context as _,
+    Result,
+};
+use std::{
+    fmt,
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+use tokio_util::sync::CancellationToken;
+
+/// Bump when the mixing changes so cached mixes are regenerated.
+const VERSION: u32 = 1;
+
+/// RMS level the speech is normalised to before the noise is added.
+const SPEECH_DBFS: f64 = -23.0;
+/// Speech quieter than this, relative to its peak, counts as silence when trimming.
+const SILENCE_DB: f64 = -40.0;
+/// The mix is scaled down when it would peak above this.
+const PEAK_LIMIT: f32 = 0.99;
+
+/// Clean speech and noise, both files or URLs, mixed at `snr_db`.
+///
+/// Written as `<speech> + <noise> @ <snr>dB`, optionally followed by `, <seconds>s` to trim the mix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseMix {
+    pub speech: String,
+    pub noise: String,
+    /// Loudness of the speech over the noise. Lower is harder for noise suppression.
+    pub snr_db: f64,
+    /// Cuts the mix after this duration. Silence at the start and end of the speech is always trimmed.
+    pub max_duration: Option<Duration>,
+}
+
+impl fmt::Display for NoiseMix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} + {} @ {}dB", self.speech, self.noise, self.snr_db)?;
+        if let Some(duration) = self.max_duration {
+            write!(f, ", {}s", duration.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NoiseMix {
+    type Err = eyre::Report;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let (sources, settings) = input
+            .rsplit_once(" @ ")
+            .ok_or_else(|| eyre!("Noise mix `{input}` has no ` @ <snr>dB`"))?;
+        let (speech, noise) = sources
+            .split_once(" + ")
+            .ok_or_else(|| eyre!("Noise mix `{input}` has no `<speech> + <noise>`"))?;
+        let (snr, duration) = match settings.split_once(',') {
+            Some((snr, duration)) => (snr, Some(duration.trim())),
+            None => (settings, None),
+        };
+        let snr_db = snr
+            .trim()
+            .strip_suffix("dB")
+            .and_then(|snr| snr.trim().parse().ok())
+            .ok_or_else(|| eyre!("Invalid SNR `{snr}` in noise mix, expected e.g. `10dB`"))?;
+        let max_duration = duration
+            .map(|duration| {
+                duration
+                    .strip_suffix('s')
+                    .and_then(|seconds| seconds.trim().parse().ok())
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                    .ok_or_else(|| eyre!("Invalid duration `{duration}` in noise mix, expected e.g. `30s`"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            speech: speech.trim().to_string(),
+            noise: noise.trim().to_string(),
+            snr_db,
+            max_duration,
+        })
+    }
+}
+
+impl FakeMediaFiles {
+    /// Mixes the audio of `mix.speech` with `mix.noise`, or reuses the mix created before from the same content. The
+    /// video of the speech source, if any, is kept. `conversion` applies to the speech, the noise is looped as is.
+    pub fn noise_mix(
+        mix: &NoiseMix,
+        conversion: &FakeMediaConversion,
+        cache_dir: impl AsRef<Path>,
+        cancel: &CancellationToken,
+    ) -> Result<Self> {
+        let cache_dir = cache_dir.as_ref();
+        let speech = Self::from_file_or_url(mix.speech.parse()?, conversion, cache_dir, cancel)?;
+        let noise = Self::from_file_or_url(mix.noise.parse()?, &FakeMediaConversion::default(), cache_dir, cancel)?;
+        let speech_audio = audio_path(&speech, &mix.speech)?;
+        let noise_audio = audio_path(&noise, &mix.noise)?;
+
+        let key = format!(
+            "{VERSION}:{}:{}:{}:{:?}",
+            file_hash(speech_audio)?,
+            file_hash(noise_audio)?,
+            mix.snr_db,
+            mix.max_duration
+        );
+        let cache_dir = cache_dir
+            .join(MEDIA_CACHE)
+            .join(NOISE_MIX_CACHE)
+            .join(string_hash(key)?);
+        let audio = cache_dir.join("audio.wav");
+        if audio.exists() {
+            mark_used(&audio);
+        } else {
+            let speech =
+                read_wav(&std::fs::read(speech_audio)?).wrap_err_with(|| format!("Reading {speech_audio:?}"))?;
+            let noise = read_wav(&std::fs::read(noise_audio)?).wrap_err_with(|| format!("Reading {noise_audio:?}"))?;
+            let mixed = mix_audio(speech, noise, mix.snr_db, mix.max_duration)?;
+            std::fs::create_dir_all(&cache_dir)?;
+            write_atomically(&audio, |out| write_wav(out, &mixed))?;
+        }
+        record_origin(&cache_dir, &mix.to_string());
+
+        Ok(Self {
+            audio: Some(audio),
+            audio_error: None,
+            video: speech.video,
+            video_error: speech.video_error,
+        })
+    }
+}
+
+fn audio_path<'a>(files: &'a FakeMediaFiles, source: &str) -> Result<&'a Path> {
+    files.audio.as_deref().ok_or_else(|| {
+        eyre!(
+            "No audio in `{source}`: {}",
+            files.audio_error.as_deref().unwrap_or("unknown error")
+        )
+    })
+}
+
+fn mix_audio(speech: MonoAudio, noise: MonoAudio, snr_db: f64, max_duration: Option<Duration>) -> Result<MonoAudio> {
+    let sample_rate = speech.sample_rate;
+    let mut speech = trim_silence(speech.samples, sample_rate);
+    if let Some(duration) = max_duration {
+        speech.truncate((duration.as_secs_f64() * sample_rate as f64) as usize);
+    }
+    let noise = resample(&noise, sample_rate);
+    if speech.is_empty() || noise.is_empty() {
+        bail!("Speech or noise source is empty");
+    }
+
+    let (speech, noise) = scale_to_snr(&speech, &noise, snr_db)?;
+    let mut samples = speech
+        .iter()
+        .zip(noise)
+        .map(|(speech, noise)| speech + noise)
+        .collect::<Vec<_>>();
+
+    // Scaling both together keeps the SNR, only the loudness drops.
+    let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    if peak > PEAK_LIMIT {
+        debug!(
+            "Scaling noise mix down by {:.1}dB to avoid clipping",
+            20.0 * (PEAK_LIMIT / peak).log10()
+        );
+        samples.iter_mut().for_each(|sample| *sample *= PEAK_LIMIT / peak);
+    }
+
+    Ok(MonoAudio { sample_rate, samples })
+}
+
+/// Normalises the speech to [`SPEECH_DBFS`] and loops the noise to the length of the speech at `snr_db` below it.
+fn scale_to_snr(speech: &[f32], noise: &[f32], snr_db: f64) -> Result<(Vec<f32>, Vec<f32>)> {
+    let speech_rms = rms(speech);
+    let noise_rms = rms(noise);
+    if speech_rms == 0.0 || noise_rms == 0.0 {
+        bail!("Speech or noise source is silent");
+    }
+
+    let speech_gain = db_to_gain(SPEECH_DBFS) / speech_rms;
+    let noise_gain = db_to_gain(SPEECH_DBFS - snr_db) / noise_rms;
+    Ok((
+        speech.iter().map(|sample| sample * speech_gain).collect(),
+        noise
+            .iter()
+            .cycle()
+            .take(speech.len())
+            .map(|sample| sample * noise_gain)
+            .collect(),
+    ))
+}
+
+/// Drops the quiet start and end of `samples`, judged in 10ms windows.
+fn trim_silence(samples: Vec<f32>, sample_rate: u32) -> Vec<f32> {
+    let window = (sample_rate as usize / 100).max(1);
+    let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    let threshold = peak * db_to_gain(SILENCE_DB);
+    let loud = |window: &[f32]| window.iter().any(|sample| sample.abs() > threshold);
+    let windows = samples.chunks(window).collect::<Vec<_>>();
+    let (Some(first), Some(last)) = (
+        windows.iter().position(|w| loud(w)),
+        windows.iter().rposition(|w| loud(w)),
+    ) else {
+        return samples;
+    };
+
+    let end = ((last + 1) * window).min(samples.len());
+    samples[first * window..end].to_vec()
+}
+
+/// Linear interpolation is plenty for background noise.
+fn resample(audio: &MonoAudio, sample_rate: u32) -> Vec<f32> {
+    if audio.sample_rate == sample_rate || audio.samples.is_empty() {
+        return audio.samples.clone();
+    }
+
+    let ratio = audio.sample_rate as f64 / sample_rate as f64;
+    let len = (audio.samples.len() as f64 / ratio) as usize;
+    (0..len)
+        .map(|index| {
+            let position = index as f64 * ratio;
+            let before = position as usize;
+            let after = (before + 1).min(audio.samples.len() - 1);
+            let fraction = (position - before as f64) as f32;
+            audio.samples[before] * (1.0 - fraction) + audio.samples[after] * fraction
+        })
+        .collect()
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+fn db_to_gain(db: f64) -> f32 {
+    10f64.powf(db / 20.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len).map(|index| (index as f32 * 0.05).sin() * amplitude).collect()
+    }
+
+    #[test]
+    fn parses_and_formats_mixes() {
+        let mix: NoiseMix = "speech.wav + https://example.com/cafe.mp3 @ -5dB, 30s".parse().unwrap();
+
+        assert_eq!(mix.speech, "speech.wav");
+        assert_eq!(mix.noise, "https://example.com/cafe.mp3");
+        assert_eq!(mix.snr_db, -5.0);
+        assert_eq!(mix.max_duration, Some(Duration::from_secs(30)));
+        assert_eq!(mix.to_string(), "speech.wav + https://example.com/cafe.mp3 @ -5dB, 30s");
+        assert!("speech.wav + noise.wav".parse::<NoiseMix>().is_err());
+        assert!("speech.wav @ 10dB".parse::<NoiseMix>().is_err());
+    }
+
+    #[test]
+    fn noise_ends_up_at_the_requested_snr_below_normalised_speech() {
+        let speech = tone(4_800, 0.8);
+        let noise = tone(1_000, 0.01);
+
+        let (speech, noise) = scale_to_snr(&speech, &noise, 10.0).unwrap();
+
+        assert_eq!(noise.len(), speech.len());
+        assert!((20.0 * rms(&speech).log10() as f64 - SPEECH_DBFS).abs() < 0.01);
+        let snr = 20.0 * (rms(&speech) / rms(&noise)).log10();
+        assert!((snr - 10.0).abs() < 0.1, "snr was {snr}");
+    }
+
+    #[test]
+    fn mixes_trims_silence_and_avoids_clipping() {
+        let mut speech = vec![0.0; 4_800];
+        speech.extend(tone(9_600, 0.5));
+        speech.extend(vec![0.0; 4_800]);
+        let speech = MonoAudio {
+            sample_rate: 48_000,
+            samples: speech,
+        };
+        let noise = MonoAudio {
+            sample_rate: 16_000,
+            samples: tone(1_600, 0.5),
+        };
+
+        let mixed = mix_audio(speech, noise, -20.0, None).unwrap();
+
+        assert_eq!(mixed.sample_rate, 48_000);
+        assert_eq!(mixed.samples.len(), 9_600);
+        assert!(mixed.samples.iter().all(|sample| sample.abs() <= PEAK_LIMIT));
+    }
+
+    #[test]
+    fn builds_and_caches_mixes_of_wav_files() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let write = |name: &str, samples: Vec<f32>| {
+            let path = dir.path().join(name);
+            let mut out = Vec::new();
+            write_wav(
+                &mut out,
+                &MonoAudio {
+                    sample_rate: 16_000,
+                    samples,
+                },
+            )
+            .unwrap();
+            std::fs::write(&path, out).unwrap();
+            path.display().to_string()
+        };
+        let mix = NoiseMix {
+            speech: write("speech.wav", tone(16_000, 0.5)),
+            noise: write("noise.wav", tone(3_000, 0.2)),
+            snr_db: 5.0,
+            max_duration: Some(Duration::from_millis(500)),
+        };
+
+        let media = FakeMediaFiles::noise_mix(
+            &mix,
+            &FakeMediaConversion::default(),
+            dir.path().join("cache"),
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        let audio = media.audio.expect("mixed audio");
+        let mixed = read_wav(&std::fs::read(&audio).unwrap()).unwrap();
+        assert_eq!(mixed.samples.len(), 8_000);
+
+        let again = FakeMediaFiles::noise_mix(
+            &mix,
+            &FakeMediaConversion::default(),
+            dir.path().join("cache"),
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(again.audio, Some(audio));
+    }
+}
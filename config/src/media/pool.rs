@@ -0,0 +1,174 @@
+use super::{
+    list_descriptions,
+    FakeMedia,
+    FakeMediaWithDescription,
+};
+use color_eyre::Result;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use strum::{
+    Display,
+    EnumIter,
+    EnumString,
+};
+
+/// Spreads `fake_media_sources` over the participants spawned from one config, so they publish distinguishable
+/// voices and videos.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct FakeMediaPool {
+    pub assignment: FakeMediaAssignment,
+    /// Descriptions or files/URLs of the `fake_media_sources` to pick from. Empty picks from every source except
+    /// `<none>`.
+    pub sources: Vec<String>,
+}
+
+impl FakeMediaPool {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Indices of the `sources` that belong to the pool, in the order the pool names them. Fails for names that match
+    /// none of the `sources`.
+    pub fn candidates(&self, sources: &[FakeMediaWithDescription]) -> Result<Vec<usize>> {
+        if self.sources.is_empty() {
+            return Ok(sources
+                .iter()
+                .enumerate()
+                .filter(|(_, source)| source.fake_media() != &FakeMedia::None)
+                .map(|(index, _)| index)
+                .collect());
+        }
+
+        let mut candidates = Vec::new();
+        for name in &self.sources {
+            let matching = sources
+                .iter()
+                .enumerate()
+                .filter(|(_, source)| source.matches(name))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            if matching.is_empty() {
+                eyre::bail!(
+                    "Fake media pool names unknown source `{name}`, expected one of: {}",
+                    list_descriptions(sources)
+                );
+            }
+            for index in matching {
+                if !candidates.contains(&index) {
+                    candidates.push(index);
+                }
+            }
+        }
+        Ok(candidates)
+    }
+
+    /// Index into `sources` for the `spawn_index`-th participant spawned from the pool. `random` is only used by
+    /// random assignment. `None` keeps the selected source.
+    pub fn pick(&self, sources: &[FakeMediaWithDescription], spawn_index: usize, random: u64) -> Result<Option<usize>> {
+        let candidates = self.candidates(sources)?;
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(match self.assignment {
+            FakeMediaAssignment::Selected => None,
+            FakeMediaAssignment::RoundRobin => Some(candidates[spawn_index % candidates.len()]),
+            FakeMediaAssignment::Random => Some(candidates[(random % candidates.len() as u64) as usize]),
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Display, EnumIter, EnumString, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum FakeMediaAssignment {
+    /// Every participant uses `fake_media_selected`.
+    #[default]
+    Selected,
+    RoundRobin,
+    Random,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        FakeMediaAssignment,
+        FakeMediaPool,
+    };
+    use crate::media::{
+        FakeMedia,
+        FakeMediaWithDescription,
+    };
+
+    fn sources() -> Vec<FakeMediaWithDescription> {
+        vec![
+            FakeMediaWithDescription::new(FakeMedia::None, None),
+            FakeMediaWithDescription::new(FakeMedia::Builtin, None),
+            FakeMediaWithDescription::new(FakeMedia::from("alice.y4m"), Some("Alice".to_string())),
+            FakeMediaWithDescription::new(FakeMedia::from("bob.y4m"), None),
+        ]
+    }
+
+    #[test]
+    fn round_robin_cycles_through_every_source_but_none() {
+        let pool = FakeMediaPool {
+            assignment: FakeMediaAssignment::RoundRobin,
+            sources: Vec::new(),
+        };
+
+        let picks = (0..4)
+            .map(|index| pool.pick(&sources(), index, 0).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(picks, [Some(1), Some(2), Some(3), Some(1)]);
+    }
+
+    #[test]
+    fn named_pool_matches_descriptions_and_files() {
+        let pool = FakeMediaPool {
+            assignment: FakeMediaAssignment::Random,
+            sources: vec!["Alice".to_string(), "bob.y4m".to_string()],
+        };
+
+        assert_eq!(pool.candidates(&sources()).unwrap(), [2, 3]);
+        assert_eq!(pool.pick(&sources(), 0, 5).unwrap(), Some(3));
+        assert_eq!(FakeMediaPool::default().pick(&sources(), 0, 5).unwrap(), None);
+    }
+
+    #[test]
+    fn unknown_pool_names_fail_with_the_sources() {
+        let pool = FakeMediaPool {
+            assignment: FakeMediaAssignment::RoundRobin,
+            sources: vec!["Alice".to_string(), "unknown".to_string()],
+        };
+
+        let error = pool.pick(&sources(), 0, 0).unwrap_err().to_string();
+
+        assert!(error.contains("unknown source `unknown`"), "{error}");
+        assert!(error.contains("<none>, <builtin>, Alice"), "{error}");
+    }
+
+    #[test]
+    fn assignments_are_kebab_case() {
+        assert_eq!(FakeMediaAssignment::RoundRobin.to_string(), "round-robin");
+        assert_eq!(
+            "round-robin".parse::<FakeMediaAssignment>().unwrap(),
+            FakeMediaAssignment::RoundRobin
+        );
+    }
+
+    #[test]
+    fn round_robin_follows_the_order_of_the_pool() {
+        let pool = FakeMediaPool {
+            assignment: FakeMediaAssignment::RoundRobin,
+            sources: vec!["bob.y4m".to_string(), "Alice".to_string(), "alice.y4m".to_string()],
+        };
+
+        let picks = (0..3)
+            .map(|index| pool.pick(&sources(), index, 0).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(picks, [Some(3), Some(2), Some(3)]);
+    }
+}
@@ -0,0 +1,111 @@
+//! What participants share when they start a screenshare.
+
+use super::{
+    FakeMediaFileOrUrl,
+    BUILTIN,
+    TEST_PATTERN,
+};
+use eyre::{
+    Context as _,
+    Result,
+};
+use url::Url;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum ScreenshareSource {
+    /// Whatever Chrome's fake capture picks.
+    #[default]
+    Builtin,
+    /// A test pattern with the participant's name, drawn by the page.
+    TestPattern,
+    /// A web page, video URL or local video file opened in a dedicated tab, which is then shared.
+    Tab(String),
+}
+
+impl ScreenshareSource {
+    pub fn is_default(&self) -> bool {
+        self == &Self::Builtin
+    }
+
+    /// The URL the dedicated tab opens, files as `file://` URLs. `None` for sources without a tab.
+    pub fn tab_url(&self) -> Result<Option<Url>> {
+        let Self::Tab(source) = self else {
+            return Ok(None);
+        };
+        let url = match source.parse::<FakeMediaFileOrUrl>()? {
+            FakeMediaFileOrUrl::Url(url) => url,
+            FakeMediaFileOrUrl::File(path) => {
+                let path = path
+                    .canonicalize()
+                    .wrap_err_with(|| format!("Failed to resolve screenshare file {path:?}"))?;
+                Url::from_file_path(&path).map_err(|_| eyre::eyre!("Invalid screenshare file {path:?}"))?
+            }
+        };
+        Ok(Some(url))
+    }
+}
+
+impl std::fmt::Display for ScreenshareSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScreenshareSource::Builtin => write!(f, "{BUILTIN}"),
+            ScreenshareSource::TestPattern => write!(f, "{TEST_PATTERN}"),
+            ScreenshareSource::Tab(source) => write!(f, "{source}"),
+        }
+    }
+}
+
+impl<T: AsRef<str>> From<T> for ScreenshareSource {
+    fn from(arg: T) -> Self {
+        match arg.as_ref().trim() {
+            "" | BUILTIN => ScreenshareSource::Builtin,
+            TEST_PATTERN => ScreenshareSource::TestPattern,
+            source => ScreenshareSource::Tab(source.to_string()),
+        }
+    }
+}
+
+impl serde::Serialize for ScreenshareSource {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ScreenshareSource {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(String::deserialize(deserializer)?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_builtin_pattern_and_tab_sources() {
+        assert_eq!(ScreenshareSource::from("<builtin>"), ScreenshareSource::Builtin);
+        assert_eq!(ScreenshareSource::from(""), ScreenshareSource::Builtin);
+        assert_eq!(
+            ScreenshareSource::from("<test-pattern>"),
+            ScreenshareSource::TestPattern
+        );
+
+        let page = ScreenshareSource::from("https://en.wikipedia.org/wiki/Special:Random");
+        assert_eq!(
+            page.tab_url().unwrap().unwrap().as_str(),
+            "https://en.wikipedia.org/wiki/Special:Random"
+        );
+        assert_eq!(page.to_string(), "https://en.wikipedia.org/wiki/Special:Random");
+
+        let file = ScreenshareSource::from(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
+        assert_eq!(file.tab_url().unwrap().unwrap().scheme(), "file");
+        assert!(ScreenshareSource::from("/does/not/exist.mp4").tab_url().is_err());
+        assert_eq!(ScreenshareSource::TestPattern.tab_url().unwrap(), None);
+    }
+}
@@ -0,0 +1,323 @@
+//! Generates identifiable Y4M/WAV fake capture files without ffmpeg.
+//!
+//! The video shows colour bars, the participant's name, the frame counter and the position in the loop. At the
+//! start of every second a marker square lights up while the audio plays a beep, so both streams can be matched.
+
+use super::{
+    cache::{
+        mark_used,
+        record_origin,
+        MEDIA_CACHE,
+        TEST_PATTERN_CACHE,
+    },
+    custom_fake_media::{
+        string_hash,
+        write_atomically,
+        FakeMediaFiles,
+    },
+    wav::write_wav_header,
+};
+use eyre::Result;
+use std::{
+    io::Write,
+    path::Path,
+};
+
+/// Bump when the generated output changes so cached files are regenerated.
+const VERSION: u32 = 1;
+
+const TEST_PATTERN_WIDTH: usize = 640;
+const TEST_PATTERN_HEIGHT: usize = 360;
+const TEST_PATTERN_FPS: usize = 30;
+/// Length of the loop Chrome plays over and over.
+const TEST_PATTERN_SECONDS: usize = 4;
+const TEST_PATTERN_SAMPLE_RATE: u32 = 48_000;
+/// How long the sync marker is shown and the beep is played at the start of every second.
+const TEST_PATTERN_MARKER_MS: usize = 100;
+
+const TEXT_SCALE: usize = 3;
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+const BARS_HEIGHT: usize = TEST_PATTERN_HEIGHT * 2 / 3;
+const MARKER_SIZE: usize = 60;
+
+impl FakeMediaFiles {
+    /// Generates a test pattern for the participant `name`, or reuses the one generated before.
+    pub fn test_pattern(name: &str, cache_dir: impl AsRef<Path>) -> Result<Self> {
+        let cache_dir = cache_dir
+            .as_ref()
+            .join(MEDIA_CACHE)
+            .join(TEST_PATTERN_CACHE)
+            .join(string_hash(format!("{VERSION}:{name}"))?);
+        std::fs::create_dir_all(&cache_dir)?;
+
+        let video = cache_dir.join("video.y4m");
+        if video.exists() {
+            mark_used(&video);
+        } else {
+            write_atomically(&video, |out| write_video(name, out))?;
+        }
+        let audio = cache_dir.join("audio.wav");
+        if !audio.exists() {
+            write_atomically(&audio, |out| write_audio(name, out))?;
+        }
+        record_origin(&cache_dir, &format!("test pattern for {name}"));
+
+        Ok(Self {
+            audio: Some(audio),
+            audio_error: None,
+            video: Some(video),
+            video_error: None,
+        })
+    }
+}
+
+/// Whether `frame` falls into the sync marker at the start of a second.
+fn is_marker_frame(frame: usize) -> bool {
+    (frame % TEST_PATTERN_FPS) * 1_000 < TEST_PATTERN_MARKER_MS * TEST_PATTERN_FPS
+}
+
+fn write_video(name: &str, out: &mut impl Write) -> Result<()> {
+    writeln!(
+        out,
+        "YUV4MPEG2 W{TEST_PATTERN_WIDTH} H{TEST_PATTERN_HEIGHT} F{TEST_PATTERN_FPS}:1 Ip A1:1 C420jpeg"
+    )?;
+    let frames = TEST_PATTERN_FPS * TEST_PATTERN_SECONDS;
+    let mut frame = Frame::new();
+    for index in 0..frames {
+        frame.render(name, index);
+        out.write_all(b"FRAME\n")?;
+        out.write_all(&frame.y)?;
+        out.write_all(&frame.u)?;
+        out.write_all(&frame.v)?;
+    }
+    Ok(())
+}
+
+/// Beeps at the start of every second with a frequency derived from `name`, so participants sound different.
+fn write_audio(name: &str, out: &mut impl Write) -> Result<()> {
+    let samples = TEST_PATTERN_SAMPLE_RATE as usize * TEST_PATTERN_SECONDS;
+    write_wav_header(out, TEST_PATTERN_SAMPLE_RATE, samples)?;
+
+    let frequency = beep_frequency(name);
+    let beep_samples = TEST_PATTERN_SAMPLE_RATE as usize * TEST_PATTERN_MARKER_MS / 1_000;
+    for sample in 0..samples {
+        let in_second = sample % TEST_PATTERN_SAMPLE_RATE as usize;
+        let value = if in_second < beep_samples {
+            let t = sample as f64 / TEST_PATTERN_SAMPLE_RATE as f64;
+            (t * frequency * std::f64::consts::TAU).sin() * 0.5 * i16::MAX as f64
+        } else {
+            0.0
+        };
+        out.write_all(&(value as i16).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Between 440 and 1040 Hz in steps of 40 Hz.
+fn beep_frequency(name: &str) -> f64 {
+    let hash = name
+        .bytes()
+        .fold(0u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as u32));
+    440.0 + (hash % 16) as f64 * 40.0
+}
+
+struct Frame {
+    y: Vec<u8>,
+    u: Vec<u8>,
+    v: Vec<u8>,
+}
+
+/// BT.601 studio range colours.
+#[derive(Clone, Copy)]
+struct Yuv(u8, u8, u8);
+
+const WHITE: Yuv = Yuv(235, 128, 128);
+const BLACK: Yuv = Yuv(16, 128, 128);
+const GRAY: Yuv = Yuv(64, 128, 128);
+const BARS: [Yuv; 7] = [
+    WHITE,
+    Yuv(210, 16, 146),  // yellow
+    Yuv(170, 166, 16),  // cyan
+    Yuv(145, 54, 34),   // green
+    Yuv(106, 202, 222), // magenta
+    Yuv(81, 90, 240),   // red
+    Yuv(41, 240, 110),  // blue
+];
+
+impl Frame {
+    fn new() -> Self {
+        let chroma = (TEST_PATTERN_WIDTH / 2) * (TEST_PATTERN_HEIGHT / 2);
+        Self {
+            y: vec![0; TEST_PATTERN_WIDTH * TEST_PATTERN_HEIGHT],
+            u: vec![0; chroma],
+            v: vec![0; chroma],
+        }
+    }
+
+    fn render(&mut self, name: &str, index: usize) {
+        let bar_width = TEST_PATTERN_WIDTH.div_ceil(BARS.len());
+        for (bar, colour) in BARS.iter().enumerate() {
+            self.fill(bar * bar_width, 0, bar_width, BARS_HEIGHT, *colour);
+        }
+        self.fill(
+            0,
+            BARS_HEIGHT,
+            TEST_PATTERN_WIDTH,
+            TEST_PATTERN_HEIGHT - BARS_HEIGHT,
+            BLACK,
+        );
+
+        let line_height = (GLYPH_HEIGHT + 3) * TEXT_SCALE;
+        let max_chars = (TEST_PATTERN_WIDTH - MARKER_SIZE - 30) / ((GLYPH_WIDTH + 1) * TEXT_SCALE);
+        let name = name.chars().take(max_chars).collect::<String>();
+        self.text(10, BARS_HEIGHT + 10, &name);
+
+        let millis = index * 1_000 / TEST_PATTERN_FPS;
+        let counter = format!(
+            "#{index:04} {:02}:{:02}.{:03}",
+            millis / 60_000,
+            millis / 1_000 % 60,
+            millis % 1_000
+        );
+        self.text(10, BARS_HEIGHT + 10 + line_height, &counter);
+
+        // Moves across the frame once per loop, a frozen stream is easy to spot.
+        let frames = TEST_PATTERN_FPS * TEST_PATTERN_SECONDS;
+        let slider_x = index * (TEST_PATTERN_WIDTH - 20) / frames;
+        self.fill(slider_x, TEST_PATTERN_HEIGHT - 20, 20, 10, WHITE);
+
+        let marker = if is_marker_frame(index) { WHITE } else { GRAY };
+        self.fill(
+            TEST_PATTERN_WIDTH - MARKER_SIZE - 10,
+            BARS_HEIGHT + 10,
+            MARKER_SIZE,
+            MARKER_SIZE,
+            marker,
+        );
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, colour: Yuv) {
+        let right = (x + width).min(TEST_PATTERN_WIDTH);
+        let bottom = (y + height).min(TEST_PATTERN_HEIGHT);
+        for row in y..bottom {
+            self.y[row * TEST_PATTERN_WIDTH + x..row * TEST_PATTERN_WIDTH + right].fill(colour.0);
+        }
+        let chroma_width = TEST_PATTERN_WIDTH / 2;
+        for row in y / 2..bottom.div_ceil(2) {
+            let range = row * chroma_width + x / 2..row * chroma_width + right.div_ceil(2);
+            self.u[range.clone()].fill(colour.1);
+            self.v[range].fill(colour.2);
+        }
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str) {
+        for (position, character) in text.chars().enumerate() {
+            let glyph_x = x + position * (GLYPH_WIDTH + 1) * TEXT_SCALE;
+            for (row, bits) in glyph(character).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.fill(
+                            glyph_x + column * TEXT_SCALE,
+                            y + row * TEXT_SCALE,
+                            TEXT_SCALE,
+                            TEXT_SCALE,
+                            WHITE,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 5x7 bitmap, one byte per row with the leftmost pixel in bit 4. Lowercase letters are drawn as uppercase and
+/// unknown characters as `?`.
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    match character.to_ascii_uppercase() {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '0' => [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+        '1' => [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        'A' => [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'B' => [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+        'C' => [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+        'D' => [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c],
+        'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+        'F' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+        'G' => [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+        'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'I' => [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+        'M' => [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'P' => [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+        'Q' => [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+        'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+        'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+        'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+        'X' => [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04],
+        'Z' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+        '-' => [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c],
+        ':' => [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00],
+        '#' => [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a],
+        '@' => [0x0e, 0x11, 0x17, 0x15, 0x17, 0x10, 0x0e],
+        _ => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_y4m_and_wav_files_and_reuses_them() {
+        let cache_dir = temp_dir::TempDir::new().unwrap();
+
+        let media = FakeMediaFiles::test_pattern("local-ada", cache_dir.path()).unwrap();
+        let video = std::fs::read(media.video.as_ref().unwrap()).unwrap();
+        let audio = std::fs::read(media.audio.as_ref().unwrap()).unwrap();
+
+        let header = b"YUV4MPEG2 W640 H360 F30:1 Ip A1:1 C420jpeg\n";
+        let frame_len = b"FRAME\n".len() + TEST_PATTERN_WIDTH * TEST_PATTERN_HEIGHT * 3 / 2;
+        assert!(video.starts_with(header));
+        assert_eq!(
+            video.len(),
+            header.len() + frame_len * TEST_PATTERN_FPS * TEST_PATTERN_SECONDS
+        );
+        assert_eq!(&audio[..4], b"RIFF");
+        assert_eq!(
+            audio.len(),
+            44 + TEST_PATTERN_SAMPLE_RATE as usize * TEST_PATTERN_SECONDS * 2
+        );
+
+        let again = FakeMediaFiles::test_pattern("local-ada", cache_dir.path()).unwrap();
+        assert_eq!(again.video, media.video);
+        let other = FakeMediaFiles::test_pattern("local-bob", cache_dir.path()).unwrap();
+        assert_ne!(other.video, media.video);
+    }
+
+    #[test]
+    fn sync_marker_covers_the_start_of_every_second() {
+        let marked = (0..TEST_PATTERN_FPS * 2)
+            .filter(|frame| is_marker_frame(*frame))
+            .collect::<Vec<_>>();
+        assert_eq!(marked, [0, 1, 2, 30, 31, 32]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Minimal WAV reading and writing for the media Chrome plays as fake audio capture.
+
+use eyre::{
+    bail,
+    eyre,
+    Result,
+};
+use std::io::Write;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+/// Mono audio with samples between `-1` and `1`.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct MonoAudio {
+    pub(super) sample_rate: u32,
+    pub(super) samples: Vec<f32>,
+}
+
+/// Reads 16/24/32 bit PCM or 32 bit float WAV data and mixes all channels down to mono.
+pub(super) fn read_wav(bytes: &[u8]) -> Result<MonoAudio> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        bail!("Not a WAV file");
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let len = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into()?) as usize;
+        let body = &bytes[offset + 8..(offset + 8 + len).min(bytes.len())];
+        match id {
+            b"fmt " if body.len() >= 16 => format = Some(body),
+            b"data" => data = Some(body),
+            _ => {}
+        }
+        // Chunks are padded to an even length.
+        offset += 8 + len + len % 2;
+    }
+    let format = format.ok_or_else(|| eyre!("WAV file has no format chunk"))?;
+    let data = data.ok_or_else(|| eyre!("WAV file has no data chunk"))?;
+
+    let read_u16 = |at: usize| u16::from_le_bytes([format[at], format[at + 1]]);
+    let mut tag = read_u16(0);
+    let channels = read_u16(2) as usize;
+    let sample_rate = u32::from_le_bytes(format[4..8].try_into()?);
+    let bits = read_u16(14);
+    if tag == FORMAT_EXTENSIBLE && format.len() >= 26 {
+        // The sub format GUID starts with the actual format tag.
+        tag = read_u16(24);
+    }
+    if channels == 0 || sample_rate == 0 {
+        bail!("WAV file has no channels or no sample rate");
+    }
+
+    let sample: fn(&[u8]) -> f32 = match (tag, bits) {
+        (FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32_768.0,
+        (FORMAT_PCM, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0,
+        (FORMAT_PCM, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+        (FORMAT_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        (tag, bits) => bail!("Unsupported WAV format {tag} with {bits} bits per sample"),
+    };
+    let sample_len = bits as usize / 8;
+    let samples = data
+        .chunks_exact(sample_len * channels)
+        .map(|frame| frame.chunks_exact(sample_len).map(sample).sum::<f32>() / channels as f32)
+        .collect();
+
+    Ok(MonoAudio { sample_rate, samples })
+}
+
+/// Writes the header of a mono 16 bit PCM WAV file with `samples` samples.
+pub(super) fn write_wav_header(out: &mut impl Write, sample_rate: u32, samples: usize) -> Result<()> {
+    let data_len = (samples * 2) as u32;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&FORMAT_PCM.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?; // mono
+    out.write_all(&sample_rate.to_le_bytes())?;
+    out.write_all(&(sample_rate * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    Ok(())
+}
+
+pub(super) fn write_wav(out: &mut impl Write, audio: &MonoAudio) -> Result<()> {
+    write_wav_header(out, audio.sample_rate, audio.samples.len())?;
+    for sample in &audio.samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        out.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_wav_reads_back() {
+        let audio = MonoAudio {
+            sample_rate: 16_000,
+            samples: vec![0.0, 0.5, -0.5, 0.25],
+        };
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, &audio).unwrap();
+
+        let read = read_wav(&bytes).unwrap();
+        assert_eq!(read.sample_rate, 16_000);
+        for (read, written) in read.samples.iter().zip(&audio.samples) {
+            assert!((read - written).abs() < 0.001, "{read} != {written}");
+        }
+    }
+
+    #[test]
+    fn stereo_float_is_mixed_down() {
+        let mut bytes = Vec::new();
+        bytes.extend(b"RIFF\0\0\0\0WAVEfmt ");
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend(FORMAT_FLOAT.to_le_bytes());
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend(8_000u32.to_le_bytes());
+        bytes.extend(64_000u32.to_le_bytes());
+        bytes.extend(8u16.to_le_bytes());
+        bytes.extend(32u16.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend(16u32.to_le_bytes());
+        for sample in [1.0f32, 0.0, -0.5, -0.5] {
+            bytes.extend(sample.to_le_bytes());
+        }
+
+        let read = read_wav(&bytes).unwrap();
+        assert_eq!(read.samples, [0.5, -0.5]);
+    }
+}
@@ -0,0 +1,166 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::fmt;
+use strum::{
+    Display,
+    EnumIter,
+    EnumString,
+};
+
+/// Network conditions a participant's browser emulates. Only local Chromium participants (also when hosted by a
+/// remote agent) apply them.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct NetworkConfig {
+    pub profile: NetworkProfile,
+    /// Latency added to every request for the `custom` profile.
+    pub latency_ms: u64,
+    /// Download throughput for the `custom` profile. `0` does not limit it.
+    pub download_kbps: u64,
+    /// Upload throughput for the `custom` profile. `0` does not limit it.
+    pub upload_kbps: u64,
+    /// Share of dropped packets for the `custom` profile, from `0` to `100`.
+    pub packet_loss_percent: f64,
+}
+
+impl NetworkConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The conditions to emulate, `None` if the network should not be throttled.
+    pub fn conditions(&self) -> Option<NetworkConditions> {
+        match self.profile {
+            NetworkProfile::None => None,
+            // Same values as the Chrome DevTools presets.
+            NetworkProfile::Slow3g => Some(NetworkConditions {
+                latency_ms: 2_000,
+                download_kbps: Some(400),
+                upload_kbps: Some(400),
+                packet_loss_percent: 0.0,
+            }),
+            NetworkProfile::Fast3g => Some(NetworkConditions {
+                latency_ms: 563,
+                download_kbps: Some(1_440),
+                upload_kbps: Some(675),
+                packet_loss_percent: 0.0,
+            }),
+            NetworkProfile::LossyWifi => Some(NetworkConditions {
+                latency_ms: 40,
+                download_kbps: Some(10_000),
+                upload_kbps: Some(5_000),
+                packet_loss_percent: 5.0,
+            }),
+            NetworkProfile::Custom => Some(NetworkConditions {
+                latency_ms: self.latency_ms,
+                download_kbps: (self.download_kbps > 0).then_some(self.download_kbps),
+                upload_kbps: (self.upload_kbps > 0).then_some(self.upload_kbps),
+                packet_loss_percent: self.packet_loss_percent.clamp(0.0, 100.0),
+            }),
+        }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            profile: NetworkProfile::None,
+            latency_ms: 0,
+            download_kbps: 0,
+            upload_kbps: 0,
+            packet_loss_percent: 0.0,
+        }
+    }
+}
+
+impl fmt::Display for NetworkConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (NetworkProfile::Custom, Some(conditions)) = (self.profile, self.conditions()) else {
+            return write!(f, "{}", self.profile);
+        };
+
+        write!(f, "{}ms", conditions.latency_ms)?;
+        match (conditions.download_kbps, conditions.upload_kbps) {
+            (None, None) => {}
+            (down, up) => {
+                let kbps = |value: Option<u64>| value.map_or_else(|| "∞".to_string(), |value| value.to_string());
+                write!(f, " {}/{}kbps", kbps(down), kbps(up))?;
+            }
+        }
+        if conditions.packet_loss_percent > 0.0 {
+            write!(f, " {}% loss", conditions.packet_loss_percent)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Display, EnumIter, EnumString, Serialize, Deserialize, PartialEq, Eq)]
+pub enum NetworkProfile {
+    #[default]
+    #[strum(to_string = "none")]
+    #[serde(rename = "none")]
+    None,
+    #[strum(to_string = "slow-3g")]
+    #[serde(rename = "slow-3g")]
+    Slow3g,
+    #[strum(to_string = "fast-3g")]
+    #[serde(rename = "fast-3g")]
+    Fast3g,
+    #[strum(to_string = "lossy-wifi")]
+    #[serde(rename = "lossy-wifi")]
+    LossyWifi,
+    /// Uses the latency, throughput and packet loss of the [`NetworkConfig`].
+    #[strum(to_string = "custom")]
+    #[serde(rename = "custom")]
+    Custom,
+}
+
+/// Resolved [`NetworkConfig`], `None` throughput means unlimited.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NetworkConditions {
+    pub latency_ms: u64,
+    pub download_kbps: Option<u64>,
+    pub upload_kbps: Option<u64>,
+    pub packet_loss_percent: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        NetworkConfig,
+        NetworkProfile,
+    };
+    use std::str::FromStr as _;
+
+    #[test]
+    fn custom_profile_uses_configured_values_and_treats_zero_throughput_as_unlimited() {
+        let config = NetworkConfig {
+            profile: NetworkProfile::Custom,
+            latency_ms: 150,
+            download_kbps: 0,
+            upload_kbps: 500,
+            packet_loss_percent: 2.5,
+        };
+
+        let conditions = config.conditions().expect("custom profile emulates conditions");
+        assert_eq!(conditions.latency_ms, 150);
+        assert_eq!(conditions.download_kbps, None);
+        assert_eq!(conditions.upload_kbps, Some(500));
+        assert_eq!(config.to_string(), "150ms ∞/500kbps 2.5% loss");
+    }
+
+    #[test]
+    fn presets_ignore_custom_values() {
+        let config = NetworkConfig {
+            profile: NetworkProfile::from_str("lossy-wifi").unwrap(),
+            latency_ms: 1_000,
+            ..Default::default()
+        };
+
+        assert_eq!(config.conditions().unwrap().latency_ms, 40);
+        assert_eq!(config.to_string(), "lossy-wifi");
+        assert_eq!(NetworkConfig::default().conditions(), None);
+    }
+}
@@ -48,6 +48,7 @@ const fn backend_name_prefix(backend: super::ParticipantBackendKind) -> &'static
         super::ParticipantBackendKind::RemoteStub => "stub-",
         super::ParticipantBackendKind::Cloudflare => "cf-",
         super::ParticipantBackendKind::AwsDeviceFarm => "aws-",
+        super::ParticipantBackendKind::Remote => "remote-",
     }
 }
 
@@ -71,6 +72,7 @@ mod tests {
             (ParticipantBackendKind::RemoteStub, "stub-"),
             (ParticipantBackendKind::Cloudflare, "cf-"),
             (ParticipantBackendKind::AwsDeviceFarm, "aws-"),
+            (ParticipantBackendKind::Remote, "remote-"),
         ];
 
         for (backend, prefix) in cases {
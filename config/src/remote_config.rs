@@ -0,0 +1,44 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// Environment variable holding the shared token of agents and the simulators that use them.
+pub const AGENT_TOKEN_ENV: &str = "HYPER_AGENT_TOKEN";
+
+/// Configuration for the `remote` backend, which runs participants on agent processes started with
+/// `hyper-client-simulator agent` on other machines.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteConfig {
+    /// `host:port` of the agents. New participants go to the agents in turn.
+    pub agents: Vec<String>,
+    /// How long to wait for the TCP connection to an agent.
+    pub connect_timeout_ms: u64,
+    /// Token the agents were started with. Falls back to the [`AGENT_TOKEN_ENV`] environment variable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl RemoteConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// `token`, or the [`AGENT_TOKEN_ENV`] environment variable if unset.
+    pub fn resolved_token(&self) -> Option<String> {
+        self.token
+            .clone()
+            .or_else(|| std::env::var(AGENT_TOKEN_ENV).ok())
+            .filter(|token| !token.is_empty())
+    }
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            agents: Vec::new(),
+            connect_timeout_ms: 10_000,
+            token: None,
+        }
+    }
+}
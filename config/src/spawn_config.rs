@@ -0,0 +1,44 @@
+use crate::ParticipantBackendKind;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// Limits applied by the participant store when many participants are spawned at once.
+///
+/// Participants that cannot start yet stay queued and are reported as pending.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SpawnConfig {
+    /// How many participants may be starting at the same time. Unset starts every participant right away.
+    pub max_concurrent_starts: Option<usize>,
+    /// Minimum delay between two consecutive participant starts.
+    pub interval_ms: u64,
+    /// Maximum number of running participants on the local backend.
+    pub max_local: Option<usize>,
+    /// Maximum number of running participants on the Cloudflare backend.
+    pub max_cloudflare: Option<usize>,
+    /// Maximum number of running participants on the remote stub backend.
+    pub max_remote_stub: Option<usize>,
+    /// Maximum number of running participants on the AWS Device Farm backend.
+    pub max_aws_device_farm: Option<usize>,
+    /// Maximum number of running participants on the remote backend, across all agents.
+    pub max_remote: Option<usize>,
+    /// Wait for the browser capacity reported by the Cloudflare worker `/limits` endpoint before creating a session.
+    pub cloudflare_worker_limits: bool,
+}
+
+impl SpawnConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn max_running(&self, backend: ParticipantBackendKind) -> Option<usize> {
+        match backend {
+            ParticipantBackendKind::Local => self.max_local,
+            ParticipantBackendKind::Cloudflare => self.max_cloudflare,
+            ParticipantBackendKind::RemoteStub => self.max_remote_stub,
+            ParticipantBackendKind::AwsDeviceFarm => self.max_aws_device_farm,
+            ParticipantBackendKind::Remote => self.max_remote,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! `agent` subcommand: host participants for simulators on other machines that use the `remote` backend.
+//!
+//! The simulator sends the whole participant config with every start; only the data directory (and with it the
+//! session cookies) comes from this machine's config. Starts must carry the agent's token, and the URLs and files the
+//! config points to are checked against `--allow-host` and `--allow-local-files`.
+
+use clap::Args;
+use client_simulator_browser::participant::{
+    AgentAccess,
+    RemoteAgent,
+};
+use client_simulator_config::{
+    Config,
+    ParticipantBackendKind,
+    TuiArgs,
+    AGENT_TOKEN_ENV,
+};
+use eyre::{
+    Context as _,
+    Result,
+};
+use std::net::SocketAddr;
+use tracing_subscriber::{
+    fmt,
+    prelude::*,
+    registry,
+    EnvFilter,
+};
+
+#[derive(Args, Debug, Clone)]
+pub struct AgentArgs {
+    /// Address to accept simulator connections on. Bind a public address only on a network you trust.
+    #[clap(long, value_name = "ADDR", default_value = "127.0.0.1:7171")]
+    pub bind: SocketAddr,
+
+    /// Shared secret simulators must send to start participants, set as `remote.token` on their side.
+    #[clap(long, value_name = "TOKEN", env = AGENT_TOKEN_ENV, hide_env_values = true)]
+    pub token: String,
+
+    /// Host the session, fake media and screenshare URLs of hosted participants may point to. Repeat to allow more
+    /// hosts. Without it, any http or https host is allowed.
+    #[clap(long = "allow-host", value_name = "HOST")]
+    pub allowed_hosts: Vec<String>,
+
+    /// Let simulators use fake media and screenshare files on this machine.
+    #[clap(long)]
+    pub allow_local_files: bool,
+
+    /// Backend that runs the hosted participants: `local` (Chromium) or `remote-stub`.
+    #[clap(long, value_name = "BACKEND", default_value = "local")]
+    pub backend: ParticipantBackendKind,
+}
+
+pub async fn run(args: AgentArgs, filter: EnvFilter) -> Result<()> {
+    init_logging(filter)?;
+    let config = Config::new(TuiArgs::default()).context("Failed to create config")?;
+
+    let access = AgentAccess {
+        token: args.token,
+        allowed_hosts: args.allowed_hosts,
+        allow_local_files: args.allow_local_files,
+    };
+    let agent = RemoteAgent::start(args.bind, config, args.backend, access).await?;
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        tracing::error!("Failed to listen for Ctrl-C: {err}");
+    }
+    tracing::info!("Closing hosted participants");
+    agent.shutdown().await;
+
+    Ok(())
+}
+
+fn init_logging(filter: EnvFilter) -> Result<()> {
+    registry()
+        .with(fmt::layer().with_writer(std::io::stderr).with_filter(filter))
+        .with(tracing_error::ErrorLayer::default())
+        .try_init()?;
+
+    Ok(())
+}
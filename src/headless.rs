@@ -1,10 +1,30 @@
+use crate::{
+    report::RunReport,
+    scenario::Scenario,
+};
+use chrono::{
+    DateTime,
+    Utc,
+};
 use clap::Args;
-use client_simulator_browser::participant::{
-    ParticipantStore,
-    ParticipantWarning,
+use client_simulator_browser::{
+    control::ControlServer,
+    participant::{
+        ParticipantStore,
+        ParticipantWarning,
+    },
 };
 use client_simulator_config::{
+    media::{
+        FakeMediaAssignment,
+        FakeMediaPool,
+        ScreenshareSource,
+    },
+    BehaviorProfile,
     Config,
+    DeviceConfig,
+    DeviceProfile,
+    NetworkConfig,
     NoiseSuppression,
     ParticipantBackendKind,
     TransportMode,
@@ -15,7 +35,17 @@ use eyre::{
     Context as _,
     Result,
 };
-use std::future::Future;
+use std::{
+    future::{
+        pending,
+        Future,
+    },
+    net::SocketAddr,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
 use tokio::sync::watch;
 use tracing_subscriber::{
     fmt::{
@@ -40,6 +70,10 @@ pub struct HeadlessArgs {
     #[clap(long = "browser-logs", value_parser = clap::builder::BoolishValueParser::new())]
     pub browser_logs: Option<bool>,
 
+    /// Serve URL and test pattern fake media through a page script in local browsers too, like remote backends.
+    #[clap(long = "inject-fake-media", value_parser = clap::builder::BoolishValueParser::new())]
+    pub inject_fake_media: Option<bool>,
+
     #[clap(long = "audio-enabled", value_parser = clap::builder::BoolishValueParser::new())]
     pub audio_enabled: Option<bool>,
 
@@ -70,20 +104,63 @@ pub struct HeadlessArgs {
     #[clap(long, value_parser = clap::builder::BoolishValueParser::new())]
     pub blur: Option<bool>,
 
+    /// How participants pick their fake media from the pool: `selected`, `round-robin` or `random`.
+    #[clap(long = "fake-media-assignment", value_name = "ASSIGNMENT")]
+    pub fake_media_assignment: Option<FakeMediaAssignment>,
+
+    /// Emulate a weaker device in local browsers, e.g. `low-end-phone`.
+    #[clap(long, value_name = "PROFILE")]
+    pub device: Option<DeviceProfile>,
+
+    /// Slow the CPU of local browsers down by RATE, e.g. `4` for a four times slower CPU.
+    #[clap(long = "cpu-throttling-rate", value_name = "RATE")]
+    pub cpu_throttling_rate: Option<f64>,
+
+    /// Maximum number of participants starting at the same time.
+    #[clap(long = "max-concurrent-starts", value_name = "COUNT")]
+    pub max_concurrent_starts: Option<usize>,
+
+    /// Minimum delay between two participant starts.
+    #[clap(long = "spawn-interval-ms", value_name = "MS")]
+    pub spawn_interval_ms: Option<u64>,
+
+    /// Wait for free browser capacity reported by the Cloudflare worker before creating sessions.
+    #[clap(long = "cloudflare-worker-limits", value_parser = clap::builder::BoolishValueParser::new())]
+    pub cloudflare_worker_limits: Option<bool>,
+
     #[clap(long = "participant", value_name = "JSON")]
     pub participants: Vec<String>,
+
+    /// Run a YAML or JSON scenario file instead of spawning `--participant` entries and waiting for Ctrl-C.
+    #[clap(long, value_name = "PATH", conflicts_with = "participants")]
+    pub scenario: Option<PathBuf>,
+
+    /// Write a JSON report of the run to PATH and a JUnit XML report next to it (`<PATH stem>.junit.xml`).
+    #[clap(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+
+    /// Serve the control API on ADDR. Without `--scenario` the run then lasts until Ctrl-C and only spawns the
+    /// `--participant` entries that were given.
+    #[clap(long = "control-bind", value_name = "ADDR")]
+    pub control_bind: Option<SocketAddr>,
 }
 
 #[derive(Debug, Clone, Default, serde::Deserialize)]
 #[serde(default, deny_unknown_fields)]
-struct ParticipantOverride {
+pub(crate) struct ParticipantOverride {
     url: Option<url::Url>,
+    /// Description or file/URL of the fake media source, takes the participant out of the fake media pool.
+    fake_media: Option<String>,
+    fake_media_pool: Option<FakeMediaPool>,
+    inject_fake_media: Option<bool>,
     backend: Option<ParticipantBackendKind>,
     headless: Option<bool>,
     browser_logs: Option<bool>,
     audio_enabled: Option<bool>,
     video_enabled: Option<bool>,
     screenshare_enabled: Option<bool>,
+    /// `<builtin>`, `<test-pattern>`, or a page, video URL or file shared from a dedicated tab.
+    screenshare_source: Option<ScreenshareSource>,
     auto_gain_control: Option<bool>,
     noise_suppression: Option<NoiseSuppression>,
     transport: Option<TransportMode>,
@@ -91,24 +168,134 @@ struct ParticipantOverride {
     video_constraint_subscribe: Option<VideoConstraint>,
     video_max_concurrent_tracks: Option<usize>,
     blur: Option<bool>,
+    network: Option<NetworkConfig>,
+    device: Option<DeviceConfig>,
+    behavior: Option<BehaviorProfile>,
+    behavior_seed: Option<u64>,
+    behavior_period_ms: Option<u64>,
 }
 
 pub async fn run(args: HeadlessArgs, filter: EnvFilter) -> Result<i32> {
     init_logging(filter)?;
+    let started_at = Utc::now();
 
     let mut global_config = Config::new(TuiArgs::default()).context("Failed to create config")?;
     apply_cli_overrides(&mut global_config, &args);
 
-    let participant_configs = build_participant_configs(global_config.clone(), &args.participants)?;
-    let store = ParticipantStore::new(global_config.data_dir());
+    let store = ParticipantStore::new(global_config.data_dir()).with_spawn_config(&global_config.spawn);
+    let scenario = args.scenario.as_deref().map(Scenario::from_file).transpose()?;
+    let control_server = match global_config.control_bind {
+        Some(addr) => Some(ControlServer::start(addr, store.clone(), global_config.clone()).await?),
+        None => None,
+    };
+
+    let result = match &scenario {
+        Some(scenario) => run_scenario(scenario, &store, &global_config, args.report.as_deref(), started_at).await,
+        None => run_participants(&args, &store, global_config, control_server.is_some(), started_at).await,
+    };
 
-    spawn_participants_or_shutdown(&store, &participant_configs).await?;
+    if let Some(control_server) = control_server {
+        control_server.shutdown().await;
+    }
+    result
+}
+
+async fn run_participants(
+    args: &HeadlessArgs,
+    store: &ParticipantStore,
+    global_config: Config,
+    control_api: bool,
+    started_at: DateTime<Utc>,
+) -> Result<i32> {
+    let participant_configs = if control_api && args.participants.is_empty() {
+        tracing::info!("Waiting for participants spawned through the control API. Press Ctrl-C to stop.");
+        Vec::new()
+    } else {
+        build_participant_configs(global_config, &args.participants)?
+    };
+
+    spawn_participants_or_shutdown(store, &participant_configs).await?;
+    let mut participants = store.values();
+
+    // Participants come and go through the control API, so only Ctrl-C ends the run.
+    let exit_code = if control_api {
+        wait_for_exit_with(
+            pending(),
+            wait_for_ctrl_c(),
+            || {
+                participants = store.values();
+                store.shutdown_all()
+            },
+            wait_for_ctrl_c,
+        )
+        .await
+    } else {
+        wait_for_exit(store.clone()).await
+    };
+    let warning = if exit_code == 0 {
+        store.warnings().into_iter().next()
+    } else {
+        None
+    };
+
+    if let Some(path) = &args.report {
+        let report_exit_code = if warning.is_some() { 1 } else { exit_code };
+        RunReport::new(started_at, report_exit_code, &participants, &[]).write(path)?;
+    }
+    if let Some((participant, warning)) = warning {
+        return Err(eyre::eyre!("{}", participant_warning_error(&participant, &warning)));
+    }
+
+    Ok(exit_code)
+}
+
+async fn run_scenario(
+    scenario: &Scenario,
+    store: &ParticipantStore,
+    global_config: &Config,
+    report: Option<&Path>,
+    started_at: DateTime<Utc>,
+) -> Result<i32> {
+    let mut failures = Vec::new();
+    let mut participants = Vec::new();
+    let exit_code = wait_for_exit_with(
+        async { failures = scenario.run(store, global_config).await },
+        wait_for_ctrl_c(),
+        || {
+            participants = store.values();
+            store.shutdown_all()
+        },
+        wait_for_ctrl_c,
+    )
+    .await;
+    // An interrupted run already drained the store while shutting down.
+    participants.extend(store.values());
 
-    let exit_code = wait_for_exit(store.clone()).await;
-    if exit_code == 0 {
-        if let Some((participant, warning)) = store.warnings().into_iter().next() {
-            return Err(eyre::eyre!("{}", participant_warning_error(&participant, &warning)));
+    if exit_code != 0 {
+        if let Some(path) = report {
+            RunReport::new(started_at, exit_code, &participants, &failures).write(path)?;
         }
+        return Ok(exit_code);
+    }
+
+    let warnings = store.warnings();
+    tracing::info!("Scenario finished, shutting down participants");
+    store.shutdown_all().await;
+
+    for failure in &failures {
+        tracing::error!("{failure}");
+    }
+    for (participant, warning) in &warnings {
+        tracing::error!("{}", participant_warning_error(participant, warning));
+    }
+
+    let exit_code = if failures.is_empty() && warnings.is_empty() {
+        0
+    } else {
+        1
+    };
+    if let Some(path) = report {
+        RunReport::new(started_at, exit_code, &participants, &failures).write(path)?;
     }
 
     Ok(exit_code)
@@ -137,6 +324,9 @@ fn apply_cli_overrides(config: &mut Config, args: &HeadlessArgs) {
     if let Some(headless) = args.headless {
         config.headless = headless;
     }
+    if let Some(inject_fake_media) = args.inject_fake_media {
+        config.inject_fake_media = inject_fake_media;
+    }
     if let Some(audio_enabled) = args.audio_enabled {
         config.audio_enabled = audio_enabled;
     }
@@ -164,21 +354,51 @@ fn apply_cli_overrides(config: &mut Config, args: &HeadlessArgs) {
     if let Some(value) = args.video_max_concurrent_tracks {
         config.video_max_concurrent_tracks = Some(value);
     }
+    if let Some(value) = args.max_concurrent_starts {
+        config.spawn.max_concurrent_starts = Some(value);
+    }
+    if let Some(value) = args.spawn_interval_ms {
+        config.spawn.interval_ms = value;
+    }
+    if let Some(value) = args.cloudflare_worker_limits {
+        config.spawn.cloudflare_worker_limits = value;
+    }
     if let Some(blur) = args.blur {
         config.blur = blur;
     }
+    if let Some(assignment) = args.fake_media_assignment {
+        config.fake_media_pool.assignment = assignment;
+    }
+    if let Some(profile) = args.device {
+        config.device.profile = profile;
+    }
+    if let Some(rate) = args.cpu_throttling_rate {
+        config.device.cpu_throttling_rate = Some(rate);
+    }
+    if let Some(addr) = args.control_bind {
+        config.control_bind = Some(addr);
+    }
 }
 
-fn apply_participant_override(mut config: Config, override_: ParticipantOverride) -> Config {
+pub(crate) fn apply_participant_override(mut config: Config, override_: ParticipantOverride) -> Result<Config> {
     if let Some(url) = override_.url {
         config.url = Some(url);
     }
+    if let Some(pool) = override_.fake_media_pool {
+        config.fake_media_pool = pool;
+    }
+    if let Some(fake_media) = override_.fake_media {
+        config.select_fake_media(&fake_media)?;
+    }
     if let Some(backend) = override_.backend {
         config.backend = backend;
     }
     if let Some(headless) = override_.headless {
         config.headless = headless;
     }
+    if let Some(inject_fake_media) = override_.inject_fake_media {
+        config.inject_fake_media = inject_fake_media;
+    }
     if let Some(browser_logs) = override_.browser_logs {
         config.browser_logs = browser_logs;
     }
@@ -191,6 +411,9 @@ fn apply_participant_override(mut config: Config, override_: ParticipantOverride
     if let Some(screenshare_enabled) = override_.screenshare_enabled {
         config.screenshare_enabled = screenshare_enabled;
     }
+    if let Some(screenshare_source) = override_.screenshare_source {
+        config.screenshare_source = screenshare_source;
+    }
     if let Some(auto_gain_control) = override_.auto_gain_control {
         config.auto_gain_control = auto_gain_control;
     }
@@ -212,7 +435,22 @@ fn apply_participant_override(mut config: Config, override_: ParticipantOverride
     if let Some(blur) = override_.blur {
         config.blur = blur;
     }
-    config
+    if let Some(network) = override_.network {
+        config.network = network;
+    }
+    if let Some(device) = override_.device {
+        config.device = device;
+    }
+    if let Some(behavior) = override_.behavior {
+        config.behavior = behavior;
+    }
+    if let Some(seed) = override_.behavior_seed {
+        config.behavior_seed = Some(seed);
+    }
+    if let Some(period_ms) = override_.behavior_period_ms {
+        config.behavior_period_ms = period_ms;
+    }
+    Ok(config)
 }
 
 fn build_participant_configs(global_config: Config, participants: &[String]) -> Result<Vec<Config>> {
@@ -225,7 +463,8 @@ fn build_participant_configs(global_config: Config, participants: &[String]) ->
         .map(|json| {
             let override_: ParticipantOverride =
                 serde_json::from_str(json).wrap_err_with(|| format!("Invalid --participant JSON: {json}"))?;
-            Ok(apply_participant_override(global_config.clone(), override_))
+            apply_participant_override(global_config.clone(), override_)
+                .wrap_err_with(|| format!("Invalid --participant JSON: {json}"))
         })
         .collect()
 }
@@ -336,6 +575,7 @@ mod tests {
     use client_simulator_browser::participant::ParticipantState;
     use client_simulator_config::{
         Config,
+        NetworkProfile,
         ParticipantBackendKind,
         VideoConstraint,
     };
@@ -397,6 +637,41 @@ mod tests {
         assert_eq!(args.audio_enabled, Some(false));
     }
 
+    #[test]
+    fn cli_parsing_accepts_scenario_file_but_not_with_participants() {
+        let cli = TestHeadlessCli::parse_from(["headless", "--scenario", "load-test.yaml"]);
+        assert_eq!(cli.args.scenario, Some(PathBuf::from("load-test.yaml")));
+
+        let error = TestHeadlessCli::try_parse_from([
+            "headless",
+            "--scenario",
+            "load-test.yaml",
+            "--participant",
+            r#"{"backend": "local"}"#,
+        ])
+        .err()
+        .expect("--scenario conflicts with --participant");
+        assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn cli_parsing_accepts_report_path() {
+        let cli = TestHeadlessCli::parse_from(["headless", "--scenario", "load-test.yaml", "--report", "out/run.json"]);
+
+        assert_eq!(cli.args.report, Some(PathBuf::from("out/run.json")));
+    }
+
+    #[test]
+    fn cli_device_options_apply_to_the_device_config() {
+        let cli = TestHeadlessCli::parse_from(["headless", "--device", "low-end-laptop", "--cpu-throttling-rate", "6"]);
+        let mut config = Config::default();
+
+        apply_cli_overrides(&mut config, &cli.args);
+
+        assert_eq!(config.device.profile, DeviceProfile::LowEndLaptop);
+        assert_eq!(config.device.cpu_throttling_rate, Some(6.0));
+    }
+
     #[test]
     fn headless_enables_browser_logs_by_default() {
         let mut config = Config::default();
@@ -440,6 +715,36 @@ mod tests {
         assert_eq!(args.participants.len(), 1);
     }
 
+    #[test]
+    fn cli_spawn_limits_override_config() {
+        let cli = TestHeadlessCli::parse_from([
+            "headless",
+            "--max-concurrent-starts",
+            "4",
+            "--spawn-interval-ms",
+            "250",
+            "--cloudflare-worker-limits",
+            "true",
+        ]);
+        let mut config = Config::default();
+
+        apply_cli_overrides(&mut config, &cli.args);
+
+        assert_eq!(config.spawn.max_concurrent_starts, Some(4));
+        assert_eq!(config.spawn.interval_ms, 250);
+        assert!(config.spawn.cloudflare_worker_limits);
+    }
+
+    #[test]
+    fn cli_control_bind_overrides_config() {
+        let cli = TestHeadlessCli::parse_from(["headless", "--control-bind", "127.0.0.1:7070"]);
+        let mut config = Config::default();
+
+        apply_cli_overrides(&mut config, &cli.args);
+
+        assert_eq!(config.control_bind, Some("127.0.0.1:7070".parse().unwrap()));
+    }
+
     #[test]
     fn participant_json_overrides_global_config() {
         let global_config = Config {
@@ -480,6 +785,116 @@ mod tests {
         assert!(!configs[0].browser_logs);
     }
 
+    #[test]
+    fn participant_json_sets_the_screenshare_source() {
+        let configs = build_participant_configs(
+            Config::default(),
+            &[
+                r#"{"screenshare_enabled":true,"screenshare_source":"https://share.dev.hyper.video/slides.html"}"#
+                    .to_string(),
+                r#"{"screenshare_source":"<test-pattern>"}"#.to_string(),
+            ],
+        )
+        .expect("participant configs");
+
+        assert_eq!(
+            configs[0].screenshare_source,
+            ScreenshareSource::Tab("https://share.dev.hyper.video/slides.html".to_string())
+        );
+        assert_eq!(configs[1].screenshare_source, ScreenshareSource::TestPattern);
+    }
+
+    #[test]
+    fn participant_json_can_inject_fake_media() {
+        let configs = build_participant_configs(
+            Config::default(),
+            &[
+                r#"{"fake_media":"<test-pattern>","inject_fake_media":true}"#.to_string(),
+                "{}".to_string(),
+            ],
+        )
+        .expect("participant configs");
+
+        assert!(configs[0].inject_fake_media);
+        assert!(!configs[1].inject_fake_media);
+    }
+
+    #[test]
+    fn participant_json_sets_network_conditions() {
+        let configs = build_participant_configs(
+            Config::default(),
+            &[r#"{"network":{"profile":"custom","latency_ms":300,"packet_loss_percent":3}}"#.to_string()],
+        )
+        .expect("participant configs");
+
+        assert_eq!(configs[0].network.profile, NetworkProfile::Custom);
+        assert_eq!(configs[0].network.latency_ms, 300);
+        assert_eq!(configs[0].network.download_kbps, 0);
+        assert_eq!(configs[0].network.packet_loss_percent, 3.0);
+    }
+
+    #[test]
+    fn participant_json_selects_fake_media_outside_of_the_pool() {
+        let cli = TestHeadlessCli::parse_from(["headless", "--fake-media-assignment", "round-robin"]);
+        let mut global_config = Config::default();
+        apply_cli_overrides(&mut global_config, &cli.args);
+
+        let configs = build_participant_configs(
+            global_config,
+            &[
+                r#"{"fake_media":"Counting (Audio)"}"#.to_string(),
+                r#"{"fake_media":"https://share.dev.hyper.video/alice.y4m"}"#.to_string(),
+                r#"{"blur":true}"#.to_string(),
+            ],
+        )
+        .expect("participant configs");
+
+        assert_eq!(
+            configs[0].fake_media_with_description().description(),
+            "Counting (Audio)"
+        );
+        assert_eq!(
+            configs[1].fake_media().to_string(),
+            "https://share.dev.hyper.video/alice.y4m"
+        );
+        assert!(configs[0].fake_media_pool.is_default());
+        assert_eq!(configs[2].fake_media_pool.assignment, FakeMediaAssignment::RoundRobin);
+    }
+
+    #[test]
+    fn participant_json_sets_device_emulation() {
+        let configs = build_participant_configs(
+            Config::default(),
+            &[
+                r#"{"device":{"profile":"mid-range-phone"}}"#.to_string(),
+                r#"{"blur":true}"#.to_string(),
+            ],
+        )
+        .expect("participant configs");
+
+        assert_eq!(configs[0].device.profile, DeviceProfile::MidRangePhone);
+        assert_eq!(configs[0].device.cpu_throttling_rate, None);
+        assert!(configs[1].device.is_default());
+    }
+
+    #[test]
+    fn participant_json_selects_behavior_profile() {
+        let configs = build_participant_configs(
+            Config::default(),
+            &[
+                r#"{"behavior":"talker","behavior_seed":42,"behavior_period_ms":3000}"#.to_string(),
+                "{}".to_string(),
+            ],
+        )
+        .expect("participant configs");
+
+        assert_eq!(configs[0].behavior, BehaviorProfile::Talker);
+        assert_eq!(configs[0].behavior_seed, Some(42));
+        assert_eq!(configs[0].behavior_period_ms, 3_000);
+        assert!(configs[1].behavior.is_none());
+        assert_eq!(configs[1].behavior_seed, None);
+    }
+
     #[test]
     fn participant_json_overrides_video_constraints_and_treats_null_tracks_as_absent() {
         let global_config = Config {
@@ -1,7 +1,11 @@
+mod agent;
 mod aws;
 mod cf;
 mod errors;
 mod headless;
+mod media;
+mod report;
+mod scenario;
 
 use clap::{
     Parser,
@@ -84,6 +88,10 @@ enum Command {
     Aws(aws::AwsArgs),
     /// Manage sessions on the Cloudflare browser simulator worker
     Cf(cf::CfArgs),
+    /// Host participants for simulators using the `remote` backend
+    Agent(agent::AgentArgs),
+    /// Manage the cache of downloaded and converted fake media
+    Media(media::MediaArgs),
 }
 
 #[cfg(test)]
@@ -359,6 +367,61 @@ mod tests {
         assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
     }
 
+    #[test]
+    fn parses_media_prune_with_age_and_size() {
+        let args = CliArgs::parse_from([
+            "hyper-client-simulator",
+            "media",
+            "prune",
+            "--max-age",
+            "30days",
+            "--max-size",
+            "2000",
+            "--dry-run",
+        ]);
+        match args.command {
+            Some(Command::Media(media::MediaArgs {
+                command: media::MediaCommand::Prune(args),
+            })) => {
+                assert_eq!(args.max_age, Some(std::time::Duration::from_secs(30 * 24 * 3_600)));
+                assert_eq!(args.max_size, Some(2_000));
+                assert!(args.dry_run);
+            }
+            other => panic!("expected media prune, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_agent_with_defaults_and_overrides() {
+        let args = CliArgs::parse_from(["hyper-client-simulator", "agent"]);
+        match args.command {
+            Some(Command::Agent(args)) => {
+                assert_eq!(args.bind, "0.0.0.0:7171".parse().unwrap());
+                assert_eq!(args.backend, client_simulator_config::ParticipantBackendKind::Local);
+            }
+            other => panic!("expected agent, got {other:?}"),
+        }
+
+        let args = CliArgs::parse_from([
+            "hyper-client-simulator",
+            "agent",
+            "--bind",
+            "127.0.0.1:9000",
+            "--backend",
+            "remote-stub",
+        ]);
+        match args.command {
+            Some(Command::Agent(args)) => {
+                assert_eq!(args.bind, "127.0.0.1:9000".parse().unwrap());
+                assert_eq!(
+                    args.backend,
+                    client_simulator_config::ParticipantBackendKind::RemoteStub
+                );
+            }
+            other => panic!("expected agent, got {other:?}"),
+        }
+    }
+
     #[test]
     fn parses_aws_setup_auth() {
         let args = CliArgs::parse_from(["hyper-client-simulator", "aws", "setup-auth"]);
@@ -401,6 +464,8 @@ async fn main() -> eyre::Result<()> {
         Some(Command::Cookie(args)) => run_cookie(args, logging_filter_from_env(logging)).await,
         Some(Command::Aws(args)) => aws::run(args, logging_filter_from_env(logging)).await,
         Some(Command::Cf(args)) => cf::run(args, logging_filter_from_env(logging)).await,
+        Some(Command::Agent(args)) => agent::run(args, logging_filter_from_env(logging)).await,
+        Some(Command::Media(args)) => media::run(args, logging_filter_from_env(logging)).await,
     }
 }
 
@@ -0,0 +1,269 @@
+//! `media` subcommands: inspect, prune, pre-warm and verify the fake media cache.
+//!
+//! Downloads, conversions, test patterns and noise mixes are cached per source under the cache dir. The automatic
+//! size cap from `fake_media_cache.max_size_mb` in config.yaml only applies while media is prepared; `prune` applies
+//! it, or a stricter one, right away.
+
+use chrono::{
+    DateTime,
+    Local,
+};
+use clap::{
+    Args,
+    Subcommand,
+};
+use client_simulator_browser::media::FakeMediaPreparations;
+use client_simulator_config::{
+    get_cache_dir,
+    media::{
+        CacheEntry,
+        FakeMedia,
+        FakeMediaCache,
+    },
+    Config,
+    TuiArgs,
+};
+use eyre::{
+    bail,
+    eyre,
+    Context as _,
+    Result,
+};
+use serde::Serialize;
+use std::time::Duration;
+use tracing_subscriber::{
+    fmt,
+    prelude::*,
+    registry,
+    EnvFilter,
+};
+
+const MB: f64 = 1_000_000.0;
+
+#[derive(Args, Debug, Clone)]
+pub struct MediaArgs {
+    #[command(subcommand)]
+    pub command: MediaCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum MediaCommand {
+    /// List cached fake media with size, last use and origin, least recently used first.
+    List(ListArgs),
+    /// Remove cached fake media by age and, least recently used first, down to a total size.
+    Prune(PruneArgs),
+    /// Download and convert every `fake_media_sources` entry from config.yaml ahead of a run.
+    Warm,
+    /// Check that the cached files are complete.
+    Verify(VerifyArgs),
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct ListArgs {
+    /// Print JSON instead of text.
+    #[clap(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct PruneArgs {
+    /// Remove media not used for this long, e.g. `30days`.
+    #[clap(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub max_age: Option<Duration>,
+    /// Shrink the cache to this many megabytes. Defaults to `fake_media_cache.max_size_mb` from config.yaml unless
+    /// `--max-age` is given.
+    #[clap(long, value_name = "MB")]
+    pub max_size: Option<u64>,
+    /// Only list what would be removed.
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct VerifyArgs {
+    /// Remove broken entries so they are downloaded or converted again on next use.
+    #[clap(long)]
+    pub remove: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct EntryJson {
+    kind: String,
+    path: String,
+    origin: Option<String>,
+    size_bytes: u64,
+    last_used: DateTime<Local>,
+}
+
+pub async fn run(args: MediaArgs, filter: EnvFilter) -> Result<()> {
+    init_logging(filter)?;
+    let config = Config::new(TuiArgs::default()).context("Failed to create config")?;
+    let cache_dir = get_cache_dir();
+    let cache = FakeMediaCache::new(&cache_dir);
+
+    match args.command {
+        MediaCommand::List(args) => {
+            let entries = cache.entries()?;
+            if args.json {
+                let entries = entries.iter().map(entry_json).collect::<Vec<_>>();
+                println!("{}", serde_json::to_string(&entries)?);
+            } else {
+                print!("{}", format_entries(&entries));
+            }
+        }
+        MediaCommand::Prune(args) => {
+            let max_size = match (args.max_size, args.max_age) {
+                (Some(max_size), _) => Some(
+                    max_size
+                        .checked_mul(MB as u64)
+                        .ok_or_else(|| eyre!("--max-size {max_size} MB is more than the cache can hold"))?,
+                ),
+                (None, Some(_)) => None,
+                (None, None) => config.fake_media_cache.max_size(),
+            };
+            let plan = cache.prune_plan(args.max_age, max_size, &[])?;
+            for entry in &plan {
+                if !args.dry_run {
+                    entry.remove()?;
+                }
+                println!("{}", format_entry(entry));
+            }
+            let freed = plan.iter().map(|entry| entry.size).sum::<u64>();
+            let verb = if args.dry_run { "Would remove" } else { "Removed" };
+            println!("{verb} {} entries, {}", plan.len(), format_size(freed));
+        }
+        MediaCommand::Warm => {
+            let mut failures = 0;
+            for source in &config.fake_media_sources {
+                // The test pattern depends on the participant name and is quick to generate on spawn.
+                if matches!(
+                    source.fake_media(),
+                    FakeMedia::None | FakeMedia::Builtin | FakeMedia::TestPattern
+                ) {
+                    continue;
+                }
+                let prepared = FakeMediaPreparations::global()
+                    .prepare(source, "", &cache_dir, config.fake_media_cache.max_size())
+                    .await;
+                match prepared.map(|files| files.and_then(|files| files.error_summary())) {
+                    Ok(None) => println!("ready   {}", source.description()),
+                    Ok(Some(errors)) => println!("partial {}: {errors}", source.description()),
+                    Err(err) => {
+                        failures += 1;
+                        println!("failed  {}: {err:#}", source.description());
+                    }
+                }
+            }
+            if failures > 0 {
+                bail!("{failures} fake media sources could not be prepared");
+            }
+        }
+        MediaCommand::Verify(args) => {
+            let mut broken = 0;
+            for entry in cache.entries()? {
+                let problems = entry.verify()?;
+                if problems.is_empty() {
+                    continue;
+                }
+                broken += 1;
+                println!("{}", format_entry(&entry));
+                for problem in problems {
+                    println!("    {problem}");
+                }
+                if args.remove {
+                    entry.remove()?;
+                    println!("    removed");
+                }
+            }
+            if broken > 0 && !args.remove {
+                bail!("{broken} cached fake media entries are broken, remove them with `media verify --remove`");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn init_logging(filter: EnvFilter) -> Result<()> {
+    registry()
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_span_events(fmt::format::FmtSpan::CLOSE)
+                .with_filter(filter),
+        )
+        .with(tracing_error::ErrorLayer::default())
+        .try_init()?;
+
+    Ok(())
+}
+
+fn entry_json(entry: &CacheEntry) -> EntryJson {
+    EntryJson {
+        kind: entry.kind.to_string(),
+        path: entry.path.display().to_string(),
+        origin: entry.origin.clone(),
+        size_bytes: entry.size,
+        last_used: entry.last_used.into(),
+    }
+}
+
+fn format_entries(entries: &[CacheEntry]) -> String {
+    if entries.is_empty() {
+        return "No cached fake media.\n".to_owned();
+    }
+    let total = entries.iter().map(|entry| entry.size).sum::<u64>();
+    let mut lines = entries.iter().map(format_entry).collect::<Vec<_>>();
+    lines.push(format!("Total: {} in {} entries", format_size(total), entries.len()));
+    lines.join("\n") + "\n"
+}
+
+/// One line per entry: kind, size, last use and where the media came from.
+fn format_entry(entry: &CacheEntry) -> String {
+    let last_used = DateTime::<Local>::from(entry.last_used).format("%Y-%m-%d %H:%M");
+    let origin = entry.origin.clone().unwrap_or_else(|| entry.path.display().to_string());
+    format!(
+        "{:<12} {:>10}  {last_used}  {origin}",
+        entry.kind.to_string(),
+        format_size(entry.size)
+    )
+}
+
+fn format_size(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / MB)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_simulator_config::media::CacheEntryKind;
+    use std::{
+        path::PathBuf,
+        time::SystemTime,
+    };
+
+    #[test]
+    fn formats_entries_with_origin_and_total() {
+        let entry = CacheEntry {
+            kind: CacheEntryKind::Download,
+            path: PathBuf::from("/cache/download-cache/abc"),
+            origin: Some("https://share.dev.hyper.video/sp.mp4".to_owned()),
+            size: 12_345_678,
+            last_used: SystemTime::now(),
+        };
+        let unknown = CacheEntry {
+            kind: CacheEntryKind::Conversion,
+            origin: None,
+            path: PathBuf::from("/cache/media-cache/def"),
+            ..entry.clone()
+        };
+
+        let text = format_entries(&[entry, unknown]);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("download        12.3 MB  "), "{text}");
+        assert!(lines[0].ends_with("  https://share.dev.hyper.video/sp.mp4"), "{text}");
+        assert!(lines[1].ends_with("  /cache/media-cache/def"), "{text}");
+        assert_eq!(lines[2], "Total: 24.7 MB in 2 entries");
+        assert_eq!(format_entries(&[]), "No cached fake media.\n");
+    }
+}
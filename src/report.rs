@@ -0,0 +1,413 @@
+//! Machine-readable summary of a `headless` run, written with `--report <path>`.
+//!
+//! The JSON report is written to `<path>`, a JUnit XML rendering of the same data next to it with a `.junit.xml`
+//! extension. Every participant becomes one JUnit test case that fails when the participant failed to start, was
+//! terminated by its backend, had a command fail, was removed from the call, ended with a warning or never joined.
+//! Start phase and command latencies are summarized per backend, glass-to-glass latencies per sender and receiver, in
+//! the JSON report only.
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use client_simulator_browser::participant::{
+    GlassToGlassSummary,
+    LatencySummary,
+    Participant,
+    ParticipantEvent,
+    ParticipantEventKind,
+    ParticipantTiming,
+    ParticipantWarning,
+};
+use client_simulator_config::ParticipantBackendKind;
+use eyre::{
+    Context as _,
+    Result,
+};
+use serde::Serialize;
+use std::{
+    fmt::Write as _,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct RunReport {
+    started_at: DateTime<Utc>,
+    finished_at: DateTime<Utc>,
+    exit_code: i32,
+    /// Failures that do not belong to a single participant, e.g. scenario steps that could not be sent.
+    failures: Vec<String>,
+    /// p50/p95 of the start phases and commands, per backend.
+    latency: Vec<LatencySummary>,
+    /// p50/p95 of the latency from a sender's test pattern to each receiver.
+    glass_to_glass: Vec<GlassToGlassSummary>,
+    participants: Vec<ParticipantReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ParticipantReport {
+    name: String,
+    backend: ParticipantBackendKind,
+    created: DateTime<Utc>,
+    /// Time from spawning the participant until it first joined the session.
+    join_latency_ms: Option<i64>,
+    /// Total time the participant spent joined.
+    joined_ms: i64,
+    warning: Option<ParticipantWarning>,
+    terminations: Vec<ParticipantEvent>,
+    command_failures: Vec<ParticipantEvent>,
+    /// All recorded state transitions, in order.
+    events: Vec<ParticipantEvent>,
+    timings: Vec<ParticipantTiming>,
+}
+
+impl RunReport {
+    pub(crate) fn new(
+        started_at: DateTime<Utc>,
+        exit_code: i32,
+        participants: &[Participant],
+        failures: &[String],
+    ) -> Self {
+        let finished_at = Utc::now();
+        Self {
+            started_at,
+            finished_at,
+            exit_code,
+            failures: failures.to_vec(),
+            latency: LatencySummary::from_participants(participants),
+            glass_to_glass: GlassToGlassSummary::from_participants(participants),
+            participants: participants
+                .iter()
+                .map(|participant| ParticipantReport::new(participant, finished_at))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize run report")?;
+        std::fs::write(path, json).wrap_err_with(|| format!("Failed to write run report {}", path.display()))?;
+
+        let junit_path = junit_path(path);
+        std::fs::write(&junit_path, self.to_junit())
+            .wrap_err_with(|| format!("Failed to write JUnit report {}", junit_path.display()))?;
+
+        tracing::info!("Wrote run report to {} and {}", path.display(), junit_path.display());
+        Ok(())
+    }
+
+    fn to_junit(&self) -> String {
+        let mut cases = self
+            .participants
+            .iter()
+            .map(|participant| {
+                let time = participant.joined_ms as f64 / 1000.0;
+                (
+                    participant.name.as_str(),
+                    participant.backend.to_string(),
+                    time,
+                    participant.failures(),
+                )
+            })
+            .collect::<Vec<_>>();
+        if !self.failures.is_empty() {
+            cases.push(("run", "headless".to_string(), 0.0, self.failures.clone()));
+        }
+
+        let failed = cases.iter().filter(|(_, _, _, failures)| !failures.is_empty()).count();
+        let time = (self.finished_at - self.started_at).as_seconds_f64();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuites name=\"hyper-client-simulator\" tests=\"{}\" failures=\"{failed}\" time=\"{time:.3}\">",
+            cases.len()
+        );
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"headless\" tests=\"{}\" failures=\"{failed}\" timestamp=\"{}\" time=\"{time:.3}\">",
+            cases.len(),
+            self.started_at.to_rfc3339()
+        );
+        for (name, classname, time, failures) in &cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{time:.3}\"",
+                escape_xml(name),
+                escape_xml(classname)
+            );
+            match failures.first() {
+                None => xml.push_str("/>\n"),
+                Some(first) => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                        escape_xml(first),
+                        escape_xml(&failures.join("\n"))
+                    );
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+}
+
+impl ParticipantReport {
+    fn new(participant: &Participant, finished_at: DateTime<Utc>) -> Self {
+        let report = Self {
+            name: participant.name.clone(),
+            backend: participant.backend,
+            created: participant.created,
+            join_latency_ms: None,
+            joined_ms: 0,
+            warning: participant.state.borrow().warning.clone(),
+            terminations: Vec::new(),
+            command_failures: Vec::new(),
+            events: Vec::new(),
+            timings: participant.history.timings(),
+        };
+        report.with_events(participant.history.events(), finished_at)
+    }
+
+    /// Derives join latency, joined time, terminations and command failures from the participant's events.
+    fn with_events(self, events: Vec<ParticipantEvent>, finished_at: DateTime<Utc>) -> Self {
+        let join_latency_ms = events
+            .iter()
+            .find(|event| event.kind == ParticipantEventKind::Joined)
+            .map(|event| (event.at - self.created).num_milliseconds());
+
+        let mut joined_ms = 0;
+        let mut joined_at = None;
+        for event in &events {
+            match event.kind {
+                ParticipantEventKind::Joined => joined_at = Some(event.at),
+                ParticipantEventKind::Left | ParticipantEventKind::Stopped => {
+                    if let Some(at) = joined_at.take() {
+                        joined_ms += (event.at - at).num_milliseconds();
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(at) = joined_at {
+            joined_ms += (finished_at - at).num_milliseconds();
+        }
+
+        let filter_events = |predicate: fn(&ParticipantEventKind) -> bool| {
+            events
+                .iter()
+                .filter(|event| predicate(&event.kind))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        Self {
+            join_latency_ms,
+            joined_ms,
+            terminations: filter_events(|kind| matches!(kind, ParticipantEventKind::Terminated { .. })),
+            command_failures: filter_events(|kind| matches!(kind, ParticipantEventKind::CommandFailed { .. })),
+            events,
+            ..self
+        }
+    }
+
+    fn failures(&self) -> Vec<String> {
+        let mut failures = self
+            .events
+            .iter()
+            .filter_map(|event| match &event.kind {
+                ParticipantEventKind::StartFailed { error } => Some(format!("Failed to start: {error}")),
+                ParticipantEventKind::Terminated { message, .. } => Some(format!("Terminated: {message}")),
+                ParticipantEventKind::CommandFailed { command, error } => {
+                    Some(format!("Command {command} failed: {error}"))
+                }
+                ParticipantEventKind::CallStatusChanged { status } if status.ends_call() => status
+                    .warning()
+                    .map(|warning| format!("{} at {}: {}", warning.title, event.at.to_rfc3339(), warning.message)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if let Some(warning) = &self.warning {
+            failures.push(format!("{}: {}", warning.title, warning.message));
+        }
+        if self.join_latency_ms.is_none() {
+            failures.push("Participant never joined the session".to_string());
+        }
+        failures
+    }
+}
+
+fn junit_path(path: &Path) -> PathBuf {
+    path.with_extension("junit.xml")
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for char in value.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use client_simulator_browser::participant::CallStatus;
+
+    fn event(created: DateTime<Utc>, offset_ms: i64, kind: ParticipantEventKind) -> ParticipantEvent {
+        ParticipantEvent {
+            at: created + TimeDelta::milliseconds(offset_ms),
+            kind,
+        }
+    }
+
+    fn participant_report(created: DateTime<Utc>) -> ParticipantReport {
+        ParticipantReport {
+            name: "sim-user".to_string(),
+            backend: ParticipantBackendKind::RemoteStub,
+            created,
+            join_latency_ms: None,
+            joined_ms: 0,
+            warning: None,
+            terminations: Vec::new(),
+            command_failures: Vec::new(),
+            events: Vec::new(),
+            timings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn derives_join_latency_joined_time_and_failures_from_events() {
+        let created = Utc::now();
+        let events = vec![
+            event(created, 0, ParticipantEventKind::Pending),
+            event(created, 300, ParticipantEventKind::Started),
+            event(created, 1_500, ParticipantEventKind::Joined),
+            event(
+                created,
+                2_000,
+                ParticipantEventKind::CommandFailed {
+                    command: "ToggleAudio".to_string(),
+                    error: "button missing".to_string(),
+                },
+            ),
+            event(
+                created,
+                3_000,
+                ParticipantEventKind::CallStatusChanged {
+                    status: CallStatus::Reconnecting,
+                },
+            ),
+            event(
+                created,
+                4_000,
+                ParticipantEventKind::CallStatusChanged {
+                    status: CallStatus::RemovedByHost,
+                },
+            ),
+            event(created, 4_500, ParticipantEventKind::Left),
+            event(created, 5_000, ParticipantEventKind::Joined),
+            event(
+                created,
+                6_000,
+                ParticipantEventKind::Terminated {
+                    level: "warn".to_string(),
+                    message: "browser closed".to_string(),
+                },
+            ),
+            event(created, 6_500, ParticipantEventKind::Stopped),
+        ];
+
+        let report = participant_report(created).with_events(events, created + TimeDelta::seconds(10));
+
+        assert_eq!(report.join_latency_ms, Some(1_500));
+        assert_eq!(report.joined_ms, 4_500);
+        assert_eq!(report.command_failures.len(), 1);
+        assert_eq!(report.terminations.len(), 1);
+        assert_eq!(
+            report.failures(),
+            vec![
+                "Command ToggleAudio failed: button missing".to_string(),
+                format!(
+                    "Removed by host at {}: The participant was removed from the space",
+                    (created + TimeDelta::milliseconds(4_000)).to_rfc3339()
+                ),
+                "Terminated: browser closed".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn participants_still_joined_count_until_the_report_is_written() {
+        let created = Utc::now();
+        let events = vec![event(created, 1_000, ParticipantEventKind::Joined)];
+
+        let report = participant_report(created).with_events(events, created + TimeDelta::seconds(3));
+
+        assert_eq!(report.joined_ms, 2_000);
+    }
+
+    #[test]
+    fn junit_report_marks_failed_participants_and_run_failures() {
+        let created = Utc::now();
+        let healthy = participant_report(created).with_events(
+            vec![
+                event(created, 1_200, ParticipantEventKind::Joined),
+                event(created, 6_200, ParticipantEventKind::Stopped),
+            ],
+            created + TimeDelta::seconds(10),
+        );
+        let failed = ParticipantReport {
+            name: "broken <user>".to_string(),
+            ..participant_report(created)
+        }
+        .with_events(
+            vec![event(
+                created,
+                10,
+                ParticipantEventKind::StartFailed {
+                    error: "no \"browser\"".to_string(),
+                },
+            )],
+            created + TimeDelta::seconds(10),
+        );
+        let report = RunReport {
+            started_at: created,
+            finished_at: created + TimeDelta::seconds(10),
+            exit_code: 1,
+            failures: vec!["Step ToggleAudio at 1s failed".to_string()],
+            latency: Vec::new(),
+            glass_to_glass: Vec::new(),
+            participants: vec![healthy, failed],
+        };
+
+        let xml = report.to_junit();
+
+        assert!(xml.contains("<testsuites name=\"hyper-client-simulator\" tests=\"3\" failures=\"2\" time=\"10.000\">"));
+        assert!(xml.contains("<testcase name=\"sim-user\" classname=\"remote-stub\" time=\"5.000\"/>"));
+        assert!(xml.contains("<testcase name=\"broken &lt;user&gt;\" classname=\"remote-stub\" time=\"0.000\">"));
+        assert!(xml.contains("<failure message=\"Failed to start: no &quot;browser&quot;\">"));
+        assert!(xml.contains("Participant never joined the session"));
+        assert!(xml.contains("<failure message=\"Step ToggleAudio at 1s failed\">"));
+    }
+
+    #[test]
+    fn report_path_gets_a_junit_sibling() {
+        assert_eq!(
+            junit_path(Path::new("out/report.json")),
+            PathBuf::from("out/report.junit.xml")
+        );
+        assert_eq!(junit_path(Path::new("report")), PathBuf::from("report.junit.xml"));
+    }
+}
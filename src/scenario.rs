@@ -0,0 +1,550 @@
+//! Declarative load-test scenarios for the `headless` command.
+//!
+//! A scenario file (YAML or JSON) lists participant groups to spawn, messages to send at fixed offsets from the
+//! start of the run, and the total run duration after which every participant is torn down. Groups pick their
+//! backend through `settings`, so the same timeline runs against any participant backend.
+//!
+//! ```yaml
+//! duration: 2m
+//! participants:
+//!   - name: speakers
+//!     count: 4
+//!     ramp_up: 20s
+//!     settings:
+//!       backend: cloudflare
+//!   - name: listeners
+//!     count: 10
+//!     start: 30s
+//!     settings:
+//!       audio_enabled: false
+//! steps:
+//!   - at: 1m
+//!     group: speakers
+//!     message: ToggleAudio
+//!   - at: 80s
+//!     group: listeners
+//!     index: 0
+//!     message:
+//!       SetNoiseSuppression: krisp-high
+//! ```
+
+use crate::headless::{
+    apply_participant_override,
+    ParticipantOverride,
+};
+use client_simulator_browser::participant::{
+    shared::messages::ParticipantMessage,
+    ParticipantStore,
+};
+use client_simulator_config::Config;
+use eyre::{
+    bail,
+    Context as _,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Deserializer,
+};
+use std::{
+    collections::HashSet,
+    path::Path,
+    time::Duration,
+};
+use tokio::{
+    task::JoinSet,
+    time::{
+        sleep_until,
+        timeout_at,
+        Instant,
+    },
+};
+
+/// How long commands still running when the scenario duration ends are waited for before they count as failed.
+const COMMAND_GRACE: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Time from the start of the run until all participants are torn down.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub duration: Duration,
+    pub participants: Vec<ParticipantGroup>,
+    #[serde(default)]
+    pub steps: Vec<ScenarioStep>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParticipantGroup {
+    /// Name used by steps to address this group.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_group_count")]
+    pub count: usize,
+    /// Offset of the first spawn of this group.
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub start: Duration,
+    /// Time over which the group's spawns are spread evenly, starting at `start`.
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub ramp_up: Duration,
+    /// Overrides applied to the headless config, in the same shape as `--participant` JSON.
+    #[serde(default)]
+    pub settings: ParticipantOverride,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioStep {
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub at: Duration,
+    /// Group to address. Steps without a group address every participant of the scenario.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Position of a single participant within the group, in spawn order.
+    #[serde(default)]
+    pub index: Option<usize>,
+    pub message: ParticipantMessage,
+}
+
+const fn default_group_count() -> usize {
+    1
+}
+
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    humantime::parse_duration(&value).map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug, Clone)]
+enum TimelineEvent {
+    Spawn { group: usize, config: Box<Config> },
+    Send { step: usize },
+}
+
+#[derive(Debug, Clone)]
+struct TimelineEntry {
+    at: Duration,
+    event: TimelineEvent,
+}
+
+impl Scenario {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read scenario file {}", path.display()))?;
+        let is_json = path.extension().is_some_and(|extension| extension == "json");
+
+        Self::parse(&content, is_json).wrap_err_with(|| format!("Invalid scenario file {}", path.display()))
+    }
+
+    fn parse(content: &str, is_json: bool) -> Result<Self> {
+        // YAML is read into a JSON value first so that messages use the same externally tagged form in both
+        // formats (`SetNoiseSuppression: krisp-high` instead of YAML's `!SetNoiseSuppression` tags).
+        let value: serde_json::Value = if is_json {
+            serde_json::from_str(content)?
+        } else {
+            yaml_serde::from_str(content)?
+        };
+        let scenario: Self = serde_json::from_value(value)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.participants.is_empty() {
+            bail!("Scenario does not spawn any participants");
+        }
+
+        let mut names = HashSet::new();
+        for group in &self.participants {
+            if group.count == 0 {
+                bail!("Participant group {} has a count of 0", group.label());
+            }
+            if let Some(name) = &group.name {
+                if !names.insert(name.as_str()) {
+                    bail!("Participant group name `{name}` is used more than once");
+                }
+            }
+            if group.start > self.duration {
+                bail!("Participant group {} starts after the scenario duration", group.label());
+            }
+        }
+
+        for step in &self.steps {
+            if step.at > self.duration {
+                bail!(
+                    "Step {} at {} is scheduled after the scenario duration",
+                    step.message,
+                    humantime::format_duration(step.at)
+                );
+            }
+            match (&step.group, step.index) {
+                (Some(name), index) => {
+                    let Some(group) = self.group_by_name(name) else {
+                        bail!("Step {} addresses unknown participant group `{name}`", step.message);
+                    };
+                    if let Some(index) = index.filter(|index| *index >= group.count) {
+                        bail!(
+                            "Step {} addresses participant {index} of group `{name}`, which only has {}",
+                            step.message,
+                            group.count
+                        );
+                    }
+                }
+                (None, Some(_)) => bail!("Step {} sets `index` without a `group`", step.message),
+                (None, None) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn group_by_name(&self, name: &str) -> Option<&ParticipantGroup> {
+        self.participants
+            .iter()
+            .find(|group| group.name.as_deref() == Some(name))
+    }
+
+    fn timeline(&self, global_config: &Config) -> Result<Vec<TimelineEntry>> {
+        let mut timeline = Vec::new();
+
+        for (group_index, group) in self.participants.iter().enumerate() {
+            let config = apply_participant_override(global_config.clone(), group.settings.clone())
+                .wrap_err_with(|| format!("Invalid settings of participant group {}", group.label()))?;
+            for participant in 0..group.count {
+                let offset = group.ramp_up.mul_f64(participant as f64 / group.count as f64);
+                timeline.push(TimelineEntry {
+                    at: group.start + offset,
+                    event: TimelineEvent::Spawn {
+                        group: group_index,
+                        config: Box::new(config.clone()),
+                    },
+                });
+            }
+        }
+
+        for (step_index, step) in self.steps.iter().enumerate() {
+            timeline.push(TimelineEntry {
+                at: step.at,
+                event: TimelineEvent::Send { step: step_index },
+            });
+        }
+
+        // Stable sort: spawns stay ahead of steps scheduled at the same offset.
+        timeline.sort_by_key(|entry| entry.at);
+        Ok(timeline)
+    }
+
+    /// Runs the scenario timeline against `store` and returns the failed steps, including commands the participants
+    /// rejected or failed to carry out, also those still running `COMMAND_GRACE` after the duration. Invalid group
+    /// settings fail the run before anything is spawned. Participants are left running; the caller tears them down.
+    pub async fn run(&self, store: &ParticipantStore, global_config: &Config) -> Vec<String> {
+        let started = Instant::now();
+        let commands_deadline = started + self.duration + COMMAND_GRACE;
+        let mut spawned: Vec<Vec<String>> = vec![Vec::new(); self.participants.len()];
+        let mut failures = Vec::new();
+        let mut commands = JoinSet::new();
+        let timeline = match self.timeline(global_config) {
+            Ok(timeline) => timeline,
+            Err(err) => return vec![format!("{err:#}")],
+        };
+
+        for entry in timeline {
+            sleep_until(started + entry.at).await;
+
+            match entry.event {
+                TimelineEvent::Spawn { group, config } => match store.spawn(&config) {
+                    Ok(name) => {
+                        tracing::info!(
+                            "Scenario spawned participant `{name}` in group {}",
+                            self.participants[group].label()
+                        );
+                        spawned[group].push(name);
+                    }
+                    Err(err) => failures.push(format!(
+                        "Failed to spawn participant in group {} at {}: {err}",
+                        self.participants[group].label(),
+                        humantime::format_duration(entry.at)
+                    )),
+                },
+                TimelineEvent::Send { step } => {
+                    let step = &self.steps[step];
+                    failures.extend(self.send_step(store, step, &spawned, &mut commands, commands_deadline));
+                }
+            }
+        }
+
+        sleep_until(started + self.duration).await;
+        if !commands.is_empty() {
+            tracing::info!("Scenario waits for {} commands that are still running", commands.len());
+        }
+        // Every command gives up at `commands_deadline`, so this ends in time.
+        while let Some(outcome) = commands.join_next().await {
+            failures.extend(outcome.ok().flatten());
+        }
+        failures
+    }
+
+    /// Sends `step` to its participants and spawns waiting for the outcomes until `deadline` into `commands`. Returns
+    /// the failures known right away.
+    fn send_step(
+        &self,
+        store: &ParticipantStore,
+        step: &ScenarioStep,
+        spawned: &[Vec<String>],
+        commands: &mut JoinSet<Option<String>>,
+        deadline: Instant,
+    ) -> Vec<String> {
+        let targets = match &step.group {
+            Some(name) => {
+                let group = self
+                    .participants
+                    .iter()
+                    .position(|group| group.name.as_deref() == Some(name.as_str()))
+                    .expect("validated group name");
+                match step.index {
+                    Some(index) => spawned[group].get(index).into_iter().cloned().collect(),
+                    None => spawned[group].clone(),
+                }
+            }
+            None => spawned.iter().flatten().cloned().collect::<Vec<_>>(),
+        };
+
+        if targets.is_empty() {
+            return vec![format!(
+                "Step {} at {} has no spawned participants to address",
+                step.message,
+                humantime::format_duration(step.at)
+            )];
+        }
+
+        let mut failures = Vec::new();
+        for name in targets {
+            let failure = format!(
+                "Step {} at {} failed for `{name}`",
+                step.message,
+                humantime::format_duration(step.at)
+            );
+            let Some(participant) = store.get(&name) else {
+                failures.push(format!("{failure}: participant is no longer in the store"));
+                continue;
+            };
+            tracing::info!("Scenario sends {} to `{name}`", step.message);
+            let outcome = participant.run_command(step.message.clone());
+            commands.spawn(async move {
+                match timeout_at(deadline, outcome).await {
+                    Ok(outcome) => outcome.err().map(|err| format!("{failure}: {err}")),
+                    Err(_) => Some(format!(
+                        "{failure}: still running {} after the scenario ended",
+                        humantime::format_duration(COMMAND_GRACE)
+                    )),
+                }
+            });
+        }
+        failures
+    }
+}
+
+impl ParticipantGroup {
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("`{name}`"),
+            None => "<unnamed>".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_simulator_config::{
+        NoiseSuppression,
+        ParticipantBackendKind,
+    };
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{
+            SystemTime,
+            UNIX_EPOCH,
+        },
+    };
+    use url::Url;
+
+    const SCENARIO_YAML: &str = r#"
+duration: 2m
+participants:
+  - name: speakers
+    count: 4
+    ramp_up: 20s
+    settings:
+      backend: cloudflare
+  - name: listeners
+    count: 2
+    start: 30s
+    settings:
+      audio_enabled: false
+steps:
+  - at: 1m
+    group: speakers
+    message: ToggleAudio
+  - at: 80s
+    group: listeners
+    index: 1
+    message:
+      SetNoiseSuppression: krisp-high
+  - at: 90s
+    message: Leave
+"#;
+
+    #[test]
+    fn parses_yaml_scenarios() {
+        let scenario = Scenario::parse(SCENARIO_YAML, false).expect("valid scenario");
+
+        assert_eq!(scenario.duration, Duration::from_secs(120));
+        assert_eq!(scenario.participants.len(), 2);
+        assert_eq!(scenario.participants[0].count, 4);
+        assert_eq!(scenario.participants[0].ramp_up, Duration::from_secs(20));
+        assert_eq!(scenario.participants[1].start, Duration::from_secs(30));
+        assert!(matches!(scenario.steps[0].message, ParticipantMessage::ToggleAudio));
+        assert!(matches!(
+            scenario.steps[1].message,
+            ParticipantMessage::SetNoiseSuppression(NoiseSuppression::KrispHigh)
+        ));
+        assert_eq!(scenario.steps[1].index, Some(1));
+        assert_eq!(scenario.steps[2].group, None);
+    }
+
+    #[test]
+    fn parses_json_scenarios() {
+        let scenario = Scenario::parse(
+            r#"{
+                "duration": "10s",
+                "participants": [{ "settings": { "backend": "remote-stub" } }],
+                "steps": [{ "at": "5s", "message": { "SetVideoMaxConcurrentTracks": 2 } }]
+            }"#,
+            true,
+        )
+        .expect("valid scenario");
+
+        assert_eq!(scenario.participants[0].count, 1);
+        assert!(matches!(
+            scenario.steps[0].message,
+            ParticipantMessage::SetVideoMaxConcurrentTracks(Some(2))
+        ));
+    }
+
+    #[test]
+    fn rejects_steps_addressing_unknown_groups_or_participants() {
+        let unknown_group = Scenario::parse(
+            "duration: 10s\nparticipants: [{}]\nsteps: [{at: 1s, group: missing, message: Leave}]",
+            false,
+        )
+        .expect_err("unknown group should fail");
+        assert!(unknown_group.to_string().contains("unknown participant group"));
+
+        let out_of_range = Scenario::parse(
+            "duration: 10s\nparticipants: [{name: a, count: 2}]\nsteps: [{at: 1s, group: a, index: 2, message: Leave}]",
+            false,
+        )
+        .expect_err("index out of range should fail");
+        assert!(out_of_range.to_string().contains("only has 2"));
+
+        let too_late = Scenario::parse(
+            "duration: 10s\nparticipants: [{}]\nsteps: [{at: 11s, message: Leave}]",
+            false,
+        )
+        .expect_err("step after duration should fail");
+        assert!(too_late.to_string().contains("after the scenario duration"));
+    }
+
+    #[test]
+    fn timeline_spreads_spawns_over_ramp_up_and_orders_steps_after_spawns() {
+        let scenario = Scenario::parse(SCENARIO_YAML, false).expect("valid scenario");
+
+        let timeline = scenario
+            .timeline(&Config::default())
+            .expect("valid participant settings");
+        let spawns = timeline
+            .iter()
+            .filter_map(|entry| match &entry.event {
+                TimelineEvent::Spawn { group, config } => Some((entry.at, *group, config.backend)),
+                TimelineEvent::Send { .. } => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spawns,
+            vec![
+                (Duration::ZERO, 0, ParticipantBackendKind::Cloudflare),
+                (Duration::from_secs(5), 0, ParticipantBackendKind::Cloudflare),
+                (Duration::from_secs(10), 0, ParticipantBackendKind::Cloudflare),
+                (Duration::from_secs(15), 0, ParticipantBackendKind::Cloudflare),
+                (Duration::from_secs(30), 1, Config::default().backend),
+                (Duration::from_secs(30), 1, Config::default().backend),
+            ]
+        );
+        assert!(timeline.windows(2).all(|pair| pair[0].at <= pair[1].at));
+        assert!(matches!(
+            timeline.last().map(|entry| &entry.event),
+            Some(TimelineEvent::Send { step: 2 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn run_drives_remote_stub_participants_and_reports_failed_steps() {
+        let data_dir = unique_test_data_dir();
+        fs::create_dir_all(&data_dir).expect("create temp data dir");
+        let store = ParticipantStore::new(&data_dir);
+        let global_config = Config {
+            url: Some(Url::parse("https://example.com/lite/demo").expect("valid url")),
+            backend: ParticipantBackendKind::RemoteStub,
+            ..Default::default()
+        };
+        let scenario = Scenario::parse(
+            r#"
+duration: 300ms
+participants:
+  - name: stubs
+    count: 2
+steps:
+  - at: 100ms
+    group: stubs
+    index: 0
+    message: ToggleAudio
+  - at: 150ms
+    group: stubs
+    index: 1
+    message: Join
+"#,
+            false,
+        )
+        .expect("valid scenario");
+
+        let failures = scenario.run(&store, &global_config).await;
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(failures.len(), 1, "{failures:?}");
+        assert!(failures[0].contains("already joined"), "{failures:?}");
+        let initially_muted = !global_config.audio_enabled;
+        let muted = store
+            .values()
+            .iter()
+            .map(|participant| participant.state.borrow().muted)
+            .collect::<Vec<_>>();
+        assert_eq!(muted, vec![!initially_muted, initially_muted]);
+
+        store.shutdown_all().await;
+    }
+
+    fn unique_test_data_dir() -> PathBuf {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("current time")
+            .as_nanos();
+        std::env::temp_dir().join(format!("hyper-client-simulator-scenario-test-{timestamp}"))
+    }
+}
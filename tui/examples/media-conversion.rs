@@ -1,10 +1,12 @@
 use clap::Parser;
 use client_simulator_config::media::{
+    FakeMediaConversion,
     FakeMediaFileOrUrl,
     FakeMediaFiles,
 };
 use directories::ProjectDirs;
 use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
 
 /// Testing `BrowserFakeMedia`.
 /// Example usage:
@@ -16,6 +18,9 @@ pub struct Args {
     /// Audio or video file.
     #[clap(short, long, value_name = "FILE or URL")]
     pub input: FakeMediaFileOrUrl,
+    /// Conversion options, e.g. `360p, 15fps, 10s`.
+    #[clap(short, long, default_value = "original")]
+    pub conversion: FakeMediaConversion,
 }
 
 fn main() {
@@ -30,7 +35,8 @@ fn main() {
     };
 
     let now = std::time::Instant::now();
-    let result = FakeMediaFiles::from_file_or_url(args.input, cache_dir).unwrap();
+    let result =
+        FakeMediaFiles::from_file_or_url(args.input, &args.conversion, cache_dir, &CancellationToken::new()).unwrap();
     println!("elapsed ms: {}", now.elapsed().as_millis());
 
     dbg!(result);
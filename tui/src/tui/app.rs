@@ -17,9 +17,12 @@ use crate::tui::keybindings::{
     KeyBindings,
     Keymap,
 };
-use client_simulator_browser::participant::{
-    ParticipantStore,
-    ParticipantWarning,
+use client_simulator_browser::{
+    control::ControlServer,
+    participant::{
+        ParticipantStore,
+        ParticipantWarning,
+    },
 };
 use client_simulator_config::{
     Config,
@@ -99,7 +102,7 @@ impl App {
     pub fn new(args: TuiArgs) -> Result<Self> {
         let config = Config::new(args)?;
         let keybindings = KeyBindings::default();
-        let participants_store = ParticipantStore::new(config.data_dir());
+        let participants_store = ParticipantStore::new(config.data_dir()).with_spawn_config(&config.spawn);
 
         Ok(Self {
             components: vec![
@@ -141,6 +144,11 @@ impl App {
             component.init(tui.size()?)?;
         }
 
+        let control_server = match self.config.control_bind {
+            Some(addr) => Some(ControlServer::start(addr, self.participants_store.clone(), self.config.clone()).await?),
+            None => None,
+        };
+
         let action_tx = action_tx.clone();
         loop {
             self.handle_events(&mut tui, action_tx.clone()).await?;
@@ -158,6 +166,10 @@ impl App {
         }
         tui.exit()?;
 
+        if let Some(control_server) = control_server {
+            control_server.shutdown().await;
+        }
+
         Ok(())
     }
 
@@ -13,9 +13,20 @@ use crate::tui::{
     FocusedTopLevelComponent,
     Theme,
 };
-use client_simulator_browser::participant::ParticipantStore;
+use client_simulator_browser::{
+    media::{
+        FakeMediaPreparations,
+        MediaProgress,
+    },
+    participant::ParticipantStore,
+};
 use client_simulator_config::{
+    get_cache_dir,
+    media::FakeMedia,
+    BehaviorProfile,
     Config,
+    DeviceProfile,
+    NetworkProfile,
     NoiseSuppression,
     ParticipantBackendKind,
     TransportMode,
@@ -42,9 +53,11 @@ enum SelectedField {
     #[default]
     Url,
     FakeMedia,
+    FakeMediaConversion,
     Mute,
     VideoDisable,
     ScreenshareDisable,
+    ScreenshareSource,
     AutoGainControl,
     NoiseSuppression,
     Transport,
@@ -52,8 +65,11 @@ enum SelectedField {
     VideoConstraintSubscribe,
     VideoMaxConcurrentTracks,
     BackgroundBlur,
+    Network,
+    Device,
     Headless,
     Backend,
+    Behavior,
     StartBrowser,
 }
 
@@ -64,9 +80,15 @@ impl SelectedField {
             SelectedField::FakeMedia => {
                 " Use audio and video from a file or a generated test stream. <enter> to edit, <del> to clear. "
             }
+            SelectedField::FakeMediaConversion => {
+                " Convert fake video, e.g. `360p, 15fps, from 5s, 10s, loop`. <enter> to edit, <del> to reset. "
+            }
             SelectedField::Mute => " Mute audio? <enter> to toggle. ",
             SelectedField::VideoDisable => " Enable video? <enter> to toggle. ",
             SelectedField::ScreenshareDisable => " Enable screenshare? <enter> to toggle. ",
+            SelectedField::ScreenshareSource => {
+                " Share `<builtin>`, `<test-pattern>`, or a page, video URL or file in a tab. <enter> to edit, <del> to reset. "
+            }
             SelectedField::AutoGainControl => " Automatically adjust volume? <enter> to toggle. ",
             SelectedField::NoiseSuppression => " Enable noise suppression? <enter> to select noise suppression model. ",
             SelectedField::Transport => " Select transport protocol. <enter> to select. ",
@@ -78,8 +100,17 @@ impl SelectedField {
                 " Select max concurrent webcam tracks. <enter> to select. "
             }
             SelectedField::BackgroundBlur => " Enable background blur? <enter> to toggle. ",
+            SelectedField::Network => {
+                " Emulate a slow or lossy network in local browsers. <enter> to select, <del> to reset. "
+            }
+            SelectedField::Device => {
+                " Emulate a slower CPU and a phone or laptop screen in local browsers. <enter> to select, <del> to reset. "
+            }
             SelectedField::Headless => " Run the browser in headless mode? When disabled, will show a browser window with which you can interact. <enter> to toggle. ",
             SelectedField::Backend => " Select the participant backend. <enter> to select, <del> to reset. ",
+            SelectedField::Behavior => {
+                " Select a scripted behavior the participant runs after joining. <enter> to select, <del> to reset. "
+            }
             SelectedField::StartBrowser => " Start a new browser session and join a hyper.video session. <enter> to start. ",
         }
     }
@@ -96,7 +127,10 @@ pub(crate) enum BrowserStartAction {
     StartSelectVideoConstraintPublishWebcam,
     StartSelectVideoConstraintSubscribe,
     StartSelectVideoMaxConcurrentTracks,
+    StartSelectNetwork,
+    StartSelectDevice,
     StartSelectBackend,
+    StartSelectBehavior,
     StartBrowser,
     Toggle,
     DeleteSelectedField,
@@ -123,7 +157,10 @@ pub struct BrowserStart {
     video_constraint_publish_webcam_list: Option<EnumListInput<VideoConstraint>>,
     video_constraint_subscribe_list: Option<EnumListInput<VideoConstraint>>,
     video_max_concurrent_tracks_list: Option<EnumListInput<VideoMaxConcurrentTracksPreset>>,
+    network_list: Option<EnumListInput<NetworkProfile>>,
+    device_list: Option<EnumListInput<DeviceProfile>>,
     backend_list: Option<EnumListInput<ParticipantBackendKind>>,
+    behavior_list: Option<EnumListInput<BehaviorProfile>>,
     participant_store: ParticipantStore,
 }
 
@@ -147,11 +184,38 @@ impl BrowserStart {
             video_constraint_subscribe_list: None,
             video_max_concurrent_tracks_list: None,
             transport_list: None,
+            network_list: None,
+            device_list: None,
             backend_list: None,
+            behavior_list: None,
             editing: None,
             participant_store,
         }
     }
+
+    /// Starts downloading and converting the selected fake media so the first participant does not wait for it.
+    fn prefetch_fake_media(&self) {
+        let source = self.config.fake_media_with_description();
+        // The test pattern shows the participant name, so it can only be generated once that is known.
+        if source.fake_media() != &FakeMedia::TestPattern {
+            FakeMediaPreparations::global().prefetch(
+                &source,
+                "",
+                &get_cache_dir(),
+                self.config.fake_media_cache.max_size(),
+            );
+        }
+    }
+
+    fn fake_media_text(&self) -> String {
+        let source = self.config.fake_media_with_description();
+        let media = source.fake_media();
+        match FakeMediaPreparations::global().progress(&source, "") {
+            None => media.to_string(),
+            Some(MediaProgress::Ready(files)) if files.error_summary().is_none() => media.to_string(),
+            Some(progress) => format!("{media} ({progress})"),
+        }
+    }
 }
 
 impl Component for BrowserStart {
@@ -184,7 +248,16 @@ impl Component for BrowserStart {
                         SelectedField::FakeMedia => {
                             let index = self.config.add_custom_fake_media(content);
                             self.config.fake_media_selected = index;
+                            self.prefetch_fake_media();
                         }
+                        SelectedField::FakeMediaConversion => match content.parse() {
+                            Ok(conversion) => {
+                                self.config.set_fake_media_conversion(conversion);
+                                self.prefetch_fake_media();
+                            }
+                            Err(err) => warn!("Ignoring invalid fake media conversion: {err}"),
+                        },
+                        SelectedField::ScreenshareSource => self.config.screenshare_source = content.into(),
                         SelectedField::Mute
                         | SelectedField::VideoDisable
                         | SelectedField::ScreenshareDisable
@@ -195,8 +268,11 @@ impl Component for BrowserStart {
                         | SelectedField::VideoConstraintSubscribe
                         | SelectedField::VideoMaxConcurrentTracks
                         | SelectedField::BackgroundBlur
+                        | SelectedField::Network
+                        | SelectedField::Device
                         | SelectedField::Headless
                         | SelectedField::Backend
+                        | SelectedField::Behavior
                         | SelectedField::StartBrowser => {}
                     }
                     // Save config immediately after edit confirmation
@@ -238,6 +314,7 @@ impl Component for BrowserStart {
                             }
                             FakeMediaWithDescriptionItem::Select => {
                                 self.config.fake_media_selected = Some(index - 1);
+                                self.prefetch_fake_media();
                             }
                         }
                     } else {
@@ -400,6 +477,62 @@ impl Component for BrowserStart {
             }
         }
 
+        if let Some(mut list) = self.network_list.take() {
+            match key.code {
+                KeyCode::Enter => {
+                    match list.finish() {
+                        Ok(value) => {
+                            self.config.network.profile = value;
+                        }
+                        Err(err) => {
+                            error!(?err, "Failed to parse");
+                        }
+                    }
+                    if let Err(e) = self.config.save() {
+                        error!(?e, "Failed to save config after edit");
+                    }
+                    return Ok(Some(Action::Activate(ActivateAction::BrowserStart)));
+                }
+                KeyCode::Esc => {
+                    return Ok(Some(Action::Activate(ActivateAction::BrowserStart)));
+                }
+                _ => {}
+            }
+            let handled = list.handle_key_event(key);
+            self.network_list = Some(list);
+            if handled {
+                return Ok(None);
+            }
+        }
+
+        if let Some(mut list) = self.device_list.take() {
+            match key.code {
+                KeyCode::Enter => {
+                    match list.finish() {
+                        Ok(value) => {
+                            self.config.device.profile = value;
+                        }
+                        Err(err) => {
+                            error!(?err, "Failed to parse");
+                        }
+                    }
+                    if let Err(e) = self.config.save() {
+                        error!(?e, "Failed to save config after edit");
+                    }
+                    return Ok(Some(Action::Activate(ActivateAction::BrowserStart)));
+                }
+                KeyCode::Esc => {
+                    return Ok(Some(Action::Activate(ActivateAction::BrowserStart)));
+                }
+                _ => {}
+            }
+            let handled = list.handle_key_event(key);
+            self.device_list = Some(list);
+            if handled {
+                return Ok(None);
+            }
+        }
+
         if let Some(mut list) = self.backend_list.take() {
             match key.code {
                 KeyCode::Enter => {
@@ -428,6 +561,34 @@ impl Component for BrowserStart {
             }
         }
 
+        if let Some(mut list) = self.behavior_list.take() {
+            match key.code {
+                KeyCode::Enter => {
+                    match list.finish() {
+                        Ok(value) => {
+                            self.config.behavior = value;
+                        }
+                        Err(err) => {
+                            error!(?err, "Failed to parse");
+                        }
+                    }
+                    if let Err(e) = self.config.save() {
+                        error!(?e, "Failed to save config after edit");
+                    }
+                    return Ok(Some(Action::Activate(ActivateAction::BrowserStart)));
+                }
+                KeyCode::Esc => {
+                    return Ok(Some(Action::Activate(ActivateAction::BrowserStart)));
+                }
+                _ => {}
+            }
+            let handled = list.handle_key_event(key);
+            self.behavior_list = Some(list);
+            if handled {
+                return Ok(None);
+            }
+        }
+
         let action = match key.code {
             KeyCode::Delete | KeyCode::Backspace => Some(BrowserStartAction::DeleteSelectedField),
 
@@ -458,12 +619,21 @@ impl Component for BrowserStart {
                 Some(BrowserStartAction::StartSelectVideoMaxConcurrentTracks)
             }
             KeyCode::Enter if self.selected == SelectedField::BackgroundBlur => Some(BrowserStartAction::Toggle),
+            KeyCode::Enter if self.selected == SelectedField::Network => Some(BrowserStartAction::StartSelectNetwork),
+            KeyCode::Enter if self.selected == SelectedField::Device => Some(BrowserStartAction::StartSelectDevice),
             KeyCode::Enter if self.selected == SelectedField::Backend => Some(BrowserStartAction::StartSelectBackend),
+            KeyCode::Enter if self.selected == SelectedField::Behavior => Some(BrowserStartAction::StartSelectBehavior),
 
             KeyCode::Enter if self.selected == SelectedField::FakeMedia => {
                 Some(BrowserStartAction::StartSelectFakeMedia)
             }
             KeyCode::Enter if self.selected == SelectedField::Url => Some(BrowserStartAction::StartEditText),
+            KeyCode::Enter if self.selected == SelectedField::FakeMediaConversion => {
+                Some(BrowserStartAction::StartEditText)
+            }
+            KeyCode::Enter if self.selected == SelectedField::ScreenshareSource => {
+                Some(BrowserStartAction::StartEditText)
+            }
 
             KeyCode::Esc if self.fake_media_builtin_list.is_some() => {
                 self.fake_media_builtin_list = None;
@@ -489,10 +659,22 @@ impl Component for BrowserStart {
                 self.transport_list = None;
                 None
             }
+            KeyCode::Esc if self.network_list.is_some() => {
+                self.network_list = None;
+                None
+            }
+            KeyCode::Esc if self.device_list.is_some() => {
+                self.device_list = None;
+                None
+            }
             KeyCode::Esc if self.backend_list.is_some() => {
                 self.backend_list = None;
                 None
             }
+            KeyCode::Esc if self.behavior_list.is_some() => {
+                self.behavior_list = None;
+                None
+            }
 
             _ => None,
         };
@@ -534,38 +716,48 @@ impl Component for BrowserStart {
                 self.selected = match self.selected {
                     SelectedField::Url => SelectedField::StartBrowser,
                     SelectedField::FakeMedia => SelectedField::Url,
-                    SelectedField::Mute => SelectedField::FakeMedia,
+                    SelectedField::FakeMediaConversion => SelectedField::FakeMedia,
+                    SelectedField::Mute => SelectedField::FakeMediaConversion,
                     SelectedField::VideoDisable => SelectedField::Mute,
                     SelectedField::ScreenshareDisable => SelectedField::VideoDisable,
-                    SelectedField::AutoGainControl => SelectedField::ScreenshareDisable,
+                    SelectedField::ScreenshareSource => SelectedField::ScreenshareDisable,
+                    SelectedField::AutoGainControl => SelectedField::ScreenshareSource,
                     SelectedField::NoiseSuppression => SelectedField::AutoGainControl,
                     SelectedField::Transport => SelectedField::NoiseSuppression,
                     SelectedField::VideoConstraintPublishWebcam => SelectedField::Transport,
                     SelectedField::VideoConstraintSubscribe => SelectedField::VideoConstraintPublishWebcam,
                     SelectedField::VideoMaxConcurrentTracks => SelectedField::VideoConstraintSubscribe,
                     SelectedField::BackgroundBlur => SelectedField::VideoMaxConcurrentTracks,
-                    SelectedField::Headless => SelectedField::BackgroundBlur,
+                    SelectedField::Network => SelectedField::BackgroundBlur,
+                    SelectedField::Device => SelectedField::Network,
+                    SelectedField::Headless => SelectedField::Device,
                     SelectedField::Backend => SelectedField::Headless,
-                    SelectedField::StartBrowser => SelectedField::Backend,
+                    SelectedField::Behavior => SelectedField::Backend,
+                    SelectedField::StartBrowser => SelectedField::Behavior,
                 };
             }
 
             BrowserStartAction::MoveDown => {
                 self.selected = match self.selected {
                     SelectedField::Url => SelectedField::FakeMedia,
-                    SelectedField::FakeMedia => SelectedField::Mute,
+                    SelectedField::FakeMedia => SelectedField::FakeMediaConversion,
+                    SelectedField::FakeMediaConversion => SelectedField::Mute,
                     SelectedField::Mute => SelectedField::VideoDisable,
                     SelectedField::VideoDisable => SelectedField::ScreenshareDisable,
-                    SelectedField::ScreenshareDisable => SelectedField::AutoGainControl,
+                    SelectedField::ScreenshareDisable => SelectedField::ScreenshareSource,
+                    SelectedField::ScreenshareSource => SelectedField::AutoGainControl,
                     SelectedField::AutoGainControl => SelectedField::NoiseSuppression,
                     SelectedField::NoiseSuppression => SelectedField::Transport,
                     SelectedField::Transport => SelectedField::VideoConstraintPublishWebcam,
                     SelectedField::VideoConstraintPublishWebcam => SelectedField::VideoConstraintSubscribe,
                     SelectedField::VideoConstraintSubscribe => SelectedField::VideoMaxConcurrentTracks,
                     SelectedField::VideoMaxConcurrentTracks => SelectedField::BackgroundBlur,
-                    SelectedField::BackgroundBlur => SelectedField::Headless,
+                    SelectedField::BackgroundBlur => SelectedField::Network,
+                    SelectedField::Network => SelectedField::Device,
+                    SelectedField::Device => SelectedField::Headless,
                     SelectedField::Headless => SelectedField::Backend,
-                    SelectedField::Backend => SelectedField::StartBrowser,
+                    SelectedField::Backend => SelectedField::Behavior,
+                    SelectedField::Behavior => SelectedField::StartBrowser,
                     SelectedField::StartBrowser => return Ok(Some(Action::Activate(ActivateAction::Participants))),
                 };
             }
@@ -587,6 +779,14 @@ impl Component for BrowserStart {
                         let content = self.config.fake_media().to_string();
                         ("Edit Fake Media", "Fake media from file", content)
                     }
+                    SelectedField::FakeMediaConversion => {
+                        let content = self.config.fake_media_with_description().conversion().to_string();
+                        ("Edit Fake Video", "e.g. 360p, 15fps, from 5s, 10s, loop", content)
+                    }
+                    SelectedField::ScreenshareSource => {
+                        let content = self.config.screenshare_source.to_string();
+                        ("Edit Screenshare Source", "<test-pattern>, a URL or a file", content)
+                    }
                     _ => {
                         return Ok(None);
                     }
@@ -667,6 +867,24 @@ impl Component for BrowserStart {
                 return Ok(None);
             }
 
+            BrowserStartAction::StartSelectNetwork => {
+                self.network_list = Some(EnumListInput::new(
+                    "Network profile",
+                    NetworkProfile::iter(),
+                    self.config.network.profile,
+                ));
+                return Ok(None);
+            }
+
+            BrowserStartAction::StartSelectDevice => {
+                self.device_list = Some(EnumListInput::new(
+                    "Device profile",
+                    DeviceProfile::iter(),
+                    self.config.device.profile,
+                ));
+                return Ok(None);
+            }
+
             BrowserStartAction::StartSelectBackend => {
                 self.backend_list = Some(EnumListInput::new(
                     "Participant backend",
@@ -676,13 +894,29 @@ impl Component for BrowserStart {
                 return Ok(None);
             }
 
+            BrowserStartAction::StartSelectBehavior => {
+                self.behavior_list = Some(EnumListInput::new(
+                    "Behavior",
+                    BehaviorProfile::iter(),
+                    self.config.behavior,
+                ));
+                return Ok(None);
+            }
+
             BrowserStartAction::DeleteSelectedField => {
                 match self.selected {
                     SelectedField::Url => self.config.url = None,
                     SelectedField::FakeMedia => {
                         self.config.fake_media_selected = Some(0);
                     }
+                    SelectedField::FakeMediaConversion => {
+                        self.config.set_fake_media_conversion(Default::default());
+                    }
+                    SelectedField::ScreenshareSource => self.config.screenshare_source = Default::default(),
+                    SelectedField::Network => self.config.network = Default::default(),
+                    SelectedField::Device => self.config.device = Default::default(),
                     SelectedField::Backend => self.config.backend = ParticipantBackendKind::default(),
+                    SelectedField::Behavior => self.config.behavior = BehaviorProfile::default(),
                     _ => return Ok(None),
                 }
                 save_config = true;
@@ -758,9 +992,11 @@ impl Component for BrowserStart {
             .constraints([
                 Constraint::Length(1), // URL
                 Constraint::Length(1), // Fake-media
+                Constraint::Length(1), // Fake-media conversion
                 Constraint::Length(1), // Muted checkbox
                 Constraint::Length(1), // Video disabled checkbox
                 Constraint::Length(1), // Screenshare disabled checkbox
+                Constraint::Length(1), // Screenshare source
                 Constraint::Length(1), // Auto gain control checkbox
                 Constraint::Length(1), // Noise suppression checkbox
                 Constraint::Length(1), // Transport
@@ -768,8 +1004,11 @@ impl Component for BrowserStart {
                 Constraint::Length(1), // Incoming video constraint
                 Constraint::Length(1), // Max concurrent video tracks
                 Constraint::Length(1), // Background blur checkbox
+                Constraint::Length(1), // Network
+                Constraint::Length(1), // Device
                 Constraint::Length(1), // Headless checkbox
                 Constraint::Length(1), // Backend
+                Constraint::Length(1), // Behavior
                 Constraint::Length(3), // Start button
             ])
             .split(area);
@@ -781,9 +1020,11 @@ impl Component for BrowserStart {
         let form_labels = [
             "URL:",
             "Fake media:",
+            "Fake video:",
             "Audio enabled:",
             "Video enabled:",
             "Screenshare enabled:",
+            "Screenshare source:",
             "Auto gain control:",
             "Noise suppression:",
             "Transport:",
@@ -791,8 +1032,11 @@ impl Component for BrowserStart {
             "Incoming constraint:",
             "Track limit:",
             "Background blur",
+            "Network:",
+            "Device:",
             "Headless:",
             "Backend:",
+            "Behavior:",
             "Start browser",
         ];
         let max_length = form_labels.iter().map(|s| s.len()).max().unwrap_or(0) + 1;
@@ -813,7 +1057,7 @@ impl Component for BrowserStart {
         current_row_index += 1;
 
         // --- Fake Media Checkbox ---
-        let content = self.config.fake_media().to_string();
+        let content = self.fake_media_text();
         let widget = widgets::label_and_text(
             form_labels[current_row_index],
             content,
@@ -824,6 +1068,18 @@ impl Component for BrowserStart {
         frame.render_widget(widget, rows[current_row_index]);
         current_row_index += 1;
 
+        // --- Fake Media Conversion ---
+        let content = self.config.fake_media_with_description().conversion().to_string();
+        let widget = widgets::label_and_text(
+            form_labels[current_row_index],
+            content,
+            max_length,
+            self.focused && self.selected == SelectedField::FakeMediaConversion,
+            &theme,
+        );
+        frame.render_widget(widget, rows[current_row_index]);
+        current_row_index += 1;
+
         // --- Audio enabled ---
         let widget = widgets::label_and_bool(
             form_labels[current_row_index],
@@ -857,6 +1113,17 @@ impl Component for BrowserStart {
         frame.render_widget(widget, rows[current_row_index]);
         current_row_index += 1;
 
+        // --- Screenshare source ---
+        let widget = widgets::label_and_text(
+            form_labels[current_row_index],
+            self.config.screenshare_source.to_string(),
+            max_length,
+            self.focused && self.selected == SelectedField::ScreenshareSource,
+            &theme,
+        );
+        frame.render_widget(widget, rows[current_row_index]);
+        current_row_index += 1;
+
         // --- Auto gain control ---
         let widget = widgets::label_and_bool(
             form_labels[current_row_index],
@@ -942,6 +1209,30 @@ impl Component for BrowserStart {
         frame.render_widget(widget, rows[current_row_index]);
         current_row_index += 1;
 
+        // --- Network ---
+        let content = self.config.network.to_string();
+        let widget = widgets::label_and_text(
+            form_labels[current_row_index],
+            content,
+            max_length,
+            self.focused && self.selected == SelectedField::Network,
+            &theme,
+        );
+        frame.render_widget(widget, rows[current_row_index]);
+        current_row_index += 1;
+
+        // --- Device ---
+        let content = self.config.device.to_string();
+        let widget = widgets::label_and_text(
+            form_labels[current_row_index],
+            content,
+            max_length,
+            self.focused && self.selected == SelectedField::Device,
+            &theme,
+        );
+        frame.render_widget(widget, rows[current_row_index]);
+        current_row_index += 1;
+
         // --- Headless Checkbox ---
         let widget = widgets::label_and_bool(
             form_labels[current_row_index],
@@ -965,6 +1256,18 @@ impl Component for BrowserStart {
         frame.render_widget(widget, rows[current_row_index]);
         current_row_index += 1;
 
+        // --- Behavior ---
+        let content = self.config.behavior.to_string();
+        let widget = widgets::label_and_text(
+            form_labels[current_row_index],
+            content,
+            max_length,
+            self.focused && self.selected == SelectedField::Behavior,
+            &theme,
+        );
+        frame.render_widget(widget, rows[current_row_index]);
+        current_row_index += 1;
+
         // --- Start Browser button ---
         let button_area = Layout::default()
             .direction(Direction::Horizontal)
@@ -999,9 +1302,18 @@ impl Component for BrowserStart {
         if let Some(list) = &mut self.transport_list {
             list.draw(frame, area)?;
         }
+        if let Some(list) = &mut self.network_list {
+            list.draw(frame, area)?;
+        }
+        if let Some(list) = &mut self.device_list {
+            list.draw(frame, area)?;
+        }
         if let Some(list) = &mut self.backend_list {
             list.draw(frame, area)?;
         }
+        if let Some(list) = &mut self.behavior_list {
+            list.draw(frame, area)?;
+        }
 
         Ok(())
     }
@@ -12,10 +12,24 @@ use crate::tui::{
     Theme,
 };
 use chrono::TimeDelta;
-use client_simulator_browser::participant::ParticipantStore;
+use client_simulator_browser::participant::{
+    CallStatus,
+    CommandResult,
+    GlassToGlassSummary,
+    LatencyMetric,
+    LatencySummary,
+    ParticipantState,
+    ParticipantStore,
+    ReceivedMedia,
+    WebRtcStats,
+};
 use client_simulator_config::{
+    media::FakeMediaWithDescription,
     Config,
+    NetworkConfig,
+    NetworkProfile,
     NoiseSuppression,
+    ParticipantBackendKind,
     VideoConstraint,
     VideoMaxConcurrentTracksPreset,
 };
@@ -25,6 +39,7 @@ use eyre::OptionExt as _;
 use ratatui::{
     layout::{
         Constraint,
+        Layout,
         Rect,
     },
     style::{
@@ -40,17 +55,22 @@ use ratatui::{
     },
     Frame,
 };
+use std::collections::BTreeMap;
 use strum::{
     Display,
     IntoEnumIterator as _,
 };
 
+/// The latency panels below the participants table never grow beyond this many rows, including borders.
+const LATENCY_PANEL_MAX_HEIGHT: u16 = 12;
+
 #[derive(Debug, Clone, PartialEq, Eq, Display, serde::Serialize, serde::Deserialize)]
 pub(crate) enum ParticipantsAction {
     MoveUp,
     MoveDown,
     StartSelectNoiseSuppression,
     StartSelectVideoSetting,
+    StartSelectNetwork,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Display, strum::EnumIter, strum::EnumString)]
@@ -76,6 +96,7 @@ pub struct Participants {
     video_constraint_publish_webcam_list: Option<EnumListInput<VideoConstraint>>,
     video_constraint_subscribe_list: Option<EnumListInput<VideoConstraint>>,
     video_max_concurrent_tracks_list: Option<EnumListInput<VideoMaxConcurrentTracksPreset>>,
+    network_list: Option<EnumListInput<NetworkProfile>>,
 }
 
 impl Participants {
@@ -92,6 +113,7 @@ impl Participants {
             video_constraint_publish_webcam_list: None,
             video_constraint_subscribe_list: None,
             video_max_concurrent_tracks_list: None,
+            network_list: None,
         }
     }
 
@@ -223,6 +245,16 @@ impl Component for Participants {
                     ));
                     return Ok(None);
                 }
+                ParticipantsAction::StartSelectNetwork => {
+                    if let Some(selected) = self.selected.as_ref().and_then(|s| self.participants.get(s)) {
+                        self.network_list = Some(EnumListInput::new(
+                            "Network profile",
+                            NetworkProfile::iter(),
+                            selected.state.borrow().network.profile,
+                        ));
+                    }
+                    return Ok(None);
+                }
             },
             _ => {}
         }
@@ -363,6 +395,33 @@ impl Component for Participants {
             }
         }
 
+        if let Some(mut list) = self.network_list.take() {
+            match key.code {
+                KeyCode::Enter => {
+                    if let Ok(profile) = list.finish() {
+                        if let Some(participant) = self.selected.as_ref().and_then(|s| self.participants.get(s)) {
+                            // Keeps the custom latency and throughput the participant was started with.
+                            let network = NetworkConfig {
+                                profile,
+                                ..participant.state.borrow().network
+                            };
+                            participant.set_network(network);
+                        }
+                    }
+                    return Ok(Some(Action::Activate(ActivateAction::Participants)));
+                }
+                KeyCode::Esc => {
+                    return Ok(Some(Action::Activate(ActivateAction::Participants)));
+                }
+                _ => {}
+            }
+            let handled = list.handle_key_event(key);
+            self.network_list = Some(list);
+            if handled {
+                return Ok(None);
+            }
+        }
+
         let action = match (key.code, &self.selected) {
             (KeyCode::Backspace | KeyCode::Delete, Some(selected)) => {
                 let prev = self.participants.prev(selected);
@@ -433,6 +492,8 @@ impl Component for Participants {
                 Some(Action::ParticipantsAction(ParticipantsAction::StartSelectVideoSetting))
             }
 
+            (KeyCode::Char('w'), Some(_)) => Some(Action::ParticipantsAction(ParticipantsAction::StartSelectNetwork)),
+
             (KeyCode::Char('b'), Some(selected)) => {
                 if let Some(participant) = self.participants.get(selected) {
                     participant.toggle_background_blur();
@@ -440,6 +501,17 @@ impl Component for Participants {
                 None
             }
 
+            (KeyCode::Char('c'), Some(selected)) => {
+                if let Some(participant) = self.participants.get(selected) {
+                    if participant.state.borrow().recording {
+                        participant.stop_recording();
+                    } else {
+                        participant.start_recording();
+                    }
+                }
+                None
+            }
+
             // navigation
             (KeyCode::Up, _) => Some(Action::ParticipantsAction(ParticipantsAction::MoveUp)),
             (KeyCode::Down, _) => Some(Action::ParticipantsAction(ParticipantsAction::MoveDown)),
@@ -455,7 +527,7 @@ impl Component for Participants {
         let [_, _, area] = header_and_two_main_areas(area)?;
 
         let help = if self.selected.is_some() {
-            " <del> to shutdown, <j>oin, <l>eave, <m>ute, <v>ideo, <s>creenshare, auto <g>ain, <n>oise suppression, <r> video constraints, <b>lur "
+            " <del> to shutdown, <j>oin, <l>eave, <m>ute, <v>ideo, <s>creenshare, auto <g>ain, <n>oise suppression, <r> video constraints, <b>lur, net<w>ork, re<c>ord "
         } else {
             ""
         };
@@ -475,6 +547,11 @@ impl Component for Participants {
             return Ok(());
         }
 
+        let (header, rows) = glass_to_glass_table(&self.participants.glass_to_glass_summaries());
+        let area = render_summary_panel(frame, area, "Glass-to-glass p50/p95", header, rows, 20, &theme);
+        let (header, rows) = latency_table(&self.participants.latency_summaries());
+        let area = render_summary_panel(frame, area, "Latency p50/p95", header, rows, 14, &theme);
+
         let header_names = [
             "Name",
             "Created",
@@ -488,6 +565,11 @@ impl Component for Participants {
             "Transport",
             "Video constraints",
             "Blur",
+            "Network",
+            "Fake media",
+            "Media",
+            "Receiving",
+            "Last command",
         ];
 
         // Prepare table data
@@ -507,8 +589,12 @@ impl Component for Participants {
                 let created = format_duration(chrono::Utc::now() - participant.created);
                 let state = participant.state.borrow();
                 let name = participant.name.clone();
-                let opened = format_bool(state.running);
-                let joined = format_bool(state.joined);
+                let opened = if state.pending {
+                    "pending".to_string()
+                } else {
+                    format_bool(state.running)
+                };
+                let joined = format_joined(&state);
                 let muted = format_bool(state.muted);
                 let video = format_bool(state.video_activated);
                 let screenshare = format_bool(state.screenshare_activated);
@@ -523,6 +609,11 @@ impl Component for Participants {
                     .unwrap_or_else(|| "∞".to_string());
                 let video_constraints = format!("out:{publish} in:{subscribe} t:{tracks}");
                 let background_blur = format_bool(state.background_blur);
+                let network = state.network.to_string();
+                let fake_media = format_fake_media(&state.fake_media);
+                let media = format_webrtc_stats(state.webrtc_stats.as_ref());
+                let receiving = format_received_media(state.received_media.as_ref());
+                let last_command = format_last_command(state.last_command.as_ref());
                 let cells = vec![
                     Cell::from(name),
                     Cell::from(created),
@@ -536,6 +627,11 @@ impl Component for Participants {
                     Cell::from(transport_mode),
                     Cell::from(video_constraints),
                     Cell::from(background_blur),
+                    Cell::from(network),
+                    Cell::from(fake_media),
+                    Cell::from(media),
+                    Cell::from(receiving),
+                    Cell::from(last_command),
                 ];
                 let style = if Some(&participant.name) == self.selected.as_ref() {
                     theme.text_selected
@@ -557,18 +653,23 @@ impl Component for Participants {
                     .title_bottom(Line::from(help).centered()),
             )
             .widths([
-                Constraint::Percentage(9),  // Name
-                Constraint::Percentage(7),  // Created
-                Constraint::Percentage(6),  // Running
-                Constraint::Percentage(6),  // Joined
-                Constraint::Percentage(6),  // Muted
-                Constraint::Percentage(7),  // Video active
-                Constraint::Percentage(8),  // Screenshare active
-                Constraint::Percentage(7),  // Auto gain
-                Constraint::Percentage(11), // Noise suppression
-                Constraint::Percentage(7),  // Transport mode
-                Constraint::Percentage(18), // Video constraints
-                Constraint::Percentage(8),  // Blur
+                Constraint::Percentage(8), // Name
+                Constraint::Percentage(4), // Created
+                Constraint::Percentage(5), // Running
+                Constraint::Percentage(8), // Joined
+                Constraint::Percentage(5), // Muted
+                Constraint::Percentage(5), // Video active
+                Constraint::Percentage(6), // Screenshare active
+                Constraint::Percentage(4), // Auto gain
+                Constraint::Percentage(5), // Noise suppression
+                Constraint::Percentage(6), // Transport mode
+                Constraint::Percentage(8), // Video constraints
+                Constraint::Percentage(3), // Blur
+                Constraint::Percentage(6), // Network
+                Constraint::Percentage(6), // Fake media
+                Constraint::Percentage(7), // Media
+                Constraint::Percentage(8), // Receiving
+                Constraint::Percentage(6), // Last command
             ])
             .column_spacing(1);
 
@@ -590,11 +691,116 @@ impl Component for Participants {
         if let Some(list) = &mut self.video_max_concurrent_tracks_list {
             list.draw(frame, area)?;
         }
+        if let Some(list) = &mut self.network_list {
+            list.draw(frame, area)?;
+        }
 
         Ok(())
     }
 }
 
+/// Renders a table of `rows` at the bottom of `area`, unless there are none, and returns the rest of `area`.
+fn render_summary_panel(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    title: &str,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    column_width: u16,
+    theme: &Theme,
+) -> Rect {
+    if rows.is_empty() {
+        return area;
+    }
+    let height = (rows.len() as u16 + 3).min(LATENCY_PANEL_MAX_HEIGHT);
+    let [area, panel_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(height)]).areas(area);
+    let widths = header
+        .iter()
+        .map(|_| Constraint::Length(column_width))
+        .collect::<Vec<_>>();
+    let header = Row::new(header).style(Style::default().bg(Color::DarkGray).fg(Color::White));
+    let table = Table::new(rows.into_iter().map(Row::new), widths)
+        .header(header)
+        .block(
+            ratatui::widgets::Block::default()
+                .borders(ratatui::widgets::Borders::ALL)
+                .border_style(theme.border(false))
+                .title(title.to_string()),
+        )
+        .column_spacing(1);
+    frame.render_widget(table, panel_area);
+    area
+}
+
+/// One row per sender and receiver with the receiver's transport, so WebTransport and WebRTC can be compared.
+fn glass_to_glass_table(summaries: &[GlassToGlassSummary]) -> (Vec<String>, Vec<Vec<String>>) {
+    let header = ["Sender → receiver", "Transport", "Video", "Audio", "A/V offset"]
+        .map(ToString::to_string)
+        .to_vec();
+    let pair = |p50: Option<i64>, p95: Option<i64>| match p50.zip(p95) {
+        Some((p50, p95)) => format!("{p50}ms/{p95}ms"),
+        None => "-".to_string(),
+    };
+    let rows = summaries
+        .iter()
+        .map(|summary| {
+            vec![
+                format!("{} → {}", summary.sender, summary.receiver),
+                summary.transport.to_string(),
+                pair(summary.video_p50_ms, summary.video_p95_ms),
+                pair(summary.audio_p50_ms, summary.audio_p95_ms),
+                summary
+                    .av_offset_p50_ms
+                    .map(|offset| format!("{offset:+}ms"))
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+    (header, rows)
+}
+
+/// One row per metric and one column per backend, each cell showing p50/p95.
+fn latency_table(summaries: &[LatencySummary]) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut backends = Vec::<ParticipantBackendKind>::new();
+    let mut metrics = BTreeMap::<LatencyMetric, Vec<(ParticipantBackendKind, String)>>::new();
+    for summary in summaries {
+        if !backends.contains(&summary.backend) {
+            backends.push(summary.backend);
+        }
+        metrics.entry(summary.metric.clone()).or_default().push((
+            summary.backend,
+            format!("{}/{}", format_latency(summary.p50_ms), format_latency(summary.p95_ms)),
+        ));
+    }
+
+    let header = std::iter::once("Metric".to_string())
+        .chain(backends.iter().map(ToString::to_string))
+        .collect();
+    let rows = metrics
+        .into_iter()
+        .map(|(metric, cells)| {
+            std::iter::once(metric.to_string())
+                .chain(backends.iter().map(|backend| {
+                    cells
+                        .iter()
+                        .find(|(cell_backend, _)| cell_backend == backend)
+                        .map(|(_, cell)| cell.clone())
+                        .unwrap_or_else(|| "-".to_string())
+                }))
+                .collect()
+        })
+        .collect();
+    (header, rows)
+}
+
+fn format_latency(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    }
+}
+
 fn participants_panel_title(participant_count: usize) -> String {
     if participant_count == 0 {
         "Participants".to_string()
@@ -606,6 +812,12 @@ fn participants_panel_title(participant_count: usize) -> String {
 #[cfg(test)]
 mod tests {
     use super::{
+        format_joined,
+        format_last_command,
+        format_received_media,
+        format_webrtc_stats,
+        glass_to_glass_table,
+        latency_table,
         participants_panel_title,
         Participants,
     };
@@ -614,8 +826,23 @@ mod tests {
         ActivateAction,
         Component,
     };
-    use client_simulator_browser::participant::ParticipantStore;
-    use client_simulator_config::Config;
+    use client_simulator_browser::participant::{
+        CallStatus,
+        CommandResult,
+        GlassToGlassSummary,
+        LatencyMetric,
+        LatencySummary,
+        ParticipantState,
+        ParticipantStore,
+        ReceivedMedia,
+        StartPhase,
+        WebRtcStats,
+    };
+    use client_simulator_config::{
+        Config,
+        ParticipantBackendKind,
+        TransportMode,
+    };
     use std::{
         fs,
         path::PathBuf,
@@ -676,6 +903,168 @@ mod tests {
         assert_eq!(participants_panel_title(5), "Participants (5)");
     }
 
+    #[test]
+    fn media_column_summarizes_webrtc_stats() {
+        assert_eq!(format_webrtc_stats(None), "-");
+
+        let stats = WebRtcStats {
+            peer_connections: 1,
+            outbound_bitrate_kbps: 1_249.6,
+            inbound_bitrate_kbps: 3_400.2,
+            packet_loss_percent: 0.42,
+            round_trip_time_ms: Some(38.4),
+            frame_width: Some(1280),
+            frame_height: Some(720),
+            frames_per_second: Some(29.97),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_webrtc_stats(Some(&stats)),
+            "↑1250k ↓3400k loss 0.4% rtt 38ms 1280x720@30"
+        );
+
+        let audio_only = WebRtcStats {
+            peer_connections: 1,
+            inbound_bitrate_kbps: 32.0,
+            ..Default::default()
+        };
+        assert_eq!(format_webrtc_stats(Some(&audio_only)), "↑0k ↓32k loss 0.0%");
+    }
+
+    #[test]
+    fn receiving_column_summarizes_received_media() {
+        assert_eq!(format_received_media(None), "-");
+
+        let media = ReceivedMedia {
+            video_tiles: 4,
+            advancing_video_tiles: 2,
+            frozen_video_tiles: 1,
+            black_video_tiles: 1,
+            audio_tracks: 1,
+            audio_level: Some(0.1),
+            ..Default::default()
+        };
+        assert_eq!(format_received_media(Some(&media)), "2/4 video 1 frozen 1 black -20dB");
+
+        let stalled = ReceivedMedia {
+            video_tiles: 1,
+            frozen_video_tiles: 1,
+            audio_tracks: 1,
+            audio_level: Some(0.0),
+            nothing_received_secs: 25.4,
+            stalled: true,
+            ..Default::default()
+        };
+        assert_eq!(format_received_media(Some(&stalled)), "nothing for 25s");
+    }
+
+    #[test]
+    fn last_command_column_marks_failed_commands() {
+        assert_eq!(format_last_command(None), "-");
+
+        let succeeded = CommandResult {
+            command: "ToggleAudio".to_string(),
+            at: chrono::Utc::now(),
+            error: None,
+        };
+        assert_eq!(format_last_command(Some(&succeeded)), "ToggleAudio");
+
+        let failed = CommandResult {
+            error: Some("button missing".to_string()),
+            ..succeeded
+        };
+        assert_eq!(format_last_command(Some(&failed)), "ToggleAudio failed");
+    }
+
+    #[test]
+    fn joined_column_shows_the_call_status_when_not_normal() {
+        let joined = ParticipantState {
+            joined: true,
+            ..Default::default()
+        };
+        assert_eq!(format_joined(&joined), "[x]");
+
+        let reconnecting = ParticipantState {
+            call_status: CallStatus::Reconnecting,
+            ..joined
+        };
+        assert_eq!(format_joined(&reconnecting), "reconnecting");
+        assert_eq!(
+            format_joined(&ParticipantState {
+                call_status: CallStatus::RemovedByHost,
+                ..Default::default()
+            }),
+            "removed-by-host"
+        );
+    }
+
+    #[test]
+    fn glass_to_glass_table_has_a_row_per_sender_and_receiver() {
+        let summary = GlassToGlassSummary {
+            sender: "alice".to_string(),
+            receiver: "bob".to_string(),
+            transport: TransportMode::WebTransport,
+            count: 12,
+            video_p50_ms: Some(180),
+            video_p95_ms: Some(240),
+            audio_p50_ms: Some(150),
+            audio_p95_ms: Some(210),
+            av_offset_p50_ms: Some(-30),
+        };
+        let video_only = GlassToGlassSummary {
+            receiver: "carol".to_string(),
+            transport: TransportMode::WebRTC,
+            audio_p50_ms: None,
+            audio_p95_ms: None,
+            av_offset_p50_ms: None,
+            ..summary.clone()
+        };
+
+        let (header, rows) = glass_to_glass_table(&[summary, video_only]);
+
+        assert_eq!(header.len(), 5);
+        assert_eq!(
+            rows,
+            vec![
+                vec!["alice → bob", "webtransport", "180ms/240ms", "150ms/210ms", "-30ms"],
+                vec!["alice → carol", "webrtc", "180ms/240ms", "-", "-"],
+            ]
+        );
+    }
+
+    #[test]
+    fn latency_table_has_a_column_per_backend() {
+        let summary = |backend, metric, p50_ms, p95_ms| LatencySummary {
+            backend,
+            metric,
+            count: 10,
+            p50_ms,
+            p95_ms,
+            max_ms: p95_ms,
+        };
+        let summaries = vec![
+            summary(ParticipantBackendKind::Cloudflare, LatencyMetric::Start, 6_000, 9_340),
+            summary(ParticipantBackendKind::Local, LatencyMetric::Start, 2_100, 3_000),
+            summary(
+                ParticipantBackendKind::Local,
+                LatencyMetric::Phase(StartPhase::Navigation),
+                450,
+                980,
+            ),
+        ];
+
+        let (header, rows) = latency_table(&summaries);
+
+        assert_eq!(header, vec!["Metric", "cloudflare", "local"]);
+        assert_eq!(
+            rows,
+            vec![
+                vec!["start", "6.0s/9.3s", "2.1s/3.0s"],
+                vec!["navigation", "-", "450ms/980ms"],
+            ]
+        );
+    }
+
     fn spawn_remote_participant(store: &ParticipantStore) {
         let mut config = Config::default();
         config.url = Some(Url::parse("https://example.com/room/demo").expect("valid url"));
@@ -716,3 +1105,77 @@ fn format_bool(value: bool) -> String {
         "[ ]".to_string()
     }
 }
+
+/// Name of the last command, and whether it failed. The error itself is in the logs.
+fn format_last_command(command: Option<&CommandResult>) -> String {
+    match command {
+        None => "-".to_string(),
+        Some(CommandResult {
+            command, error: None, ..
+        }) => command.clone(),
+        Some(CommandResult { command, .. }) => format!("{command} failed"),
+    }
+}
+
+/// The call status instead of the checkbox while the frontend shows something other than the normal call.
+fn format_joined(state: &ParticipantState) -> String {
+    match state.call_status {
+        CallStatus::Normal => format_bool(state.joined),
+        status => status.to_string(),
+    }
+}
+
+/// Description of the fake media source, or the file name for custom sources without one.
+fn format_fake_media(fake_media: &FakeMediaWithDescription) -> String {
+    match fake_media.description() {
+        "" => {
+            let source = fake_media.fake_media().to_string();
+            source.rsplit('/').next().unwrap_or_default().to_string()
+        }
+        description => description.to_string(),
+    }
+}
+
+fn format_received_media(media: Option<&ReceivedMedia>) -> String {
+    let Some(media) = media else {
+        return "-".to_string();
+    };
+    if media.stalled {
+        return format!("nothing for {:.0}s", media.nothing_received_secs);
+    }
+
+    let mut summary = format!("{}/{} video", media.advancing_video_tiles, media.video_tiles);
+    if media.frozen_video_tiles > 0 {
+        summary.push_str(&format!(" {} frozen", media.frozen_video_tiles));
+    }
+    if media.black_video_tiles > 0 {
+        summary.push_str(&format!(" {} black", media.black_video_tiles));
+    }
+    match media.audio_level {
+        Some(level) if level > 0.0 => summary.push_str(&format!(" {:.0}dB", 20.0 * level.log10())),
+        Some(_) => summary.push_str(" silent"),
+        None => {}
+    }
+    summary
+}
+
+fn format_webrtc_stats(stats: Option<&WebRtcStats>) -> String {
+    let Some(stats) = stats.filter(|stats| stats.peer_connections > 0) else {
+        return "-".to_string();
+    };
+
+    let mut summary = format!(
+        "↑{:.0}k ↓{:.0}k loss {:.1}%",
+        stats.outbound_bitrate_kbps, stats.inbound_bitrate_kbps, stats.packet_loss_percent
+    );
+    if let Some(rtt) = stats.round_trip_time_ms {
+        summary.push_str(&format!(" rtt {rtt:.0}ms"));
+    }
+    if let (Some(width), Some(height)) = (stats.frame_width, stats.frame_height) {
+        summary.push_str(&format!(" {width}x{height}"));
+        if let Some(fps) = stats.frames_per_second {
+            summary.push_str(&format!("@{fps:.0}"));
+        }
+    }
+    summary
+}